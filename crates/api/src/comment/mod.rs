@@ -1,3 +1,4 @@
 pub mod distinguish;
 pub mod like;
+pub mod react;
 pub mod save;
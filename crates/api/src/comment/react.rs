@@ -0,0 +1,64 @@
+use activitypub_federation::config::Data;
+use actix_web::web::Json;
+use lemmy_api_common::{
+  comment::CreateCommentReaction,
+  context::LemmyContext,
+  post::ReactionsResponse,
+  send_activity::{ActivityChannel, SendActivityData},
+  utils::check_community_user_action,
+};
+use lemmy_db_schema::{
+  source::{
+    comment::Comment,
+    community::Community,
+    post::Post,
+    reaction::{Reaction, ReactionForm},
+  },
+  traits::Crud,
+};
+use lemmy_db_views::structs::LocalUserView;
+use lemmy_utils::{error::LemmyError, utils::validation::is_valid_reaction};
+
+#[tracing::instrument(skip(context))]
+pub async fn react_to_comment(
+  data: Json<CreateCommentReaction>,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> Result<Json<ReactionsResponse>, LemmyError> {
+  is_valid_reaction(&data.shortcode)?;
+  let comment = Comment::read(&mut context.pool(), data.comment_id).await?;
+  let post = Post::read(&mut context.pool(), comment.post_id).await?;
+  check_community_user_action(
+    &local_user_view.person,
+    post.community_id,
+    &mut context.pool(),
+  )
+  .await?;
+
+  let form = ReactionForm {
+    person_id: local_user_view.person.id,
+    post_id: post.id,
+    comment_id: Some(comment.id),
+    shortcode: data.shortcode.clone(),
+  };
+  if data.add {
+    Reaction::react(&mut context.pool(), &form).await?;
+  } else {
+    Reaction::remove(&mut context.pool(), &form).await?;
+  }
+
+  ActivityChannel::submit_activity(
+    SendActivityData::ReactPostOrComment(
+      comment.ap_id,
+      local_user_view.person,
+      Community::read(&mut context.pool(), post.community_id).await?,
+      data.shortcode.clone(),
+      data.add,
+    ),
+    &context,
+  )
+  .await?;
+
+  let reactions = Reaction::list(&mut context.pool(), post.id, Some(comment.id)).await?;
+  Ok(Json(ReactionsResponse { reactions }))
+}
@@ -0,0 +1,40 @@
+use activitypub_federation::config::Data;
+use actix_web::web::Json;
+use lemmy_api_common::{
+  community::ApproveCommunityFollower,
+  context::LemmyContext,
+  send_activity::{ActivityChannel, SendActivityData},
+  utils::is_mod_or_admin,
+  SuccessResponse,
+};
+use lemmy_db_schema::{
+  source::{
+    community::{Community, CommunityFollower},
+    person::Person,
+  },
+  traits::{Crud, Followable},
+};
+use lemmy_db_views::structs::LocalUserView;
+use lemmy_utils::error::LemmyError;
+
+#[tracing::instrument(skip(context))]
+pub async fn approve_community_follower(
+  data: Json<ApproveCommunityFollower>,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> Result<Json<SuccessResponse>, LemmyError> {
+  let community_id = data.community_id;
+  is_mod_or_admin(&mut context.pool(), &local_user_view.person, community_id).await?;
+
+  CommunityFollower::follow_accepted(&mut context.pool(), community_id, data.follower_id).await?;
+
+  let community = Community::read(&mut context.pool(), community_id).await?;
+  let follower = Person::read(&mut context.pool(), data.follower_id).await?;
+  ActivityChannel::submit_activity(
+    SendActivityData::AcceptFollower(community, follower),
+    &context,
+  )
+  .await?;
+
+  Ok(Json(SuccessResponse::default()))
+}
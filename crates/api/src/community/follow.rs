@@ -4,7 +4,7 @@ use lemmy_api_common::{
   community::{CommunityResponse, FollowCommunity},
   context::LemmyContext,
   send_activity::{ActivityChannel, SendActivityData},
-  utils::check_community_user_action,
+  utils::{check_community_user_action, is_community_member},
 };
 use lemmy_db_schema::{
   source::{
@@ -35,6 +35,9 @@ pub async fn follow_community(
       check_community_user_action(&local_user_view.person, community.id, &mut context.pool())
         .await?;
 
+      // Communities which require an application only accept new followers after a mod approves
+      community_follower_form.pending =
+        !is_community_member(local_user_view.person.id, &community, &mut context.pool()).await?;
       CommunityFollower::follow(&mut context.pool(), &community_follower_form)
         .await
         .with_lemmy_type(LemmyErrorType::CommunityFollowerAlreadyExists)?;
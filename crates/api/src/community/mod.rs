@@ -1,4 +1,5 @@
 pub mod add_mod;
+pub mod approve_follower;
 pub mod ban;
 pub mod block;
 pub mod follow;
@@ -16,7 +16,7 @@ use lemmy_db_schema::{
   PostFeatureType,
 };
 use lemmy_db_views::structs::LocalUserView;
-use lemmy_utils::error::LemmyError;
+use lemmy_utils::error::{LemmyError, LemmyErrorType};
 
 #[tracing::instrument(skip(context))]
 pub async fn feature_post(
@@ -27,13 +27,20 @@ pub async fn feature_post(
   let post_id = data.post_id;
   let orig_post = Post::read(&mut context.pool(), post_id).await?;
 
-  check_community_mod_action(
-    &local_user_view.person,
-    orig_post.community_id,
-    false,
-    &mut context.pool(),
-  )
-  .await?;
+  if data.feature_type == PostFeatureType::Profile {
+    // Only the creator can feature a post on their own profile
+    if orig_post.creator_id != local_user_view.person.id {
+      Err(LemmyErrorType::NoPostEditAllowed)?
+    }
+  } else {
+    check_community_mod_action(
+      &local_user_view.person,
+      orig_post.community_id,
+      false,
+      &mut context.pool(),
+    )
+    .await?;
+  }
 
   if data.feature_type == PostFeatureType::Local {
     is_admin(&local_user_view)?;
@@ -41,34 +48,41 @@ pub async fn feature_post(
 
   // Update the post
   let post_id = data.post_id;
-  let new_post: PostUpdateForm = if data.feature_type == PostFeatureType::Community {
-    PostUpdateForm {
+  let new_post: PostUpdateForm = match data.feature_type {
+    PostFeatureType::Community => PostUpdateForm {
       featured_community: Some(data.featured),
       ..Default::default()
-    }
-  } else {
-    PostUpdateForm {
+    },
+    PostFeatureType::Local => PostUpdateForm {
       featured_local: Some(data.featured),
       ..Default::default()
-    }
+    },
+    PostFeatureType::Profile => PostUpdateForm {
+      featured_profile: Some(data.featured),
+      ..Default::default()
+    },
   };
   let post = Post::update(&mut context.pool(), post_id, &new_post).await?;
 
-  // Mod tables
-  let form = ModFeaturePostForm {
-    mod_person_id: local_user_view.person.id,
-    post_id: data.post_id,
-    featured: data.featured,
-    is_featured_community: data.feature_type == PostFeatureType::Community,
-  };
+  // Featuring on the own profile is not a mod action, and remote instances read it from the
+  // featured collection of the creator, so there is nothing to log or federate.
+  if data.feature_type != PostFeatureType::Profile {
+    // Mod tables
+    let form = ModFeaturePostForm {
+      mod_person_id: local_user_view.person.id,
+      post_id: data.post_id,
+      featured: data.featured,
+      is_featured_community: data.feature_type == PostFeatureType::Community,
+    };
 
-  ModFeaturePost::create(&mut context.pool(), &form).await?;
+    ModFeaturePost::create(&mut context.pool(), &form).await?;
 
-  ActivityChannel::submit_activity(
-    SendActivityData::FeaturePost(post, local_user_view.person.clone(), data.featured),
-    &context,
-  )
-  .await?;
+    ActivityChannel::submit_activity(
+      SendActivityData::FeaturePost(post, local_user_view.person.clone(), data.featured),
+      &context,
+    )
+    .await?;
+  }
 
   build_post_response(
     &context,
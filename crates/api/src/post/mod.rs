@@ -3,4 +3,5 @@ pub mod get_link_metadata;
 pub mod like;
 pub mod lock;
 pub mod mark_read;
+pub mod react;
 pub mod save;
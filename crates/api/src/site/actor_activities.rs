@@ -0,0 +1,33 @@
+use actix_web::web::{Data, Json, Query};
+use lemmy_api_common::{
+  context::LemmyContext,
+  site::{ListActorActivities, ListActorActivitiesResponse},
+  utils::is_admin,
+};
+use lemmy_db_schema::source::activity::SentActivity;
+use lemmy_db_views::structs::LocalUserView;
+use lemmy_utils::error::LemmyError;
+
+/// Lists the stored activities of an actor, so that admins can review them for abuse. Only
+/// activities which were sent out or announced by this instance are stored with their json.
+#[tracing::instrument(skip(context))]
+pub async fn list_actor_activities(
+  data: Query<ListActorActivities>,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> Result<Json<ListActorActivitiesResponse>, LemmyError> {
+  is_admin(&local_user_view)?;
+
+  let activities = SentActivity::list_for_actor(
+    &mut context.pool(),
+    &data.actor_id.clone().into(),
+    data.page,
+    data.limit,
+  )
+  .await?
+  .into_iter()
+  .map(|a| a.data)
+  .collect();
+
+  Ok(Json(ListActorActivitiesResponse { activities }))
+}
@@ -2,6 +2,7 @@ use activitypub_federation::config::Data;
 use actix_web::web::Json;
 use lemmy_api_common::{
   context::LemmyContext,
+  send_activity::{ActivityChannel, SendActivityData},
   site::{BlockInstance, BlockInstanceResponse},
 };
 use lemmy_db_schema::{
@@ -34,6 +35,12 @@ pub async fn block_instance(
       .with_lemmy_type(LemmyErrorType::InstanceBlockAlreadyExists)?;
   }
 
+  ActivityChannel::submit_activity(
+    SendActivityData::BlockInstance(local_user_view.person, instance_id, data.block),
+    &context,
+  )
+  .await?;
+
   Ok(Json(BlockInstanceResponse {
     blocked: data.block,
   }))
@@ -1,3 +1,4 @@
+pub mod actor_activities;
 pub mod block;
 pub mod federated_instances;
 pub mod leave_admin;
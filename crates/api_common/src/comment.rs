@@ -2,6 +2,7 @@ use lemmy_db_schema::{
   newtypes::{CommentId, CommentReportId, CommunityId, LanguageId, LocalUserId, PostId},
   CommentSortType,
   ListingType,
+  ReplyPolicy,
 };
 use lemmy_db_views::structs::{CommentReportView, CommentView};
 use serde::{Deserialize, Serialize};
@@ -19,6 +20,8 @@ pub struct CreateComment {
   pub post_id: PostId,
   pub parent_id: Option<CommentId>,
   pub language_id: Option<LanguageId>,
+  /// Who can reply to the comment, defaults to everyone.
+  pub reply_policy: Option<ReplyPolicy>,
 }
 
 #[skip_serializing_none]
@@ -39,6 +42,7 @@ pub struct EditComment {
   pub comment_id: CommentId,
   pub content: Option<String>,
   pub language_id: Option<LanguageId>,
+  pub reply_policy: Option<ReplyPolicy>,
 }
 
 #[skip_serializing_none]
@@ -59,6 +63,8 @@ pub struct DistinguishComment {
 pub struct DeleteComment {
   pub comment_id: CommentId,
   pub deleted: bool,
+  /// An optional note which is shown in place of the deleted comment.
+  pub reason: Option<String>,
 }
 
 #[skip_serializing_none]
@@ -91,6 +97,18 @@ pub struct CommentResponse {
   pub recipient_ids: Vec<LocalUserId>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "full", derive(TS))]
+#[cfg_attr(feature = "full", ts(export))]
+/// React to a comment with an emoji, or remove the reaction.
+pub struct CreateCommentReaction {
+  pub comment_id: CommentId,
+  /// A unicode emoji, or the shortcode of a custom emoji like `:blobcat:`.
+  pub shortcode: String,
+  /// False to remove a previous reaction with the same emoji.
+  pub add: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[cfg_attr(feature = "full", derive(TS))]
 #[cfg_attr(feature = "full", ts(export))]
@@ -1,11 +1,12 @@
 use lemmy_db_schema::{
   newtypes::{CommunityId, LanguageId, PersonId},
   source::site::Site,
+  CommunityMembershipMode,
   ListingType,
   SortType,
 };
 use lemmy_db_views_actor::structs::{CommunityModeratorView, CommunityView, PersonView};
-use serde::{Deserialize, Serialize};
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
 use serde_with::skip_serializing_none;
 #[cfg(feature = "full")]
 use ts_rs::TS;
@@ -53,6 +54,14 @@ pub struct CreateCommunity {
   pub nsfw: Option<bool>,
   /// Whether to restrict posting only to moderators.
   pub posting_restricted_to_mods: Option<bool>,
+  /// Whether users need an approved application to participate.
+  pub membership_mode: Option<CommunityMembershipMode>,
+  /// The sort which is used by default when browsing the community.
+  pub default_sort_type: Option<SortType>,
+  /// Whether new posts are marked as NSFW if the creator doesn't specify it.
+  pub default_post_nsfw: Option<bool>,
+  /// The main language of the community, used for discovery.
+  pub primary_language_id: Option<LanguageId>,
   pub discussion_languages: Option<Vec<LanguageId>>,
 }
 
@@ -74,6 +83,8 @@ pub struct ListCommunities {
   pub type_: Option<ListingType>,
   pub sort: Option<SortType>,
   pub show_nsfw: Option<bool>,
+  /// Only list communities with this primary language.
+  pub primary_language_id: Option<LanguageId>,
   pub page: Option<i64>,
   pub limit: Option<i64>,
 }
@@ -146,6 +157,17 @@ pub struct EditCommunity {
   pub nsfw: Option<bool>,
   /// Whether to restrict posting only to moderators.
   pub posting_restricted_to_mods: Option<bool>,
+  /// Whether users need an approved application to participate.
+  pub membership_mode: Option<CommunityMembershipMode>,
+  /// The sort which is used by default when browsing the community. `null` or an empty string
+  /// removes it, so that the global default is used.
+  #[serde(default, deserialize_with = "deserialize_clearable_sort_type")]
+  #[cfg_attr(feature = "full", ts(type = "SortType | \"\" | null"))]
+  pub default_sort_type: Option<Option<SortType>>,
+  /// Whether new posts are marked as NSFW if the creator doesn't specify it.
+  pub default_post_nsfw: Option<bool>,
+  /// The main language of the community, used for discovery.
+  pub primary_language_id: Option<LanguageId>,
   pub discussion_languages: Option<Vec<LanguageId>>,
 }
 
@@ -190,6 +212,15 @@ pub struct FollowCommunity {
   pub follow: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "full", derive(TS))]
+#[cfg_attr(feature = "full", ts(export))]
+/// Approve a pending follower of a community which requires an application for membership.
+pub struct ApproveCommunityFollower {
+  pub community_id: CommunityId,
+  pub follower_id: PersonId,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[cfg_attr(feature = "full", derive(TS))]
 #[cfg_attr(feature = "full", ts(export))]
@@ -217,3 +248,54 @@ pub struct TransferCommunity {
   pub community_id: CommunityId,
   pub person_id: PersonId,
 }
+
+/// A missing value is deserialized as `None`, so that it is left unchanged. Both `null` and an
+/// empty string are deserialized as `Some(None)`, so that the value is removed.
+fn deserialize_clearable_sort_type<'de, D>(
+  deserializer: D,
+) -> Result<Option<Option<SortType>>, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  #[derive(Deserialize)]
+  #[serde(untagged)]
+  enum SortTypeOrEmpty {
+    SortType(SortType),
+    Empty(String),
+  }
+
+  match Option::<SortTypeOrEmpty>::deserialize(deserializer)? {
+    Some(SortTypeOrEmpty::SortType(sort)) => Ok(Some(Some(sort))),
+    Some(SortTypeOrEmpty::Empty(s)) if !s.is_empty() => {
+      Err(D::Error::custom(format!("unknown sort type: {s}")))
+    }
+    _ => Ok(Some(None)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn test_edit_community_default_sort_type() {
+    let edit = |default_sort_type: Option<serde_json::Value>| {
+      let mut data = json!({ "community_id": 1 });
+      if let Some(d) = default_sort_type {
+        data["default_sort_type"] = d;
+      }
+      serde_json::from_value::<EditCommunity>(data).map(|e| e.default_sort_type)
+    };
+
+    assert_eq!(None, edit(None).unwrap());
+    assert_eq!(Some(Some(SortType::Hot)), edit(Some(json!("Hot"))).unwrap());
+    // the default sort can be removed again
+    assert_eq!(Some(None), edit(Some(json!(null))).unwrap());
+    assert_eq!(Some(None), edit(Some(json!(""))).unwrap());
+    assert!(edit(Some(json!("Sideways"))).is_err());
+  }
+}
@@ -6,8 +6,15 @@ use lemmy_utils::{
   rate_limit::RateLimitCell,
   settings::{structs::Settings, SETTINGS},
 };
+use moka::future::Cache;
 use reqwest_middleware::ClientWithMiddleware;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
+use url::Url;
+
+/// Number of recently received activity ids which are kept in memory.
+const RECEIVED_ACTIVITIES_CACHE_SIZE: u64 = 10_000;
+/// Number of webfinger lookups which are kept in memory, for each of found and not found.
+const WEBFINGER_CACHE_SIZE: u64 = 10_000;
 
 #[derive(Clone)]
 pub struct LemmyContext {
@@ -15,6 +22,8 @@ pub struct LemmyContext {
   client: Arc<ClientWithMiddleware>,
   secret: Arc<Secret>,
   rate_limit_cell: RateLimitCell,
+  received_activities: Cache<Url, ()>,
+  webfinger_cache: WebfingerCache,
 }
 
 impl LemmyContext {
@@ -29,6 +38,8 @@ impl LemmyContext {
       client: Arc::new(client),
       secret: Arc::new(secret),
       rate_limit_cell,
+      received_activities: Cache::new(RECEIVED_ACTIVITIES_CACHE_SIZE),
+      webfinger_cache: WebfingerCache::new(&SETTINGS),
     }
   }
   pub fn pool(&self) -> DbPool<'_> {
@@ -49,4 +60,59 @@ impl LemmyContext {
   pub fn rate_limit_cell(&self) -> &RateLimitCell {
     &self.rate_limit_cell
   }
+  /// Ids of activities which were recently received. This allows skipping duplicates before
+  /// doing any expensive work, the received_activity table remains the source of truth.
+  pub fn received_activities(&self) -> &Cache<Url, ()> {
+    &self.received_activities
+  }
+  pub fn webfinger_cache(&self) -> &WebfingerCache {
+    &self.webfinger_cache
+  }
+}
+
+/// Actor ids which webfinger identifiers like `user@example.com` were resolved to. Failed lookups
+/// are remembered separately, for a shorter time.
+#[derive(Clone)]
+pub struct WebfingerCache {
+  found: Cache<String, Url>,
+  not_found: Cache<String, ()>,
+}
+
+impl WebfingerCache {
+  fn new(settings: &Settings) -> Self {
+    let config = &settings.federation;
+    WebfingerCache {
+      found: Cache::builder()
+        .max_capacity(WEBFINGER_CACHE_SIZE)
+        .time_to_live(Duration::from_secs(config.webfinger_cache_seconds))
+        .build(),
+      not_found: Cache::builder()
+        .max_capacity(WEBFINGER_CACHE_SIZE)
+        .time_to_live(Duration::from_secs(config.webfinger_negative_cache_seconds))
+        .build(),
+    }
+  }
+
+  /// Returns `Some(None)` if the lookup failed recently, and `None` if it is not cached.
+  pub fn get(&self, identifier: &str) -> Option<Option<Url>> {
+    let identifier = identifier.to_lowercase();
+    if let Some(actor_id) = self.found.get(&identifier) {
+      Some(Some(actor_id))
+    } else if self.not_found.contains_key(&identifier) {
+      Some(None)
+    } else {
+      None
+    }
+  }
+
+  pub async fn insert(&self, identifier: &str, actor_id: Option<Url>) {
+    let identifier = identifier.to_lowercase();
+    match actor_id {
+      Some(actor_id) => {
+        self.not_found.invalidate(&identifier).await;
+        self.found.insert(identifier, actor_id).await;
+      }
+      None => self.not_found.insert(identifier, ()).await,
+    }
+  }
 }
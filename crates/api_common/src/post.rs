@@ -1,7 +1,10 @@
 use lemmy_db_schema::{
   newtypes::{CommentId, CommunityId, DbUrl, LanguageId, PostId, PostReportId},
+  source::reaction::Reaction,
+  CommentSortType,
   ListingType,
   PostFeatureType,
+  ReplyPolicy,
   SortType,
 };
 use lemmy_db_views::structs::{PaginationCursor, PostReportView, PostView};
@@ -28,6 +31,10 @@ pub struct CreatePost {
   pub honeypot: Option<String>,
   pub nsfw: Option<bool>,
   pub language_id: Option<LanguageId>,
+  /// Who can comment on the post, defaults to everyone.
+  pub reply_policy: Option<ReplyPolicy>,
+  /// The sort which is used by default when viewing the comments of the post.
+  pub default_comment_sort_type: Option<CommentSortType>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -100,6 +107,26 @@ pub struct CreatePostLike {
   pub score: i16,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "full", derive(TS))]
+#[cfg_attr(feature = "full", ts(export))]
+/// React to a post with an emoji, or remove the reaction.
+pub struct CreatePostReaction {
+  pub post_id: PostId,
+  /// A unicode emoji, or the shortcode of a custom emoji like `:blobcat:`.
+  pub shortcode: String,
+  /// False to remove a previous reaction with the same emoji.
+  pub add: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "full", derive(TS))]
+#[cfg_attr(feature = "full", ts(export))]
+/// The reactions to a post or comment, after reacting to it.
+pub struct ReactionsResponse {
+  pub reactions: Vec<Reaction>,
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[cfg_attr(feature = "full", derive(TS))]
@@ -114,8 +141,11 @@ pub struct EditPost {
   pub body: Option<String>,
   pub nsfw: Option<bool>,
   pub language_id: Option<LanguageId>,
+  pub reply_policy: Option<ReplyPolicy>,
+  pub default_comment_sort_type: Option<CommentSortType>,
 }
 
+#[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[cfg_attr(feature = "full", derive(TS))]
 #[cfg_attr(feature = "full", ts(export))]
@@ -123,6 +153,8 @@ pub struct EditPost {
 pub struct DeletePost {
   pub post_id: PostId,
   pub deleted: bool,
+  /// An optional note which is shown in place of the deleted post.
+  pub reason: Option<String>,
 }
 
 #[skip_serializing_none]
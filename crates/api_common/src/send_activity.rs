@@ -7,7 +7,7 @@ use crate::{
 use activitypub_federation::config::Data;
 use futures::future::BoxFuture;
 use lemmy_db_schema::{
-  newtypes::{CommunityId, DbUrl, PersonId},
+  newtypes::{CommunityId, DbUrl, InstanceId, PersonId},
   source::{
     comment::Comment,
     community::Community,
@@ -19,6 +19,7 @@ use lemmy_db_schema::{
 use lemmy_db_views::structs::PrivateMessageView;
 use lemmy_utils::error::LemmyResult;
 use once_cell::sync::{Lazy, OnceCell};
+use std::sync::atomic::AtomicUsize;
 use tokio::{
   sync::{
     mpsc,
@@ -35,6 +36,10 @@ type MatchOutgoingActivitiesBoxed =
 /// This static is necessary so that the api_common crates don't need to depend on lemmy_apub
 pub static MATCH_OUTGOING_ACTIVITIES: OnceCell<MatchOutgoingActivitiesBoxed> = OnceCell::new();
 
+/// Number of federation workers running in this process, one for each instance which activities
+/// are sent to. It is updated by lemmy_federate, which lemmy_apub can't depend on.
+pub static ACTIVE_FEDERATION_WORKERS: AtomicUsize = AtomicUsize::new(0);
+
 #[derive(Debug)]
 pub enum SendActivityData {
   CreatePost(Post),
@@ -48,7 +53,10 @@ pub enum SendActivityData {
   DeleteComment(Comment, Person, Community),
   RemoveComment(Comment, Person, Community, Option<String>),
   LikePostOrComment(DbUrl, Person, Community, i16),
+  ReactPostOrComment(DbUrl, Person, Community, String, bool),
   FollowCommunity(Community, Person, bool),
+  AcceptFollower(Community, Person),
+  BlockInstance(Person, InstanceId, bool),
   UpdateCommunity(Person, Community),
   DeleteCommunity(Person, Community, bool),
   RemoveCommunity(Person, Community, Option<String>, bool),
@@ -42,9 +42,11 @@ use lemmy_db_views_moderator::structs::{
   ModTransferCommunityView,
 };
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use serde_with::skip_serializing_none;
 #[cfg(feature = "full")]
 use ts_rs::TS;
+use url::Url;
 
 #[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -258,6 +260,11 @@ pub struct EditSite {
   pub registration_mode: Option<RegistrationMode>,
   /// Whether to email admins for new reports.
   pub reports_email_admins: Option<bool>,
+  /// The max number of http requests to resolve a federated object. Set to 0 to use the value from
+  /// the config file. Raising it above the limit at startup only takes effect after a restart.
+  pub federation_http_fetch_limit: Option<i32>,
+  /// Whether to send votes to and accept votes from other instances.
+  pub federate_votes: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -408,6 +415,27 @@ pub struct GetUnreadRegistrationApplicationCountResponse {
   pub registration_applications: i64,
 }
 
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "full", derive(TS))]
+#[cfg_attr(feature = "full", ts(export))]
+/// Lists the stored activities of an actor, for moderation review. Only for admins.
+pub struct ListActorActivities {
+  #[cfg_attr(feature = "full", ts(type = "string"))]
+  pub actor_id: Url,
+  pub page: Option<i64>,
+  pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "full", derive(TS))]
+#[cfg_attr(feature = "full", ts(export))]
+/// The activities of the actor as json, newest first.
+pub struct ListActorActivitiesResponse {
+  #[cfg_attr(feature = "full", ts(type = "unknown[]"))]
+  pub activities: Vec<Value>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[cfg_attr(feature = "full", derive(TS))]
 #[cfg_attr(feature = "full", ts(export))]
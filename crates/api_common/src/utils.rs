@@ -12,18 +12,20 @@ use lemmy_db_schema::{
   newtypes::{CommunityId, DbUrl, PersonId, PostId},
   source::{
     comment::{Comment, CommentUpdateForm},
-    community::{Community, CommunityModerator, CommunityUpdateForm},
+    community::{Community, CommunityFollower, CommunityModerator, CommunityUpdateForm},
     email_verification::{EmailVerification, EmailVerificationForm},
     instance::Instance,
     local_site::LocalSite,
     local_site_rate_limit::LocalSiteRateLimit,
     password_reset_request::PasswordResetRequest,
-    person::{Person, PersonUpdateForm},
+    person::{Person, PersonFollower, PersonUpdateForm},
     person_block::PersonBlock,
     post::{Post, PostRead},
   },
   traits::Crud,
   utils::DbPool,
+  CommunityMembershipMode,
+  ReplyPolicy,
 };
 use lemmy_db_views::{comment_view::CommentQuery, structs::LocalUserView};
 use lemmy_db_views_actor::structs::{
@@ -155,6 +157,62 @@ pub async fn check_community_user_action(
   Ok(())
 }
 
+/// In a local community which requires an application for membership, only accepted followers
+/// and moderators are members. All other communities are open to everyone.
+pub async fn is_community_member(
+  person_id: PersonId,
+  community: &Community,
+  pool: &mut DbPool<'_>,
+) -> LemmyResult<bool> {
+  if !community.local || community.membership_mode == CommunityMembershipMode::Open {
+    return Ok(true);
+  }
+  Ok(
+    CommunityFollower::is_accepted_follower(pool, community.id, person_id).await?
+      || CommunityView::is_mod_or_admin(pool, person_id, community.id).await?,
+  )
+}
+
+/// Check that the given user is allowed to post and comment in the community, see
+/// [`is_community_member`].
+#[tracing::instrument(skip_all)]
+pub async fn check_community_membership(
+  person_id: PersonId,
+  community: &Community,
+  pool: &mut DbPool<'_>,
+) -> LemmyResult<()> {
+  if is_community_member(person_id, community, pool).await? {
+    Ok(())
+  } else {
+    Err(LemmyErrorType::CommunityMembershipRequired)?
+  }
+}
+
+/// Check that the given user may reply to a post or comment with the given reply policy.
+///
+/// The author can always reply to their own content.
+pub async fn check_reply_policy(
+  person_id: PersonId,
+  parent_creator_id: PersonId,
+  reply_policy: ReplyPolicy,
+  pool: &mut DbPool<'_>,
+) -> LemmyResult<()> {
+  if person_id == parent_creator_id {
+    return Ok(());
+  }
+  let allowed = match reply_policy {
+    ReplyPolicy::Everyone => true,
+    ReplyPolicy::Followers => {
+      PersonFollower::is_follower(pool, parent_creator_id, person_id).await?
+    }
+    ReplyPolicy::Nobody => false,
+  };
+  if !allowed {
+    Err(LemmyErrorType::RepliesNotAllowed)?
+  }
+  Ok(())
+}
+
 async fn check_community_deleted_removed(
   community_id: CommunityId,
   pool: &mut DbPool<'_>,
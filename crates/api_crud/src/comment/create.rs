@@ -6,8 +6,10 @@ use lemmy_api_common::{
   context::LemmyContext,
   send_activity::{ActivityChannel, SendActivityData},
   utils::{
+    check_community_membership,
     check_community_user_action,
     check_post_deleted_or_removed,
+    check_reply_policy,
     generate_local_apub_endpoint,
     get_post,
     local_site_to_slur_regex,
@@ -20,6 +22,7 @@ use lemmy_db_schema::{
     actor_language::CommunityLanguage,
     comment::{Comment, CommentInsertForm, CommentLike, CommentLikeForm, CommentUpdateForm},
     comment_reply::{CommentReply, CommentReplyUpdateForm},
+    community::Community,
     local_site::LocalSite,
     person_mention::{PersonMention, PersonMentionUpdateForm},
   },
@@ -31,7 +34,7 @@ use lemmy_utils::{
   utils::{
     mention::scrape_text_for_mentions,
     slurs::remove_slurs,
-    validation::is_valid_body_field,
+    validation::{check_renderable_content, is_valid_body_field},
   },
 };
 
@@ -50,6 +53,7 @@ pub async fn create_comment(
     &local_site_to_slur_regex(&local_site),
   );
   is_valid_body_field(&Some(content.clone()), false)?;
+  check_renderable_content(&content, context.settings())?;
 
   // Check for a community ban
   let post_id = data.post_id;
@@ -57,12 +61,17 @@ pub async fn create_comment(
   let community_id = post.community_id;
 
   check_community_user_action(&local_user_view.person, community_id, &mut context.pool()).await?;
+  let community = Community::read(&mut context.pool(), community_id).await?;
+  check_community_membership(local_user_view.person.id, &community, &mut context.pool()).await?;
   check_post_deleted_or_removed(&post)?;
 
   // Check if post is locked, no new comments
   if post.locked {
     Err(LemmyErrorType::Locked)?
   }
+  if post.archived {
+    Err(LemmyErrorType::PostIsArchived)?
+  }
 
   // Fetch the parent, if it exists
   let parent_opt = if let Some(parent_id) = data.parent_id {
@@ -80,6 +89,19 @@ pub async fn create_comment(
     check_comment_depth(parent)?;
   }
 
+  // The reply policy of the parent comment applies if there is one, otherwise that of the post
+  let (parent_creator_id, reply_policy) = match parent_opt.as_ref() {
+    Some(parent) => (parent.creator_id, parent.reply_policy),
+    None => (post.creator_id, post.reply_policy),
+  };
+  check_reply_policy(
+    local_user_view.person.id,
+    parent_creator_id,
+    reply_policy,
+    &mut context.pool(),
+  )
+  .await?;
+
   CommunityLanguage::is_allowed_community_language(
     &mut context.pool(),
     data.language_id,
@@ -105,6 +127,7 @@ pub async fn create_comment(
     .post_id(data.post_id)
     .creator_id(local_user_view.person.id)
     .language_id(language_id)
+    .reply_policy(data.reply_policy)
     .build();
 
   // Create the comment
@@ -50,6 +50,7 @@ pub async fn delete_comment(
     comment_id,
     &CommentUpdateForm {
       deleted: Some(deleted),
+      delete_reason: Some(data.reason.clone().filter(|_| deleted)),
       ..Default::default()
     },
   )
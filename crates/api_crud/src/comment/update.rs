@@ -22,7 +22,7 @@ use lemmy_utils::{
   utils::{
     mention::scrape_text_for_mentions,
     slurs::remove_slurs,
-    validation::is_valid_body_field,
+    validation::{check_renderable_content, is_valid_body_field},
   },
 };
 
@@ -63,11 +63,15 @@ pub async fn update_comment(
     .as_ref()
     .map(|c| remove_slurs(c, &local_site_to_slur_regex(&local_site)));
   is_valid_body_field(&content, false)?;
+  if let Some(content) = &content {
+    check_renderable_content(content, context.settings())?;
+  }
 
   let comment_id = data.comment_id;
   let form = CommentUpdateForm {
     content,
     language_id: data.language_id,
+    reply_policy: data.reply_policy,
     updated: Some(Some(naive_now())),
     ..Default::default()
   };
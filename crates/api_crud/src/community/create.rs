@@ -92,6 +92,10 @@ pub async fn create_community(
     .inbox_url(Some(generate_inbox_url(&community_actor_id)?))
     .shared_inbox_url(Some(generate_shared_inbox_url(&community_actor_id)?))
     .posting_restricted_to_mods(data.posting_restricted_to_mods)
+    .membership_mode(data.membership_mode)
+    .default_sort_type(data.default_sort_type)
+    .default_post_nsfw(data.default_post_nsfw)
+    .primary_language_id(data.primary_language_id)
     .instance_id(site_view.site.instance_id)
     .build();
 
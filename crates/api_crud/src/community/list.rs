@@ -32,6 +32,7 @@ pub async fn list_communities(
   let communities = CommunityQuery {
     listing_type,
     show_nsfw,
+    primary_language_id: data.primary_language_id,
     sort,
     local_user: local_user.as_ref(),
     page,
@@ -67,6 +67,10 @@ pub async fn update_community(
     banner,
     nsfw: data.nsfw,
     posting_restricted_to_mods: data.posting_restricted_to_mods,
+    membership_mode: data.membership_mode,
+    default_sort_type: data.default_sort_type,
+    default_post_nsfw: data.default_post_nsfw,
+    primary_language_id: data.primary_language_id,
     updated: Some(Some(naive_now())),
     ..Default::default()
   };
@@ -7,6 +7,7 @@ use lemmy_api_common::{
   request::fetch_site_data,
   send_activity::{ActivityChannel, SendActivityData},
   utils::{
+    check_community_membership,
     check_community_user_action,
     generate_local_apub_endpoint,
     honeypot_check,
@@ -32,7 +33,13 @@ use lemmy_utils::{
   spawn_try_task,
   utils::{
     slurs::{check_slurs, check_slurs_opt},
-    validation::{check_url_scheme, clean_url_params, is_valid_body_field, is_valid_post_title},
+    validation::{
+      check_renderable_content,
+      check_url_scheme,
+      clean_url_params,
+      is_valid_body_field,
+      is_valid_post_title,
+    },
   },
 };
 use tracing::Instrument;
@@ -56,6 +63,8 @@ pub async fn create_post(
   let url = data_url.map(clean_url_params).map(Into::into); // TODO no good way to handle a "clear"
 
   is_valid_post_title(&data.name)?;
+  // the title is always shown, so the post is visible as long as the title is
+  check_renderable_content(&data.name, context.settings())?;
   is_valid_body_field(&data.body, true)?;
   check_url_scheme(&data.url)?;
 
@@ -68,6 +77,7 @@ pub async fn create_post(
 
   let community_id = data.community_id;
   let community = Community::read(&mut context.pool(), community_id).await?;
+  check_community_membership(local_user_view.person.id, &community, &mut context.pool()).await?;
   if community.posting_restricted_to_mods {
     let community_id = data.community_id;
     let is_mod = CommunityView::is_mod_or_admin(
@@ -116,12 +126,14 @@ pub async fn create_post(
     .body(data.body.clone())
     .community_id(data.community_id)
     .creator_id(local_user_view.person.id)
-    .nsfw(data.nsfw)
+    .nsfw(Some(data.nsfw.unwrap_or(community.default_post_nsfw)))
     .embed_title(embed_title)
     .embed_description(embed_description)
     .embed_video_url(embed_video_url)
     .language_id(language_id)
     .thumbnail_url(thumbnail_url)
+    .reply_policy(data.reply_policy)
+    .default_comment_sort_type(data.default_comment_sort_type)
     .build();
 
   let inserted_post = Post::create(&mut context.pool(), &post_form)
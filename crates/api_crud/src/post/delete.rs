@@ -46,6 +46,7 @@ pub async fn delete_post(
     data.post_id,
     &PostUpdateForm {
       deleted: Some(data.deleted),
+      delete_reason: Some(data.reason.clone().filter(|_| data.deleted)),
       ..Default::default()
     },
   )
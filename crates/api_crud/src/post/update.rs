@@ -22,7 +22,13 @@ use lemmy_utils::{
   error::{LemmyError, LemmyErrorExt, LemmyErrorType},
   utils::{
     slurs::check_slurs_opt,
-    validation::{check_url_scheme, clean_url_params, is_valid_body_field, is_valid_post_title},
+    validation::{
+      check_renderable_content,
+      check_url_scheme,
+      clean_url_params,
+      is_valid_body_field,
+      is_valid_post_title,
+    },
   },
 };
 use std::ops::Deref;
@@ -47,6 +53,7 @@ pub async fn update_post(
 
   if let Some(name) = &data.name {
     is_valid_post_title(name)?;
+    check_renderable_content(name, context.settings())?;
   }
 
   is_valid_body_field(&data.body, true)?;
@@ -93,6 +100,8 @@ pub async fn update_post(
     embed_video_url,
     language_id: data.language_id,
     thumbnail_url: Some(thumbnail_url),
+    reply_policy: data.reply_policy,
+    default_comment_sort_type: data.default_comment_sort_type.map(Some),
     updated: Some(Some(naive_now())),
     ..Default::default()
   };
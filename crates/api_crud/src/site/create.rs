@@ -515,6 +515,8 @@ mod tests {
       updated: None,
       registration_mode: site_registration_mode,
       reports_email_admins: false,
+      federation_http_fetch_limit: None,
+      federate_votes: true,
     }
   }
 
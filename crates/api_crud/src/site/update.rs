@@ -90,6 +90,10 @@ pub async fn update_site(
     captcha_enabled: data.captcha_enabled,
     captcha_difficulty: data.captcha_difficulty.clone(),
     reports_email_admins: data.reports_email_admins,
+    federation_http_fetch_limit: data
+      .federation_http_fetch_limit
+      .map(|limit| (limit > 0).then_some(limit)),
+    federate_votes: data.federate_votes,
     ..Default::default()
   };
 
@@ -514,6 +518,8 @@ mod tests {
       updated: None,
       registration_mode: site_registration_mode,
       reports_email_admins: false,
+      federation_http_fetch_limit: None,
+      federate_votes: true,
     }
   }
 
@@ -572,6 +578,8 @@ mod tests {
       taglines: None,
       registration_mode: site_registration_mode,
       reports_email_admins: None,
+      federation_http_fetch_limit: None,
+      federate_votes: None,
     }
   }
 }
@@ -0,0 +1,245 @@
+use crate::{
+  activities::{generate_activity_id, send_lemmy_activity, verify_person},
+  fetcher::{site_or_community_or_user::SiteOrCommunityOrUser, user_or_community::UserOrCommunity},
+  insert_received_activity,
+  objects::{instance::ApubSite, person::ApubPerson},
+  protocol::activities::block::block_actor::BlockActor,
+};
+use activitypub_federation::{
+  config::Data,
+  kinds::activity::BlockType,
+  protocol::verification::verify_urls_match,
+  traits::{ActivityHandler, Actor},
+};
+use lemmy_api_common::context::LemmyContext;
+use lemmy_db_schema::{
+  source::{
+    activity::ActivitySendTargets,
+    community::{CommunityFollower, CommunityFollowerForm},
+    community_block::{CommunityBlock, CommunityBlockForm},
+    instance_block::{InstanceBlock, InstanceBlockForm},
+    person::{PersonFollower, PersonFollowerForm},
+    person_block::{PersonBlock, PersonBlockForm},
+  },
+  traits::{Blockable, Followable},
+};
+use lemmy_utils::error::LemmyError;
+use url::Url;
+
+impl BlockActor {
+  pub(in crate::activities::block) fn new(
+    actor: &ApubPerson,
+    target: &ApubSite,
+    context: &Data<LemmyContext>,
+  ) -> Result<BlockActor, LemmyError> {
+    Ok(BlockActor {
+      actor: actor.id().into(),
+      to: Some([target.id().into()]),
+      object: target.id().into(),
+      kind: BlockType::Block,
+      id: generate_activity_id(
+        BlockType::Block,
+        &context.settings().get_protocol_and_hostname(),
+      )?,
+      target: None,
+    })
+  }
+
+  #[tracing::instrument(skip_all)]
+  pub async fn send(
+    actor: &ApubPerson,
+    target: &ApubSite,
+    context: &Data<LemmyContext>,
+  ) -> Result<(), LemmyError> {
+    let block = BlockActor::new(actor, target, context)?;
+    let inbox = ActivitySendTargets::to_inbox(target.shared_inbox_or_inbox());
+    send_lemmy_activity(context, block, actor, inbox, true).await
+  }
+}
+
+#[async_trait::async_trait]
+impl ActivityHandler for BlockActor {
+  type DataType = LemmyContext;
+  type Error = LemmyError;
+
+  fn id(&self) -> &Url {
+    &self.id
+  }
+
+  fn actor(&self) -> &Url {
+    self.actor.inner()
+  }
+
+  #[tracing::instrument(skip_all)]
+  async fn verify(&self, context: &Data<LemmyContext>) -> Result<(), LemmyError> {
+    insert_received_activity(&self.id, context).await?;
+    verify_person(&self.actor, context).await?;
+    if let Some(to) = &self.to {
+      verify_urls_match(to[0].inner(), self.object.inner())?;
+    }
+    Ok(())
+  }
+
+  #[tracing::instrument(skip_all)]
+  async fn receive(self, context: &Data<LemmyContext>) -> Result<(), LemmyError> {
+    let person = self.actor.dereference(context).await?;
+    // Blocks are only sent to the blocked actor, so it has to be one of ours
+    let object = self.object.dereference_local(context).await?;
+
+    // Also remove an existing follow, so that we don't keep delivering to the blocking user
+    match object {
+      SiteOrCommunityOrUser::Site(site) => {
+        let form = InstanceBlockForm {
+          person_id: person.id,
+          instance_id: site.instance_id,
+        };
+        InstanceBlock::block(&mut context.pool(), &form).await?;
+      }
+      SiteOrCommunityOrUser::UserOrCommunity(UserOrCommunity::User(u)) => {
+        let form = PersonBlockForm {
+          person_id: person.id,
+          target_id: u.id,
+        };
+        PersonBlock::block(&mut context.pool(), &form).await?;
+        let form = PersonFollowerForm {
+          person_id: u.id,
+          follower_id: person.id,
+          pending: false,
+        };
+        PersonFollower::unfollow(&mut context.pool(), &form).await?;
+      }
+      SiteOrCommunityOrUser::UserOrCommunity(UserOrCommunity::Community(c)) => {
+        let form = CommunityBlockForm {
+          person_id: person.id,
+          community_id: c.id,
+        };
+        CommunityBlock::block(&mut context.pool(), &form).await?;
+        let form = CommunityFollowerForm {
+          community_id: c.id,
+          person_id: person.id,
+          pending: false,
+        };
+        CommunityFollower::unfollow(&mut context.pool(), &form).await?;
+      }
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::{
+    objects::{
+      community::tests::parse_lemmy_community,
+      person::tests::parse_lemmy_person,
+      tests::init_context,
+    },
+    protocol::activities::block::undo_block_actor::UndoBlockActor,
+  };
+  use activitypub_federation::kinds::activity::UndoType;
+  use lemmy_db_schema::{
+    source::{community::Community, person::Person, site::Site},
+    traits::Crud,
+  };
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_receive_block_instance() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let block = BlockActor {
+      actor: person.actor_id.clone().into(),
+      to: Some([site.actor_id.clone().into()]),
+      object: site.actor_id.clone().into(),
+      kind: BlockType::Block,
+      id: Url::parse("https://enterprise.lemmy.ml/activities/block/2").unwrap(),
+      target: None,
+    };
+    let form = InstanceBlockForm {
+      person_id: person.id,
+      instance_id: site.instance_id,
+    };
+
+    // the block is recorded for the user
+    block.clone().receive(&context).await.unwrap();
+    let removed = InstanceBlock::unblock(&mut context.pool(), &form)
+      .await
+      .unwrap();
+    assert_eq!(1, removed);
+
+    // undo removes the block again
+    block.clone().receive(&context).await.unwrap();
+    let undo = UndoBlockActor {
+      actor: person.actor_id.clone().into(),
+      to: None,
+      object: block,
+      kind: UndoType::Undo,
+      id: Url::parse("https://enterprise.lemmy.ml/activities/undo/2").unwrap(),
+    };
+    undo.receive(&context).await.unwrap();
+    let removed = InstanceBlock::unblock(&mut context.pool(), &form)
+      .await
+      .unwrap();
+    assert_eq!(0, removed);
+
+    Person::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_receive_block_community() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+    let block = BlockActor {
+      actor: person.actor_id.clone().into(),
+      to: None,
+      object: community.actor_id.clone().into(),
+      kind: BlockType::Block,
+      id: Url::parse("https://enterprise.lemmy.ml/activities/block/1").unwrap(),
+      target: None,
+    };
+    let form = CommunityBlockForm {
+      person_id: person.id,
+      community_id: community.id,
+    };
+
+    block.clone().receive(&context).await.unwrap();
+    let removed = CommunityBlock::unblock(&mut context.pool(), &form)
+      .await
+      .unwrap();
+    assert_eq!(1, removed);
+
+    // undo removes the block again
+    block.clone().receive(&context).await.unwrap();
+    let undo = UndoBlockActor {
+      actor: person.actor_id.clone().into(),
+      to: None,
+      object: block,
+      kind: UndoType::Undo,
+      id: Url::parse("https://enterprise.lemmy.ml/activities/undo/1").unwrap(),
+    };
+    undo.receive(&context).await.unwrap();
+    let removed = CommunityBlock::unblock(&mut context.pool(), &form)
+      .await
+      .unwrap();
+    assert_eq!(0, removed);
+
+    Person::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Community::delete(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+}
@@ -1,6 +1,6 @@
 use crate::{
   activities::{
-    block::{generate_cc, SiteOrCommunity},
+    block::{apply_remote_ban_policy, generate_cc, SiteOrCommunity},
     community::send_activity_in_community,
     generate_activity_id,
     send_lemmy_activity,
@@ -21,10 +21,7 @@ use activitypub_federation::{
 };
 use anyhow::anyhow;
 use chrono::{DateTime, Utc};
-use lemmy_api_common::{
-  context::LemmyContext,
-  utils::{remove_user_data, remove_user_data_in_community},
-};
+use lemmy_api_common::{context::LemmyContext, utils::remove_user_data_in_community};
 use lemmy_db_schema::{
   source::{
     activity::ActivitySendTargets,
@@ -165,9 +162,13 @@ impl ActivityHandler for BlockUser {
           },
         )
         .await?;
-        if self.remove_data.unwrap_or(false) {
-          remove_user_data(blocked_person.id, context).await?;
-        }
+        apply_remote_ban_policy(
+          blocked_person.id,
+          self.remove_data.unwrap_or(false),
+          context.settings().federation.remote_ban_content_policy,
+          context,
+        )
+        .await?;
 
         // write mod log
         let form = ModBanForm {
@@ -1,7 +1,12 @@
 use crate::{
-  objects::{community::ApubCommunity, instance::ApubSite},
+  objects::{community::ApubCommunity, instance::ApubSite, person::ApubPerson},
   protocol::{
-    activities::block::{block_user::BlockUser, undo_block_user::UndoBlockUser},
+    activities::block::{
+      block_actor::BlockActor,
+      block_user::BlockUser,
+      undo_block_actor::UndoBlockActor,
+      undo_block_user::UndoBlockUser,
+    },
     objects::{group::Group, instance::Instance},
   },
 };
@@ -15,20 +20,25 @@ use lemmy_api_common::{
   community::BanFromCommunity,
   context::LemmyContext,
   person::BanPerson,
-  utils::check_expire_time,
+  utils::{check_expire_time, remove_user_data},
 };
 use lemmy_db_schema::{
-  newtypes::CommunityId,
+  newtypes::{CommunityId, InstanceId, PersonId},
   source::{community::Community, person::Person, site::Site},
   traits::Crud,
   utils::DbPool,
 };
 use lemmy_db_views::structs::SiteView;
-use lemmy_utils::error::{LemmyError, LemmyResult};
+use lemmy_utils::{
+  error::{LemmyError, LemmyResult},
+  settings::structs::RemoteBanContentPolicy,
+};
 use serde::Deserialize;
 use url::Url;
 
+pub mod block_actor;
 pub mod block_user;
+pub mod undo_block_actor;
 pub mod undo_block_user;
 
 #[derive(Clone, Debug)]
@@ -203,3 +213,47 @@ pub(crate) async fn send_ban_from_community(
     .await
   }
 }
+
+/// Removes the content of a remote user who was banned by their home instance, depending on the
+/// local policy and on whether the home instance asked to remove it.
+pub(crate) async fn apply_remote_ban_policy(
+  person_id: PersonId,
+  remove_data: bool,
+  policy: RemoteBanContentPolicy,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<()> {
+  let remove = match policy {
+    RemoteBanContentPolicy::FollowHomeInstance => remove_data,
+    RemoteBanContentPolicy::Remove => true,
+    RemoteBanContentPolicy::Keep => false,
+  };
+  if remove {
+    remove_user_data(person_id, context).await?;
+  }
+  Ok(())
+}
+
+/// Sends a user's block of a remote instance to its instance actor, so that the instance knows
+/// about the block. Blocks of single users or communities are not federated.
+pub(crate) async fn send_block_instance(
+  actor: Person,
+  instance_id: InstanceId,
+  block: bool,
+  context: Data<LemmyContext>,
+) -> LemmyResult<()> {
+  // Instances without a site (e.g. other software) don't have an instance actor to send to
+  let Some(site) = Site::read_from_instance_id(&mut context.pool(), instance_id).await? else {
+    return Ok(());
+  };
+  let local_domain = context.settings().get_hostname_without_port()?;
+  if site.actor_id.domain() == Some(local_domain.as_str()) {
+    return Ok(());
+  }
+  let actor: ApubPerson = actor.into();
+  let site: ApubSite = site.into();
+  if block {
+    BlockActor::send(&actor, &site, &context).await
+  } else {
+    UndoBlockActor::send(&actor, &site, &context).await
+  }
+}
@@ -0,0 +1,106 @@
+use crate::{
+  activities::{generate_activity_id, send_lemmy_activity, verify_person},
+  fetcher::{site_or_community_or_user::SiteOrCommunityOrUser, user_or_community::UserOrCommunity},
+  insert_received_activity,
+  objects::{instance::ApubSite, person::ApubPerson},
+  protocol::activities::block::{block_actor::BlockActor, undo_block_actor::UndoBlockActor},
+};
+use activitypub_federation::{
+  config::Data,
+  kinds::activity::UndoType,
+  protocol::verification::verify_urls_match,
+  traits::{ActivityHandler, Actor},
+};
+use lemmy_api_common::context::LemmyContext;
+use lemmy_db_schema::{
+  source::{
+    activity::ActivitySendTargets,
+    community_block::{CommunityBlock, CommunityBlockForm},
+    instance_block::{InstanceBlock, InstanceBlockForm},
+    person_block::{PersonBlock, PersonBlockForm},
+  },
+  traits::Blockable,
+};
+use lemmy_utils::error::LemmyError;
+use url::Url;
+
+impl UndoBlockActor {
+  #[tracing::instrument(skip_all)]
+  pub async fn send(
+    actor: &ApubPerson,
+    target: &ApubSite,
+    context: &Data<LemmyContext>,
+  ) -> Result<(), LemmyError> {
+    let object = BlockActor::new(actor, target, context)?;
+    let undo = UndoBlockActor {
+      actor: actor.id().into(),
+      to: Some([target.id().into()]),
+      object,
+      kind: UndoType::Undo,
+      id: generate_activity_id(
+        UndoType::Undo,
+        &context.settings().get_protocol_and_hostname(),
+      )?,
+    };
+    let inbox = ActivitySendTargets::to_inbox(target.shared_inbox_or_inbox());
+    send_lemmy_activity(context, undo, actor, inbox, true).await
+  }
+}
+
+#[async_trait::async_trait]
+impl ActivityHandler for UndoBlockActor {
+  type DataType = LemmyContext;
+  type Error = LemmyError;
+
+  fn id(&self) -> &Url {
+    &self.id
+  }
+
+  fn actor(&self) -> &Url {
+    self.actor.inner()
+  }
+
+  #[tracing::instrument(skip_all)]
+  async fn verify(&self, context: &Data<LemmyContext>) -> Result<(), LemmyError> {
+    insert_received_activity(&self.id, context).await?;
+    verify_urls_match(self.actor.inner(), self.object.actor.inner())?;
+    verify_person(&self.actor, context).await?;
+    self.object.verify(context).await?;
+    if let Some(to) = &self.to {
+      verify_urls_match(to[0].inner(), self.object.object.inner())?;
+    }
+    Ok(())
+  }
+
+  #[tracing::instrument(skip_all)]
+  async fn receive(self, context: &Data<LemmyContext>) -> Result<(), LemmyError> {
+    let person = self.actor.dereference(context).await?;
+    let object = self.object.object.dereference_local(context).await?;
+
+    match object {
+      SiteOrCommunityOrUser::Site(site) => {
+        let form = InstanceBlockForm {
+          person_id: person.id,
+          instance_id: site.instance_id,
+        };
+        InstanceBlock::unblock(&mut context.pool(), &form).await?;
+      }
+      SiteOrCommunityOrUser::UserOrCommunity(UserOrCommunity::User(u)) => {
+        let form = PersonBlockForm {
+          person_id: person.id,
+          target_id: u.id,
+        };
+        PersonBlock::unblock(&mut context.pool(), &form).await?;
+      }
+      SiteOrCommunityOrUser::UserOrCommunity(UserOrCommunity::Community(c)) => {
+        let form = CommunityBlockForm {
+          person_id: person.id,
+          community_id: c.id,
+        };
+        CommunityBlock::unblock(&mut context.pool(), &form).await?;
+      }
+    }
+
+    Ok(())
+  }
+}
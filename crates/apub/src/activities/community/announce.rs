@@ -25,6 +25,7 @@ use lemmy_api_common::context::LemmyContext;
 use lemmy_db_schema::source::{activity::ActivitySendTargets, community::CommunityFollower};
 use lemmy_utils::error::{LemmyError, LemmyErrorType, LemmyResult};
 use serde_json::Value;
+use tracing::debug;
 use url::Url;
 
 #[async_trait::async_trait]
@@ -153,6 +154,11 @@ impl ActivityHandler for AnnounceActivity {
 
   #[tracing::instrument(skip_all)]
   async fn receive(self, context: &Data<Self::DataType>) -> Result<(), LemmyError> {
+    // The same activity is often announced by multiple communities, skip it before fetching
+    if context.received_activities().contains_key(self.object.id()) {
+      debug!("Ignoring already received activity {}", self.object.id());
+      return Ok(());
+    }
     let object: AnnouncableActivities = self.object.object(context).await?.try_into()?;
 
     // This is only for sending, not receiving so we reject it.
@@ -211,3 +211,142 @@ pub(crate) async fn send_feature_post(
     CollectionRemove::send_remove_featured_post(&community, &post, &actor, &context).await
   }
 }
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::objects::{
+    community::tests::parse_lemmy_community,
+    person::tests::parse_lemmy_person,
+    tests::init_context,
+  };
+  use activitypub_federation::kinds::activity::RemoveType;
+  use lemmy_db_schema::source::{
+    community::CommunityUpdateForm,
+    instance::Instance,
+    person::PersonInsertForm,
+    post::PostInsertForm,
+    site::Site,
+  };
+  use serial_test::serial;
+
+  async fn is_mod(person: &Person, community: &Community, context: &Data<LemmyContext>) -> bool {
+    CommunityModerator::get_person_moderated_communities(&mut context.pool(), person.id)
+      .await
+      .unwrap()
+      .contains(&community.id)
+  }
+
+  async fn is_featured(post: &Post, context: &Data<LemmyContext>) -> bool {
+    Post::read(&mut context.pool(), post.id)
+      .await
+      .unwrap()
+      .featured_community
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_receive_collection_add_and_remove() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+    let moderators_url = Url::parse("https://enterprise.lemmy.ml/c/tenforward/moderators").unwrap();
+    let form = CommunityUpdateForm {
+      moderators_url: Some(moderators_url.clone().into()),
+      ..Default::default()
+    };
+    let community: ApubCommunity = Community::update(&mut context.pool(), community.id, &form)
+      .await
+      .unwrap()
+      .into();
+    let featured_url: Url = community.featured_url.clone().unwrap().into();
+    let form = PostInsertForm::builder()
+      .name("featured post".to_string())
+      .creator_id(person.id)
+      .community_id(community.id)
+      .ap_id(Some(
+        Url::parse("https://enterprise.lemmy.ml/post/1")
+          .unwrap()
+          .into(),
+      ))
+      .build();
+    let post = Post::create(&mut context.pool(), &form).await.unwrap();
+
+    let add = |object: Url, target: Url, id: &str| CollectionAdd {
+      actor: person.actor_id.clone().into(),
+      to: vec![public()],
+      object,
+      target,
+      cc: vec![community.id()],
+      kind: AddType::Add,
+      id: Url::parse(&format!("https://enterprise.lemmy.ml/activities/add/{id}")).unwrap(),
+      audience: Some(community.id().into()),
+    };
+    let remove = |object: Url, target: Url, id: &str| CollectionRemove {
+      actor: person.actor_id.clone().into(),
+      to: vec![public()],
+      object,
+      target,
+      cc: vec![community.id()],
+      kind: RemoveType::Remove,
+      id: Url::parse(&format!(
+        "https://enterprise.lemmy.ml/activities/remove/{id}"
+      ))
+      .unwrap(),
+      audience: Some(community.id().into()),
+    };
+
+    // mods are added and removed via the moderators collection
+    let activity = add(person.id(), moderators_url.clone(), "1");
+    activity.verify(&context).await.unwrap();
+    activity.receive(&context).await.unwrap();
+    assert!(is_mod(&person, &community, &context).await);
+    let activity = remove(person.id(), moderators_url.clone(), "1");
+    activity.verify(&context).await.unwrap();
+    activity.receive(&context).await.unwrap();
+    assert!(!is_mod(&person, &community, &context).await);
+
+    // posts are featured and unfeatured via the featured collection
+    let activity = add(post.ap_id.clone().into(), featured_url.clone(), "2");
+    activity.verify(&context).await.unwrap();
+    activity.receive(&context).await.unwrap();
+    assert!(is_featured(&post, &context).await);
+    let activity = remove(post.ap_id.clone().into(), featured_url.clone(), "2");
+    activity.verify(&context).await.unwrap();
+    activity.receive(&context).await.unwrap();
+    assert!(!is_featured(&post, &context).await);
+
+    // users from other instances who aren't mods can't change the collections
+    let other_instance = Instance::read_or_create(&mut context.pool(), "example.com".to_string())
+      .await
+      .unwrap();
+    let form = PersonInsertForm::builder()
+      .name("other".to_string())
+      .public_key("pubkey".to_string())
+      .instance_id(other_instance.id)
+      .actor_id(Some(
+        Url::parse("https://example.com/u/other").unwrap().into(),
+      ))
+      .local(Some(false))
+      .build();
+    let other = Person::create(&mut context.pool(), &form).await.unwrap();
+    let mut activity = add(post.ap_id.clone().into(), featured_url, "3");
+    activity.actor = other.actor_id.clone().into();
+    assert!(activity.verify(&context).await.is_err());
+
+    Instance::delete(&mut context.pool(), other_instance.id)
+      .await
+      .unwrap();
+    Post::delete(&mut context.pool(), post.id).await.unwrap();
+    Person::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Community::delete(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+}
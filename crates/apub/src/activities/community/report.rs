@@ -1,5 +1,10 @@
 use crate::{
-  activities::{generate_activity_id, send_lemmy_activity, verify_person_in_community},
+  activities::{
+    deletion::DeletableObjects,
+    generate_activity_id,
+    send_lemmy_activity,
+    verify_person_in_community,
+  },
   insert_received_activity,
   objects::{community::ApubCommunity, person::ApubPerson},
   protocol::{activities::community::report::Report, InCommunity},
@@ -11,18 +16,21 @@ use activitypub_federation::{
   kinds::activity::FlagType,
   traits::{ActivityHandler, Actor},
 };
-use lemmy_api_common::context::LemmyContext;
+use lemmy_api_common::{context::LemmyContext, utils::send_new_report_email_to_admins};
 use lemmy_db_schema::{
   source::{
     activity::ActivitySendTargets,
     comment_report::{CommentReport, CommentReportForm},
     community::Community,
+    local_site::LocalSite,
     person::Person,
+    post::Post,
     post_report::{PostReport, PostReportForm},
   },
-  traits::Reportable,
+  traits::{Crud, Reportable},
 };
-use lemmy_utils::error::LemmyError;
+use lemmy_utils::error::{LemmyError, LemmyErrorType};
+use tracing::debug;
 use url::Url;
 
 impl Report {
@@ -76,6 +84,10 @@ impl ActivityHandler for Report {
   async fn verify(&self, context: &Data<Self::DataType>) -> Result<(), LemmyError> {
     insert_received_activity(&self.id, context).await?;
     let community = self.community(context).await?;
+    // reports are handled by the moderators of the community's instance
+    if !community.local {
+      Err(LemmyErrorType::ObjectNotLocal)?
+    }
     verify_person_in_community(&self.actor, &community, context).await?;
     Ok(())
   }
@@ -83,8 +95,19 @@ impl ActivityHandler for Report {
   #[tracing::instrument(skip_all)]
   async fn receive(self, context: &Data<Self::DataType>) -> Result<(), LemmyError> {
     let actor = self.actor.dereference(context).await?;
-    match self.object.dereference(context).await? {
-      PostOrComment::Post(post) => {
+    let community = self.community(context).await?;
+
+    // Objects of our communities are always known, so there is no need to fetch unknown objects.
+    // They were either never federated to us or don't belong to this community.
+    let Ok(object) = DeletableObjects::read_from_db(self.object.inner(), context).await else {
+      debug!("Ignoring report of unknown object {}", self.object.inner());
+      return Ok(());
+    };
+    let reported_person_id = match object {
+      DeletableObjects::Post(post) => {
+        if post.community_id != community.id {
+          Err(LemmyErrorType::InvalidCommunity)?
+        }
         let report_form = PostReportForm {
           creator_id: actor.id,
           post_id: post.id,
@@ -94,8 +117,13 @@ impl ActivityHandler for Report {
           original_post_body: post.body.clone(),
         };
         PostReport::report(&mut context.pool(), &report_form).await?;
+        post.creator_id
       }
-      PostOrComment::Comment(comment) => {
+      DeletableObjects::Comment(comment) => {
+        let post = Post::read(&mut context.pool(), comment.post_id).await?;
+        if post.community_id != community.id {
+          Err(LemmyErrorType::InvalidCommunity)?
+        }
         let report_form = CommentReportForm {
           creator_id: actor.id,
           comment_id: comment.id,
@@ -103,8 +131,100 @@ impl ActivityHandler for Report {
           reason: self.summary.clone(),
         };
         CommentReport::report(&mut context.pool(), &report_form).await?;
+        comment.creator_id
+      }
+      // only posts and comments can be reported to a community
+      DeletableObjects::Community(_) | DeletableObjects::PrivateMessage(_) => {
+        debug!("Ignoring report of {}", self.object.inner());
+        return Ok(());
       }
     };
+
+    // Email the admins, same as for reports by local users
+    let local_site = LocalSite::read(&mut context.pool()).await?;
+    if local_site.reports_email_admins {
+      let reported_person = Person::read(&mut context.pool(), reported_person_id).await?;
+      send_new_report_email_to_admins(
+        &actor.name,
+        &reported_person.name,
+        &mut context.pool(),
+        context.settings(),
+      )
+      .await?;
+    }
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::{
+    objects::{
+      community::tests::parse_lemmy_community,
+      person::tests::parse_lemmy_person,
+      post::ApubPost,
+      tests::init_context,
+    },
+    protocol::tests::file_to_json_object,
+  };
+  use activitypub_federation::traits::Object;
+  use lemmy_db_schema::source::site::Site;
+  use lemmy_db_views::structs::PostReportView;
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_receive_report() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+    let json = file_to_json_object("assets/lemmy/objects/page.json").unwrap();
+    let post = ApubPost::from_json(json, &context).await.unwrap();
+    let report = |object: &str| Report {
+      actor: person.actor_id.clone().into(),
+      to: [community.actor_id.clone().into()],
+      object: ObjectId::parse(object).unwrap(),
+      summary: "spam".to_string(),
+      kind: FlagType::Flag,
+      id: Url::parse("https://ds9.lemmy.ml/activities/flag/1").unwrap(),
+      audience: None,
+    };
+
+    // a known post creates a report
+    let context2 = context.reset_request_count();
+    report(post.ap_id.inner().as_str())
+      .receive(&context2)
+      .await
+      .unwrap();
+    let count =
+      PostReportView::get_report_count(&mut context.pool(), person.id, true, Some(community.id))
+        .await
+        .unwrap();
+    assert_eq!(1, count);
+
+    // an unknown object is ignored without fetching it
+    report("https://enterprise.lemmy.ml/post/404")
+      .receive(&context2)
+      .await
+      .unwrap();
+    let count =
+      PostReportView::get_report_count(&mut context.pool(), person.id, true, Some(community.id))
+        .await
+        .unwrap();
+    assert_eq!(1, count);
+    assert_eq!(0, context2.request_count());
+
+    Post::delete(&mut context.pool(), post.id).await.unwrap();
+    Person::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Community::delete(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+}
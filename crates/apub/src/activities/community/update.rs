@@ -85,9 +85,83 @@ impl ActivityHandler for UpdateCommunity {
   async fn receive(self, context: &Data<Self::DataType>) -> Result<(), LemmyError> {
     let community = self.community(context).await?;
 
-    let community_update_form = self.object.into_update_form();
+    let primary_language_id = self.object.primary_language_id(&mut context.pool()).await?;
+    // remote moderators can also send updates, but only the community's instance knows how many
+    // users are active
+    if self.actor.inner().domain() == community.actor_id.inner().domain() {
+      self
+        .object
+        .store_active_users(&community, &mut context.pool())
+        .await?;
+    }
+    let community_update_form = self.object.into_update_form(primary_language_id);
 
     Community::update(&mut context.pool(), community.id, &community_update_form).await?;
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::objects::{
+    community::tests::parse_lemmy_community,
+    person::tests::parse_lemmy_person,
+    tests::init_context,
+  };
+  use lemmy_db_schema::source::site::Site;
+  use serial_test::serial;
+  use uuid::Uuid;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_receive_update_community() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+    assert!(community.description.is_some());
+    assert!(!community.nsfw);
+
+    let mut group = community.clone().into_json(&context).await.unwrap();
+    group.name = Some("Updated Title".to_string());
+    group.sensitive = Some(true);
+    group.summary = None;
+    group.source = None;
+    let update = UpdateCommunity {
+      actor: person.id().into(),
+      to: vec![public()],
+      object: Box::new(group),
+      cc: vec![community.id()],
+      kind: UpdateType::Update,
+      // random id, as received activities are stored permanently
+      id: Url::parse(&format!(
+        "https://enterprise.lemmy.ml/activities/update/{}",
+        Uuid::new_v4()
+      ))
+      .unwrap(),
+      audience: Some(community.id().into()),
+    };
+    update.verify(&context).await.unwrap();
+    update.receive(&context).await.unwrap();
+
+    let updated = Community::read(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    assert_eq!("Updated Title", updated.title);
+    assert!(updated.nsfw);
+    // the description was removed
+    assert_eq!(None, updated.description);
+    assert_eq!(context.request_count(), 0);
+
+    Person::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Community::delete(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+}
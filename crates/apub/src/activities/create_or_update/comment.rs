@@ -2,6 +2,7 @@ use crate::{
   activities::{
     check_community_deleted_or_removed,
     community::send_activity_in_community,
+    deletion::verify_object_not_deleted,
     generate_activity_id,
     verify_is_public,
     verify_person_in_community,
@@ -124,6 +125,9 @@ impl ActivityHandler for CreateOrUpdateNote {
     verify_domains_match(self.actor.inner(), self.object.id.inner())?;
     check_community_deleted_or_removed(&community)?;
     check_post_deleted_or_removed(&post)?;
+    if self.kind == CreateOrUpdateType::Update {
+      verify_object_not_deleted(self.object.id.inner(), context).await?;
+    }
 
     ApubComment::verify(&self.object, self.actor.inner(), context).await?;
     Ok(())
@@ -144,19 +148,24 @@ impl ActivityHandler for CreateOrUpdateNote {
       }
     }
 
+    // the vote counts from the origin instance are stored by from_json, and already include the
+    // author's vote
+    let federated_votes = self.object.upvotes.is_some() && self.object.downvotes.is_some();
     let comment = ApubComment::from_json(self.object, context).await?;
 
-    // author likes their own comment by default
-    let like_form = CommentLikeForm {
-      comment_id: comment.id,
-      post_id: comment.post_id,
-      person_id: comment.creator_id,
-      score: 1,
-    };
-    CommentLike::like(&mut context.pool(), &like_form).await?;
-
-    // Calculate initial hot_rank
-    CommentAggregates::update_hot_rank(&mut context.pool(), comment.id).await?;
+    if !federated_votes {
+      // author likes their own comment by default
+      let like_form = CommentLikeForm {
+        comment_id: comment.id,
+        post_id: comment.post_id,
+        person_id: comment.creator_id,
+        score: 1,
+      };
+      CommentLike::like(&mut context.pool(), &like_form).await?;
+
+      // Calculate initial hot_rank
+      CommentAggregates::update_hot_rank(&mut context.pool(), comment.id).await?;
+    }
 
     let do_send_email = self.kind == CreateOrUpdateType::Create;
     let post_id = comment.post_id;
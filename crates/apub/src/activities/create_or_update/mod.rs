@@ -1,3 +1,4 @@
 pub mod comment;
+pub mod poll_vote;
 pub mod post;
 pub mod private_message;
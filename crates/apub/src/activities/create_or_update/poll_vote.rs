@@ -0,0 +1,67 @@
+use crate::{
+  activities::verify_person,
+  insert_received_activity,
+  protocol::activities::create_or_update::poll_vote::CreatePollVote,
+};
+use activitypub_federation::{
+  config::Data,
+  protocol::verification::{verify_domains_match, verify_urls_match},
+  traits::ActivityHandler,
+};
+use chrono::Utc;
+use lemmy_api_common::context::LemmyContext;
+use lemmy_db_schema::source::poll::PollVoteForm;
+use lemmy_utils::error::{LemmyError, LemmyErrorType};
+use url::Url;
+
+/// Votes are only sent to the instance of the poll, so only polls of local posts can receive them.
+#[async_trait::async_trait]
+impl ActivityHandler for CreatePollVote {
+  type DataType = LemmyContext;
+  type Error = LemmyError;
+
+  fn id(&self) -> &Url {
+    &self.id
+  }
+
+  fn actor(&self) -> &Url {
+    self.actor.inner()
+  }
+
+  #[tracing::instrument(skip_all)]
+  async fn verify(&self, context: &Data<Self::DataType>) -> Result<(), LemmyError> {
+    insert_received_activity(&self.id, context).await?;
+    verify_person(&self.actor, context).await?;
+    verify_domains_match(self.actor.inner(), &self.object.id)?;
+    verify_urls_match(self.actor.inner(), self.object.attributed_to.inner())?;
+
+    let poll = self.object.in_reply_to.dereference_local(context).await?;
+    if !poll.post.local {
+      Err(LemmyErrorType::CouldntFindObject)?
+    }
+    if poll.poll.end_time.is_some_and(|end| end < Utc::now()) {
+      Err(LemmyErrorType::PollEnded)?
+    }
+    if !poll.options.iter().any(|o| o.name == self.object.name) {
+      Err(LemmyErrorType::PollOptionNotFound)?
+    }
+    Ok(())
+  }
+
+  #[tracing::instrument(skip_all)]
+  async fn receive(self, context: &Data<Self::DataType>) -> Result<(), LemmyError> {
+    let poll = self.object.in_reply_to.dereference_local(context).await?;
+    let voter = self.actor.dereference(context).await?;
+    let option = poll
+      .options
+      .iter()
+      .find(|o| o.name == self.object.name)
+      .ok_or(LemmyErrorType::PollOptionNotFound)?;
+    let form = PollVoteForm {
+      poll_option_id: option.id,
+      person_id: voter.id,
+    };
+    poll.poll.vote(&mut context.pool(), &form).await?;
+    Ok(())
+  }
+}
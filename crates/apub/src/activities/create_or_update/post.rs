@@ -2,6 +2,7 @@ use crate::{
   activities::{
     check_community_deleted_or_removed,
     community::send_activity_in_community,
+    deletion::verify_object_not_deleted,
     generate_activity_id,
     verify_is_public,
     verify_mod_action,
@@ -125,6 +126,7 @@ impl ActivityHandler for CreateOrUpdatePage {
         }
       }
       CreateOrUpdateType::Update => {
+        verify_object_not_deleted(self.object.id.inner(), context).await?;
         let is_mod_action = self.object.is_mod_action(context).await?;
         if is_mod_action {
           verify_mod_action(&self.actor, &community, context).await?;
@@ -22,6 +22,7 @@ use lemmy_db_schema::{
       ModRemovePostForm,
     },
     post::{Post, PostUpdateForm},
+    private_message::{PrivateMessage, PrivateMessageUpdateForm},
   },
   traits::Crud,
 };
@@ -66,7 +67,14 @@ impl ActivityHandler for Delete {
       )
       .await
     } else {
-      receive_delete_action(self.object.id(), &self.actor, true, context).await
+      receive_delete_action(
+        self.object.id(),
+        &self.actor,
+        true,
+        self.delete_reason,
+        context,
+      )
+      .await
     }
   }
 }
@@ -85,6 +93,11 @@ impl Delete {
       &context.settings().get_protocol_and_hostname(),
     )?;
     let cc: Option<Url> = community.map(|c| c.actor_id.clone().into());
+    let delete_reason = if summary.is_none() {
+      object.delete_reason()
+    } else {
+      None
+    };
     Ok(Delete {
       actor: actor.actor_id.clone().into(),
       to: vec![to],
@@ -92,6 +105,7 @@ impl Delete {
       cc: cc.into_iter().collect(),
       kind: DeleteType::Delete,
       summary,
+      delete_reason,
       id,
       audience: community.map(|c| c.actor_id.clone().into()),
     })
@@ -163,7 +177,239 @@ pub(in crate::activities) async fn receive_remove_action(
       )
       .await?;
     }
-    DeletableObjects::PrivateMessage(_) => unimplemented!(),
+    DeletableObjects::PrivateMessage(pm) => {
+      // private messages can't be removed by mods, so this is a deletion by the sender, which was
+      // checked during verification
+      PrivateMessage::update(
+        &mut context.pool(),
+        pm.id,
+        &PrivateMessageUpdateForm {
+          deleted: Some(true),
+          ..Default::default()
+        },
+      )
+      .await?;
+    }
   }
   Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::{
+    objects::{
+      community::tests::parse_lemmy_community,
+      person::tests::parse_lemmy_person,
+      post::ApubPost,
+      private_message::ApubPrivateMessage,
+      tests::init_context,
+    },
+    protocol::tests::file_to_json_object,
+  };
+  use activitypub_federation::{kinds::public, traits::Object};
+  use lemmy_db_schema::source::{
+    instance::Instance,
+    person::{Person, PersonInsertForm},
+    site::Site,
+  };
+  use serial_test::serial;
+  use std::ops::Deref;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_self_delete_with_reason() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+    let json = file_to_json_object("assets/lemmy/objects/page.json").unwrap();
+    let post = ApubPost::from_json(json, &context).await.unwrap();
+
+    let form = PostUpdateForm {
+      delete_reason: Some(Some("deleted by user".to_string())),
+      ..Default::default()
+    };
+    let with_reason: ApubPost = Post::update(&mut context.pool(), post.id, &form)
+      .await
+      .unwrap()
+      .into();
+    let delete = Delete::new(
+      &person,
+      DeletableObjects::Post(with_reason),
+      public(),
+      Some(community.deref()),
+      None,
+      &context,
+    )
+    .unwrap();
+
+    // clear the reason again, so that we can check it is restored from the activity
+    let form = PostUpdateForm {
+      delete_reason: Some(None),
+      ..Default::default()
+    };
+    Post::update(&mut context.pool(), post.id, &form)
+      .await
+      .unwrap();
+
+    let json = serde_json::to_string(&delete).unwrap();
+    let delete: Delete = serde_json::from_str(&json).unwrap();
+    assert_eq!(None, delete.summary);
+    assert_eq!(Some("deleted by user"), delete.delete_reason.as_deref());
+    delete.receive(&context).await.unwrap();
+
+    let post = Post::read(&mut context.pool(), post.id).await.unwrap();
+    assert!(post.deleted);
+    assert!(!post.removed);
+    assert_eq!(Some("deleted by user".to_string()), post.delete_reason);
+    assert_eq!(context.request_count(), 0);
+
+    Post::delete(&mut context.pool(), post.id).await.unwrap();
+    Person::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Community::delete(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_delete_private_message() {
+    let context = init_context().await;
+    let (sender, site) = parse_lemmy_person(&context).await;
+    let json = file_to_json_object("assets/pleroma/objects/person.json").unwrap();
+    let recipient_id = Url::parse("https://queer.hacktivis.me/users/lanodan").unwrap();
+    ApubPerson::verify(&json, &recipient_id, &context)
+      .await
+      .unwrap();
+    let recipient = ApubPerson::from_json(json, &context).await.unwrap();
+    let json = file_to_json_object("assets/lemmy/objects/chat_message.json").unwrap();
+    let pm = ApubPrivateMessage::from_json(json, &context).await.unwrap();
+    assert!(!pm.deleted);
+
+    // another user on the same instance can't delete it
+    let form = PersonInsertForm::builder()
+      .name("riker".to_string())
+      .public_key("pubkey".to_string())
+      .instance_id(sender.instance_id)
+      .actor_id(Some(
+        Url::parse("https://enterprise.lemmy.ml/u/riker")
+          .unwrap()
+          .into(),
+      ))
+      .local(Some(false))
+      .build();
+    let other: ApubPerson = Person::create(&mut context.pool(), &form)
+      .await
+      .unwrap()
+      .into();
+    let delete = Delete::new(
+      &other,
+      DeletableObjects::PrivateMessage(pm.clone()),
+      recipient_id.clone(),
+      None,
+      Some(String::new()),
+      &context,
+    )
+    .unwrap();
+    let err = delete.verify(&context).await.unwrap_err();
+    assert_eq!(LemmyErrorType::EditPrivateMessageNotAllowed, err.error_type);
+
+    // a delete with summary is handled as removal, which used to panic for private messages
+    let delete = Delete::new(
+      &sender,
+      DeletableObjects::PrivateMessage(pm.clone()),
+      recipient_id,
+      None,
+      Some(String::new()),
+      &context,
+    )
+    .unwrap();
+    delete.verify(&context).await.unwrap();
+    delete.receive(&context).await.unwrap();
+    let pm = PrivateMessage::read(&mut context.pool(), pm.id)
+      .await
+      .unwrap();
+    assert!(pm.deleted);
+    assert_eq!(context.request_count(), 0);
+
+    PrivateMessage::delete(&mut context.pool(), pm.id)
+      .await
+      .unwrap();
+    for person_id in [sender.id, recipient.id, other.id] {
+      Person::delete(&mut context.pool(), person_id)
+        .await
+        .unwrap();
+    }
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_remove_by_non_mod_rejected() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+    let json = file_to_json_object("assets/lemmy/objects/page.json").unwrap();
+    let post = ApubPost::from_json(json, &context).await.unwrap();
+
+    let other_instance = Instance::read_or_create(&mut context.pool(), "example.com".to_string())
+      .await
+      .unwrap();
+    let form = PersonInsertForm::builder()
+      .name("other".to_string())
+      .public_key("pubkey".to_string())
+      .instance_id(other_instance.id)
+      .actor_id(Some(
+        Url::parse("https://example.com/u/other").unwrap().into(),
+      ))
+      .local(Some(false))
+      .build();
+    let other: ApubPerson = Person::create(&mut context.pool(), &form)
+      .await
+      .unwrap()
+      .into();
+
+    // a remote user who isn't a mod of the community can't remove posts in it
+    let remove = Delete::new(
+      &other,
+      DeletableObjects::Post(post.clone()),
+      public(),
+      Some(community.deref()),
+      Some("spam".to_string()),
+      &context,
+    )
+    .unwrap();
+    let err = remove.verify(&context).await.unwrap_err();
+    assert_eq!(LemmyErrorType::NotAModerator, err.error_type);
+
+    // the same removal from the instance of the community is allowed
+    let remove = Delete::new(
+      &person,
+      DeletableObjects::Post(post.clone()),
+      public(),
+      Some(community.deref()),
+      Some("spam".to_string()),
+      &context,
+    )
+    .unwrap();
+    remove.verify(&context).await.unwrap();
+
+    Instance::delete(&mut context.pool(), other_instance.id)
+      .await
+      .unwrap();
+    Post::delete(&mut context.pool(), post.id).await.unwrap();
+    Person::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Community::delete(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+}
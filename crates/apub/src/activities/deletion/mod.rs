@@ -40,7 +40,7 @@ use lemmy_db_schema::{
   },
   traits::Crud,
 };
-use lemmy_utils::error::LemmyError;
+use lemmy_utils::error::{LemmyError, LemmyErrorType};
 use std::ops::Deref;
 use url::Url;
 
@@ -171,6 +171,40 @@ impl DeletableObjects {
       DeletableObjects::PrivateMessage(p) => p.ap_id.clone().into(),
     }
   }
+
+  /// Note left by the creator when deleting a post or comment.
+  pub(crate) fn delete_reason(&self) -> Option<String> {
+    match self {
+      DeletableObjects::Comment(c) => c.delete_reason.clone(),
+      DeletableObjects::Post(p) => p.delete_reason.clone(),
+      DeletableObjects::Community(_) | DeletableObjects::PrivateMessage(_) => None,
+    }
+  }
+
+  /// Whether the object was deleted by its creator or removed by a mod.
+  pub(crate) fn is_deleted_or_removed(&self) -> bool {
+    match self {
+      DeletableObjects::Community(c) => c.deleted || c.removed,
+      DeletableObjects::Comment(c) => c.deleted || c.removed,
+      DeletableObjects::Post(p) => p.deleted || p.removed,
+      DeletableObjects::PrivateMessage(p) => p.deleted,
+    }
+  }
+}
+
+/// Rejects actions on an object which is already deleted or removed, so that for example an
+/// update can't resurrect tombstoned content. Objects which aren't known locally pass the check.
+#[tracing::instrument(skip_all)]
+pub(crate) async fn verify_object_not_deleted(
+  ap_id: &Url,
+  context: &Data<LemmyContext>,
+) -> Result<(), LemmyError> {
+  if let Ok(object) = DeletableObjects::read_from_db(ap_id, context).await {
+    if object.is_deleted_or_removed() {
+      Err(LemmyErrorType::Deleted)?
+    }
+  }
+  Ok(())
 }
 
 #[tracing::instrument(skip_all)]
@@ -213,9 +247,14 @@ pub(in crate::activities) async fn verify_delete_activity(
       )
       .await?;
     }
-    DeletableObjects::PrivateMessage(_) => {
+    DeletableObjects::PrivateMessage(pm) => {
       verify_person(&activity.actor, context).await?;
       verify_domains_match(activity.actor.inner(), activity.object.id())?;
+      // only the sender can delete a private message
+      let creator = Person::read(&mut context.pool(), pm.creator_id).await?;
+      if creator.actor_id.inner() != activity.actor.inner() {
+        Err(LemmyErrorType::EditPrivateMessageNotAllowed)?
+      }
     }
   }
   Ok(())
@@ -245,6 +284,7 @@ async fn receive_delete_action(
   object: &Url,
   actor: &ObjectId<ApubPerson>,
   deleted: bool,
+  reason: Option<String>,
   context: &Data<LemmyContext>,
 ) -> Result<(), LemmyError> {
   match DeletableObjects::read_from_db(object, context).await? {
@@ -273,6 +313,7 @@ async fn receive_delete_action(
           post.id,
           &PostUpdateForm {
             deleted: Some(deleted),
+            delete_reason: Some(reason),
             ..Default::default()
           },
         )
@@ -286,6 +327,7 @@ async fn receive_delete_action(
           comment.id,
           &CommentUpdateForm {
             deleted: Some(deleted),
+            delete_reason: Some(reason),
             ..Default::default()
           },
         )
@@ -22,6 +22,7 @@ use lemmy_db_schema::{
       ModRemovePostForm,
     },
     post::{Post, PostUpdateForm},
+    private_message::{PrivateMessage, PrivateMessageUpdateForm},
   },
   traits::Crud,
 };
@@ -58,7 +59,7 @@ impl ActivityHandler for UndoDelete {
       )
       .await
     } else {
-      receive_delete_action(self.object.object.id(), &self.actor, false, context).await
+      receive_delete_action(self.object.object.id(), &self.actor, false, None, context).await
     }
   }
 }
@@ -155,8 +156,132 @@ impl UndoDelete {
         )
         .await?;
       }
-      DeletableObjects::PrivateMessage(_) => unimplemented!(),
+      DeletableObjects::PrivateMessage(pm) => {
+        PrivateMessage::update(
+          &mut context.pool(),
+          pm.id,
+          &PrivateMessageUpdateForm {
+            deleted: Some(false),
+            ..Default::default()
+          },
+        )
+        .await?;
+      }
     }
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::{
+    objects::{
+      comment::ApubComment,
+      community::tests::parse_lemmy_community,
+      person::tests::parse_lemmy_person,
+      post::ApubPost,
+      tests::init_context,
+    },
+    protocol::tests::file_to_json_object,
+  };
+  use activitypub_federation::{kinds::public, traits::Object};
+  use lemmy_db_schema::source::{person::Person, site::Site};
+  use serial_test::serial;
+  use std::ops::Deref;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_undo_remove_post_and_comment() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+    let json = file_to_json_object("assets/lemmy/objects/page.json").unwrap();
+    let post = ApubPost::from_json(json, &context).await.unwrap();
+    let url = Url::parse("https://enterprise.lemmy.ml/comment/38741").unwrap();
+    let json = file_to_json_object("assets/lemmy/objects/note.json").unwrap();
+    ApubComment::verify(&json, &url, &context).await.unwrap();
+    let comment = ApubComment::from_json(json, &context).await.unwrap();
+
+    // both were removed by a moderator before
+    let form = PostUpdateForm {
+      removed: Some(true),
+      ..Default::default()
+    };
+    let post: ApubPost = Post::update(&mut context.pool(), post.id, &form)
+      .await
+      .unwrap()
+      .into();
+    let form = CommentUpdateForm {
+      removed: Some(true),
+      ..Default::default()
+    };
+    let comment: ApubComment = Comment::update(&mut context.pool(), comment.id, &form)
+      .await
+      .unwrap()
+      .into();
+
+    // a summary marks the undone delete as a removal by a moderator
+    for object in [
+      DeletableObjects::Post(post.clone()),
+      DeletableObjects::Comment(comment.clone()),
+    ] {
+      let undo = UndoDelete::new(
+        &person,
+        object,
+        public(),
+        Some(community.deref()),
+        Some(String::new()),
+        &context,
+      )
+      .unwrap();
+      undo.verify(&context).await.unwrap();
+      undo.receive(&context).await.unwrap();
+    }
+    let post = Post::read(&mut context.pool(), post.id).await.unwrap();
+    assert!(!post.removed);
+    let comment = Comment::read(&mut context.pool(), comment.id)
+      .await
+      .unwrap();
+    assert!(!comment.removed);
+
+    // posts deleted by their creator are restored the same way, without a summary
+    let form = PostUpdateForm {
+      deleted: Some(true),
+      ..Default::default()
+    };
+    let post: ApubPost = Post::update(&mut context.pool(), post.id, &form)
+      .await
+      .unwrap()
+      .into();
+    let undo = UndoDelete::new(
+      &person,
+      DeletableObjects::Post(post.clone()),
+      public(),
+      Some(community.deref()),
+      None,
+      &context,
+    )
+    .unwrap();
+    undo.verify(&context).await.unwrap();
+    undo.receive(&context).await.unwrap();
+    let post = Post::read(&mut context.pool(), post.id).await.unwrap();
+    assert!(!post.deleted);
+    assert_eq!(context.request_count(), 0);
+
+    Comment::delete(&mut context.pool(), comment.id)
+      .await
+      .unwrap();
+    Post::delete(&mut context.pool(), post.id).await.unwrap();
+    Person::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Community::delete(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+}
@@ -0,0 +1,117 @@
+use crate::{
+  activities::{generate_activity_id, send_lemmy_activity},
+  insert_received_activity,
+  objects::{community::ApubCommunity, person::ApubPerson},
+  protocol::activities::following::{
+    accept_batch_follow::AcceptBatchFollow,
+    batch_follow::BatchFollow,
+  },
+};
+use activitypub_federation::{
+  config::Data,
+  fetch::object_id::ObjectId,
+  kinds::activity::AcceptType,
+  protocol::verification::verify_urls_match,
+  traits::{ActivityHandler, Actor},
+};
+use futures::future::try_join_all;
+use lemmy_api_common::context::LemmyContext;
+use lemmy_db_schema::{
+  source::{activity::ActivitySendTargets, community::CommunityFollower},
+  traits::Followable,
+};
+use lemmy_utils::error::{LemmyError, LemmyErrorType};
+use url::Url;
+
+impl AcceptBatchFollow {
+  /// Confirms the `accepted` follows of the batch to the instance which sent it.
+  #[tracing::instrument(skip_all)]
+  pub async fn send(
+    batch: BatchFollow,
+    accepted: Vec<ObjectId<ApubPerson>>,
+    community: &ApubCommunity,
+    context: &Data<LemmyContext>,
+  ) -> Result<(), LemmyError> {
+    let site = batch.actor.dereference(context).await?;
+    let accept = AcceptBatchFollow {
+      actor: community.id().into(),
+      to: [site.id().into()],
+      object: batch,
+      accepted,
+      kind: AcceptType::Accept,
+      id: generate_activity_id(
+        AcceptType::Accept,
+        &context.settings().get_protocol_and_hostname(),
+      )?,
+    };
+    let inbox = ActivitySendTargets::to_inbox(site.shared_inbox_or_inbox());
+    send_lemmy_activity(context, accept, community, inbox, true).await
+  }
+
+  /// Only follows which were part of the batch can be accepted.
+  fn verify_accepted_in_batch(&self) -> Result<(), LemmyError> {
+    let in_batch =
+      |p: &ObjectId<ApubPerson>| self.object.follow.iter().any(|f| f.inner() == p.inner());
+    if !self.accepted.iter().all(in_batch) {
+      Err(LemmyErrorType::CouldntFindObject)?
+    }
+    Ok(())
+  }
+}
+
+/// Handle accepted batch follows
+#[async_trait::async_trait]
+impl ActivityHandler for AcceptBatchFollow {
+  type DataType = LemmyContext;
+  type Error = LemmyError;
+
+  fn id(&self) -> &Url {
+    &self.id
+  }
+
+  fn actor(&self) -> &Url {
+    self.actor.inner()
+  }
+
+  #[tracing::instrument(skip_all)]
+  async fn verify(&self, context: &Data<LemmyContext>) -> Result<(), LemmyError> {
+    insert_received_activity(&self.id, context).await?;
+    verify_urls_match(self.actor.inner(), self.object.object.inner())?;
+    verify_urls_match(self.to[0].inner(), self.object.actor.inner())?;
+    self.object.verify_same_instance()?;
+    self.verify_accepted_in_batch()?;
+    Ok(())
+  }
+
+  #[tracing::instrument(skip_all)]
+  async fn receive(self, context: &Data<LemmyContext>) -> Result<(), LemmyError> {
+    let community = self.actor.dereference(context).await?;
+    let persons = try_join_all(self.accepted.iter().map(|p| p.dereference(context))).await?;
+    // This will throw an error if no follow was requested
+    for person in persons {
+      CommunityFollower::follow_accepted(&mut context.pool(), community.id, person.id).await?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::protocol::tests::file_to_json_object;
+
+  #[test]
+  fn test_accept_batch_follow_only_accepts_batch_entries() {
+    let accept: AcceptBatchFollow =
+      file_to_json_object("assets/lemmy/activities/following/accept_batch_follow.json").unwrap();
+    accept.verify_accepted_in_batch().unwrap();
+
+    // users which are only unfollowed by the batch can't be accepted
+    let mut unfollowed = accept;
+    unfollowed.accepted = unfollowed.object.unfollow.clone();
+    assert!(unfollowed.verify_accepted_in_batch().is_err());
+  }
+}
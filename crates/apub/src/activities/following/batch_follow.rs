@@ -0,0 +1,314 @@
+use crate::{
+  activities::{generate_activity_id, send_lemmy_activity, verify_person_in_community},
+  insert_received_activity,
+  objects::{community::ApubCommunity, instance::ApubSite, person::ApubPerson},
+  protocol::activities::following::{
+    accept_batch_follow::AcceptBatchFollow,
+    batch_follow::{BatchFollow, BatchFollowType},
+  },
+};
+use activitypub_federation::{
+  config::Data,
+  fetch::object_id::ObjectId,
+  protocol::verification::verify_urls_match,
+  traits::{ActivityHandler, Actor},
+};
+use futures::future::try_join_all;
+use lemmy_api_common::{context::LemmyContext, utils::is_community_member};
+use lemmy_db_schema::{
+  source::{
+    activity::ActivitySendTargets,
+    community::{CommunityFollower, CommunityFollowerForm},
+    site::Site,
+  },
+  utils::FETCH_LIMIT_MAX,
+};
+use lemmy_utils::error::{LemmyError, LemmyErrorType};
+use url::Url;
+
+impl BatchFollow {
+  /// Follows and unfollows the community for the given users of the local instance, which sends
+  /// the batch as `actor`. New follows are stored as pending, until the community accepts them with
+  /// a single [AcceptBatchFollow]. At most [FETCH_LIMIT_MAX] users can be given.
+  #[tracing::instrument(skip_all)]
+  pub async fn send(
+    actor: &ApubSite,
+    community: &ApubCommunity,
+    follow: &[ApubPerson],
+    unfollow: &[ApubPerson],
+    context: &Data<LemmyContext>,
+  ) -> Result<(), LemmyError> {
+    if follow.len() + unfollow.len() > FETCH_LIMIT_MAX as usize {
+      Err(LemmyErrorType::TooManyItems)?
+    }
+    let follow_forms = follow
+      .iter()
+      .map(|p| CommunityFollowerForm {
+        community_id: community.id,
+        person_id: p.id,
+        pending: true,
+      })
+      .collect();
+    CommunityFollower::apply_batch(
+      &mut context.pool(),
+      community.id,
+      follow_forms,
+      unfollow.iter().map(|p| p.id).collect(),
+    )
+    .await?;
+
+    let batch = BatchFollow {
+      actor: actor.id().into(),
+      to: [community.id().into()],
+      object: community.id().into(),
+      follow: follow.iter().map(|p| p.id().into()).collect(),
+      unfollow: unfollow.iter().map(|p| p.id().into()).collect(),
+      kind: BatchFollowType::BatchFollow,
+      id: generate_activity_id(
+        BatchFollowType::BatchFollow,
+        &context.settings().get_protocol_and_hostname(),
+      )?,
+    };
+    let inbox = if community.local {
+      ActivitySendTargets::empty()
+    } else {
+      ActivitySendTargets::to_inbox(community.shared_inbox_or_inbox())
+    };
+    send_lemmy_activity(context, batch, actor, inbox, true).await
+  }
+
+  /// An instance can only change the follows of its own users, so the actor needs to be the
+  /// instance actor of every entry. Each entry is fetched on receive, so their number is limited.
+  pub(crate) fn verify_same_instance(&self) -> Result<(), LemmyError> {
+    if self.follow.len() + self.unfollow.len() > FETCH_LIMIT_MAX as usize {
+      Err(LemmyErrorType::TooManyItems)?
+    }
+    for person in self.follow.iter().chain(self.unfollow.iter()) {
+      let instance_actor = Site::instance_actor_id_from_url(person.inner().clone());
+      verify_urls_match(self.actor.inner(), &instance_actor)?;
+    }
+    Ok(())
+  }
+}
+
+#[async_trait::async_trait]
+impl ActivityHandler for BatchFollow {
+  type DataType = LemmyContext;
+  type Error = LemmyError;
+
+  fn id(&self) -> &Url {
+    &self.id
+  }
+
+  fn actor(&self) -> &Url {
+    self.actor.inner()
+  }
+
+  #[tracing::instrument(skip_all)]
+  async fn verify(&self, context: &Data<LemmyContext>) -> Result<(), LemmyError> {
+    insert_received_activity(&self.id, context).await?;
+    verify_urls_match(self.to[0].inner(), self.object.inner())?;
+    self.verify_same_instance()?;
+    self.actor.dereference(context).await?;
+    let community = self.object.dereference(context).await?;
+    if !community.local {
+      Err(LemmyErrorType::ObjectNotLocal)?
+    }
+    Ok(())
+  }
+
+  #[tracing::instrument(skip_all)]
+  async fn receive(self, context: &Data<LemmyContext>) -> Result<(), LemmyError> {
+    let community = self.object.dereference(context).await?;
+    // fetch everyone first, so that a single failure rejects the whole batch
+    let follow = try_join_all(self.follow.iter().map(|p| async {
+      verify_person_in_community(p, &community, context).await?;
+      let person = p.dereference(context).await?;
+      // communities which require an application only accept the follow after a mod approves
+      let pending = !is_community_member(person.id, &community, &mut context.pool()).await?;
+      Ok::<_, LemmyError>((person, pending))
+    }))
+    .await?;
+    let unfollow = try_join_all(self.unfollow.iter().map(|p| p.dereference(context))).await?;
+
+    let follow_forms = follow
+      .iter()
+      .map(|(person, pending)| CommunityFollowerForm {
+        community_id: community.id,
+        person_id: person.id,
+        pending: *pending,
+      })
+      .collect();
+    CommunityFollower::apply_batch(
+      &mut context.pool(),
+      community.id,
+      follow_forms,
+      unfollow.iter().map(|p| p.id).collect(),
+    )
+    .await?;
+
+    // accepted follows are confirmed at once, pending ones individually once a mod approves them
+    let accepted: Vec<ObjectId<ApubPerson>> = follow
+      .iter()
+      .filter(|(_, pending)| !pending)
+      .map(|(person, _)| person.id().into())
+      .collect();
+    if !accepted.is_empty() {
+      AcceptBatchFollow::send(self, accepted, &community, context).await?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::{
+    objects::{
+      community::tests::parse_lemmy_community,
+      person::tests::parse_lemmy_person,
+      tests::init_context,
+    },
+    protocol::tests::file_to_json_object,
+  };
+  use lemmy_db_schema::{
+    source::{
+      community::{Community, CommunityUpdateForm},
+      person::{Person, PersonInsertForm},
+      site::Site,
+    },
+    traits::{Crud, Followable},
+    CommunityMembershipMode,
+  };
+  use serial_test::serial;
+
+  #[test]
+  fn test_batch_follow_only_affects_own_users() {
+    let mut batch: BatchFollow =
+      file_to_json_object("assets/lemmy/activities/following/batch_follow.json").unwrap();
+    batch.verify_same_instance().unwrap();
+
+    let mut other_instance = batch.clone();
+    other_instance.unfollow.push(
+      Url::parse("http://enterprise.lemmy.ml/u/picard")
+        .unwrap()
+        .into(),
+    );
+    assert!(other_instance.verify_same_instance().is_err());
+
+    // ordinary users can't change the follows of others on their instance
+    let mut user_actor = batch.clone();
+    user_actor.actor = Url::parse("http://ds9.lemmy.ml/u/lemmy_alpha")
+      .unwrap()
+      .into();
+    assert!(user_actor.verify_same_instance().is_err());
+
+    let mut too_large = batch;
+    too_large.follow = (0..=FETCH_LIMIT_MAX)
+      .map(|i| {
+        Url::parse(&format!("http://ds9.lemmy.ml/u/user_{i}"))
+          .unwrap()
+          .into()
+      })
+      .collect();
+    assert!(too_large.verify_same_instance().is_err());
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_receive_batch_follow() {
+    let context = init_context().await;
+    let (picard, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+
+    // pretend the community is local and requires an application, so that no accept is sent
+    let form = CommunityUpdateForm {
+      local: Some(true),
+      membership_mode: Some(CommunityMembershipMode::RequireApplication),
+      ..Default::default()
+    };
+    let community: ApubCommunity = Community::update(&mut context.pool(), community.id, &form)
+      .await
+      .unwrap()
+      .into();
+
+    // other users of the same instance as the actor
+    let mut persons = vec![];
+    for name in ["riker", "data"] {
+      let form = PersonInsertForm::builder()
+        .name(name.to_string())
+        .public_key("pubkey".to_string())
+        .instance_id(picard.instance_id)
+        .local(Some(false))
+        .actor_id(Some(
+          Url::parse(&format!("https://enterprise.lemmy.ml/u/{name}"))
+            .unwrap()
+            .into(),
+        ))
+        .build();
+      let person: ApubPerson = Person::create(&mut context.pool(), &form)
+        .await
+        .unwrap()
+        .into();
+      persons.push(person);
+    }
+    let (riker, data) = (&persons[0], &persons[1]);
+    let form = CommunityFollowerForm {
+      community_id: community.id,
+      person_id: data.id,
+      pending: false,
+    };
+    CommunityFollower::follow(&mut context.pool(), &form)
+      .await
+      .unwrap();
+
+    let batch = BatchFollow {
+      actor: site.id().into(),
+      to: [community.id().into()],
+      object: community.id().into(),
+      follow: vec![picard.id().into(), riker.id().into()],
+      unfollow: vec![data.id().into()],
+      kind: BatchFollowType::BatchFollow,
+      id: Url::parse("https://enterprise.lemmy.ml/activities/batchfollow/1").unwrap(),
+    };
+    batch.verify_same_instance().unwrap();
+    batch.receive(&context).await.unwrap();
+
+    // new follows wait for a mod to approve them, the unfollow is applied right away
+    for person in [&picard, riker] {
+      let subscribed =
+        CommunityFollower::is_accepted_follower(&mut context.pool(), community.id, person.id)
+          .await
+          .unwrap();
+      assert!(!subscribed);
+      CommunityFollower::follow_accepted(&mut context.pool(), community.id, person.id)
+        .await
+        .unwrap();
+    }
+    let form = CommunityFollowerForm {
+      community_id: community.id,
+      person_id: data.id,
+      pending: false,
+    };
+    assert_eq!(
+      0,
+      CommunityFollower::unfollow(&mut context.pool(), &form)
+        .await
+        .unwrap()
+    );
+    assert_eq!(context.request_count(), 0);
+
+    for person in persons.iter().chain([&picard]) {
+      Person::delete(&mut context.pool(), person.id)
+        .await
+        .unwrap();
+    }
+    Community::delete(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+}
@@ -16,7 +16,7 @@ use activitypub_federation::{
   protocol::verification::verify_urls_match,
   traits::{ActivityHandler, Actor},
 };
-use lemmy_api_common::context::LemmyContext;
+use lemmy_api_common::{context::LemmyContext, utils::is_community_member};
 use lemmy_db_schema::{
   source::{
     activity::ActivitySendTargets,
@@ -71,6 +71,24 @@ impl Follow {
   }
 }
 
+/// Stores a follow of a community which was received from another instance. Communities which
+/// require an application only accept the follow after a mod approves it, so it is pending until
+/// then. Returns true if the follow is pending.
+pub(in crate::activities::following) async fn store_community_follow(
+  person: &ApubPerson,
+  community: &ApubCommunity,
+  context: &Data<LemmyContext>,
+) -> Result<bool, LemmyError> {
+  let pending = !is_community_member(person.id, community, &mut context.pool()).await?;
+  let form = CommunityFollowerForm {
+    community_id: community.id,
+    person_id: person.id,
+    pending,
+  };
+  CommunityFollower::follow(&mut context.pool(), &form).await?;
+  Ok(pending)
+}
+
 #[async_trait::async_trait]
 impl ActivityHandler for Follow {
   type DataType = LemmyContext;
@@ -112,15 +130,82 @@ impl ActivityHandler for Follow {
         PersonFollower::follow(&mut context.pool(), &form).await?;
       }
       UserOrCommunity::Community(c) => {
-        let form = CommunityFollowerForm {
-          community_id: c.id,
-          person_id: actor.id,
-          pending: false,
-        };
-        CommunityFollower::follow(&mut context.pool(), &form).await?;
+        let pending = store_community_follow(&actor, &c, context).await?;
+        if pending {
+          return Ok(());
+        }
       }
     }
 
     AcceptFollow::send(self, context).await
   }
 }
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::objects::{
+    community::tests::parse_lemmy_community,
+    person::tests::parse_lemmy_person,
+    tests::init_context,
+  };
+  use lemmy_db_schema::{
+    source::{
+      community::{Community, CommunityUpdateForm},
+      person::Person,
+      site::Site,
+    },
+    traits::Crud,
+    CommunityMembershipMode,
+  };
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_follow_restricted_community_is_pending() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+
+    // pretend the community is local and requires an application
+    let form = CommunityUpdateForm {
+      local: Some(true),
+      membership_mode: Some(CommunityMembershipMode::RequireApplication),
+      ..Default::default()
+    };
+    let community: ApubCommunity = Community::update(&mut context.pool(), community.id, &form)
+      .await
+      .unwrap()
+      .into();
+
+    let follow = Follow::new(&person, &community, &context).unwrap();
+    follow.receive(&context).await.unwrap();
+    assert!(
+      !CommunityFollower::is_accepted_follower(&mut context.pool(), community.id, person.id)
+        .await
+        .unwrap()
+    );
+
+    // the follow is only accepted once a mod approves it
+    CommunityFollower::follow_accepted(&mut context.pool(), community.id, person.id)
+      .await
+      .unwrap();
+    assert!(
+      CommunityFollower::is_accepted_follower(&mut context.pool(), community.id, person.id)
+        .await
+        .unwrap()
+    );
+    assert_eq!(context.request_count(), 0);
+
+    Person::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Community::delete(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+}
@@ -1,6 +1,10 @@
 use crate::{
   objects::{community::ApubCommunity, person::ApubPerson},
-  protocol::activities::following::{follow::Follow, undo_follow::UndoFollow},
+  protocol::activities::following::{
+    accept::AcceptFollow,
+    follow::Follow,
+    undo_follow::UndoFollow,
+  },
 };
 use activitypub_federation::config::Data;
 use lemmy_api_common::context::LemmyContext;
@@ -8,6 +12,8 @@ use lemmy_db_schema::source::{community::Community, person::Person};
 use lemmy_utils::error::LemmyError;
 
 pub mod accept;
+pub mod accept_batch_follow;
+pub mod batch_follow;
 pub mod follow;
 pub mod undo_follow;
 
@@ -25,3 +31,19 @@ pub async fn send_follow_community(
     UndoFollow::send(&actor, &community, context).await
   }
 }
+
+/// Accept a pending follow, after a moderator approved the application for membership. Local
+/// followers don't need to be notified.
+pub async fn send_accept_follower(
+  community: Community,
+  person: Person,
+  context: &Data<LemmyContext>,
+) -> Result<(), LemmyError> {
+  if person.local {
+    return Ok(());
+  }
+  let community: ApubCommunity = community.into();
+  let actor: ApubPerson = person.into();
+  let follow = Follow::new(&actor, &community, context)?;
+  AcceptFollow::send(follow, context).await
+}
@@ -1,7 +1,7 @@
-use self::following::send_follow_community;
+use self::following::{send_accept_follower, send_follow_community};
 use crate::{
   activities::{
-    block::{send_ban_from_community, send_ban_from_site},
+    block::{send_ban_from_community, send_ban_from_site, send_block_instance},
     community::{
       collection_add::{send_add_mod_to_community, send_feature_post},
       lock_page::send_lock_post,
@@ -15,6 +15,7 @@ use crate::{
       send_apub_delete_private_message,
       DeletableObjects,
     },
+    reaction::send_emoji_react,
     voting::send_like_activity,
   },
   objects::{community::ApubCommunity, person::ApubPerson},
@@ -33,17 +34,21 @@ use activitypub_federation::{
   traits::{ActivityHandler, Actor},
 };
 use anyhow::anyhow;
+use diesel::OptionalExtension;
 use lemmy_api_common::{
   context::LemmyContext,
   send_activity::{ActivityChannel, SendActivityData},
+  utils::check_community_membership,
 };
 use lemmy_db_schema::source::{
   activity::{ActivitySendTargets, ActorType, SentActivity, SentActivityForm},
   community::Community,
+  local_site::LocalSite,
 };
 use lemmy_db_views_actor::structs::{CommunityPersonBanView, CommunityView};
 use lemmy_utils::error::{LemmyError, LemmyErrorExt, LemmyErrorType, LemmyResult};
 use serde::Serialize;
+use serde_json::Value;
 use std::ops::Deref;
 use tracing::info;
 use url::{ParseError, Url};
@@ -54,6 +59,7 @@ pub mod community;
 pub mod create_or_update;
 pub mod deletion;
 pub mod following;
+pub mod reaction;
 pub mod voting;
 
 /// Checks that the specified Url actually identifies a Person (by fetching it), and that the person
@@ -96,6 +102,18 @@ pub(crate) async fn verify_person_in_community(
   }
 }
 
+/// In a local community which requires an application for membership, only accepted followers
+/// and moderators are allowed to participate.
+#[tracing::instrument(skip_all)]
+pub(crate) async fn verify_community_member(
+  person_id: &ObjectId<ApubPerson>,
+  community: &ApubCommunity,
+  context: &Data<LemmyContext>,
+) -> Result<(), LemmyError> {
+  let person = person_id.dereference(context).await?;
+  check_community_membership(person.id, community, &mut context.pool()).await
+}
+
 /// Verify that mod action in community was performed by a moderator.
 ///
 /// * `mod_id` - Activitypub ID of the mod or admin who performed the action
@@ -190,6 +208,14 @@ pub(crate) trait GetActorType {
   fn actor_type(&self) -> ActorType;
 }
 
+/// Stores the activity in the `sent_activity` table. Actual delivery happens in lemmy_federate.
+/// Deliveries which fail with a retriable error are stored in the `federation_queue` table and
+/// retried with exponential backoff, so a remote instance being down doesn't lose the activity.
+/// Permanent failures (4xx responses) are dropped by the federation library and not retried.
+///
+/// If federation is disabled, the activity is still stored so that it can be fetched from this
+/// instance, but it isn't sent to any remote inbox. If only vote federation is disabled, votes
+/// and announces or undos of them are dropped entirely.
 #[tracing::instrument(skip_all)]
 async fn send_lemmy_activity<Activity, ActorT>(
   data: &Data<LemmyContext>,
@@ -203,12 +229,25 @@ where
   ActorT: Actor + GetActorType,
   Activity: ActivityHandler<Error = LemmyError>,
 {
-  info!("Saving outgoing activity to queue {}", activity.id());
-  let activity = WithContext::new(activity, CONTEXT.deref().clone());
+  // read without cache, so that nothing is sent right after federation was disabled
+  let local_site = LocalSite::read(&mut data.pool()).await.optional()?;
+  let activity_id = activity.id().clone();
+  let activity = serde_json::to_value(WithContext::new(activity, CONTEXT.deref().clone()))?;
+  if !local_site.as_ref().map_or(true, |l| l.federate_votes) && is_vote(&activity) {
+    info!("Vote federation is disabled, dropping {activity_id}");
+    return Ok(());
+  }
+  info!("Saving outgoing activity to queue {activity_id}");
+  let send_targets = if local_site.map_or(true, |l| l.federation_enabled) {
+    send_targets
+  } else {
+    info!("Federation is disabled, not sending {activity_id}");
+    ActivitySendTargets::empty()
+  };
 
   let form = SentActivityForm {
-    ap_id: activity.id().clone().into(),
-    data: serde_json::to_value(activity)?,
+    ap_id: activity_id.into(),
+    data: activity,
     sensitive,
     send_inboxes: send_targets
       .inboxes
@@ -225,6 +264,15 @@ where
   Ok(())
 }
 
+/// Whether the activity is a vote, or an undo or announce of one.
+fn is_vote(activity: &Value) -> bool {
+  match activity.get("type").and_then(Value::as_str) {
+    Some("Like" | "Dislike") => true,
+    Some("Undo" | "Announce") => activity.get("object").is_some_and(is_vote),
+    _ => false,
+  }
+}
+
 pub async fn handle_outgoing_activities(context: Data<LemmyContext>) -> LemmyResult<()> {
   while let Some(data) = ActivityChannel::retrieve_activity().await {
     match_outgoing_activities(data, &context.reset_request_count()).await?
@@ -294,9 +342,16 @@ pub async fn match_outgoing_activities(
       LikePostOrComment(object_id, person, community, score) => {
         send_like_activity(object_id, person, community, score, context).await
       }
+      ReactPostOrComment(object_id, person, community, shortcode, add) => {
+        send_emoji_react(object_id, person, community, shortcode, add, context).await
+      }
       FollowCommunity(community, person, follow) => {
         send_follow_community(community, person, follow, &context).await
       }
+      AcceptFollower(community, person) => send_accept_follower(community, person, &context).await,
+      BlockInstance(actor, instance_id, block) => {
+        send_block_instance(actor, instance_id, block, context).await
+      }
       UpdateCommunity(actor, community) => send_update_community(community, actor, context).await,
       DeleteCommunity(actor, community, removed) => {
         let deletable = DeletableObjects::Community(community.clone().into());
@@ -339,3 +394,84 @@ pub async fn match_outgoing_activities(
   fed_task.await?;
   Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::{
+    objects::{person::tests::parse_lemmy_person, tests::init_context},
+    protocol::{activities::voting::vote::Vote, tests::file_to_json_object},
+  };
+  use lemmy_db_schema::{
+    source::{
+      instance::Instance,
+      local_site::{LocalSiteInsertForm, LocalSiteUpdateForm},
+      person::Person,
+      site::{Site, SiteInsertForm},
+    },
+    traits::Crud,
+  };
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_send_with_federation_disabled() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let instance = Instance::read_or_create(&mut context.pool(), "my_domain.tld".to_string())
+      .await
+      .unwrap();
+    let site_form = SiteInsertForm::builder()
+      .name("test site".to_string())
+      .instance_id(instance.id)
+      .build();
+    let local = Site::create(&mut context.pool(), &site_form).await.unwrap();
+    let form = LocalSiteInsertForm::builder()
+      .site_id(local.id)
+      .federation_enabled(Some(false))
+      .build();
+    LocalSite::create(&mut context.pool(), &form).await.unwrap();
+
+    let remote_inbox = Url::parse("https://remote.example/inbox").unwrap();
+    let (context, person) = (&context, &person);
+    let send = move |targets: ActivitySendTargets| async move {
+      let mut vote: Vote =
+        file_to_json_object("assets/lemmy/activities/voting/like_note.json").unwrap();
+      vote.id = generate_activity_id("like", "https://enterprise.lemmy.ml").unwrap();
+      send_lemmy_activity(context, vote.clone(), person, targets, false)
+        .await
+        .unwrap();
+      SentActivity::read_from_apub_id(&mut context.pool(), &vote.id.into())
+        .await
+        .unwrap()
+    };
+
+    // the activity is stored, so it can still be fetched locally, but has no remote targets
+    let mut targets = ActivitySendTargets::to_inbox(remote_inbox.clone());
+    targets.set_all_instances();
+    let sent = send(targets.clone()).await;
+    assert!(sent.send_inboxes.is_empty());
+    assert!(!sent.send_all_instances);
+
+    let form = LocalSiteUpdateForm {
+      federation_enabled: Some(true),
+      ..Default::default()
+    };
+    LocalSite::update(&mut context.pool(), &form).await.unwrap();
+    let sent = send(targets).await;
+    assert_eq!(vec![Some(remote_inbox.into())], sent.send_inboxes);
+    assert!(sent.send_all_instances);
+
+    LocalSite::delete(&mut context.pool()).await.unwrap();
+    Instance::delete(&mut context.pool(), instance.id)
+      .await
+      .unwrap();
+    Person::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+}
@@ -0,0 +1,149 @@
+use crate::{
+  activities::community::send_activity_in_community,
+  activity_lists::AnnouncableActivities,
+  fetcher::post_or_comment::PostOrComment,
+  objects::{community::ApubCommunity, person::ApubPerson},
+  protocol::activities::reaction::{emoji_react::EmojiReact, undo_emoji_react::UndoEmojiReact},
+};
+use activitypub_federation::{config::Data, fetch::object_id::ObjectId};
+use lemmy_api_common::context::LemmyContext;
+use lemmy_db_schema::{
+  newtypes::DbUrl,
+  source::{
+    activity::ActivitySendTargets,
+    community::Community,
+    person::Person,
+    reaction::ReactionForm,
+  },
+};
+use lemmy_utils::error::LemmyError;
+
+pub mod emoji_react;
+pub mod undo_emoji_react;
+
+/// Sends a reaction to the community, or undoes a previous one with the same emoji if `add` is
+/// false. Remote platforms which don't understand reactions reject or ignore the activity, which
+/// doesn't affect delivery of other activities.
+pub(crate) async fn send_emoji_react(
+  object_id: DbUrl,
+  actor: Person,
+  community: Community,
+  shortcode: String,
+  add: bool,
+  context: Data<LemmyContext>,
+) -> Result<(), LemmyError> {
+  let object_id: ObjectId<PostOrComment> = object_id.try_into()?;
+  let actor: ApubPerson = actor.into();
+  let community: ApubCommunity = community.into();
+
+  let empty = ActivitySendTargets::empty();
+  let react = EmojiReact::new(object_id, &actor, &community, shortcode, &context)?;
+  let activity = if add {
+    AnnouncableActivities::EmojiReact(react)
+  } else {
+    let undo = UndoEmojiReact::new(react, &actor, &community, &context)?;
+    AnnouncableActivities::UndoEmojiReact(undo)
+  };
+  send_activity_in_community(activity, &actor, &community, empty, false, &context).await
+}
+
+fn reaction_form(actor: &ApubPerson, object: &PostOrComment, shortcode: &str) -> ReactionForm {
+  let (post_id, comment_id) = match object {
+    PostOrComment::Post(p) => (p.id, None),
+    PostOrComment::Comment(c) => (c.post_id, Some(c.id)),
+  };
+  ReactionForm {
+    person_id: actor.id,
+    post_id,
+    comment_id,
+    shortcode: shortcode.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::{
+    objects::{
+      community::tests::parse_lemmy_community,
+      person::tests::parse_lemmy_person,
+      post::ApubPost,
+      tests::init_context,
+    },
+    protocol::tests::file_to_json_object,
+  };
+  use activitypub_federation::traits::{ActivityHandler, Object};
+  use lemmy_db_schema::{
+    source::{post::Post, reaction::Reaction, site::Site},
+    traits::Crud,
+  };
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_emoji_react_and_undo() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+    let json = file_to_json_object("assets/lemmy/objects/page.json").unwrap();
+    let post = ApubPost::from_json(json, &context).await.unwrap();
+    let object_id: ObjectId<PostOrComment> = post.ap_id.clone().into();
+
+    // reacting twice with the same emoji only stores one reaction
+    for _ in 0..2 {
+      let react = EmojiReact::new(
+        object_id.clone(),
+        &person,
+        &community,
+        "👍".into(),
+        &context,
+      )
+      .unwrap();
+      let json = serde_json::to_string(&react).unwrap();
+      let react: EmojiReact = serde_json::from_str(&json).unwrap();
+      react.verify(&context).await.unwrap();
+      react.receive(&context).await.unwrap();
+    }
+    let reactions = Reaction::list(&mut context.pool(), post.id, None)
+      .await
+      .unwrap();
+    assert_eq!(1, reactions.len());
+    assert_eq!("👍", reactions[0].shortcode);
+    assert_eq!(person.id, reactions[0].person_id);
+
+    let react = EmojiReact::new(
+      object_id.clone(),
+      &person,
+      &community,
+      "👍".into(),
+      &context,
+    )
+    .unwrap();
+    let undo = UndoEmojiReact::new(react, &person, &community, &context).unwrap();
+    let json = serde_json::to_string(&undo).unwrap();
+    let undo: UndoEmojiReact = serde_json::from_str(&json).unwrap();
+    undo.verify(&context).await.unwrap();
+    undo.receive(&context).await.unwrap();
+    let reactions = Reaction::list(&mut context.pool(), post.id, None)
+      .await
+      .unwrap();
+    assert!(reactions.is_empty());
+
+    // reactions which aren't a single emoji are rejected
+    let react = EmojiReact::new(object_id, &person, &community, String::new(), &context).unwrap();
+    assert!(react.verify(&context).await.is_err());
+    assert_eq!(context.request_count(), 0);
+
+    Post::delete(&mut context.pool(), post.id).await.unwrap();
+    Person::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Community::delete(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+}
@@ -2,6 +2,7 @@ use crate::{
   activities::community::send_activity_in_community,
   activity_lists::AnnouncableActivities,
   fetcher::post_or_comment::PostOrComment,
+  local_site_cached,
   objects::{comment::ApubComment, community::ApubCommunity, person::ApubPerson, post::ApubPost},
   protocol::activities::voting::{
     undo_vote::UndoVote,
@@ -21,7 +22,7 @@ use lemmy_db_schema::{
   },
   traits::Likeable,
 };
-use lemmy_utils::error::LemmyError;
+use lemmy_utils::error::{LemmyError, LemmyResult};
 
 pub mod undo_vote;
 pub mod vote;
@@ -52,6 +53,15 @@ pub(crate) async fn send_like_activity(
   }
 }
 
+/// Whether votes are federated. If not, outgoing votes are dropped in [send_lemmy_activity] and
+/// incoming ones are ignored.
+///
+/// [send_lemmy_activity]: crate::activities::send_lemmy_activity
+async fn federate_votes(context: &Data<LemmyContext>) -> LemmyResult<bool> {
+  let local_site = local_site_cached(context).await?;
+  Ok(local_site.map_or(true, |l| l.federate_votes))
+}
+
 #[tracing::instrument(skip_all)]
 async fn vote_comment(
   vote_type: &VoteType,
@@ -114,3 +124,130 @@ async fn undo_vote_post(
   PostLike::remove(&mut context.pool(), person_id, post_id).await?;
   Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::{
+    objects::{
+      community::tests::parse_lemmy_community,
+      person::tests::parse_lemmy_person,
+      tests::init_context,
+    },
+    protocol::tests::file_to_json_object,
+    LOCAL_SITE_DATA_CACHE,
+  };
+  use activitypub_federation::traits::{ActivityHandler, Object};
+  use chrono::Utc;
+  use lemmy_db_schema::{
+    aggregates::structs::PostAggregates,
+    source::{
+      activity::SentActivity,
+      instance::Instance,
+      local_site::{LocalSite, LocalSiteInsertForm, LocalSiteUpdateForm},
+      post::Post,
+      site::{Site, SiteInsertForm},
+    },
+    traits::Crud,
+  };
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_vote_federation_disabled() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+    let json = file_to_json_object("assets/lemmy/objects/page.json").unwrap();
+    let post = ApubPost::from_json(json, &context).await.unwrap();
+    let object_id: ObjectId<PostOrComment> = post.ap_id.clone().into();
+
+    let instance = Instance::read_or_create(&mut context.pool(), "my_domain.tld".to_string())
+      .await
+      .unwrap();
+    let site_form = SiteInsertForm::builder()
+      .name("test site".to_string())
+      .instance_id(instance.id)
+      .build();
+    let local = Site::create(&mut context.pool(), &site_form).await.unwrap();
+    let form = LocalSiteInsertForm::builder()
+      .site_id(local.id)
+      .federate_votes(Some(false))
+      .build();
+    LocalSite::create(&mut context.pool(), &form).await.unwrap();
+    LOCAL_SITE_DATA_CACHE.invalidate_all();
+
+    let (context, person, community) = (&context, &person, &community);
+    let receive_like = || async {
+      let vote = Vote::new(
+        object_id.clone(),
+        person,
+        community,
+        VoteType::Like,
+        context,
+      )
+      .unwrap();
+      let json = serde_json::to_string(&vote).unwrap();
+      let vote: Vote = serde_json::from_str(&json).unwrap();
+      vote.verify(context).await.unwrap();
+      vote.receive(context).await.unwrap();
+      PostAggregates::read(&mut context.pool(), post.id)
+        .await
+        .unwrap()
+        .upvotes
+    };
+    // only likes which were stored during this test
+    let start = Utc::now();
+    let send_like = || async {
+      send_like_activity(
+        post.ap_id.clone(),
+        person.0.clone(),
+        community.0.clone(),
+        1,
+        context.reset_request_count(),
+      )
+      .await
+      .unwrap();
+      SentActivity::list_for_actor(&mut context.pool(), &person.actor_id, None, None)
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|a| a.published >= start && a.data["type"] == "Like")
+        .collect::<Vec<_>>()
+    };
+
+    // the incoming like is ignored, and the outgoing one isn't stored for sending
+    assert_eq!(0, receive_like().await);
+    assert!(send_like().await.is_empty());
+
+    let form = LocalSiteUpdateForm {
+      federate_votes: Some(true),
+      ..Default::default()
+    };
+    LocalSite::update(&mut context.pool(), &form).await.unwrap();
+    LOCAL_SITE_DATA_CACHE.invalidate_all();
+    assert_eq!(1, receive_like().await);
+    let sent = send_like().await;
+    assert_eq!(1, sent.len());
+    assert_eq!(Some("Like"), sent[0].data["type"].as_str());
+    assert_eq!(context.request_count(), 0);
+
+    LocalSite::delete(&mut context.pool()).await.unwrap();
+    LOCAL_SITE_DATA_CACHE.invalidate_all();
+    Site::delete(&mut context.pool(), local.id).await.unwrap();
+    Instance::delete(&mut context.pool(), instance.id)
+      .await
+      .unwrap();
+    Post::delete(&mut context.pool(), post.id).await.unwrap();
+    Person::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Community::delete(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+}
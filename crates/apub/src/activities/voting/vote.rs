@@ -2,7 +2,7 @@ use crate::{
   activities::{
     generate_activity_id,
     verify_person_in_community,
-    voting::{vote_comment, vote_post},
+    voting::{federate_votes, vote_comment, vote_post},
   },
   insert_received_activity,
   objects::{community::ApubCommunity, person::ApubPerson},
@@ -72,6 +72,9 @@ impl ActivityHandler for Vote {
 
   #[tracing::instrument(skip_all)]
   async fn receive(self, context: &Data<LemmyContext>) -> Result<(), LemmyError> {
+    if !federate_votes(context).await? {
+      return Ok(());
+    }
     let actor = self.actor.dereference(context).await?;
     let object = self.object.dereference(context).await?;
     match object {
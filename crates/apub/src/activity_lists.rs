@@ -1,8 +1,14 @@
 use crate::{
+  http::count_inbox_rate_limit,
   objects::community::ApubCommunity,
   protocol::{
     activities::{
-      block::{block_user::BlockUser, undo_block_user::UndoBlockUser},
+      block::{
+        block_actor::BlockActor,
+        block_user::BlockUser,
+        undo_block_actor::UndoBlockActor,
+        undo_block_user::UndoBlockUser,
+      },
       community::{
         announce::{AnnounceActivity, RawAnnouncableActivities},
         collection_add::CollectionAdd,
@@ -15,9 +21,17 @@ use crate::{
         chat_message::CreateOrUpdateChatMessage,
         note::CreateOrUpdateNote,
         page::CreateOrUpdatePage,
+        poll_vote::CreatePollVote,
       },
       deletion::{delete::Delete, delete_user::DeleteUser, undo_delete::UndoDelete},
-      following::{accept::AcceptFollow, follow::Follow, undo_follow::UndoFollow},
+      following::{
+        accept::AcceptFollow,
+        accept_batch_follow::AcceptBatchFollow,
+        batch_follow::BatchFollow,
+        follow::Follow,
+        undo_follow::UndoFollow,
+      },
+      reaction::{emoji_react::EmojiReact, undo_emoji_react::UndoEmojiReact},
       voting::{undo_vote::UndoVote, vote::Vote},
     },
     objects::page::Page,
@@ -26,8 +40,9 @@ use crate::{
 };
 use activitypub_federation::{config::Data, traits::ActivityHandler};
 use lemmy_api_common::context::LemmyContext;
-use lemmy_utils::error::LemmyError;
+use lemmy_utils::error::{LemmyError, LemmyErrorExt, LemmyErrorType};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use url::Url;
 
 /// List of activities which the shared inbox can handle.
@@ -41,7 +56,12 @@ use url::Url;
 pub enum SharedInboxActivities {
   Follow(Follow),
   AcceptFollow(AcceptFollow),
+  AcceptBatchFollow(AcceptBatchFollow),
   UndoFollow(UndoFollow),
+  BatchFollow(BatchFollow),
+  BlockActor(BlockActor),
+  UndoBlockActor(UndoBlockActor),
+  CreatePollVote(CreatePollVote),
   CreateOrUpdatePrivateMessage(CreateOrUpdateChatMessage),
   Report(Report),
   AnnounceActivity(AnnounceActivity),
@@ -56,6 +76,9 @@ pub enum SharedInboxActivities {
 pub enum GroupInboxActivities {
   Follow(Follow),
   UndoFollow(UndoFollow),
+  BatchFollow(BatchFollow),
+  BlockActor(BlockActor),
+  UndoBlockActor(UndoBlockActor),
   Report(Report),
   /// This is a catch-all and needs to be last
   AnnouncableActivities(RawAnnouncableActivities),
@@ -69,6 +92,9 @@ pub enum PersonInboxActivities {
   Follow(Follow),
   AcceptFollow(AcceptFollow),
   UndoFollow(UndoFollow),
+  BlockActor(BlockActor),
+  UndoBlockActor(UndoBlockActor),
+  CreatePollVote(CreatePollVote),
   CreateOrUpdatePrivateMessage(CreateOrUpdateChatMessage),
   Delete(Delete),
   UndoDelete(UndoDelete),
@@ -85,6 +111,8 @@ pub enum AnnouncableActivities {
   CreateOrUpdatePost(CreateOrUpdatePage),
   Vote(Vote),
   UndoVote(UndoVote),
+  EmojiReact(EmojiReact),
+  UndoEmojiReact(UndoEmojiReact),
   Delete(Delete),
   UndoDelete(UndoDelete),
   UpdateCommunity(UpdateCommunity),
@@ -105,7 +133,59 @@ pub enum AnnouncableActivities {
 pub enum SiteInboxActivities {
   BlockUser(BlockUser),
   UndoBlockUser(UndoBlockUser),
+  BlockActor(BlockActor),
+  UndoBlockActor(UndoBlockActor),
   DeleteUser(DeleteUser),
+  AcceptBatchFollow(AcceptBatchFollow),
+}
+
+/// Wrapper for incoming activities which aborts verification if it takes too long.
+///
+/// Verifying can trigger remote fetches of actors and parent objects, which may be very slow.
+/// Without a limit, a single activity could tie up an inbox worker indefinitely.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct VerifyWithTimeout<A>(A);
+
+#[async_trait::async_trait]
+impl<A> ActivityHandler for VerifyWithTimeout<A>
+where
+  A: ActivityHandler<DataType = LemmyContext, Error = LemmyError> + Send + Sync,
+{
+  type DataType = LemmyContext;
+  type Error = LemmyError;
+
+  fn id(&self) -> &Url {
+    self.0.id()
+  }
+
+  fn actor(&self) -> &Url {
+    self.0.actor()
+  }
+
+  async fn verify(&self, context: &Data<Self::DataType>) -> Result<(), Self::Error> {
+    // this only runs once the signature was verified with the key of the actor
+    count_inbox_rate_limit(self.actor(), context.settings());
+    let timeout = Duration::from_secs(context.settings().federation.verify_timeout_seconds);
+    verify_with_timeout(&self.0, timeout, context).await
+  }
+
+  async fn receive(self, context: &Data<Self::DataType>) -> Result<(), Self::Error> {
+    self.0.receive(context).await
+  }
+}
+
+async fn verify_with_timeout<A>(
+  activity: &A,
+  timeout: Duration,
+  context: &Data<LemmyContext>,
+) -> Result<(), LemmyError>
+where
+  A: ActivityHandler<DataType = LemmyContext, Error = LemmyError> + Sync,
+{
+  tokio::time::timeout(timeout, activity.verify(context))
+    .await
+    .with_lemmy_type(LemmyErrorType::ActivityVerificationTimeout)?
 }
 
 #[async_trait::async_trait]
@@ -118,6 +198,8 @@ impl InCommunity for AnnouncableActivities {
       CreateOrUpdatePost(a) => a.community(context).await,
       Vote(a) => a.community(context).await,
       UndoVote(a) => a.community(context).await,
+      EmojiReact(a) => a.community(context).await,
+      UndoEmojiReact(a) => a.community(context).await,
       Delete(a) => a.community(context).await,
       UndoDelete(a) => a.community(context).await,
       UpdateCommunity(a) => a.community(context).await,
@@ -137,10 +219,12 @@ mod tests {
   #![allow(clippy::unwrap_used)]
   #![allow(clippy::indexing_slicing)]
 
+  use super::*;
   use crate::{
-    activity_lists::{GroupInboxActivities, PersonInboxActivities, SiteInboxActivities},
-    protocol::tests::{test_json, test_parse_lemmy_item},
+    objects::tests::init_context,
+    protocol::tests::{file_to_json_object, test_json, test_parse_lemmy_item},
   };
+  use serial_test::serial;
 
   #[test]
   fn test_group_inbox() {
@@ -165,6 +249,23 @@ mod tests {
     )
     .unwrap();
     test_json::<PersonInboxActivities>("assets/mastodon/activities/follow.json").unwrap();
+    let path = "assets/mastodon/activities/create_poll_vote.json";
+    let vote = file_to_json_object::<PersonInboxActivities>(path).unwrap();
+    assert!(matches!(vote, PersonInboxActivities::CreatePollVote(_)));
+  }
+
+  #[test]
+  fn test_shared_inbox_block() {
+    let path = "assets/lemmy/activities/block/block_actor.json";
+    let block = file_to_json_object::<SharedInboxActivities>(path).unwrap();
+    assert!(matches!(block, SharedInboxActivities::BlockActor(_)));
+    // bans by moderators must not be handled as a personal block
+    let path = "assets/lemmy/activities/block/block_user.json";
+    let ban = file_to_json_object::<SharedInboxActivities>(path).unwrap();
+    assert!(matches!(
+      ban,
+      SharedInboxActivities::RawAnnouncableActivities(_)
+    ));
   }
 
   #[test]
@@ -173,5 +274,53 @@ mod tests {
       "assets/lemmy/activities/deletion/delete_user.json",
     )
     .unwrap();
+    let path = "assets/lemmy/activities/block/block_actor.json";
+    let block = file_to_json_object::<SiteInboxActivities>(path).unwrap();
+    assert!(matches!(block, SiteInboxActivities::BlockActor(_)));
+    let path = "assets/lemmy/activities/following/accept_batch_follow.json";
+    let accept = file_to_json_object::<SiteInboxActivities>(path).unwrap();
+    assert!(matches!(
+      accept,
+      SharedInboxActivities::AcceptBatchFollow(_)
+    ));
+  }
+
+  /// Activity whose verification waits for a remote fetch which never completes
+  struct StalledActivity(Url);
+
+  #[async_trait::async_trait]
+  impl ActivityHandler for StalledActivity {
+    type DataType = LemmyContext;
+    type Error = LemmyError;
+
+    fn id(&self) -> &Url {
+      &self.0
+    }
+
+    fn actor(&self) -> &Url {
+      &self.0
+    }
+
+    async fn verify(&self, _context: &Data<Self::DataType>) -> Result<(), Self::Error> {
+      tokio::time::sleep(Duration::from_secs(3600)).await;
+      Ok(())
+    }
+
+    async fn receive(self, _context: &Data<Self::DataType>) -> Result<(), Self::Error> {
+      Ok(())
+    }
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_verify_timeout() {
+    let context = init_context().await;
+    let activity = StalledActivity(Url::parse("https://example.com/activities/1").unwrap());
+
+    let res = verify_with_timeout(&activity, Duration::from_millis(50), &context).await;
+    assert_eq!(
+      res.unwrap_err().error_type,
+      LemmyErrorType::ActivityVerificationTimeout
+    );
   }
 }
@@ -11,7 +11,7 @@ use lemmy_api_common::{
   utils::check_private_instance,
 };
 use lemmy_db_schema::{
-  source::{comment::Comment, community::Community, local_site::LocalSite},
+  source::{comment::Comment, community::Community, local_site::LocalSite, post::Post},
   traits::Crud,
 };
 use lemmy_db_views::{comment_view::CommentQuery, structs::LocalUserView};
@@ -32,7 +32,16 @@ pub async fn list_comments(
   } else {
     data.community_id
   };
-  let sort = data.sort;
+  // Without an explicit sort, use the default of the post if it has one
+  let sort = match (data.sort, data.post_id) {
+    (None, Some(post_id)) => {
+      Post::read(&mut context.pool(), post_id)
+        .await
+        .with_lemmy_type(LemmyErrorType::CouldntFindPost)?
+        .default_comment_sort_type
+    }
+    (sort, _) => sort,
+  };
   let max_depth = data.max_depth;
   let saved_only = data.saved_only.unwrap_or_default();
 
@@ -10,7 +10,10 @@ use lemmy_api_common::{
   post::{GetPosts, GetPostsResponse},
   utils::check_private_instance,
 };
-use lemmy_db_schema::source::{community::Community, local_site::LocalSite};
+use lemmy_db_schema::{
+  source::{community::Community, local_site::LocalSite},
+  traits::Crud,
+};
 use lemmy_db_views::{
   post_view::PostQuery,
   structs::{LocalUserView, PaginationCursor},
@@ -27,8 +30,6 @@ pub async fn list_posts(
 
   check_private_instance(&local_user_view, &local_site)?;
 
-  let sort = data.sort;
-
   let page = data.page;
   let limit = data.limit;
   let community_id = if let Some(name) = &data.community_name {
@@ -37,6 +38,14 @@ pub async fn list_posts(
   } else {
     data.community_id
   };
+  // use the default sort of the community, unless the client requested a specific one
+  let sort = match (data.sort, community_id) {
+    (None, Some(community_id)) => Community::read(&mut context.pool(), community_id)
+      .await
+      .ok()
+      .and_then(|c| c.default_sort_type),
+    (sort, _) => sort,
+  };
   let saved_only = data.saved_only.unwrap_or_default();
 
   let liked_only = data.liked_only.unwrap_or_default();
@@ -1,8 +1,6 @@
-use crate::objects::{
-  comment::ApubComment,
-  community::ApubCommunity,
-  person::ApubPerson,
-  post::ApubPost,
+use crate::{
+  fetcher::retry::dereference_with_retry,
+  objects::{comment::ApubComment, community::ApubCommunity, person::ApubPerson, post::ApubPost},
 };
 use activitypub_federation::{config::Data, fetch::object_id::ObjectId};
 use actix_web::web::Json;
@@ -159,7 +157,7 @@ pub async fn import_settings(
         .map(|f| (f, context.reset_request_count()))
         .map(|(followed, context)| async move {
           // need to reset outgoing request count to avoid running into limit
-          let community = followed.dereference(&context).await?;
+          let community = dereference_with_retry(&followed, &context).await?;
           let form = CommunityFollowerForm {
             person_id,
             community_id: community.id,
@@ -188,7 +186,7 @@ pub async fn import_settings(
         .into_iter()
         .map(|s| (s, context.reset_request_count()))
         .map(|(saved, context)| async move {
-          let post = saved.dereference(&context).await?;
+          let post = dereference_with_retry(&saved, &context).await?;
           let form = PostSavedForm {
             person_id,
             post_id: post.id,
@@ -216,7 +214,7 @@ pub async fn import_settings(
         .into_iter()
         .map(|s| (s, context.reset_request_count()))
         .map(|(saved, context)| async move {
-          let comment = saved.dereference(&context).await?;
+          let comment = dereference_with_retry(&saved, &context).await?;
           let form = CommentSavedForm {
             person_id,
             comment_id: comment.id,
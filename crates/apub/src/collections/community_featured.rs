@@ -1,4 +1,5 @@
 use crate::{
+  collections::handle_collection_items,
   objects::{community::ApubCommunity, post::ApubPost},
   protocol::collections::group_featured::GroupFeatured,
 };
@@ -8,9 +9,9 @@ use activitypub_federation::{
   protocol::verification::verify_domains_match,
   traits::{ActivityHandler, Collection, Object},
 };
-use futures::future::{join_all, try_join_all};
+use futures::future::try_join_all;
 use lemmy_api_common::{context::LemmyContext, utils::generate_featured_url};
-use lemmy_db_schema::{source::post::Post, utils::FETCH_LIMIT_MAX};
+use lemmy_db_schema::source::post::Post;
 use lemmy_utils::error::LemmyError;
 use url::Url;
 
@@ -61,28 +62,16 @@ impl Collection for ApubCommunityFeatured {
   where
     Self: Sized,
   {
-    let mut posts = apub.ordered_items;
-    if posts.len() as i64 > FETCH_LIMIT_MAX {
-      posts = posts
-        .get(0..(FETCH_LIMIT_MAX as usize))
-        .unwrap_or_default()
-        .to_vec();
-    }
-
     // We intentionally ignore errors here. This is because the outbox might contain posts from old
     // Lemmy versions, or from other software which we cant parse. In that case, we simply skip the
     // item and only parse the ones that work.
     // process items in parallel, to avoid long delay from fetch_site_metadata() and other processing
-    join_all(posts.into_iter().map(|post| {
-      async {
-        // use separate request counter for each item, otherwise there will be problems with
-        // parallel processing
-        let verify = post.verify(data).await;
-        if verify.is_ok() {
-          post.receive(data).await.ok();
-        }
+    handle_collection_items(apub.ordered_items, data, |post| async move {
+      let verify = post.verify(data).await;
+      if verify.is_ok() {
+        post.receive(data).await.ok();
       }
-    }))
+    })
     .await;
 
     // This return value is unused, so just set an empty vec
@@ -1,4 +1,5 @@
 use crate::{
+  collections::handle_collection_items,
   objects::{community::ApubCommunity, person::ApubPerson},
   protocol::collections::group_moderators::GroupModerators,
 };
@@ -77,21 +78,22 @@ impl Collection for ApubCommunityModerators {
     }
 
     // Add new mods to database which have been added to moderators collection
-    for mod_id in apub.ordered_items {
+    let mod_users = handle_collection_items(apub.ordered_items, data, |mod_id| {
       // Ignore errors as mod accounts might be deleted or instances unavailable.
-      let mod_user: Option<ApubPerson> = mod_id.dereference(data).await.ok();
-      if let Some(mod_user) = mod_user {
-        if !current_moderators
-          .iter()
-          .map(|c| c.moderator.actor_id.clone())
-          .any(|x| x == mod_user.actor_id)
-        {
-          let community_moderator_form = CommunityModeratorForm {
-            community_id: owner.id,
-            person_id: mod_user.id,
-          };
-          CommunityModerator::join(&mut data.pool(), &community_moderator_form).await?;
-        }
+      async move { mod_id.dereference(data).await.ok() }
+    })
+    .await;
+    for mod_user in mod_users.into_iter().flatten() {
+      if !current_moderators
+        .iter()
+        .map(|c| c.moderator.actor_id.clone())
+        .any(|x| x == mod_user.actor_id)
+      {
+        let community_moderator_form = CommunityModeratorForm {
+          community_id: owner.id,
+          person_id: mod_user.id,
+        };
+        CommunityModerator::join(&mut data.pool(), &community_moderator_form).await?;
       }
     }
 
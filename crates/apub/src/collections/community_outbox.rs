@@ -1,5 +1,8 @@
 use crate::{
   activity_lists::AnnouncableActivities,
+  collections::handle_collection_items,
+  http_fetch_limit,
+  local_site_data_cached,
   objects::{community::ApubCommunity, post::ApubPost},
   protocol::{
     activities::{
@@ -7,23 +10,25 @@ use crate::{
       create_or_update::page::CreateOrUpdatePage,
       CreateOrUpdateType,
     },
-    collections::group_outbox::GroupOutbox,
+    collections::group_outbox::{GroupOutbox, GroupOutboxPage},
   },
 };
 use activitypub_federation::{
   config::Data,
+  fetch::fetch_object_http,
   kinds::collection::OrderedCollectionType,
   protocol::verification::verify_domains_match,
   traits::{ActivityHandler, Collection},
 };
-use futures::future::join_all;
 use lemmy_api_common::{context::LemmyContext, utils::generate_outbox_url};
 use lemmy_db_schema::{
   source::{person::Person, post::Post},
   traits::Crud,
   utils::FETCH_LIMIT_MAX,
 };
-use lemmy_utils::error::LemmyError;
+use lemmy_utils::error::{LemmyError, LemmyErrorType, LemmyResult};
+use std::future::Future;
+use tracing::debug;
 use url::Url;
 
 #[derive(Clone, Debug)]
@@ -63,6 +68,7 @@ impl Collection for ApubCommunityOutbox {
       id: generate_outbox_url(&owner.actor_id)?.into(),
       total_items: ordered_items.len() as i32,
       ordered_items,
+      first: None,
     })
   }
 
@@ -82,31 +88,132 @@ impl Collection for ApubCommunityOutbox {
     _owner: &Self::Owner,
     data: &Data<Self::DataType>,
   ) -> Result<Self, LemmyError> {
-    let mut outbox_activities = apub.ordered_items;
-    if outbox_activities.len() as i64 > FETCH_LIMIT_MAX {
-      outbox_activities = outbox_activities
-        .get(0..(FETCH_LIMIT_MAX as usize))
-        .unwrap_or_default()
-        .to_vec();
-    }
+    let outbox_activities = outbox_items(apub, data).await?;
 
     // We intentionally ignore errors here. This is because the outbox might contain posts from old
     // Lemmy versions, or from other software which we cant parse. In that case, we simply skip the
     // item and only parse the ones that work.
     // process items in parallel, to avoid long delay from fetch_site_metadata() and other processing
-    join_all(outbox_activities.into_iter().map(|activity| {
-      async {
-        // use separate request counter for each item, otherwise there will be problems with
-        // parallel processing
-        let verify = activity.verify(data).await;
-        if verify.is_ok() {
-          activity.receive(data).await.ok();
-        }
+    handle_collection_items(outbox_activities, data, |activity| async move {
+      let verify = activity.verify(data).await;
+      if verify.is_ok() {
+        activity.receive(data).await.ok();
       }
-    }))
+    })
     .await;
 
     // This return value is unused, so just set an empty vec
     Ok(ApubCommunityOutbox(Vec::new()))
   }
 }
+
+/// Returns the items of an outbox, including those on further pages for outboxes which are split
+/// into pages.
+pub(crate) async fn outbox_items(
+  outbox: GroupOutbox,
+  data: &Data<LemmyContext>,
+) -> LemmyResult<Vec<AnnounceActivity>> {
+  let mut outbox_activities = outbox.ordered_items;
+  if outbox_activities.len() as i64 > FETCH_LIMIT_MAX {
+    outbox_activities = outbox_activities
+      .get(0..(FETCH_LIMIT_MAX as usize))
+      .unwrap_or_default()
+      .to_vec();
+  }
+
+  // Large outboxes from other software are split into pages, which need to be fetched separately
+  if let Some(first) = outbox.first {
+    let domain = outbox.id.domain().ok_or(LemmyErrorType::UrlWithoutDomain)?;
+    let local_site_data = local_site_data_cached(data).await?;
+    let max_pages = http_fetch_limit(domain, local_site_data.local_site.as_ref(), data.settings());
+    let outbox_id = &outbox.id;
+    let pages = fetch_outbox_pages(first, max_pages, |url| async move {
+      let page: GroupOutboxPage = fetch_object_http(&url, data).await?.object;
+      verify_domains_match(outbox_id, &page.id)?;
+      Ok(page)
+    })
+    .await;
+    outbox_activities.extend(pages);
+  }
+  Ok(outbox_activities)
+}
+
+/// Follows the `next` links of a paginated outbox starting from `first`, and returns the items of
+/// all pages. At most `max_pages` pages are fetched. A page which can't be fetched or parsed ends
+/// the import, but the items from previous pages are still returned.
+async fn fetch_outbox_pages<F, Fut>(
+  first: Url,
+  max_pages: u32,
+  fetch_page: F,
+) -> Vec<AnnounceActivity>
+where
+  F: Fn(Url) -> Fut,
+  Fut: Future<Output = LemmyResult<GroupOutboxPage>>,
+{
+  let mut items = vec![];
+  let mut visited = vec![];
+  let mut next = Some(first);
+  while let Some(url) = next.take() {
+    // a page linking back to an earlier one would loop forever
+    if visited.len() >= max_pages as usize || visited.contains(&url) {
+      break;
+    }
+    visited.push(url.clone());
+    match fetch_page(url.clone()).await {
+      Ok(page) => {
+        items.extend(page.ordered_items);
+        next = page.next;
+      }
+      Err(e) => debug!("Failed to fetch outbox page {url}: {e}"),
+    }
+  }
+  items
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::protocol::tests::file_to_json_object;
+
+  fn read_page(url: &Url) -> LemmyResult<GroupOutboxPage> {
+    match url.query() {
+      Some("page=1") => file_to_json_object("assets/lemmy/collections/group_outbox_page_1.json"),
+      Some("page=2") => file_to_json_object("assets/lemmy/collections/group_outbox_page_2.json"),
+      _ => Err(LemmyErrorType::CouldntFindObject.into()),
+    }
+  }
+
+  #[tokio::test]
+  async fn test_fetch_outbox_pages() {
+    let outbox: GroupOutbox =
+      file_to_json_object("assets/lemmy/collections/group_outbox_paginated.json").unwrap();
+    let first = outbox.first.unwrap();
+
+    let items = fetch_outbox_pages(first.clone(), 10, |url| async move { read_page(&url) }).await;
+    let ids: Vec<_> = items.iter().map(|i| i.id.as_str()).collect();
+    assert_eq!(
+      vec![
+        "https://ds9.lemmy.ml/activities/announce/b204fe9f-b13d-4af2-9d22-239ac2d892e6",
+        "https://ds9.lemmy.ml/activities/announce/c6c960ce-c8d8-4231-925e-3ba367468f18",
+      ],
+      ids
+    );
+
+    // the fetch limit applies
+    let items = fetch_outbox_pages(first.clone(), 1, |url| async move { read_page(&url) }).await;
+    assert_eq!(1, items.len());
+
+    // items from before a broken page are kept
+    let items = fetch_outbox_pages(first, 10, |url| async move {
+      if url.query() == Some("page=2") {
+        return Err(LemmyErrorType::CouldntFindObject.into());
+      }
+      read_page(&url)
+    })
+    .await;
+    assert_eq!(1, items.len());
+  }
+}
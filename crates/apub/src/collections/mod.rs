@@ -1,4 +1,96 @@
+use activitypub_federation::config::Data;
+use futures::{Future, StreamExt};
+use lemmy_api_common::context::LemmyContext;
+use lemmy_db_schema::utils::FETCH_LIMIT_MAX;
+
 pub(crate) mod community_featured;
 pub(crate) mod community_follower;
 pub(crate) mod community_moderators;
 pub(crate) mod community_outbox;
+pub(crate) mod person_featured;
+
+/// Handles the items of a remote collection with `handle_item`, for example to dereference and
+/// store them. Items are handled in parallel, but at most `federation.collection_item_concurrency`
+/// at once so that the remote instance isn't overwhelmed. Only the first [FETCH_LIMIT_MAX] items
+/// are handled.
+///
+/// Returns the results in the order of the items. Errors for individual items need to be handled
+/// by `handle_item`, so that they don't abort the rest of the batch.
+pub(crate) async fn handle_collection_items<T, F, Fut>(
+  items: Vec<T>,
+  data: &Data<LemmyContext>,
+  handle_item: F,
+) -> Vec<Fut::Output>
+where
+  F: FnMut(T) -> Fut,
+  Fut: Future,
+{
+  let concurrency = data.settings().federation.collection_item_concurrency;
+  handle_items(items, FETCH_LIMIT_MAX as usize, concurrency, handle_item).await
+}
+
+/// Runs `handle_item` on the first `limit` items, with at most `concurrency` of them in flight.
+async fn handle_items<T, F, Fut>(
+  items: Vec<T>,
+  limit: usize,
+  concurrency: usize,
+  handle_item: F,
+) -> Vec<Fut::Output>
+where
+  F: FnMut(T) -> Fut,
+  Fut: Future,
+{
+  futures::stream::iter(items.into_iter().take(limit))
+    .map(handle_item)
+    // a concurrency of 0 would never make progress
+    .buffered(concurrency.max(1))
+    .collect()
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use lemmy_utils::error::LemmyErrorType;
+  use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+  };
+
+  #[tokio::test]
+  async fn test_handle_items_concurrency() {
+    let in_flight = AtomicUsize::new(0);
+    let max_in_flight = AtomicUsize::new(0);
+    // mock which dereferences 10 items, every third of them fails
+    let dereference = |item: usize| {
+      let (in_flight, max_in_flight) = (&in_flight, &max_in_flight);
+      async move {
+        let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        max_in_flight.fetch_max(current, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        in_flight.fetch_sub(1, Ordering::SeqCst);
+        if item % 3 == 0 {
+          Err(LemmyErrorType::CouldntFindObject)
+        } else {
+          Ok(item)
+        }
+      }
+    };
+
+    let results = handle_items((0..10).collect(), 50, 3, dereference).await;
+    assert_eq!(3, max_in_flight.load(Ordering::SeqCst));
+    // failed items don't abort the others, and the order is kept
+    assert_eq!(10, results.len());
+    let dereferenced: Vec<_> = results.into_iter().flatten().collect();
+    assert_eq!(vec![1, 2, 4, 5, 7, 8], dereferenced);
+
+    // the limit caps the total number of items
+    max_in_flight.store(0, Ordering::SeqCst);
+    let results = handle_items((0..10).collect(), 4, 8, dereference).await;
+    assert_eq!(4, results.len());
+    assert_eq!(4, max_in_flight.load(Ordering::SeqCst));
+  }
+}
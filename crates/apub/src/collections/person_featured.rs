@@ -0,0 +1,161 @@
+use crate::{
+  collections::handle_collection_items,
+  objects::{person::ApubPerson, post::ApubPost},
+  protocol::collections::group_featured::GroupFeatured,
+};
+use activitypub_federation::{
+  config::Data,
+  kinds::collection::OrderedCollectionType,
+  protocol::verification::verify_domains_match,
+  traits::{ActivityHandler, Collection, Object},
+};
+use futures::future::try_join_all;
+use lemmy_api_common::{context::LemmyContext, utils::generate_featured_url};
+use lemmy_db_schema::source::post::Post;
+use lemmy_utils::error::LemmyError;
+use url::Url;
+
+/// Posts which a user has featured (pinned) on their own profile.
+#[derive(Clone, Debug)]
+pub(crate) struct ApubPersonFeatured(Vec<ApubPost>);
+
+#[async_trait::async_trait]
+impl Collection for ApubPersonFeatured {
+  type Owner = ApubPerson;
+  type DataType = LemmyContext;
+  type Kind = GroupFeatured;
+  type Error = LemmyError;
+
+  async fn read_local(
+    owner: &Self::Owner,
+    data: &Data<Self::DataType>,
+  ) -> Result<Self::Kind, Self::Error> {
+    let ordered_items = try_join_all(
+      Post::list_featured_for_person(&mut data.pool(), owner.id)
+        .await?
+        .into_iter()
+        .map(ApubPost::from)
+        .map(|p| p.into_json(data)),
+    )
+    .await?;
+    Ok(GroupFeatured {
+      r#type: OrderedCollectionType::OrderedCollection,
+      id: generate_featured_url(&owner.actor_id)?.into(),
+      total_items: ordered_items.len() as i32,
+      ordered_items,
+    })
+  }
+
+  async fn verify(
+    apub: &Self::Kind,
+    expected_domain: &Url,
+    _data: &Data<Self::DataType>,
+  ) -> Result<(), Self::Error> {
+    verify_domains_match(expected_domain, &apub.id)?;
+    Ok(())
+  }
+
+  async fn from_json(
+    apub: Self::Kind,
+    owner: &Self::Owner,
+    data: &Data<Self::DataType>,
+  ) -> Result<Self, Self::Error>
+  where
+    Self: Sized,
+  {
+    // Errors are ignored so that a single unparseable item doesnt prevent reading the others.
+    // Posts by other users are skipped, as only the person themselves can feature on their
+    // profile.
+    let featured_post_ids = handle_collection_items(apub.ordered_items, data, |post| async move {
+      let creator = post.creator().ok()?;
+      if creator.inner() != owner.actor_id.inner() {
+        return None;
+      }
+      post.verify(data).await.ok()?;
+      ApubPost::from_json(post, data).await.ok().map(|p| p.id)
+    })
+    .await
+    .into_iter()
+    .flatten()
+    .collect();
+    Post::update_featured_for_person(&mut data.pool(), owner.id, featured_post_ids).await?;
+
+    // This return value is unused, so just set an empty vec
+    Ok(ApubPersonFeatured(Vec::new()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::{
+    objects::{
+      community::tests::parse_lemmy_community,
+      person::tests::parse_lemmy_person,
+      tests::init_context,
+    },
+    protocol::tests::file_to_json_object,
+  };
+  use lemmy_db_schema::{
+    source::{community::Community, person::Person, post::PostUpdateForm, site::Site},
+    traits::Crud,
+  };
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_person_featured_round_trip() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+    let page = file_to_json_object("assets/lemmy/objects/page.json").unwrap();
+    let post = ApubPost::from_json(page, &context).await.unwrap();
+
+    // pin the post to the profile of its creator
+    let form = PostUpdateForm {
+      featured_profile: Some(true),
+      ..Default::default()
+    };
+    Post::update(&mut context.pool(), post.id, &form)
+      .await
+      .unwrap();
+
+    let featured = ApubPersonFeatured::read_local(&person, &context)
+      .await
+      .unwrap();
+    assert_eq!(1, featured.total_items);
+    assert_eq!(post.ap_id.inner(), featured.ordered_items[0].id.inner());
+
+    // unpin it again, then parse the collection which should restore the pin
+    let form = PostUpdateForm {
+      featured_profile: Some(false),
+      ..Default::default()
+    };
+    Post::update(&mut context.pool(), post.id, &form)
+      .await
+      .unwrap();
+    let url = Url::parse("https://enterprise.lemmy.ml/u/picard").unwrap();
+    ApubPersonFeatured::verify(&featured, &url, &context)
+      .await
+      .unwrap();
+    ApubPersonFeatured::from_json(featured, &person, &context)
+      .await
+      .unwrap();
+    assert_eq!(context.request_count(), 0);
+
+    let post = Post::read(&mut context.pool(), post.id).await.unwrap();
+    assert!(post.featured_profile);
+
+    Post::delete(&mut context.pool(), post.id).await.unwrap();
+    Person::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Community::delete(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+}
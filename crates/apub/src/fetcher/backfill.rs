@@ -0,0 +1,126 @@
+use crate::{
+  check_apub_id_valid_with_strictness,
+  collections::community_outbox::outbox_items,
+  objects::community::ApubCommunity,
+  protocol::{
+    activities::community::announce::AnnounceActivity,
+    collections::group_outbox::GroupOutbox,
+    objects::group::Group,
+  },
+};
+use activitypub_federation::{
+  config::Data,
+  fetch::fetch_object_http,
+  protocol::verification::verify_domains_match,
+  traits::ActivityHandler,
+};
+use lemmy_api_common::context::LemmyContext;
+use lemmy_utils::error::LemmyResult;
+use std::future::Future;
+use tracing::debug;
+
+/// Imports the latest posts of a remote community from its outbox, for example after an admin
+/// added the community. At most `limit` outbox items are handled.
+///
+/// Returns the number of newly received items. Items which were received before are skipped, so
+/// the backfill can be repeated without creating duplicates.
+#[tracing::instrument(skip(community, context))]
+pub async fn backfill_community(
+  community: &ApubCommunity,
+  limit: usize,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<usize> {
+  // read the outbox url from the group, as other software may use a different one than Lemmy
+  let group: Group = fetch_object_http(community.actor_id.inner(), context)
+    .await?
+    .object;
+  let outbox: GroupOutbox = fetch_object_http(group.outbox.inner(), context)
+    .await?
+    .object;
+  verify_domains_match(community.actor_id.inner(), &outbox.id)?;
+
+  let items = outbox_items(outbox, context).await?;
+  let inserted = receive_items(items, limit, context, |activity| async move {
+    activity.verify(context).await?;
+    activity.receive(context).await
+  })
+  .await;
+  Ok(inserted)
+}
+
+/// Passes up to `limit` items from allowed instances to `receive`, one after another, and returns
+/// how many of them were received successfully.
+async fn receive_items<F, Fut>(
+  items: Vec<AnnounceActivity>,
+  limit: usize,
+  context: &Data<LemmyContext>,
+  receive: F,
+) -> usize
+where
+  F: Fn(AnnounceActivity) -> Fut,
+  Fut: Future<Output = LemmyResult<()>>,
+{
+  let mut inserted = 0;
+  for activity in items.into_iter().take(limit) {
+    let id = activity.id.clone();
+    if let Err(e) = check_apub_id_valid_with_strictness(&id, false, context).await {
+      debug!("Skipping outbox item {id}: {e}");
+      continue;
+    }
+    // already received items fail here, as the activity id is stored when verifying
+    match receive(activity).await {
+      Ok(()) => inserted += 1,
+      Err(e) => debug!("Failed to receive outbox item {id}: {e}"),
+    }
+  }
+  inserted
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::{
+    insert_received_activity,
+    objects::tests::init_context,
+    protocol::tests::file_to_json_object,
+  };
+  use serial_test::serial;
+  use url::Url;
+  use uuid::Uuid;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_backfill_skips_duplicates() {
+    let context = init_context().await;
+    let outbox: GroupOutbox =
+      file_to_json_object("assets/lemmy/collections/group_outbox.json").unwrap();
+    let mut items = outbox.ordered_items;
+    // use new ids, so that the items weren't received by a previous test run
+    for item in &mut items {
+      let id = format!(
+        "https://ds9.lemmy.ml/activities/announce/{}",
+        Uuid::new_v4()
+      );
+      item.id = Url::parse(&id).unwrap();
+    }
+    // the outbox contains the first item twice
+    items.push(items[0].clone());
+    assert_eq!(3, items.len());
+
+    // receiving only stores the activity, which is where duplicates are rejected
+    let context_ref = &context;
+    let receive = move |activity: AnnounceActivity| async move {
+      insert_received_activity(&activity.id, context_ref).await
+    };
+
+    let inserted = receive_items(items.clone(), 1, &context, receive).await;
+    assert_eq!(1, inserted);
+    let inserted = receive_items(items.clone(), 10, &context, receive).await;
+    assert_eq!(1, inserted);
+    let inserted = receive_items(items, 10, &context, receive).await;
+    assert_eq!(0, inserted);
+  }
+}
@@ -1,6 +1,6 @@
 use activitypub_federation::{
   config::Data,
-  fetch::webfinger::webfinger_resolve_actor,
+  fetch::{object_id::ObjectId, webfinger::webfinger_resolve_actor},
   traits::{Actor, Object},
 };
 use diesel::NotFound;
@@ -8,11 +8,16 @@ use itertools::Itertools;
 use lemmy_api_common::context::LemmyContext;
 use lemmy_db_schema::traits::ApubActor;
 use lemmy_db_views::structs::LocalUserView;
-use lemmy_utils::error::LemmyError;
+use lemmy_utils::error::{LemmyError, LemmyErrorType, LemmyResult};
 
+pub mod backfill;
+pub mod nodeinfo;
 pub mod post_or_comment;
+pub mod redirect;
+pub mod retry;
 pub mod search;
 pub mod site_or_community_or_user;
+pub mod stale_actors;
 pub mod user_or_community;
 
 /// Resolve actor identifier like `!news@example.com` to user or community object.
@@ -47,7 +52,8 @@ where
       Ok(actor?.into())
     } else if local_user_view.is_some() {
       // Fetch the actor from its home instance using webfinger
-      let actor: ActorType = webfinger_resolve_actor(&identifier.to_lowercase(), context).await?;
+      let actor: ActorType =
+        webfinger_resolve_actor_cached(&identifier.to_lowercase(), context).await?;
       Ok(actor)
     } else {
       Err(NotFound.into())
@@ -63,3 +69,121 @@ where
     )
   }
 }
+
+/// Same as [webfinger_resolve_actor], but remembers which actor the identifier resolved to for
+/// `federation.webfinger_cache_seconds`. Failed lookups are remembered for
+/// `federation.webfinger_negative_cache_seconds`.
+#[tracing::instrument(skip(context))]
+pub async fn webfinger_resolve_actor_cached<Kind>(
+  identifier: &str,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<Kind>
+where
+  Kind: Object<DataType = LemmyContext, Error = LemmyError> + Actor + Send + 'static,
+  for<'de2> <Kind as Object>::Kind: serde::Deserialize<'de2>,
+{
+  let cache = context.webfinger_cache();
+  match cache.get(identifier) {
+    Some(Some(actor_id)) => return ObjectId::<Kind>::from(actor_id).dereference(context).await,
+    Some(None) => Err(LemmyErrorType::CouldntFindObject)?,
+    None => {}
+  }
+  let actor = webfinger_resolve_actor::<LemmyContext, Kind>(identifier, context).await;
+  cache
+    .insert(identifier, actor.as_ref().ok().map(Actor::id))
+    .await;
+  actor
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::objects::{
+    person::{tests::parse_lemmy_person, ApubPerson},
+    tests::init_context_with_client,
+  };
+  use lemmy_db_schema::{
+    source::{person::Person, site::Site},
+    traits::Crud,
+  };
+  use reqwest::{Request, Response};
+  use reqwest_middleware::{ClientBuilder, Middleware, Next};
+  use serial_test::serial;
+  use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+  };
+  use task_local_extensions::Extensions;
+
+  /// Answers webfinger requests for `picard@enterprise.lemmy.ml`, and counts all requests.
+  #[derive(Clone, Default)]
+  struct WebfingerMiddleware(Arc<AtomicUsize>);
+
+  #[async_trait::async_trait]
+  impl Middleware for WebfingerMiddleware {
+    async fn handle(
+      &self,
+      req: Request,
+      _extensions: &mut Extensions,
+      _next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+      self.0.fetch_add(1, Ordering::SeqCst);
+      let res = if req
+        .url()
+        .as_str()
+        .ends_with("acct:picard@enterprise.lemmy.ml")
+      {
+        let body = r#"{
+          "subject": "acct:picard@enterprise.lemmy.ml",
+          "links": [{
+            "rel": "self",
+            "type": "application/activity+json",
+            "href": "https://enterprise.lemmy.ml/u/picard"
+          }]
+        }"#;
+        http::Response::builder()
+          .header("Content-Type", "application/jrd+json")
+          .body(body)
+      } else {
+        http::Response::builder().status(404).body("not found")
+      };
+      Ok(res.unwrap().into())
+    }
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_webfinger_cache() {
+    let middleware = WebfingerMiddleware::default();
+    let client = ClientBuilder::new(reqwest::Client::default())
+      .with(middleware.clone())
+      .build();
+    let context = init_context_with_client(client).await;
+    let (person, site) = parse_lemmy_person(&context).await;
+
+    for _ in 0..2 {
+      let actor: ApubPerson =
+        webfinger_resolve_actor_cached("picard@enterprise.lemmy.ml", &context)
+          .await
+          .unwrap();
+      assert_eq!(person.id, actor.id);
+    }
+    assert_eq!(1, middleware.0.load(Ordering::SeqCst));
+
+    // failed lookups are cached too
+    for _ in 0..2 {
+      let actor: LemmyResult<ApubPerson> =
+        webfinger_resolve_actor_cached("riker@enterprise.lemmy.ml", &context).await;
+      assert!(actor.is_err());
+    }
+    assert_eq!(2, middleware.0.load(Ordering::SeqCst));
+
+    Person::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+}
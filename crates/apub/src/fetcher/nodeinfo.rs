@@ -0,0 +1,180 @@
+use activitypub_federation::config::Data;
+use lemmy_api_common::{
+  context::LemmyContext,
+  nodeinfo::{NodeInfo, NodeInfoWellKnown},
+};
+use lemmy_db_schema::source::instance::{Instance, InstanceForm};
+use lemmy_utils::{error::LemmyResult, spawn_try_task};
+use moka::future::Cache;
+use once_cell::sync::Lazy;
+use std::time::Duration;
+use tracing::debug;
+use url::Url;
+
+/// Stored as software name for instances which don't provide valid NodeInfo.
+pub const UNKNOWN_SOFTWARE: &str = "unknown";
+
+const NODEINFO_SCHEMA_PREFIX: &str = "http://nodeinfo.diaspora.software/ns/schema/2.";
+
+/// Domains for which the software is being detected, or was detected recently. Many objects from
+/// a new instance usually arrive at once, this makes sure that NodeInfo is only fetched once.
+static RECENT_DETECTIONS: Lazy<Cache<String, ()>> = Lazy::new(|| {
+  Cache::builder()
+    .max_capacity(10_000)
+    .time_to_live(Duration::from_secs(60 * 60))
+    .build()
+});
+
+/// Detects the software of a remote instance in the background, if it is not known yet. The result
+/// is stored in the instance row, so NodeInfo is only fetched on first contact. Afterwards the
+/// values are kept up to date by the scheduled task.
+pub(crate) fn spawn_update_instance_software(instance: &Instance, context: &Data<LemmyContext>) {
+  if instance.software.is_some() || RECENT_DETECTIONS.contains_key(&instance.domain) {
+    return;
+  }
+  let instance = instance.clone();
+  let context = context.reset_request_count();
+  spawn_try_task(async move {
+    // concurrent calls for the same domain wait for the first one instead of fetching again
+    RECENT_DETECTIONS
+      .get_with(instance.domain.clone(), async {
+        update_instance_software(&instance, &context)
+          .await
+          .map_err(|e| debug!("Failed to store software of {}: {e}", instance.domain))
+          .ok();
+      })
+      .await;
+    Ok(())
+  });
+}
+
+/// Same as [spawn_update_instance_software], but waits for the result and returns the updated
+/// instance. This is needed on first contact with an instance if minimum versions are configured,
+/// as nothing from it would be accepted before its software is known.
+pub(crate) async fn detect_instance_software(
+  domain: &str,
+  context: &LemmyContext,
+) -> LemmyResult<Instance> {
+  let instance = Instance::read_or_create(&mut context.pool(), domain.to_string()).await?;
+  if instance.software.is_some() {
+    return Ok(instance);
+  }
+  RECENT_DETECTIONS
+    .get_with(instance.domain.clone(), async {
+      update_instance_software(&instance, context)
+        .await
+        .map_err(|e| debug!("Failed to store software of {}: {e}", instance.domain))
+        .ok();
+    })
+    .await;
+  Ok(Instance::read_or_create(&mut context.pool(), domain.to_string()).await?)
+}
+
+async fn update_instance_software(instance: &Instance, context: &LemmyContext) -> LemmyResult<()> {
+  let node_info = match fetch_node_info(&instance.domain, context).await {
+    Ok(n) => Some(n),
+    Err(e) => {
+      debug!("Failed to fetch nodeinfo for {}: {e}", instance.domain);
+      None
+    }
+  };
+  let form = software_form(&instance.domain, node_info);
+  Instance::update(&mut context.pool(), instance.id, &form).await?;
+  Ok(())
+}
+
+/// Follows `/.well-known/nodeinfo` to the linked NodeInfo 2.x document.
+async fn fetch_node_info(domain: &str, context: &LemmyContext) -> LemmyResult<NodeInfo> {
+  let well_known_url = Url::parse(&format!("https://{domain}/.well-known/nodeinfo"))?;
+  let well_known: NodeInfoWellKnown = context
+    .client()
+    .get(well_known_url.as_str())
+    .send()
+    .await?
+    .error_for_status()?
+    .json()
+    .await?;
+  let href = node_info_href(well_known, &well_known_url)
+    .ok_or_else(|| anyhow::anyhow!("No nodeinfo link for {domain}"))?;
+  let node_info = context
+    .client()
+    .get(href.as_str())
+    .send()
+    .await?
+    .error_for_status()?
+    .json()
+    .await?;
+  Ok(node_info)
+}
+
+/// Picks the newest supported NodeInfo link. Links to other domains are ignored.
+fn node_info_href(well_known: NodeInfoWellKnown, well_known_url: &Url) -> Option<Url> {
+  well_known
+    .links
+    .into_iter()
+    .filter(|l| l.rel.as_str().starts_with(NODEINFO_SCHEMA_PREFIX))
+    .filter(|l| l.href.domain() == well_known_url.domain())
+    .max_by(|a, b| a.rel.cmp(&b.rel))
+    .map(|l| l.href)
+}
+
+fn software_form(domain: &str, node_info: Option<NodeInfo>) -> InstanceForm {
+  let software = node_info.and_then(|n| n.software).unwrap_or_default();
+  InstanceForm::builder()
+    .domain(domain.to_string())
+    .software(Some(
+      software
+        .name
+        .unwrap_or_else(|| UNKNOWN_SOFTWARE.to_string()),
+    ))
+    .version(software.version)
+    .build()
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::{objects::tests::init_context, protocol::tests::file_to_json_object};
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_store_node_info_software() {
+    let context = init_context().await;
+    let well_known: NodeInfoWellKnown =
+      file_to_json_object("assets/mastodon/nodeinfo/well_known.json").unwrap();
+    let well_known_url = Url::parse("https://mastodon.example/.well-known/nodeinfo").unwrap();
+    assert_eq!(
+      node_info_href(well_known, &well_known_url)
+        .unwrap()
+        .as_str(),
+      "https://mastodon.example/nodeinfo/2.0"
+    );
+
+    let node_info: NodeInfo =
+      file_to_json_object("assets/mastodon/nodeinfo/nodeinfo.json").unwrap();
+    let instance = Instance::read_or_create(&mut context.pool(), "mastodon.example".to_string())
+      .await
+      .unwrap();
+    let form = software_form(&instance.domain, Some(node_info));
+    Instance::update(&mut context.pool(), instance.id, &form)
+      .await
+      .unwrap();
+    let updated = Instance::read_or_create(&mut context.pool(), "mastodon.example".to_string())
+      .await
+      .unwrap();
+    assert_eq!(Some("mastodon".to_string()), updated.software);
+    assert_eq!(Some("4.2.1".to_string()), updated.version);
+
+    let form = software_form("no-nodeinfo.example", None);
+    assert_eq!(Some(UNKNOWN_SOFTWARE.to_string()), form.software);
+    assert_eq!(None, form.version);
+
+    Instance::delete(&mut context.pool(), instance.id)
+      .await
+      .unwrap();
+  }
+}
@@ -1,18 +1,26 @@
 use crate::{
-  objects::{comment::ApubComment, community::ApubCommunity, post::ApubPost},
+  objects::{comment::ApubComment, community::ApubCommunity, poll::ApubPoll, post::ApubPost},
   protocol::{
-    objects::{note::Note, page::Page},
+    objects::{note::Note, page::Page, question::Question, tombstone::Tombstone},
     InCommunity,
   },
 };
-use activitypub_federation::{config::Data, traits::Object};
+use activitypub_federation::{
+  config::Data,
+  protocol::verification::verify_domains_match,
+  traits::Object,
+};
 use chrono::{DateTime, Utc};
 use lemmy_api_common::context::LemmyContext;
 use lemmy_db_schema::{
-  source::{community::Community, post::Post},
+  source::{
+    comment::{Comment, CommentUpdateForm},
+    community::Community,
+    post::{Post, PostUpdateForm},
+  },
   traits::Crud,
 };
-use lemmy_utils::error::LemmyError;
+use lemmy_utils::error::{LemmyError, LemmyErrorType};
 use serde::Deserialize;
 use url::Url;
 
@@ -26,7 +34,11 @@ pub enum PostOrComment {
 #[serde(untagged)]
 pub enum PageOrNote {
   Page(Box<Page>),
+  /// Polls are stored as posts
+  Question(Box<Question>),
   Note(Note),
+  /// Returned by some software instead of HTTP 410 if the object was deleted
+  Tombstone(Tombstone),
 }
 
 #[async_trait::async_trait]
@@ -73,7 +85,9 @@ impl Object for PostOrComment {
   ) -> Result<(), LemmyError> {
     match apub {
       PageOrNote::Page(a) => ApubPost::verify(a, expected_domain, data).await,
+      PageOrNote::Question(a) => ApubPoll::verify(a, expected_domain, data).await,
       PageOrNote::Note(a) => ApubComment::verify(a, expected_domain, data).await,
+      PageOrNote::Tombstone(t) => Ok(verify_domains_match(&t.id, expected_domain)?),
     }
   }
 
@@ -81,11 +95,65 @@ impl Object for PostOrComment {
   async fn from_json(apub: PageOrNote, context: &Data<LemmyContext>) -> Result<Self, LemmyError> {
     Ok(match apub {
       PageOrNote::Page(p) => PostOrComment::Post(ApubPost::from_json(*p, context).await?),
+      PageOrNote::Question(q) => PostOrComment::Post(ApubPoll::from_json(*q, context).await?.post),
       PageOrNote::Note(n) => PostOrComment::Comment(ApubComment::from_json(n, context).await?),
+      PageOrNote::Tombstone(t) => {
+        if let Some(object) = Self::read_from_id(t.id.clone(), context).await? {
+          object.apply_tombstone(&t, context).await?;
+        }
+        Err(LemmyErrorType::Deleted)?
+      }
     })
   }
 }
 
+impl PostOrComment {
+  /// Updates the local copy, because the origin instance returned a tombstone for it. If the
+  /// tombstone has a deletion time the object was deleted by its creator, otherwise it is marked
+  /// as removed, as the origin may have taken it down for moderation reasons. Nothing is changed
+  /// if the tombstone is for a different type of object.
+  async fn apply_tombstone(
+    self,
+    tombstone: &Tombstone,
+    context: &Data<LemmyContext>,
+  ) -> Result<(), LemmyError> {
+    let former_type = tombstone.former_type.as_deref();
+    let deleted = tombstone.deleted.is_some();
+    // the deletion time is stored as last update, if it is known
+    let updated = tombstone.deleted.map(Some);
+    match self {
+      PostOrComment::Post(p)
+        if !(p.deleted || p.removed)
+          && matches!(
+            former_type,
+            None | Some("Page" | "Question" | "Article" | "Video")
+          ) =>
+      {
+        let form = PostUpdateForm {
+          deleted: Some(true).filter(|_| deleted),
+          removed: Some(true).filter(|_| !deleted),
+          updated,
+          ..Default::default()
+        };
+        Post::update(&mut context.pool(), p.id, &form).await?;
+      }
+      PostOrComment::Comment(c)
+        if !(c.deleted || c.removed) && matches!(former_type, None | Some("Note")) =>
+      {
+        let form = CommentUpdateForm {
+          deleted: Some(true).filter(|_| deleted),
+          removed: Some(true).filter(|_| !deleted),
+          updated,
+          ..Default::default()
+        };
+        Comment::update(&mut context.pool(), c.id, &form).await?;
+      }
+      _ => {}
+    }
+    Ok(())
+  }
+}
+
 #[async_trait::async_trait]
 impl InCommunity for PostOrComment {
   async fn community(&self, context: &Data<LemmyContext>) -> Result<ApubCommunity, LemmyError> {
@@ -100,3 +168,94 @@ impl InCommunity for PostOrComment {
     Ok(Community::read(&mut context.pool(), cid).await?.into())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::{
+    objects::{
+      community::tests::parse_lemmy_community,
+      person::tests::parse_lemmy_person,
+      tests::init_context,
+    },
+    protocol::tests::file_to_json_object,
+  };
+  use lemmy_db_schema::source::{person::Person, site::Site};
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_dereference_tombstone() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+    let json = file_to_json_object("assets/lemmy/objects/page.json").unwrap();
+    let post = ApubPost::from_json(json, &context).await.unwrap();
+    assert!(!post.deleted);
+
+    // without a deletion time it is unknown why the post is gone, so it is only marked removed
+    let url = Url::parse("https://enterprise.lemmy.ml/post/55143").unwrap();
+    let tombstone: PageOrNote = serde_json::from_value(serde_json::json!({
+      "id": url,
+      "type": "Tombstone",
+      "formerType": "Page"
+    }))
+    .unwrap();
+    PostOrComment::verify(&tombstone, &url, &context)
+      .await
+      .unwrap();
+    let err = PostOrComment::from_json(tombstone, &context)
+      .await
+      .unwrap_err();
+    assert_eq!(LemmyErrorType::Deleted, err.error_type);
+    let read_post = Post::read(&mut context.pool(), post.id).await.unwrap();
+    assert!(read_post.removed);
+    assert!(!read_post.deleted);
+    let form = PostUpdateForm {
+      removed: Some(false),
+      ..Default::default()
+    };
+    Post::update(&mut context.pool(), post.id, &form)
+      .await
+      .unwrap();
+
+    let tombstone: PageOrNote = serde_json::from_value(serde_json::json!({
+      "id": url,
+      "type": "Tombstone",
+      "formerType": "Page",
+      "deleted": "2023-11-01T10:00:00Z"
+    }))
+    .unwrap();
+    assert!(matches!(tombstone, PageOrNote::Tombstone(_)));
+
+    PostOrComment::verify(&tombstone, &url, &context)
+      .await
+      .unwrap();
+    let err = PostOrComment::from_json(tombstone, &context)
+      .await
+      .unwrap_err();
+    assert_eq!(LemmyErrorType::Deleted, err.error_type);
+
+    let post = Post::read(&mut context.pool(), post.id).await.unwrap();
+    assert!(post.deleted);
+    assert!(!post.removed);
+    assert_eq!(Some("2023-11-01T10:00:00Z".parse().unwrap()), post.updated);
+    assert_eq!(context.request_count(), 0);
+
+    // tombstones from other software have a different format
+    let lotide: PageOrNote = file_to_json_object("assets/lotide/objects/tombstone.json").unwrap();
+    assert!(matches!(lotide, PageOrNote::Tombstone(t) if t.former_type.as_deref() == Some("Note")));
+
+    Post::delete(&mut context.pool(), post.id).await.unwrap();
+    Person::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Community::delete(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+}
@@ -0,0 +1,222 @@
+use crate::check_apub_id_valid_with_strictness;
+use activitypub_federation::{
+  config::Data,
+  fetch::{fetch_object_http, object_id::ObjectId},
+  protocol::verification::verify_domains_match,
+  traits::Object,
+};
+use lemmy_api_common::context::LemmyContext;
+use lemmy_utils::error::{LemmyError, LemmyErrorType, LemmyResult};
+use moka::future::Cache;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::{fmt::Debug, time::Duration};
+use url::Url;
+
+/// Canonical ids of objects which were served at a different url, so that the alias isn't
+/// fetched again every time it is dereferenced.
+static REDIRECT_ALIASES: Lazy<Cache<Url, Url>> = Lazy::new(|| {
+  Cache::builder()
+    .max_capacity(10_000)
+    .time_to_live(Duration::from_secs(60 * 60 * 24))
+    .build()
+});
+
+/// Same as [ObjectId::dereference], but also accepts objects which are served at a different url
+/// than their canonical id, for example `https://example.com/@alice` for the actor
+/// `https://example.com/users/alice`. In that case the object is fetched once more from its id,
+/// and only accepted if it is served there with the same id. Ids on a different domain than the
+/// url are rejected, so that no instance can serve objects in the name of another. Every url
+/// which the object is fetched from, including after http redirects, needs to pass
+/// [check_apub_id_valid_with_strictness].
+///
+/// Objects which are already known locally are dereferenced as usual, and so are aliases which
+/// were resolved recently.
+pub async fn dereference_following_redirect<Kind>(
+  object_id: &ObjectId<Kind>,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<Kind>
+where
+  Kind: Object<DataType = LemmyContext, Error = LemmyError> + Debug + Send + 'static,
+  for<'de2> <Kind as Object>::Kind: serde::Deserialize<'de2>,
+{
+  let url = object_id.inner();
+  if let Some(canonical_id) = REDIRECT_ALIASES.get(url) {
+    return ObjectId::<Kind>::from(canonical_id)
+      .dereference(context)
+      .await;
+  }
+  if Kind::read_from_id(url.clone(), context).await?.is_some() {
+    return object_id.dereference(context).await;
+  }
+  check_apub_id_valid_with_strictness(url, false, context).await?;
+
+  let mut res = fetch_object_http::<_, Value>(url, context).await?;
+  if &res.url != url {
+    // the object was served after a http redirect
+    check_apub_id_valid_with_strictness(&res.url, false, context).await?;
+  }
+  if let Some(id) = canonical_id(&res.url, &res.object)? {
+    check_apub_id_valid_with_strictness(&id, false, context).await?;
+    res = fetch_object_http(&id, context).await?;
+    // only a single redirect is followed, so the object has to be served at its id
+    if res.url != id || canonical_id(&res.url, &res.object)?.is_some() {
+      Err(LemmyErrorType::ObjectIdMismatch)?
+    }
+  }
+
+  let object: Kind::Kind = serde_json::from_value(res.object)?;
+  Kind::verify(&object, &res.url, context).await?;
+  let object = Kind::from_json(object, context).await?;
+  if &res.url != url {
+    REDIRECT_ALIASES.insert(url.clone(), res.url).await;
+  }
+  Ok(object)
+}
+
+/// Returns the id of the object, if it was fetched from a different url on the same domain.
+fn canonical_id(fetched_from: &Url, object: &Value) -> LemmyResult<Option<Url>> {
+  let id = object
+    .get("id")
+    .and_then(Value::as_str)
+    .ok_or(LemmyErrorType::ObjectIdMismatch)?;
+  let id = Url::parse(id)?;
+  if &id == fetched_from {
+    return Ok(None);
+  }
+  verify_domains_match(&id, fetched_from)?;
+  Ok(Some(id))
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::{
+    objects::{
+      instance::tests::parse_lemmy_instance,
+      person::ApubPerson,
+      tests::init_context_with_client,
+    },
+    protocol::tests::file_to_json_object,
+  };
+  use lemmy_db_schema::{
+    source::{person::Person, site::Site},
+    traits::Crud,
+  };
+  use reqwest::{Request, Response, ResponseBuilderExt};
+  use reqwest_middleware::{ClientBuilder, Middleware, Next};
+  use serde_json::json;
+  use serial_test::serial;
+  use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+  };
+  use task_local_extensions::Extensions;
+
+  /// Serves picard at his id and at the alias `/@picard`, and riker with an id on another domain.
+  /// Counts all requests.
+  #[derive(Clone, Default)]
+  struct AliasMiddleware(Arc<AtomicUsize>);
+
+  #[async_trait::async_trait]
+  impl Middleware for AliasMiddleware {
+    async fn handle(
+      &self,
+      req: Request,
+      _extensions: &mut Extensions,
+      _next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+      self.0.fetch_add(1, Ordering::SeqCst);
+      let mut person: Value = file_to_json_object("assets/lemmy/objects/person.json").unwrap();
+      // so that the featured collection isn't fetched
+      person.as_object_mut().unwrap().remove("featured");
+      let body = match req.url().path() {
+        "/u/picard" | "/@picard" => Some(person),
+        "/@riker" => {
+          person["id"] = json!("https://evil.example/u/riker");
+          Some(person)
+        }
+        _ => None,
+      };
+      let res = http::Response::builder().url(req.url().clone());
+      let res = match body {
+        Some(body) => res
+          .header("Content-Type", "application/activity+json")
+          .body(body.to_string()),
+        None => res.status(404).body("not found".to_string()),
+      };
+      Ok(res.unwrap().into())
+    }
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_dereference_alias() {
+    let middleware = AliasMiddleware::default();
+    let client = ClientBuilder::new(reqwest::Client::default())
+      .with(middleware.clone())
+      .build();
+    let context = init_context_with_client(client).await;
+    let site = parse_lemmy_instance(&context).await;
+
+    // the object is fetched from the alias, and once more from its id
+    let alias: ObjectId<ApubPerson> = Url::parse("https://enterprise.lemmy.ml/@picard")
+      .unwrap()
+      .into();
+    let person = dereference_following_redirect(&alias, &context)
+      .await
+      .unwrap();
+    assert_eq!(
+      "https://enterprise.lemmy.ml/u/picard",
+      person.actor_id.inner().as_str()
+    );
+    assert_eq!(2, middleware.0.load(Ordering::SeqCst));
+
+    // the alias is remembered, so it isn't fetched again
+    let again = dereference_following_redirect(&alias, &context)
+      .await
+      .unwrap();
+    assert_eq!(person.id, again.id);
+    assert_eq!(2, middleware.0.load(Ordering::SeqCst));
+
+    // ids on another domain are rejected
+    let spoofed: ObjectId<ApubPerson> = Url::parse("https://enterprise.lemmy.ml/@riker")
+      .unwrap()
+      .into();
+    assert!(dereference_following_redirect(&spoofed, &context)
+      .await
+      .is_err());
+
+    Person::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+
+  #[test]
+  fn test_canonical_id_same_domain() {
+    let url = Url::parse("https://example.com/@alice").unwrap();
+    let id = Url::parse("https://example.com/users/alice").unwrap();
+    let object = json!({"id": id, "type": "Person"});
+    assert_eq!(Some(id.clone()), canonical_id(&url, &object).unwrap());
+
+    // fetching from the canonical id is self-consistent
+    assert_eq!(None, canonical_id(&id, &object).unwrap());
+  }
+
+  #[test]
+  fn test_canonical_id_other_domain() {
+    let url = Url::parse("https://example.com/@alice").unwrap();
+    let object = json!({"id": "https://evil.example/users/alice", "type": "Person"});
+    assert!(canonical_id(&url, &object).is_err());
+
+    let without_id = json!({"type": "Person"});
+    assert_eq!(
+      Some(LemmyErrorType::ObjectIdMismatch),
+      canonical_id(&url, &without_id).err().map(|e| e.error_type)
+    );
+  }
+}
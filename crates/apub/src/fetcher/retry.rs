@@ -0,0 +1,154 @@
+use crate::fetcher::redirect::dereference_following_redirect;
+use activitypub_federation::{config::Data, fetch::object_id::ObjectId, traits::Object};
+use lemmy_api_common::context::LemmyContext;
+use lemmy_utils::{
+  error::{LemmyError, LemmyErrorType, LemmyResult},
+  request::exponential_backoff,
+};
+use reqwest::StatusCode;
+use std::{fmt::Debug, future::Future, io::ErrorKind, time::Duration};
+use tracing::debug;
+
+/// How often a fetch which failed with a transient error is attempted again.
+const MAX_RETRIES: i32 = 3;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(5);
+
+/// Same as [dereference_following_redirect], but retries a few times with increasing delay if
+/// fetching fails because of a network problem or a temporary server error. Permanent errors like
+/// a missing or invalid object are returned immediately.
+///
+/// Every attempt counts towards the http fetch limit, so retries can't be used to get around it.
+pub async fn dereference_with_retry<Kind>(
+  object_id: &ObjectId<Kind>,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<Kind>
+where
+  Kind: Object<DataType = LemmyContext, Error = LemmyError> + Debug + Send + 'static,
+  for<'de2> <Kind as Object>::Kind: serde::Deserialize<'de2>,
+{
+  retry_transient(
+    || dereference_following_redirect(object_id, context),
+    RETRY_BASE_DELAY,
+  )
+  .await
+}
+
+async fn retry_transient<T, F, Fut>(f: F, base_delay: Duration) -> LemmyResult<T>
+where
+  F: Fn() -> Fut,
+  Fut: Future<Output = LemmyResult<T>>,
+{
+  let mut retry_count = 0;
+  loop {
+    match f().await {
+      Err(e) if retry_count < MAX_RETRIES && is_transient(&e) => {
+        let delay = exponential_backoff(base_delay, retry_count, RETRY_MAX_DELAY);
+        debug!("Fetch failed, retrying in {delay:?}: {e}");
+        tokio::time::sleep(delay).await;
+        retry_count += 1;
+      }
+      res => return res,
+    }
+  }
+}
+
+/// Errors which may go away by themselves, like timeouts, connection problems or server errors.
+fn is_transient(err: &LemmyError) -> bool {
+  if matches!(err.error_type, LemmyErrorType::HttpFetchLimitExceeded(_)) {
+    return false;
+  }
+  let transient_reqwest = |e: &reqwest::Error| {
+    e.is_timeout()
+      || e.is_connect()
+      || e
+        .status()
+        .is_some_and(|s| s.is_server_error() || s == StatusCode::TOO_MANY_REQUESTS)
+  };
+  err.inner.chain().any(|cause| {
+    if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+      transient_reqwest(e)
+    } else if let Some(reqwest_middleware::Error::Reqwest(e)) = cause.downcast_ref() {
+      transient_reqwest(e)
+    } else if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+      matches!(
+        e.kind(),
+        ErrorKind::TimedOut
+          | ErrorKind::ConnectionRefused
+          | ErrorKind::ConnectionReset
+          | ErrorKind::ConnectionAborted
+          | ErrorKind::Interrupted
+      )
+    } else {
+      false
+    }
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use std::sync::atomic::{AtomicI32, Ordering};
+
+  fn timeout() -> LemmyError {
+    std::io::Error::new(ErrorKind::TimedOut, "timed out").into()
+  }
+
+  #[tokio::test]
+  async fn test_success_after_retry() {
+    let attempts = AtomicI32::new(0);
+    let res = retry_transient(
+      || async {
+        if attempts.fetch_add(1, Ordering::SeqCst) < 2 {
+          Err(timeout())
+        } else {
+          Ok("object")
+        }
+      },
+      Duration::ZERO,
+    )
+    .await;
+    assert_eq!("object", res.unwrap());
+    assert_eq!(3, attempts.load(Ordering::SeqCst));
+  }
+
+  #[tokio::test]
+  async fn test_gives_up_after_max_retries() {
+    let attempts = AtomicI32::new(0);
+    let res: LemmyResult<()> = retry_transient(
+      || async {
+        attempts.fetch_add(1, Ordering::SeqCst);
+        Err(timeout())
+      },
+      Duration::ZERO,
+    )
+    .await;
+    assert!(res.is_err());
+    assert_eq!(MAX_RETRIES + 1, attempts.load(Ordering::SeqCst));
+  }
+
+  #[tokio::test]
+  async fn test_not_found_is_not_retried() {
+    let attempts = AtomicI32::new(0);
+    let res: LemmyResult<()> = retry_transient(
+      || async {
+        attempts.fetch_add(1, Ordering::SeqCst);
+        Err(activitypub_federation::error::Error::NotFound.into())
+      },
+      Duration::ZERO,
+    )
+    .await;
+    assert!(res.is_err());
+    assert_eq!(1, attempts.load(Ordering::SeqCst));
+
+    // neither is hitting the fetch limit
+    let limit = LemmyError::from(LemmyErrorType::HttpFetchLimitExceeded(
+      "example.com".to_string(),
+    ));
+    assert!(!is_transient(&limit));
+    assert!(is_transient(&timeout()));
+  }
+}
@@ -1,12 +1,15 @@
 use crate::{
-  objects::{comment::ApubComment, community::ApubCommunity, person::ApubPerson, post::ApubPost},
-  protocol::objects::{group::Group, note::Note, page::Page, person::Person},
-};
-use activitypub_federation::{
-  config::Data,
-  fetch::{object_id::ObjectId, webfinger::webfinger_resolve_actor},
-  traits::Object,
+  fetcher::{retry::dereference_with_retry, webfinger_resolve_actor_cached},
+  objects::{
+    comment::ApubComment,
+    community::ApubCommunity,
+    person::ApubPerson,
+    poll::ApubPoll,
+    post::ApubPost,
+  },
+  protocol::objects::{group::Group, note::Note, page::Page, person::Person, question::Question},
 };
+use activitypub_federation::{config::Data, fetch::object_id::ObjectId, traits::Object};
 use chrono::{DateTime, Utc};
 use lemmy_api_common::context::LemmyContext;
 use lemmy_utils::error::{LemmyError, LemmyErrorType};
@@ -24,7 +27,7 @@ pub(crate) async fn search_query_to_object_id(
   Ok(match Url::parse(query) {
     Ok(url) => {
       // its already an url, just go with it
-      ObjectId::from(url).dereference(context).await?
+      dereference_with_retry(&ObjectId::from(url), context).await?
     }
     Err(_) => {
       // not an url, try to resolve via webfinger
@@ -33,10 +36,10 @@ pub(crate) async fn search_query_to_object_id(
       let identifier = chars.as_str();
       match kind {
         Some('@') => SearchableObjects::Person(
-          webfinger_resolve_actor::<LemmyContext, ApubPerson>(identifier, context).await?,
+          webfinger_resolve_actor_cached::<ApubPerson>(identifier, context).await?,
         ),
         Some('!') => SearchableObjects::Community(
-          webfinger_resolve_actor::<LemmyContext, ApubCommunity>(identifier, context).await?,
+          webfinger_resolve_actor_cached::<ApubCommunity>(identifier, context).await?,
         ),
         _ => return Err(LemmyErrorType::InvalidQuery)?,
       }
@@ -71,6 +74,7 @@ pub(crate) enum SearchableKinds {
   Group(Group),
   Person(Person),
   Page(Page),
+  Question(Box<Question>),
   Note(Note),
 }
 
@@ -142,6 +146,7 @@ impl Object for SearchableObjects {
       SearchableKinds::Group(a) => ApubCommunity::verify(a, expected_domain, data).await,
       SearchableKinds::Person(a) => ApubPerson::verify(a, expected_domain, data).await,
       SearchableKinds::Page(a) => ApubPost::verify(a, expected_domain, data).await,
+      SearchableKinds::Question(a) => ApubPoll::verify(a, expected_domain, data).await,
       SearchableKinds::Note(a) => ApubComment::verify(a, expected_domain, data).await,
     }
   }
@@ -154,6 +159,7 @@ impl Object for SearchableObjects {
       SAT::Group(g) => SO::Community(ApubCommunity::from_json(g, context).await?),
       SAT::Person(p) => SO::Person(ApubPerson::from_json(p, context).await?),
       SAT::Page(p) => SO::Post(ApubPost::from_json(p, context).await?),
+      SAT::Question(q) => SO::Post(ApubPoll::from_json(*q, context).await?.post),
       SAT::Note(n) => SO::Comment(ApubComment::from_json(n, context).await?),
     })
   }
@@ -42,10 +42,16 @@ impl Object for SiteOrCommunityOrUser {
 
   #[tracing::instrument(skip_all)]
   async fn read_from_id(
-    _object_id: Url,
-    _data: &Data<Self::DataType>,
+    object_id: Url,
+    data: &Data<Self::DataType>,
   ) -> Result<Option<Self>, LemmyError> {
-    unimplemented!();
+    let site = ApubSite::read_from_id(object_id.clone(), data).await?;
+    Ok(match site {
+      Some(o) => Some(SiteOrCommunityOrUser::Site(o)),
+      None => UserOrCommunity::read_from_id(object_id, data)
+        .await?
+        .map(SiteOrCommunityOrUser::UserOrCommunity),
+    })
   }
 
   #[tracing::instrument(skip_all)]
@@ -75,8 +81,15 @@ impl Object for SiteOrCommunityOrUser {
   }
 
   #[tracing::instrument(skip_all)]
-  async fn from_json(_apub: Self::Kind, _data: &Data<Self::DataType>) -> Result<Self, LemmyError> {
-    unimplemented!();
+  async fn from_json(apub: Self::Kind, data: &Data<Self::DataType>) -> Result<Self, LemmyError> {
+    Ok(match apub {
+      SiteOrPersonOrGroup::Instance(a) => {
+        SiteOrCommunityOrUser::Site(ApubSite::from_json(a, data).await?)
+      }
+      SiteOrPersonOrGroup::PersonOrGroup(a) => {
+        SiteOrCommunityOrUser::UserOrCommunity(UserOrCommunity::from_json(a, data).await?)
+      }
+    })
   }
 }
 
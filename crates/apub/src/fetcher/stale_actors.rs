@@ -0,0 +1,146 @@
+use crate::fetcher::user_or_community::UserOrCommunity;
+use activitypub_federation::{config::Data, fetch::object_id::ObjectId};
+use chrono::{Duration, Utc};
+use futures::StreamExt;
+use itertools::Itertools;
+use lemmy_api_common::context::LemmyContext;
+use lemmy_db_schema::{
+  newtypes::{DbUrl, InstanceId},
+  source::{community::Community, person::Person},
+};
+use lemmy_utils::error::LemmyError;
+use tracing::{info, warn};
+
+/// Outcome of [refresh_stale_actors].
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RefreshedActors {
+  pub refreshed: usize,
+  pub failed: usize,
+}
+
+/// Re-fetches remote users and communities which were not updated for longer than `older_than`,
+/// so that their profile and public key stay current even if they never send us any activity.
+///
+/// Actors are grouped by instance. Up to `concurrency` instances are handled in parallel, while
+/// actors of the same instance are fetched one after another with `instance_interval` between
+/// requests, to avoid hammering a single server. At most `limit` users and `limit` communities are
+/// handled per call, least recently refreshed first.
+///
+/// `older_than` should not be shorter than one day, otherwise the federation library considers
+/// the actors as fresh and returns them from the database without fetching.
+#[tracing::instrument(skip(context))]
+pub async fn refresh_stale_actors(
+  older_than: Duration,
+  limit: i64,
+  concurrency: usize,
+  instance_interval: std::time::Duration,
+  context: &Data<LemmyContext>,
+) -> Result<RefreshedActors, LemmyError> {
+  let cutoff = Utc::now() - older_than;
+  let persons = Person::list_stale_remote(&mut context.pool(), cutoff, limit).await?;
+  let communities = Community::list_stale_remote(&mut context.pool(), cutoff, limit).await?;
+  let by_instance: Vec<(InstanceId, Vec<DbUrl>)> = persons
+    .into_iter()
+    .map(|p| (p.instance_id, p.actor_id))
+    .chain(communities.into_iter().map(|c| (c.instance_id, c.actor_id)))
+    .into_group_map()
+    .into_iter()
+    .collect();
+
+  let results = futures::stream::iter(
+    by_instance
+      .into_iter()
+      // each instance gets its own request counter, otherwise we quickly run into the limit
+      .map(|(instance_id, actors)| (instance_id, actors, context.reset_request_count()))
+      .map(|(instance_id, actors, context)| async move {
+        let mut res = RefreshedActors::default();
+        for (i, actor_id) in actors.into_iter().enumerate() {
+          if i > 0 {
+            tokio::time::sleep(instance_interval).await;
+          }
+          let object_id: ObjectId<UserOrCommunity> = actor_id.into();
+          match object_id.dereference(&context).await {
+            Ok(_) => res.refreshed += 1,
+            Err(e) => {
+              warn!(
+                "Failed to refresh actor {} of instance {instance_id:?}: {e}",
+                object_id.inner()
+              );
+              res.failed += 1;
+            }
+          }
+        }
+        res
+      }),
+  )
+  .buffer_unordered(concurrency)
+  .collect::<Vec<_>>()
+  .await;
+
+  let total = results
+    .into_iter()
+    .fold(RefreshedActors::default(), |acc, r| RefreshedActors {
+      refreshed: acc.refreshed + r.refreshed,
+      failed: acc.failed + r.failed,
+    });
+  info!(
+    "Refreshed {} stale actors, {} failed",
+    total.refreshed, total.failed
+  );
+  Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::objects::{person::tests::parse_lemmy_person, tests::init_context};
+  use lemmy_db_schema::{
+    source::{person::PersonUpdateForm, site::Site},
+    traits::Crud,
+  };
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_refresh_stale_actors() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let older_than = Duration::days(7);
+    let interval = std::time::Duration::ZERO;
+
+    // freshly fetched, so nothing to do
+    let res = refresh_stale_actors(older_than, 50, 4, interval, &context)
+      .await
+      .unwrap();
+    assert_eq!(RefreshedActors::default(), res);
+
+    let form = PersonUpdateForm {
+      last_refreshed_at: Some(Utc::now() - Duration::days(30)),
+      ..Default::default()
+    };
+    Person::update(&mut context.pool(), person.id, &form)
+      .await
+      .unwrap();
+    let stale = Person::list_stale_remote(&mut context.pool(), Utc::now() - older_than, 50)
+      .await
+      .unwrap();
+    assert_eq!(
+      vec![person.id],
+      stale.iter().map(|p| p.id).collect::<Vec<_>>()
+    );
+
+    // the test instance is not reachable, so we only check that a fetch was attempted
+    let res = refresh_stale_actors(older_than, 50, 4, interval, &context)
+      .await
+      .unwrap();
+    assert_eq!(1, res.refreshed + res.failed);
+
+    Person::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+}
@@ -1,9 +1,14 @@
 use crate::{
-  http::{create_apub_response, create_apub_tombstone_response, redirect_remote_object},
+  http::{
+    check_signed_fetch,
+    create_apub_response,
+    create_apub_tombstone_response,
+    redirect_remote_object,
+  },
   objects::comment::ApubComment,
 };
 use activitypub_federation::{config::Data, traits::Object};
-use actix_web::{web::Path, HttpResponse};
+use actix_web::{web::Path, HttpRequest, HttpResponse};
 use lemmy_api_common::context::LemmyContext;
 use lemmy_db_schema::{newtypes::CommentId, source::comment::Comment, traits::Crud};
 use lemmy_utils::error::LemmyError;
@@ -18,8 +23,12 @@ pub(crate) struct CommentQuery {
 #[tracing::instrument(skip_all)]
 pub(crate) async fn get_apub_comment(
   info: Path<CommentQuery>,
+  request: HttpRequest,
   context: Data<LemmyContext>,
 ) -> Result<HttpResponse, LemmyError> {
+  if let Some(res) = check_signed_fetch(&request, &context).await {
+    return Ok(res);
+  }
   let id = CommentId(info.comment_id.parse::<i32>()?);
   let comment: ApubComment = Comment::read(&mut context.pool(), id).await?.into();
   if !comment.local {
@@ -1,16 +1,28 @@
 use crate::{
-  activity_lists::GroupInboxActivities,
+  activity_lists::{GroupInboxActivities, VerifyWithTimeout},
   collections::{
     community_featured::ApubCommunityFeatured,
     community_follower::ApubCommunityFollower,
     community_moderators::ApubCommunityModerators,
     community_outbox::ApubCommunityOutbox,
   },
-  http::{create_apub_response, create_apub_tombstone_response},
-  objects::{community::ApubCommunity, person::ApubPerson},
+  fetcher::site_or_community_or_user::SiteOrCommunityOrUser,
+  http::{
+    check_inbox_rate_limit,
+    check_signed_fetch,
+    create_apub_response,
+    create_apub_tombstone_response,
+    ignore_configured_activity,
+    ignore_unknown_activity,
+    key_refresh::receive_activity_with_key_refresh,
+    signature_algorithm,
+    stats::count_received_activity,
+    store_signature_algorithm,
+    UnknownActivity,
+  },
+  objects::community::ApubCommunity,
 };
 use activitypub_federation::{
-  actix_web::inbox::receive_activity,
   config::Data,
   protocol::context::WithContext,
   traits::{Collection, Object},
@@ -53,17 +65,37 @@ pub async fn community_inbox(
   body: Bytes,
   data: Data<LemmyContext>,
 ) -> Result<HttpResponse, LemmyError> {
-  receive_activity::<WithContext<GroupInboxActivities>, ApubPerson, LemmyContext>(
-    request, body, &data,
-  )
-  .await
+  let activity = serde_json::from_slice::<UnknownActivity>(&body).ok();
+  if let Some(res) = check_inbox_rate_limit(activity.as_ref(), data.settings()) {
+    return Ok(res);
+  }
+  if let Some(res) = ignore_configured_activity(activity.as_ref(), &request, &body, &data).await? {
+    return Ok(res);
+  }
+  if let Some(res) = ignore_unknown_activity(activity.as_ref()) {
+    return Ok(res);
+  }
+  let signature_algorithm = signature_algorithm(&request);
+  // instance actors sign batch follows
+  let res = receive_activity_with_key_refresh::<
+    VerifyWithTimeout<WithContext<GroupInboxActivities>>,
+    SiteOrCommunityOrUser,
+  >(request, body, &data)
+  .await?;
+  count_received_activity();
+  store_signature_algorithm(signature_algorithm, activity.as_ref(), &data).await;
+  Ok(res)
 }
 
 /// Returns an empty followers collection, only populating the size (for privacy).
 pub(crate) async fn get_apub_community_followers(
   info: web::Path<CommunityQuery>,
+  request: HttpRequest,
   context: Data<LemmyContext>,
 ) -> Result<HttpResponse, LemmyError> {
+  if let Some(res) = check_signed_fetch(&request, &context).await {
+    return Ok(res);
+  }
   let community =
     Community::read_from_name(&mut context.pool(), &info.community_name, false).await?;
   let followers = ApubCommunityFollower::read_local(&community.into(), &context).await?;
@@ -74,8 +106,12 @@ pub(crate) async fn get_apub_community_followers(
 /// activites like votes or comments).
 pub(crate) async fn get_apub_community_outbox(
   info: web::Path<CommunityQuery>,
+  request: HttpRequest,
   context: Data<LemmyContext>,
 ) -> Result<HttpResponse, LemmyError> {
+  if let Some(res) = check_signed_fetch(&request, &context).await {
+    return Ok(res);
+  }
   let community: ApubCommunity =
     Community::read_from_name(&mut context.pool(), &info.community_name, false)
       .await?
@@ -90,8 +126,12 @@ pub(crate) async fn get_apub_community_outbox(
 #[tracing::instrument(skip_all)]
 pub(crate) async fn get_apub_community_moderators(
   info: web::Path<CommunityQuery>,
+  request: HttpRequest,
   context: Data<LemmyContext>,
 ) -> Result<HttpResponse, LemmyError> {
+  if let Some(res) = check_signed_fetch(&request, &context).await {
+    return Ok(res);
+  }
   let community: ApubCommunity =
     Community::read_from_name(&mut context.pool(), &info.community_name, false)
       .await?
@@ -106,8 +146,12 @@ pub(crate) async fn get_apub_community_moderators(
 /// Returns collection of featured (stickied) posts.
 pub(crate) async fn get_apub_community_featured(
   info: web::Path<CommunityQuery>,
+  request: HttpRequest,
   context: Data<LemmyContext>,
 ) -> Result<HttpResponse, LemmyError> {
+  if let Some(res) = check_signed_fetch(&request, &context).await {
+    return Ok(res);
+  }
   let community: ApubCommunity =
     Community::read_from_name(&mut context.pool(), &info.community_name, false)
       .await?
@@ -0,0 +1,575 @@
+use crate::http::{
+  signer_cache::{invalidate_signer_key, signer_key_id, with_signer_key_id, CachedSigner},
+  UnknownActivity,
+};
+use activitypub_federation::{
+  actix_web::inbox::receive_activity,
+  config::Data,
+  error::Error,
+  fetch::object_id::ObjectId,
+  traits::{ActivityHandler, Actor, Object},
+};
+use actix_web::{web::Bytes, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use lemmy_api_common::context::LemmyContext;
+use lemmy_utils::error::{LemmyError, LemmyResult};
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use std::{
+  collections::{HashMap, VecDeque},
+  sync::Mutex,
+  time::{Duration, Instant},
+};
+use tracing::debug;
+use url::Url;
+
+/// Minimum time between refetching the same actor because of an invalid signature, so that
+/// requests with bad signatures can't make us fetch the actor over and over.
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long a previous key of an actor is still accepted after the rotation was noticed, for
+/// activities which were signed shortly before it and are delivered late.
+const PREVIOUS_KEY_DURATION: Duration = Duration::from_secs(600);
+
+/// Maximum number of previous keys which are kept per actor.
+const MAX_PREVIOUS_KEYS: usize = 2;
+
+/// Actors which were refetched because of an invalid signature, with the time of the refetch.
+static KEY_REFRESHES: Lazy<Mutex<HashMap<Url, Instant>>> = Lazy::new(Default::default);
+
+/// Keys which actors had before a rotation, newest first, with the time the rotation was noticed.
+static PREVIOUS_KEYS: Lazy<Mutex<HashMap<Url, VecDeque<(String, Instant)>>>> =
+  Lazy::new(Default::default);
+
+tokio::task_local! {
+  /// Key which [WithPreviousKey] verifies signatures with.
+  static PREVIOUS_KEY: String;
+}
+
+/// Same as [receive_activity], but if the signature can't be verified with the stored key of the
+/// actor, the activity is also verified with the keys which the actor had before its recent key
+/// rotations. If none of them matches, the actor is refetched once in case it rotated its key
+/// again, and the activity is received again. The previous key is remembered for a few minutes,
+/// so that activities signed shortly before the rotation are still accepted.
+pub(crate) async fn receive_activity_with_key_refresh<Activity, ActorT>(
+  request: HttpRequest,
+  body: Bytes,
+  data: &Data<LemmyContext>,
+) -> LemmyResult<HttpResponse>
+where
+  Activity: ActivityHandler<DataType = LemmyContext, Error = LemmyError>
+    + DeserializeOwned
+    + Send
+    + 'static,
+  ActorT: Object<DataType = LemmyContext, Error = LemmyError> + Actor + Send + Sync + 'static,
+  for<'de2> <ActorT as Object>::Kind: serde::Deserialize<'de2> + Send + Sync,
+{
+  let key_id = signer_key_id(&request);
+  let err = match receive_with_cached_key::<Activity, ActorT>(
+    key_id.clone(),
+    request.clone(),
+    body.clone(),
+    data,
+  )
+  .await
+  {
+    Err(e) if is_invalid_signature(&e) => e,
+    res => return res,
+  };
+  // The body hash is checked before the signature, so the body is the one which was signed
+  let Ok(activity) = serde_json::from_slice::<UnknownActivity>(&body) else {
+    return Err(err);
+  };
+  let actor_id = ObjectId::<ActorT>::from(activity.actor);
+  if let Some(key_id) = &key_id {
+    invalidate_signer_key(key_id, actor_id.inner()).await;
+  }
+
+  for key in previous_keys(actor_id.inner()) {
+    // all checks run again, only the signature is verified with the previous key
+    let res = PREVIOUS_KEY
+      .scope(
+        key,
+        receive_activity::<Activity, WithPreviousKey<ActorT>, LemmyContext>(
+          request.clone(),
+          body.clone(),
+          data,
+        ),
+      )
+      .await;
+    match res {
+      Err(e) if is_invalid_signature(&e) => {}
+      res => return res,
+    }
+  }
+
+  if !refresh_allowed(actor_id.inner()) {
+    return Err(err);
+  }
+  let stored_key = actor_id
+    .dereference_local(data)
+    .await
+    .ok()
+    .map(|actor| actor.public_key_pem().to_string());
+  let current_key = match actor_id.dereference_forced(data).await {
+    Ok(actor) => actor.public_key_pem().to_string(),
+    Err(e) => {
+      debug!(
+        "Failed to refetch {} after invalid signature: {e}",
+        actor_id.inner()
+      );
+      return Err(err);
+    }
+  };
+  debug!("Refetched {} after invalid signature", actor_id.inner());
+  if let Some(stored_key) = stored_key.filter(|key| key != &current_key) {
+    store_previous_key(actor_id.inner().clone(), stored_key);
+  }
+  // all checks run again, now with the current key of the actor
+  receive_with_cached_key::<Activity, ActorT>(key_id, request, body, data).await
+}
+
+/// Receives the activity with the key of the signer from [CachedSigner], if the signature has a key
+/// id.
+async fn receive_with_cached_key<Activity, ActorT>(
+  key_id: Option<String>,
+  request: HttpRequest,
+  body: Bytes,
+  data: &Data<LemmyContext>,
+) -> LemmyResult<HttpResponse>
+where
+  Activity: ActivityHandler<DataType = LemmyContext, Error = LemmyError>
+    + DeserializeOwned
+    + Send
+    + 'static,
+  ActorT: Object<DataType = LemmyContext, Error = LemmyError> + Actor + Send + Sync + 'static,
+  for<'de2> <ActorT as Object>::Kind: serde::Deserialize<'de2> + Send + Sync,
+{
+  match key_id {
+    Some(key_id) => {
+      let receive =
+        receive_activity::<Activity, CachedSigner<ActorT>, LemmyContext>(request, body, data);
+      with_signer_key_id(key_id, receive).await
+    }
+    None => receive_activity::<Activity, ActorT, LemmyContext>(request, body, data).await,
+  }
+}
+
+fn is_invalid_signature(err: &LemmyError) -> bool {
+  matches!(
+    err.inner.downcast_ref::<Error>(),
+    Some(Error::ActivitySignatureInvalid)
+  )
+}
+
+/// Returns true and records the refetch if the actor wasn't refetched recently.
+fn refresh_allowed(actor_id: &Url) -> bool {
+  let mut refreshes = KEY_REFRESHES.lock().expect("lock key refreshes");
+  refreshes.retain(|_, refreshed| refreshed.elapsed() < MIN_REFRESH_INTERVAL);
+  if refreshes.contains_key(actor_id) {
+    return false;
+  }
+  refreshes.insert(actor_id.clone(), Instant::now());
+  true
+}
+
+/// Remembers the key which the actor had before a rotation, dropping expired and the oldest keys.
+fn store_previous_key(actor_id: Url, key: String) {
+  let mut previous_keys = PREVIOUS_KEYS.lock().expect("lock previous keys");
+  previous_keys.retain(|_, keys| {
+    keys.retain(|(_, rotated)| rotated.elapsed() < PREVIOUS_KEY_DURATION);
+    !keys.is_empty()
+  });
+  let keys = previous_keys.entry(actor_id).or_default();
+  keys.push_front((key, Instant::now()));
+  keys.truncate(MAX_PREVIOUS_KEYS);
+}
+
+/// Keys which the actor had before its recent rotations, newest first.
+fn previous_keys(actor_id: &Url) -> Vec<String> {
+  let previous_keys = PREVIOUS_KEYS.lock().expect("lock previous keys");
+  previous_keys
+    .get(actor_id)
+    .into_iter()
+    .flatten()
+    .filter(|(_, rotated)| rotated.elapsed() < PREVIOUS_KEY_DURATION)
+    .map(|(key, _)| key.clone())
+    .collect()
+}
+
+/// Same as the wrapped actor, but signatures are verified with the key in [PREVIOUS_KEY] instead
+/// of its current key.
+struct WithPreviousKey<ActorT> {
+  actor: ActorT,
+  key: String,
+}
+
+impl<ActorT> WithPreviousKey<ActorT> {
+  fn new(actor: ActorT) -> Self {
+    let key = PREVIOUS_KEY.try_with(Clone::clone).unwrap_or_default();
+    WithPreviousKey { actor, key }
+  }
+}
+
+#[async_trait::async_trait]
+impl<ActorT> Object for WithPreviousKey<ActorT>
+where
+  ActorT: Object<DataType = LemmyContext, Error = LemmyError> + Send + Sync + 'static,
+  <ActorT as Object>::Kind: Send + Sync,
+{
+  type DataType = LemmyContext;
+  type Kind = ActorT::Kind;
+  type Error = LemmyError;
+
+  fn last_refreshed_at(&self) -> Option<DateTime<Utc>> {
+    self.actor.last_refreshed_at()
+  }
+
+  async fn read_from_id(
+    object_id: Url,
+    data: &Data<Self::DataType>,
+  ) -> Result<Option<Self>, LemmyError> {
+    Ok(ActorT::read_from_id(object_id, data).await?.map(Self::new))
+  }
+
+  async fn delete(self, data: &Data<Self::DataType>) -> Result<(), LemmyError> {
+    self.actor.delete(data).await
+  }
+
+  async fn into_json(self, data: &Data<Self::DataType>) -> Result<Self::Kind, LemmyError> {
+    self.actor.into_json(data).await
+  }
+
+  async fn verify(
+    json: &Self::Kind,
+    expected_domain: &Url,
+    data: &Data<Self::DataType>,
+  ) -> Result<(), LemmyError> {
+    ActorT::verify(json, expected_domain, data).await
+  }
+
+  async fn from_json(json: Self::Kind, data: &Data<Self::DataType>) -> Result<Self, LemmyError> {
+    Ok(Self::new(ActorT::from_json(json, data).await?))
+  }
+}
+
+impl<ActorT> Actor for WithPreviousKey<ActorT>
+where
+  ActorT: Object<DataType = LemmyContext, Error = LemmyError> + Actor + Send + Sync + 'static,
+  <ActorT as Object>::Kind: Send + Sync,
+{
+  fn id(&self) -> Url {
+    self.actor.id()
+  }
+
+  fn public_key_pem(&self) -> &str {
+    &self.key
+  }
+
+  fn private_key_pem(&self) -> Option<String> {
+    None
+  }
+
+  fn inbox(&self) -> Url {
+    self.actor.inbox()
+  }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::{
+    objects::{
+      person::{tests::parse_lemmy_person, ApubPerson},
+      tests::init_context_with_client,
+    },
+    protocol::{objects::person::Person, tests::file_to_json_object},
+  };
+  use activitypub_federation::{
+    activity_sending::SendActivityTask,
+    http_signatures::{generate_actor_keypair, Keypair},
+  };
+  use actix_web::test::TestRequest;
+  use http::HeaderMap;
+  use lemmy_db_schema::{
+    source::{
+      person::{Person as DbPerson, PersonUpdateForm},
+      site::Site,
+    },
+    traits::Crud,
+  };
+  use reqwest::{Request, Response};
+  use reqwest_middleware::{ClientBuilder, Middleware, Next};
+  use serde::{Deserialize, Serialize};
+  use serial_test::serial;
+  use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+  };
+  use task_local_extensions::Extensions;
+
+  const INBOX: &str = "https://lemmy.test/inbox";
+
+  /// Serves the person json with the current key and captures requests to [INBOX], instead of
+  /// sending them.
+  #[derive(Clone, Default)]
+  pub(crate) struct RemoteMiddleware {
+    person: Arc<Mutex<String>>,
+    fetches: Arc<AtomicUsize>,
+    sent: Arc<Mutex<Vec<(HeaderMap, Vec<u8>)>>>,
+  }
+
+  #[async_trait::async_trait]
+  impl Middleware for RemoteMiddleware {
+    async fn handle(
+      &self,
+      req: Request,
+      _extensions: &mut Extensions,
+      _next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+      let res = match req.url().as_str() {
+        INBOX => {
+          let body = req.body().and_then(|b| b.as_bytes()).unwrap().to_vec();
+          self
+            .sent
+            .lock()
+            .unwrap()
+            .push((req.headers().clone(), body));
+          http::Response::builder().body(String::new())
+        }
+        "https://enterprise.lemmy.ml/u/picard" => {
+          self.fetches.fetch_add(1, Ordering::SeqCst);
+          http::Response::builder()
+            .header("Content-Type", "application/activity+json")
+            .body(self.person.lock().unwrap().clone())
+        }
+        _ => http::Response::builder()
+          .status(404)
+          .body("not found".to_string()),
+      };
+      Ok(res.unwrap().into())
+    }
+  }
+
+  #[derive(Clone, Debug, Deserialize, Serialize)]
+  struct TestActivity {
+    actor: ObjectId<ApubPerson>,
+    #[serde(rename = "type")]
+    kind: String,
+    id: Url,
+  }
+
+  #[async_trait::async_trait]
+  impl ActivityHandler for TestActivity {
+    type DataType = LemmyContext;
+    type Error = LemmyError;
+
+    fn id(&self) -> &Url {
+      &self.id
+    }
+
+    fn actor(&self) -> &Url {
+      self.actor.inner()
+    }
+
+    async fn verify(&self, _data: &Data<Self::DataType>) -> LemmyResult<()> {
+      Ok(())
+    }
+
+    async fn receive(self, _data: &Data<Self::DataType>) -> LemmyResult<()> {
+      Ok(())
+    }
+  }
+
+  /// Signs an activity of the person with the given key, and returns it as received request.
+  async fn signed_request(
+    person: &ApubPerson,
+    keypair: &Keypair,
+    middleware: &RemoteMiddleware,
+    context: &Data<LemmyContext>,
+  ) -> (HttpRequest, Bytes) {
+    signed_request_with_type("Test", person, keypair, middleware, context).await
+  }
+
+  /// Same as [signed_request], for an activity of the given type.
+  pub(crate) async fn signed_request_with_type(
+    kind: &str,
+    person: &ApubPerson,
+    keypair: &Keypair,
+    middleware: &RemoteMiddleware,
+    context: &Data<LemmyContext>,
+  ) -> (HttpRequest, Bytes) {
+    let mut signer = person.clone();
+    signer.0.private_key = Some(keypair.private_key.clone());
+    let activity = TestActivity {
+      actor: person.actor_id.clone().into(),
+      kind: kind.to_string(),
+      id: Url::parse("https://enterprise.lemmy.ml/activities/test/1").unwrap(),
+    };
+    let inbox = Url::parse(INBOX).unwrap();
+    let tasks = SendActivityTask::prepare(&activity, &signer, vec![inbox], context)
+      .await
+      .unwrap();
+    for task in tasks {
+      task.sign_and_send(context).await.unwrap();
+    }
+    let (headers, body) = middleware.sent.lock().unwrap().pop().unwrap();
+    let mut request = TestRequest::post().uri("/inbox");
+    for (name, value) in &headers {
+      request = request.insert_header((name.as_str(), value.to_str().unwrap()));
+    }
+    (request.to_http_request(), body.into())
+  }
+
+  async fn receive(request: HttpRequest, body: Bytes, context: &Data<LemmyContext>) -> bool {
+    receive_activity_with_key_refresh::<TestActivity, ApubPerson>(request, body, context)
+      .await
+      .is_ok()
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_rotated_key() {
+    let middleware = RemoteMiddleware::default();
+    let client = ClientBuilder::new(reqwest::Client::default())
+      .with(middleware.clone())
+      .build();
+    let context = init_context_with_client(client).await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    KEY_REFRESHES.lock().unwrap().clear();
+    let key_id = format!("{}#main-key", person.actor_id);
+    invalidate_signer_key(&key_id, person.actor_id.inner()).await;
+
+    // the remote instance rotated the key, but we still have the old one stored
+    let old_key = generate_actor_keypair().unwrap();
+    let new_key = generate_actor_keypair().unwrap();
+    let form = PersonUpdateForm {
+      public_key: Some(old_key.public_key.clone()),
+      ..Default::default()
+    };
+    DbPerson::update(&mut context.pool(), person.id, &form)
+      .await
+      .unwrap();
+    let mut json: Person = file_to_json_object("assets/lemmy/objects/person.json").unwrap();
+    json.featured = None;
+    json.public_key.public_key_pem = new_key.public_key.clone();
+    *middleware.person.lock().unwrap() = serde_json::to_string(&json).unwrap();
+
+    // a single refetch of the actor makes the signature valid
+    let (request, body) = signed_request(&person, &new_key, &middleware, &context).await;
+    assert!(receive(request, body, &context).await);
+    assert_eq!(1, middleware.fetches.load(Ordering::SeqCst));
+    let stored = DbPerson::read(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    assert_eq!(new_key.public_key, stored.public_key);
+
+    // activities signed shortly before the rotation are still accepted, without another refetch
+    let (request, body) = signed_request(&person, &old_key, &middleware, &context).await;
+    assert!(receive(request, body, &context).await);
+    assert_eq!(1, middleware.fetches.load(Ordering::SeqCst));
+
+    // other keys are rejected as well
+    let other_key = generate_actor_keypair().unwrap();
+    let (request, body) = signed_request(&person, &other_key, &middleware, &context).await;
+    assert!(!receive(request, body, &context).await);
+    assert_eq!(1, middleware.fetches.load(Ordering::SeqCst));
+
+    DbPerson::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+
+  /// Stores the key for the person, and serves the person json with `remote_key`.
+  pub(crate) async fn set_keys(
+    person: &ApubPerson,
+    stored_key: &Keypair,
+    remote_key: &Keypair,
+    middleware: &RemoteMiddleware,
+    context: &Data<LemmyContext>,
+  ) {
+    let form = PersonUpdateForm {
+      public_key: Some(stored_key.public_key.clone()),
+      ..Default::default()
+    };
+    DbPerson::update(&mut context.pool(), person.id, &form)
+      .await
+      .unwrap();
+    let mut json: Person = file_to_json_object("assets/lemmy/objects/person.json").unwrap();
+    json.featured = None;
+    json.public_key.public_key_pem = remote_key.public_key.clone();
+    *middleware.person.lock().unwrap() = serde_json::to_string(&json).unwrap();
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_signer_key_cached() {
+    let middleware = RemoteMiddleware::default();
+    let client = ClientBuilder::new(reqwest::Client::default())
+      .with(middleware.clone())
+      .build();
+    let context = init_context_with_client(client).await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    KEY_REFRESHES.lock().unwrap().clear();
+    let key_id = format!("{}#main-key", person.actor_id);
+    invalidate_signer_key(&key_id, person.actor_id.inner()).await;
+    let key = generate_actor_keypair().unwrap();
+    let rotated_key = generate_actor_keypair().unwrap();
+    let unused_key = generate_actor_keypair().unwrap();
+    set_keys(&person, &key, &key, &middleware, &context).await;
+
+    // the key is read for the first activity
+    let (request, body) = signed_request(&person, &key, &middleware, &context).await;
+    assert!(receive(request, body, &context).await);
+
+    // the second activity with the same key id uses the cached key, even though the stored key
+    // changed in the meantime
+    set_keys(&person, &unused_key, &key, &middleware, &context).await;
+    let (request, body) = signed_request(&person, &key, &middleware, &context).await;
+    assert!(receive(request, body, &context).await);
+    assert_eq!(0, middleware.fetches.load(Ordering::SeqCst));
+
+    // after a rotation the cached key doesn't match, so it is invalidated and the actor refetched
+    set_keys(&person, &key, &rotated_key, &middleware, &context).await;
+    let (request, body) = signed_request(&person, &rotated_key, &middleware, &context).await;
+    assert!(receive(request, body, &context).await);
+    assert_eq!(1, middleware.fetches.load(Ordering::SeqCst));
+
+    // the rotated key replaced the cached one
+    set_keys(&person, &unused_key, &rotated_key, &middleware, &context).await;
+    let (request, body) = signed_request(&person, &rotated_key, &middleware, &context).await;
+    assert!(receive(request, body, &context).await);
+    assert_eq!(1, middleware.fetches.load(Ordering::SeqCst));
+
+    DbPerson::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+
+  #[test]
+  fn test_previous_keys_bounded() {
+    let actor_id = Url::parse("https://enterprise.lemmy.ml/u/riker").unwrap();
+    for key in ["key1", "key2", "key3"] {
+      store_previous_key(actor_id.clone(), key.to_string());
+    }
+    assert_eq!(vec!["key3", "key2"], previous_keys(&actor_id));
+
+    // expired keys aren't accepted anymore
+    let expired = Instant::now() - PREVIOUS_KEY_DURATION;
+    for (_, rotated) in PREVIOUS_KEYS
+      .lock()
+      .unwrap()
+      .get_mut(&actor_id)
+      .unwrap()
+      .iter_mut()
+    {
+      *rotated = expired;
+    }
+    assert!(previous_keys(&actor_id).is_empty());
+  }
+}
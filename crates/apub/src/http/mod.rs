@@ -1,38 +1,341 @@
 use crate::{
-  activity_lists::SharedInboxActivities,
-  fetcher::user_or_community::UserOrCommunity,
+  activity_lists::{SharedInboxActivities, VerifyWithTimeout},
+  fetcher::site_or_community_or_user::SiteOrCommunityOrUser,
+  http::{key_refresh::receive_activity_with_key_refresh, stats::count_received_activity},
+  insert_received_activity,
   protocol::objects::tombstone::Tombstone,
   CONTEXT,
 };
 use activitypub_federation::{
-  actix_web::inbox::receive_activity,
+  actix_web::signing_actor,
   config::Data,
   protocol::context::WithContext,
+  traits::ActivityHandler,
   FEDERATION_CONTENT_TYPE,
 };
 use actix_web::{web, web::Bytes, HttpRequest, HttpResponse};
 use http::{header::LOCATION, StatusCode};
 use lemmy_api_common::context::LemmyContext;
-use lemmy_db_schema::{newtypes::DbUrl, source::activity::SentActivity};
-use lemmy_utils::error::{LemmyError, LemmyResult};
+use lemmy_db_schema::{
+  newtypes::DbUrl,
+  source::activity::{ReceivedActivity, SentActivity},
+};
+use lemmy_utils::{
+  error::{LemmyError, LemmyResult},
+  rate_limit::{rate_limiter::InstantSecs, BucketConfig, DomainRateLimitState},
+  settings::structs::Settings,
+};
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
-use std::ops::Deref;
+use std::{ops::Deref, sync::Mutex};
+use tracing::{debug, info, warn};
 use url::Url;
 
 mod comment;
 mod community;
+mod key_refresh;
 mod person;
 mod post;
 pub mod routes;
+mod signer_cache;
 pub mod site;
+pub mod stats;
 
 pub async fn shared_inbox(
   request: HttpRequest,
   body: Bytes,
   data: Data<LemmyContext>,
 ) -> LemmyResult<HttpResponse> {
-  receive_activity::<SharedInboxActivities, UserOrCommunity, LemmyContext>(request, body, &data)
-    .await
+  // the checks below only need a few fields, so the body is parsed for them once
+  let activity = serde_json::from_slice::<UnknownActivity>(&body).ok();
+  if let Some(res) = check_inbox_rate_limit(activity.as_ref(), data.settings()) {
+    return Ok(res);
+  }
+  if let Some(res) = ignore_configured_activity(activity.as_ref(), &request, &body, &data).await? {
+    return Ok(res);
+  }
+  if let Some(res) = ignore_unknown_activity(activity.as_ref()) {
+    return Ok(res);
+  }
+  let signature_algorithm = signature_algorithm(&request);
+  let res = receive_activity_with_key_refresh::<
+    VerifyWithTimeout<SharedInboxActivities>,
+    SiteOrCommunityOrUser,
+  >(request, body, &data)
+  .await?;
+  count_received_activity();
+  store_signature_algorithm(signature_algorithm, activity.as_ref(), &data).await;
+  Ok(res)
+}
+
+/// Activity types which are handled by at least one of the inboxes. `Page` is included so that
+/// it keeps getting rejected with a proper error, as we only send it.
+const KNOWN_ACTIVITY_TYPES: [&str; 17] = [
+  "Accept",
+  "Add",
+  "Announce",
+  "BatchFollow",
+  "Block",
+  "Create",
+  "Delete",
+  "Dislike",
+  "EmojiReact",
+  "Flag",
+  "Follow",
+  "Like",
+  "Lock",
+  "Page",
+  "Remove",
+  "Undo",
+  "Update",
+];
+
+/// Minimal fields which any activity needs to have, regardless of its type.
+#[derive(Deserialize)]
+struct UnknownActivity {
+  id: Url,
+  actor: Url,
+  #[serde(rename = "type")]
+  kind: String,
+}
+
+/// Acknowledges activities with one of the `federation.ignored_activity_types`, like `View` which
+/// is sent by Peertube. They are received as [IgnoredActivity], so the signature is verified and
+/// the id stored like for any other activity, but nothing else is done with them.
+async fn ignore_configured_activity(
+  activity: Option<&UnknownActivity>,
+  request: &HttpRequest,
+  body: &Bytes,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<Option<HttpResponse>> {
+  let Some(activity) = activity else {
+    return Ok(None);
+  };
+  if !context
+    .settings()
+    .federation
+    .ignored_activity_types
+    .contains(&activity.kind)
+  {
+    return Ok(None);
+  }
+  let res = receive_activity_with_key_refresh::<
+    VerifyWithTimeout<IgnoredActivity>,
+    SiteOrCommunityOrUser,
+  >(request.clone(), body.clone(), context)
+  .await?;
+  Ok(Some(res))
+}
+
+/// Activity of one of the `federation.ignored_activity_types`. Only the id is stored, so that
+/// duplicates are rejected.
+#[derive(Debug, Deserialize)]
+struct IgnoredActivity {
+  id: Url,
+  actor: Url,
+  #[serde(rename = "type")]
+  kind: String,
+}
+
+#[async_trait::async_trait]
+impl ActivityHandler for IgnoredActivity {
+  type DataType = LemmyContext;
+  type Error = LemmyError;
+
+  fn id(&self) -> &Url {
+    &self.id
+  }
+
+  fn actor(&self) -> &Url {
+    &self.actor
+  }
+
+  async fn verify(&self, context: &Data<LemmyContext>) -> LemmyResult<()> {
+    insert_received_activity(&self.id, context).await
+  }
+
+  async fn receive(self, _context: &Data<LemmyContext>) -> LemmyResult<()> {
+    debug!(
+      "Ignoring activity {} of type {} from {}",
+      self.id, self.kind, self.actor
+    );
+    Ok(())
+  }
+}
+
+/// Checks if the body is a well-formed activity of a type which Lemmy doesn't implement, for
+/// example a new activity type from another platform. Such activities are acknowledged without
+/// processing, because returning an error would make the sender retry them forever.
+fn ignore_unknown_activity(activity: Option<&UnknownActivity>) -> Option<HttpResponse> {
+  let activity = activity?;
+  if KNOWN_ACTIVITY_TYPES.contains(&activity.kind.as_str()) {
+    return None;
+  }
+  info!(
+    "Ignoring activity {} of unknown type {} from {}",
+    activity.id, activity.kind, activity.actor
+  );
+  Some(HttpResponse::Accepted().finish())
+}
+
+/// Buckets for `federation.inbox_rate_limit`, shared by all inboxes.
+static INBOX_RATE_LIMIT: Lazy<Mutex<DomainRateLimitState>> = Lazy::new(Default::default);
+
+/// Rejects the activity with HTTP 429 if the instance of its actor sent too many activities
+/// recently. The signature isn't verified at this point, so this only checks the limit. Activities
+/// are counted with [count_inbox_rate_limit] once their signature is verified, so that nobody can
+/// use up the limit of another instance. The signature is verified with the key of the actor, so
+/// a sender can't avoid its own limit by naming another key.
+fn check_inbox_rate_limit(
+  activity: Option<&UnknownActivity>,
+  settings: &Settings,
+) -> Option<HttpResponse> {
+  let domain = activity?.actor.domain()?.to_lowercase();
+  if inbox_rate_limited(&domain, &INBOX_RATE_LIMIT, settings, InstantSecs::now()) {
+    debug!("Rate limited activities from {domain}");
+    Some(HttpResponse::TooManyRequests().finish())
+  } else {
+    None
+  }
+}
+
+/// Counts an activity towards the rate limit of its actor's instance. This is called for every
+/// activity once its signature was verified with the key of the actor, regardless of whether the
+/// activity is accepted afterwards.
+pub(crate) fn count_inbox_rate_limit(actor: &Url, settings: &Settings) {
+  if let Some(domain) = actor.domain() {
+    count_inbox_activity(
+      &domain.to_lowercase(),
+      &INBOX_RATE_LIMIT,
+      settings,
+      InstantSecs::now(),
+    );
+  }
+}
+
+fn inbox_rate_limited(
+  domain: &str,
+  state: &Mutex<DomainRateLimitState>,
+  settings: &Settings,
+  now: InstantSecs,
+) -> bool {
+  inbox_rate_limit_config(domain, settings).is_some()
+    && state
+      .lock()
+      .expect("Failed to lock inbox rate limit mutex")
+      .is_limited(domain, now, |domain| unlimited_or(domain, settings))
+}
+
+fn count_inbox_activity(
+  domain: &str,
+  state: &Mutex<DomainRateLimitState>,
+  settings: &Settings,
+  now: InstantSecs,
+) {
+  if inbox_rate_limit_config(domain, settings).is_some() {
+    state
+      .lock()
+      .expect("Failed to lock inbox rate limit mutex")
+      .check(domain, now, |domain| unlimited_or(domain, settings));
+  }
+}
+
+/// Bucket for activities from the given domain, or `None` if they are not limited.
+fn inbox_rate_limit_config(domain: &str, settings: &Settings) -> Option<BucketConfig> {
+  let config = &settings.federation;
+  let limit = config
+    .inbox_rate_limit_overrides
+    .iter()
+    .find(|(d, _)| d.eq_ignore_ascii_case(domain))
+    .map(|(_, limit)| *limit)
+    .unwrap_or(config.inbox_rate_limit);
+  (limit > 0).then_some(BucketConfig {
+    capacity: limit,
+    secs_to_refill: 60,
+  })
+}
+
+fn unlimited_or(domain: &str, settings: &Settings) -> BucketConfig {
+  inbox_rate_limit_config(domain, settings).unwrap_or(BucketConfig {
+    capacity: u32::MAX,
+    secs_to_refill: 1,
+  })
+}
+
+/// HTTP signature scheme of the request, which is either the draft-cavage scheme together with
+/// its `algorithm` parameter, or RFC 9421.
+fn signature_algorithm(request: &HttpRequest) -> Option<String> {
+  let headers = request.headers();
+  if headers.contains_key("signature-input") {
+    return Some("rfc9421".to_string());
+  }
+  let signature = headers.get("signature")?.to_str().ok()?;
+  let algorithm = signature
+    .split(',')
+    .find_map(|param| param.trim().strip_prefix("algorithm="))
+    .map(|algorithm| algorithm.trim_matches('"').to_lowercase());
+  Some(match algorithm {
+    Some(algorithm) => format!("draft-cavage/{algorithm}"),
+    None => "draft-cavage".to_string(),
+  })
+}
+
+/// Stores the signature scheme of a received activity, for debugging federation issues. This can
+/// only be done once the activity was received, as it is only inserted into the database during
+/// verification.
+async fn store_signature_algorithm(
+  signature_algorithm: Option<String>,
+  activity: Option<&UnknownActivity>,
+  context: &LemmyContext,
+) {
+  let (Some(algorithm), Some(activity)) = (signature_algorithm, activity) else {
+    return;
+  };
+  let res = ReceivedActivity::set_signature_algorithm(
+    &mut context.pool(),
+    &activity.id.clone().into(),
+    &algorithm,
+  )
+  .await;
+  if let Err(e) = res {
+    warn!("Failed to store signature algorithm: {e}");
+  }
+}
+
+/// Rejects requests for objects with HTTP 401 unless they are signed by a remote actor, if this is
+/// required by `federation.signed_fetch`.
+async fn check_signed_fetch(
+  request: &HttpRequest,
+  context: &Data<LemmyContext>,
+) -> Option<HttpResponse> {
+  signed_fetch_response(request, context.settings(), context).await
+}
+
+async fn signed_fetch_response(
+  request: &HttpRequest,
+  settings: &Settings,
+  context: &Data<LemmyContext>,
+) -> Option<HttpResponse> {
+  let federation = &settings.federation;
+  if !federation.signed_fetch {
+    return None;
+  }
+  if !federation.signed_fetch_activities && request.path().starts_with("/activities/") {
+    return None;
+  }
+  let local = request
+    .peer_addr()
+    .is_some_and(|addr| addr.ip().is_loopback());
+  if federation.signed_fetch_allow_local && local {
+    return None;
+  }
+  match signing_actor::<SiteOrCommunityOrUser>(request, None, context).await {
+    Ok(_) => None,
+    Err(e) => {
+      debug!("Rejected unsigned fetch of {}: {e}", request.path());
+      Some(HttpResponse::Unauthorized().finish())
+    }
+  }
 }
 
 /// Convert the data to json and turn it into an HTTP Response with the correct ActivityPub
@@ -80,8 +383,12 @@ pub struct ActivityQuery {
 #[tracing::instrument(skip_all)]
 pub(crate) async fn get_activity(
   info: web::Path<ActivityQuery>,
-  context: web::Data<LemmyContext>,
+  request: HttpRequest,
+  context: Data<LemmyContext>,
 ) -> Result<HttpResponse, LemmyError> {
+  if let Some(res) = check_signed_fetch(&request, &context).await {
+    return Ok(res);
+  }
   let settings = context.settings();
   let activity_id = Url::parse(&format!(
     "{}/activities/{}/{}",
@@ -99,3 +406,410 @@ pub(crate) async fn get_activity(
     create_apub_response(&activity.data)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::{
+    http::key_refresh::tests::{set_keys, signed_request_with_type, RemoteMiddleware},
+    objects::{
+      person::tests::parse_lemmy_person,
+      tests::{init_context, init_context_with_client},
+    },
+  };
+  use activitypub_federation::{
+    config::FederationConfig,
+    fetch::fetch_object_http,
+    http_signatures::generate_actor_keypair,
+  };
+  use actix_web::test::TestRequest;
+  use http::HeaderMap;
+  use lemmy_db_schema::{
+    source::{
+      activity::ReceivedActivity,
+      person::{Person, PersonUpdateForm},
+      site::Site,
+    },
+    traits::Crud,
+  };
+  use lemmy_utils::{error::LemmyErrorType, settings::SETTINGS};
+  use reqwest::{Request, Response};
+  use reqwest_middleware::{ClientBuilder, Middleware, Next};
+  use serde_json::Value;
+  use serial_test::serial;
+  use std::{fs::read, sync::Arc};
+  use task_local_extensions::Extensions;
+  use uuid::Uuid;
+
+  /// Captures the headers of outgoing requests instead of sending them.
+  #[derive(Clone, Default)]
+  struct CaptureMiddleware(Arc<Mutex<Vec<HeaderMap>>>);
+
+  #[async_trait::async_trait]
+  impl Middleware for CaptureMiddleware {
+    async fn handle(
+      &self,
+      req: Request,
+      _extensions: &mut Extensions,
+      _next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+      self.0.lock().unwrap().push(req.headers().clone());
+      let res = http::Response::builder()
+        .status(404)
+        .body("not found".to_string());
+      Ok(res.unwrap().into())
+    }
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_ignore_unknown_activity() {
+    let context = init_context().await;
+    let ap_id = Url::parse(&format!(
+      "https://misskey.example/activities/{}",
+      Uuid::new_v4()
+    ))
+    .unwrap();
+    let body = serde_json::to_vec(&serde_json::json!({
+      "id": ap_id,
+      "actor": "https://misskey.example/users/alice",
+      "type": "Bite",
+      "object": "https://ds9.lemmy.ml/u/lemmy_alpha",
+    }))
+    .unwrap();
+    let request = TestRequest::post().uri("/inbox").to_http_request();
+
+    let res = shared_inbox(request, body.into(), context.reset_request_count())
+      .await
+      .unwrap();
+    assert_eq!(StatusCode::ACCEPTED, res.status());
+
+    // the activity wasnt stored, so it would still be processed if it becomes known later
+    ReceivedActivity::create(&mut context.pool(), &ap_id.into())
+      .await
+      .unwrap();
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_ignore_configured_activity() {
+    let middleware = RemoteMiddleware::default();
+    let client = ClientBuilder::new(reqwest::Client::default())
+      .with(middleware.clone())
+      .build();
+    let context = init_context_with_client(client).await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let key = generate_actor_keypair().unwrap();
+    set_keys(&person, &key, &key, &middleware, &context).await;
+
+    // unsigned activities are rejected and not stored, so they can't block the id for a genuine
+    // one
+    let (_, body) = signed_request_with_type("View", &person, &key, &middleware, &context).await;
+    let unsigned = TestRequest::post().uri("/inbox").to_http_request();
+    shared_inbox(unsigned, body, context.reset_request_count())
+      .await
+      .unwrap_err();
+    let ap_id: DbUrl = Url::parse("https://enterprise.lemmy.ml/activities/test/1")
+      .unwrap()
+      .into();
+    assert!(
+      ReceivedActivity::read_from_apub_id(&mut context.pool(), &ap_id)
+        .await
+        .is_err()
+    );
+
+    // signed ones are stored and acknowledged, so that duplicates are rejected
+    let (request, body) =
+      signed_request_with_type("View", &person, &key, &middleware, &context).await;
+    let res = shared_inbox(request, body, context.reset_request_count())
+      .await
+      .unwrap();
+    assert_eq!(StatusCode::OK, res.status());
+    ReceivedActivity::read_from_apub_id(&mut context.pool(), &ap_id)
+      .await
+      .unwrap();
+    let (request, body) =
+      signed_request_with_type("View", &person, &key, &middleware, &context).await;
+    let err = shared_inbox(request, body, context.reset_request_count())
+      .await
+      .unwrap_err();
+    assert_eq!(LemmyErrorType::ActivityAlreadyReceived, err.error_type);
+
+    ReceivedActivity::delete(&mut context.pool(), &ap_id)
+      .await
+      .unwrap();
+    Person::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+
+  #[test]
+  fn test_inbox_rate_limit() {
+    let mut settings = SETTINGS.clone();
+    settings.federation.inbox_rate_limit = 5;
+    settings
+      .federation
+      .inbox_rate_limit_overrides
+      .insert("trusted.example".to_string(), 100);
+    let state = Mutex::new(DomainRateLimitState::default());
+    let now = InstantSecs::now();
+    let limited = |domain: &str| inbox_rate_limited(domain, &state, &settings, now);
+    let count = |domain: &str| count_inbox_activity(domain, &state, &settings, now);
+
+    // unverified requests don't count, so they can't use up the limit of an instance
+    for _ in 0..10 {
+      assert!(!limited("spam.example"));
+    }
+    for _ in 0..5 {
+      assert!(!limited("spam.example"));
+      count("spam.example");
+    }
+    assert!(limited("spam.example"));
+    assert!(limited("SPAM.example"));
+
+    // other instances are unaffected
+    assert!(!limited("other.example"));
+    // overrides can give an instance a higher limit
+    for _ in 0..10 {
+      count("trusted.example");
+    }
+    assert!(!limited("trusted.example"));
+  }
+
+  /// Request which claims to be signed by a key of the given domain
+  fn signed_request(domain: &str) -> HttpRequest {
+    TestRequest::post()
+      .uri("/inbox")
+      .insert_header((
+        "signature",
+        format!(
+          r#"keyId="https://{domain}/u/alice#main-key",algorithm="rsa-sha256",headers="(request-target) host date digest",signature="abc""#
+        ),
+      ))
+      .to_http_request()
+  }
+
+  fn unknown_activity(domain: &str) -> Bytes {
+    let body = serde_json::to_vec(&serde_json::json!({
+      "id": format!("https://{domain}/activities/{}", Uuid::new_v4()),
+      "actor": format!("https://{domain}/u/alice"),
+      "type": "Bite",
+      "object": "https://ds9.lemmy.ml/u/lemmy_alpha",
+    }))
+    .unwrap();
+    body.into()
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_inbox_rate_limit_response() {
+    let context = init_context().await;
+    let settings = context.settings();
+    assert!(settings.federation.inbox_rate_limit > 0);
+
+    // use up the limit of one instance with activities whose signature was verified
+    let actor = Url::parse("https://flood.example/u/alice").unwrap();
+    for _ in 0..settings.federation.inbox_rate_limit {
+      count_inbox_rate_limit(&actor, settings);
+    }
+    let res = shared_inbox(
+      signed_request("flood.example"),
+      unknown_activity("flood.example"),
+      context.reset_request_count(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(StatusCode::TOO_MANY_REQUESTS, res.status());
+
+    // naming the key of another instance doesn't help, as the actor's key verifies the signature
+    let res = shared_inbox(
+      signed_request("calm.example"),
+      unknown_activity("flood.example"),
+      context.reset_request_count(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(StatusCode::TOO_MANY_REQUESTS, res.status());
+
+    // other instances are unaffected
+    let res = shared_inbox(
+      signed_request("calm.example"),
+      unknown_activity("calm.example"),
+      context.reset_request_count(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(StatusCode::ACCEPTED, res.status());
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_store_signature_algorithm() {
+    let context = init_context().await;
+    let ap_id: DbUrl = Url::parse("https://ds9.lemmy.ml/activities/follow/signature-test")
+      .unwrap()
+      .into();
+    let body = serde_json::to_vec(&serde_json::json!({
+      "id": ap_id,
+      "actor": "https://ds9.lemmy.ml/u/lemmy_alpha",
+      "type": "Follow",
+    }))
+    .unwrap();
+    ReceivedActivity::create(&mut context.pool(), &ap_id)
+      .await
+      .unwrap();
+
+    let request = TestRequest::post()
+      .uri("/inbox")
+      .insert_header((
+        "signature",
+        r#"keyId="https://ds9.lemmy.ml/u/lemmy_alpha#main-key",algorithm="rsa-sha256",headers="(request-target) host date digest",signature="abc""#,
+      ))
+      .to_http_request();
+    let activity = serde_json::from_slice(&body).ok();
+    store_signature_algorithm(signature_algorithm(&request), activity.as_ref(), &context).await;
+    let received = ReceivedActivity::read_from_apub_id(&mut context.pool(), &ap_id)
+      .await
+      .unwrap();
+    assert_eq!(
+      Some("draft-cavage/rsa-sha256"),
+      received.signature_algorithm.as_deref()
+    );
+
+    let request = TestRequest::post()
+      .uri("/inbox")
+      .insert_header((
+        "signature-input",
+        r#"sig1=("@method" "@target-uri");keyid="a""#,
+      ))
+      .insert_header(("signature", "sig1=:YWJj:"))
+      .to_http_request();
+    assert_eq!(Some("rfc9421".to_string()), signature_algorithm(&request));
+    let unsigned = TestRequest::post().uri("/inbox").to_http_request();
+    assert_eq!(None, signature_algorithm(&unsigned));
+  }
+
+  #[test]
+  fn test_known_activity_not_ignored() {
+    for path in [
+      "assets/lemmy/activities/following/follow.json",
+      "assets/pleroma/activities/emoji_react.json",
+    ] {
+      let activity = serde_json::from_slice(&read(path).unwrap()).ok();
+      assert!(ignore_unknown_activity(activity.as_ref()).is_none());
+    }
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_signed_fetch() {
+    let context = init_context().await;
+    let mut settings = SETTINGS.clone();
+    let request = || TestRequest::get().uri("/post/1").to_http_request();
+
+    // unsigned requests are allowed unless signatures are required
+    assert!(signed_fetch_response(&request(), &settings, &context)
+      .await
+      .is_none());
+    settings.federation.signed_fetch = true;
+    let res = signed_fetch_response(&request(), &settings, &context)
+      .await
+      .unwrap();
+    assert_eq!(StatusCode::UNAUTHORIZED, res.status());
+
+    // activities can be exempted
+    let activity = TestRequest::get()
+      .uri("/activities/like/1")
+      .to_http_request();
+    assert!(signed_fetch_response(&activity, &settings, &context)
+      .await
+      .is_some());
+    settings.federation.signed_fetch_activities = false;
+    assert!(signed_fetch_response(&activity, &settings, &context)
+      .await
+      .is_none());
+
+    // local requests can be exempted
+    let local = TestRequest::get()
+      .uri("/post/1")
+      .peer_addr("127.0.0.1:8536".parse().unwrap())
+      .to_http_request();
+    assert!(signed_fetch_response(&local, &settings, &context)
+      .await
+      .is_some());
+    settings.federation.signed_fetch_allow_local = true;
+    assert!(signed_fetch_response(&local, &settings, &context)
+      .await
+      .is_none());
+    assert!(signed_fetch_response(&request(), &settings, &context)
+      .await
+      .is_some());
+    assert_eq!(context.request_count(), 0);
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_signed_fetch_with_signature() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let keypair = generate_actor_keypair().unwrap();
+    let form = PersonUpdateForm {
+      public_key: Some(keypair.public_key.clone()),
+      ..Default::default()
+    };
+    Person::update(&mut context.pool(), person.id, &form)
+      .await
+      .unwrap();
+    let mut signer = person.clone();
+    signer.0.private_key = Some(keypair.private_key);
+
+    // fetch with a config which signs requests like the one in main, and capture the request
+    let capture = CaptureMiddleware::default();
+    let client = ClientBuilder::new(reqwest::Client::default())
+      .with(capture.clone())
+      .build();
+    let config = FederationConfig::builder()
+      .domain("example.com")
+      .app_data(context.deref().clone())
+      .client(client)
+      .signed_fetch_actor(&signer)
+      .build()
+      .await
+      .unwrap();
+    let url = Url::parse("https://lemmy.test/post/1").unwrap();
+    assert!(
+      fetch_object_http::<_, Value>(&url, &config.to_request_data())
+        .await
+        .is_err()
+    );
+    let headers = capture.0.lock().unwrap().pop().unwrap();
+    let signed = headers
+      .iter()
+      .fold(
+        TestRequest::get().uri("/post/1"),
+        |request, (name, value)| request.insert_header((name.as_str(), value.to_str().unwrap())),
+      )
+      .to_http_request();
+
+    // the signature of a known actor is accepted
+    let mut settings = SETTINGS.clone();
+    settings.federation.signed_fetch = true;
+    assert!(signed_fetch_response(&signed, &settings, &context)
+      .await
+      .is_none());
+    let unsigned = TestRequest::get().uri("/post/1").to_http_request();
+    assert!(signed_fetch_response(&unsigned, &settings, &context)
+      .await
+      .is_some());
+    assert_eq!(context.request_count(), 0);
+
+    Person::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+}
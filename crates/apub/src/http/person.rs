@@ -1,20 +1,32 @@
 use crate::{
-  activity_lists::PersonInboxActivities,
+  activity_lists::{PersonInboxActivities, VerifyWithTimeout},
+  collections::person_featured::ApubPersonFeatured,
   fetcher::user_or_community::UserOrCommunity,
-  http::{create_apub_response, create_apub_tombstone_response},
+  http::{
+    check_inbox_rate_limit,
+    check_signed_fetch,
+    create_apub_response,
+    create_apub_tombstone_response,
+    ignore_configured_activity,
+    ignore_unknown_activity,
+    key_refresh::receive_activity_with_key_refresh,
+    signature_algorithm,
+    stats::count_received_activity,
+    store_signature_algorithm,
+    UnknownActivity,
+  },
   objects::person::ApubPerson,
   protocol::collections::empty_outbox::EmptyOutbox,
 };
 use activitypub_federation::{
-  actix_web::inbox::receive_activity,
   config::Data,
   protocol::context::WithContext,
-  traits::Object,
+  traits::{Collection, Object},
 };
 use actix_web::{web, web::Bytes, HttpRequest, HttpResponse};
 use lemmy_api_common::{context::LemmyContext, utils::generate_outbox_url};
 use lemmy_db_schema::{source::person::Person, traits::ApubActor};
-use lemmy_utils::error::LemmyError;
+use lemmy_utils::error::{LemmyError, LemmyErrorType};
 use serde::Deserialize;
 
 #[derive(Deserialize)]
@@ -49,19 +61,57 @@ pub async fn person_inbox(
   body: Bytes,
   data: Data<LemmyContext>,
 ) -> Result<HttpResponse, LemmyError> {
-  receive_activity::<WithContext<PersonInboxActivities>, UserOrCommunity, LemmyContext>(
-    request, body, &data,
-  )
-  .await
+  let activity = serde_json::from_slice::<UnknownActivity>(&body).ok();
+  if let Some(res) = check_inbox_rate_limit(activity.as_ref(), data.settings()) {
+    return Ok(res);
+  }
+  if let Some(res) = ignore_configured_activity(activity.as_ref(), &request, &body, &data).await? {
+    return Ok(res);
+  }
+  if let Some(res) = ignore_unknown_activity(activity.as_ref()) {
+    return Ok(res);
+  }
+  let signature_algorithm = signature_algorithm(&request);
+  let res = receive_activity_with_key_refresh::<
+    VerifyWithTimeout<WithContext<PersonInboxActivities>>,
+    UserOrCommunity,
+  >(request, body, &data)
+  .await?;
+  count_received_activity();
+  store_signature_algorithm(signature_algorithm, activity.as_ref(), &data).await;
+  Ok(res)
 }
 
 #[tracing::instrument(skip_all)]
 pub(crate) async fn get_apub_person_outbox(
   info: web::Path<PersonQuery>,
+  request: HttpRequest,
   context: Data<LemmyContext>,
 ) -> Result<HttpResponse, LemmyError> {
+  if let Some(res) = check_signed_fetch(&request, &context).await {
+    return Ok(res);
+  }
   let person = Person::read_from_name(&mut context.pool(), &info.user_name, false).await?;
   let outbox_id = generate_outbox_url(&person.actor_id)?.into();
   let outbox = EmptyOutbox::new(outbox_id)?;
   create_apub_response(&outbox)
 }
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn get_apub_person_featured(
+  info: web::Path<PersonQuery>,
+  request: HttpRequest,
+  context: Data<LemmyContext>,
+) -> Result<HttpResponse, LemmyError> {
+  if let Some(res) = check_signed_fetch(&request, &context).await {
+    return Ok(res);
+  }
+  let person: ApubPerson = Person::read_from_name(&mut context.pool(), &info.user_name, false)
+    .await?
+    .into();
+  if person.deleted {
+    Err(LemmyErrorType::Deleted)?
+  }
+  let featured = ApubPersonFeatured::read_local(&person, &context).await?;
+  create_apub_response(&featured)
+}
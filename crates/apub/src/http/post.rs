@@ -1,9 +1,14 @@
 use crate::{
-  http::{create_apub_response, create_apub_tombstone_response, redirect_remote_object},
+  http::{
+    check_signed_fetch,
+    create_apub_response,
+    create_apub_tombstone_response,
+    redirect_remote_object,
+  },
   objects::post::ApubPost,
 };
 use activitypub_federation::{config::Data, traits::Object};
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 use lemmy_api_common::context::LemmyContext;
 use lemmy_db_schema::{newtypes::PostId, source::post::Post, traits::Crud};
 use lemmy_utils::error::LemmyError;
@@ -18,8 +23,12 @@ pub(crate) struct PostQuery {
 #[tracing::instrument(skip_all)]
 pub(crate) async fn get_apub_post(
   info: web::Path<PostQuery>,
+  request: HttpRequest,
   context: Data<LemmyContext>,
 ) -> Result<HttpResponse, LemmyError> {
+  if let Some(res) = check_signed_fetch(&request, &context).await {
+    return Ok(res);
+  }
   let id = PostId(info.post_id.parse::<i32>()?);
   let post: ApubPost = Post::read(&mut context.pool(), id).await?.into();
   if !post.local {
@@ -9,10 +9,11 @@ use crate::http::{
     get_apub_community_outbox,
   },
   get_activity,
-  person::{get_apub_person_http, get_apub_person_outbox, person_inbox},
+  person::{get_apub_person_featured, get_apub_person_http, get_apub_person_outbox, person_inbox},
   post::get_apub_post,
   shared_inbox,
   site::{get_apub_site_http, get_apub_site_inbox, get_apub_site_outbox},
+  stats::get_federation_stats,
 };
 use actix_web::{
   guard::{Guard, GuardContext},
@@ -24,6 +25,7 @@ pub fn config(cfg: &mut web::ServiceConfig) {
   cfg
     .route("/", web::get().to(get_apub_site_http))
     .route("/site_outbox", web::get().to(get_apub_site_outbox))
+    .route("/federation_stats", web::get().to(get_federation_stats))
     .route(
       "/c/{community_name}",
       web::get().to(get_apub_community_http),
@@ -49,6 +51,10 @@ pub fn config(cfg: &mut web::ServiceConfig) {
       "/u/{user_name}/outbox",
       web::get().to(get_apub_person_outbox),
     )
+    .route(
+      "/u/{user_name}/featured",
+      web::get().to(get_apub_person_featured),
+    )
     .route("/post/{post_id}", web::get().to(get_apub_post))
     .route("/comment/{comment_id}", web::get().to(get_apub_comment))
     .route("/activities/{type_}/{id}", web::get().to(get_activity));
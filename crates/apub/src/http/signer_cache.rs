@@ -0,0 +1,178 @@
+use activitypub_federation::{
+  config::Data,
+  traits::{Actor, Object},
+};
+use actix_web::HttpRequest;
+use chrono::{DateTime, Utc};
+use lemmy_api_common::context::LemmyContext;
+use lemmy_utils::error::{LemmyError, LemmyErrorType};
+use moka::future::Cache;
+use once_cell::sync::Lazy;
+use std::{future::Future, marker::PhantomData, time::Duration};
+use url::Url;
+
+/// Activities often arrive in bursts which are signed with the same key, and the key needs to be
+/// read from the signing actor for each one. Keys are kept in memory for this long, so that a
+/// burst only reads them once.
+const SIGNER_CACHE_DURATION: Duration = Duration::from_secs(5);
+
+/// Public keys of actors which recently signed an activity, by key id and actor id.
+static SIGNER_KEYS: Lazy<Cache<(String, Url), CachedKey>> = Lazy::new(|| {
+  Cache::builder()
+    .max_capacity(10_000)
+    .time_to_live(SIGNER_CACHE_DURATION)
+    .build()
+});
+
+tokio::task_local! {
+  /// Key id from the signature of the activity which is being received.
+  static SIGNER_KEY_ID: String;
+}
+
+#[derive(Clone)]
+struct CachedKey {
+  key: String,
+  inbox: Url,
+  last_refreshed_at: Option<DateTime<Utc>>,
+}
+
+/// Returns the key id from the draft-cavage `Signature` header of the request.
+pub(crate) fn signer_key_id(request: &HttpRequest) -> Option<String> {
+  let signature = request.headers().get("signature")?.to_str().ok()?;
+  signature
+    .split(',')
+    .find_map(|param| param.trim().strip_prefix("keyId="))
+    .map(|key_id| key_id.trim_matches('"').to_string())
+}
+
+/// Runs `f` with the signature key id of the received activity, so that [CachedSigner] can look up
+/// the key.
+pub(crate) async fn with_signer_key_id<F: Future>(key_id: String, f: F) -> F::Output {
+  SIGNER_KEY_ID.scope(key_id, f).await
+}
+
+/// Removes the cached key, after a signature couldn't be verified with it.
+pub(crate) async fn invalidate_signer_key(key_id: &str, actor_id: &Url) {
+  SIGNER_KEYS
+    .invalidate(&(key_id.to_string(), actor_id.clone()))
+    .await;
+}
+
+/// Only the public key of the actor which signed an activity. It is read from the cache if the
+/// activity is signed with a key id that was seen recently, otherwise from the wrapped actor type.
+/// A cached key is replaced as soon as the actor is fetched again with a different key.
+pub(crate) struct CachedSigner<ActorT> {
+  id: Url,
+  key: String,
+  inbox: Url,
+  last_refreshed_at: Option<DateTime<Utc>>,
+  actor: PhantomData<fn() -> ActorT>,
+}
+
+impl<ActorT: Actor> CachedSigner<ActorT> {
+  async fn cache(actor: ActorT) -> Self {
+    let signer = CachedSigner {
+      id: actor.id(),
+      key: actor.public_key_pem().to_string(),
+      inbox: actor.inbox(),
+      last_refreshed_at: actor.last_refreshed_at(),
+      actor: PhantomData,
+    };
+    if let Ok(key_id) = SIGNER_KEY_ID.try_with(Clone::clone) {
+      let cached = CachedKey {
+        key: signer.key.clone(),
+        inbox: signer.inbox.clone(),
+        last_refreshed_at: signer.last_refreshed_at,
+      };
+      // replaces the entry if the actor was fetched again with a rotated key
+      SIGNER_KEYS
+        .insert((key_id, signer.id.clone()), cached)
+        .await;
+    }
+    signer
+  }
+}
+
+#[async_trait::async_trait]
+impl<ActorT> Object for CachedSigner<ActorT>
+where
+  ActorT: Object<DataType = LemmyContext, Error = LemmyError> + Actor + Send + Sync + 'static,
+  <ActorT as Object>::Kind: Send + Sync,
+{
+  type DataType = LemmyContext;
+  type Kind = ActorT::Kind;
+  type Error = LemmyError;
+
+  fn last_refreshed_at(&self) -> Option<DateTime<Utc>> {
+    self.last_refreshed_at
+  }
+
+  async fn read_from_id(
+    object_id: Url,
+    data: &Data<Self::DataType>,
+  ) -> Result<Option<Self>, LemmyError> {
+    if let Ok(key_id) = SIGNER_KEY_ID.try_with(Clone::clone) {
+      if let Some(cached) = SIGNER_KEYS.get(&(key_id, object_id.clone())) {
+        return Ok(Some(CachedSigner {
+          id: object_id,
+          key: cached.key,
+          inbox: cached.inbox,
+          last_refreshed_at: cached.last_refreshed_at,
+          actor: PhantomData,
+        }));
+      }
+    }
+    match ActorT::read_from_id(object_id, data).await? {
+      Some(actor) => Ok(Some(Self::cache(actor).await)),
+      None => Ok(None),
+    }
+  }
+
+  async fn delete(self, data: &Data<Self::DataType>) -> Result<(), LemmyError> {
+    if let Some(actor) = ActorT::read_from_id(self.id, data).await? {
+      actor.delete(data).await?;
+    }
+    Ok(())
+  }
+
+  async fn into_json(self, data: &Data<Self::DataType>) -> Result<Self::Kind, LemmyError> {
+    match ActorT::read_from_id(self.id, data).await? {
+      Some(actor) => actor.into_json(data).await,
+      None => Err(LemmyErrorType::NotFound)?,
+    }
+  }
+
+  async fn verify(
+    json: &Self::Kind,
+    expected_domain: &Url,
+    data: &Data<Self::DataType>,
+  ) -> Result<(), LemmyError> {
+    ActorT::verify(json, expected_domain, data).await
+  }
+
+  async fn from_json(json: Self::Kind, data: &Data<Self::DataType>) -> Result<Self, LemmyError> {
+    Ok(Self::cache(ActorT::from_json(json, data).await?).await)
+  }
+}
+
+impl<ActorT> Actor for CachedSigner<ActorT>
+where
+  ActorT: Object<DataType = LemmyContext, Error = LemmyError> + Actor + Send + Sync + 'static,
+  <ActorT as Object>::Kind: Send + Sync,
+{
+  fn id(&self) -> Url {
+    self.id.clone()
+  }
+
+  fn public_key_pem(&self) -> &str {
+    &self.key
+  }
+
+  fn private_key_pem(&self) -> Option<String> {
+    None
+  }
+
+  fn inbox(&self) -> Url {
+    self.inbox.clone()
+  }
+}
@@ -1,21 +1,26 @@
 use crate::{
-  activity_lists::SiteInboxActivities,
-  http::create_apub_response,
+  activity_lists::{SiteInboxActivities, VerifyWithTimeout},
+  http::{
+    check_inbox_rate_limit,
+    check_signed_fetch,
+    create_apub_response,
+    ignore_configured_activity,
+    ignore_unknown_activity,
+    key_refresh::receive_activity_with_key_refresh,
+    UnknownActivity,
+  },
   objects::{instance::ApubSite, person::ApubPerson},
   protocol::collections::empty_outbox::EmptyOutbox,
 };
-use activitypub_federation::{
-  actix_web::inbox::receive_activity,
-  config::Data,
-  protocol::context::WithContext,
-  traits::Object,
-};
+use activitypub_federation::{config::Data, protocol::context::WithContext, traits::Object};
 use actix_web::{web::Bytes, HttpRequest, HttpResponse};
 use lemmy_api_common::context::LemmyContext;
 use lemmy_db_views::structs::SiteView;
 use lemmy_utils::error::LemmyError;
 use url::Url;
 
+/// The instance actor is served without a signature even if `federation.signed_fetch` is enabled,
+/// as others may need its key to verify signed fetches.
 pub(crate) async fn get_apub_site_http(
   context: Data<LemmyContext>,
 ) -> Result<HttpResponse, LemmyError> {
@@ -27,8 +32,12 @@ pub(crate) async fn get_apub_site_http(
 
 #[tracing::instrument(skip_all)]
 pub(crate) async fn get_apub_site_outbox(
+  request: HttpRequest,
   context: Data<LemmyContext>,
 ) -> Result<HttpResponse, LemmyError> {
+  if let Some(res) = check_signed_fetch(&request, &context).await {
+    return Ok(res);
+  }
   let outbox_id = format!(
     "{}/site_outbox",
     context.settings().get_protocol_and_hostname()
@@ -43,7 +52,17 @@ pub async fn get_apub_site_inbox(
   body: Bytes,
   data: Data<LemmyContext>,
 ) -> Result<HttpResponse, LemmyError> {
-  receive_activity::<WithContext<SiteInboxActivities>, ApubPerson, LemmyContext>(
+  let activity = serde_json::from_slice::<UnknownActivity>(&body).ok();
+  if let Some(res) = check_inbox_rate_limit(activity.as_ref(), data.settings()) {
+    return Ok(res);
+  }
+  if let Some(res) = ignore_configured_activity(activity.as_ref(), &request, &body, &data).await? {
+    return Ok(res);
+  }
+  if let Some(res) = ignore_unknown_activity(activity.as_ref()) {
+    return Ok(res);
+  }
+  receive_activity_with_key_refresh::<VerifyWithTimeout<WithContext<SiteInboxActivities>>, ApubPerson>(
     request, body, &data,
   )
   .await
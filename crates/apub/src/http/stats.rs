@@ -0,0 +1,201 @@
+use activitypub_federation::config::Data;
+use actix_web::HttpResponse;
+use chrono::Utc;
+use lemmy_api_common::{
+  context::LemmyContext,
+  send_activity::ACTIVE_FEDERATION_WORKERS,
+  utils::is_admin,
+};
+use lemmy_db_schema::source::activity::SentActivity;
+use lemmy_db_views::structs::LocalUserView;
+use lemmy_utils::error::LemmyError;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::{atomic::Ordering, Mutex};
+
+/// Overview of federation, so that instance operators can monitor it.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct FederationStats {
+  /// Upper bound for the activities which are waiting to be sent, summed up over all instances.
+  /// Every activity after the last one delivered to an instance is counted for it, even those
+  /// which aren't addressed to the instance, so the actual number is usually lower.
+  pub pending_outgoing: i64,
+  /// Instances which activities are sent to
+  pub instances: usize,
+  /// Instances where the last delivery failed, these are retried with increasing delay
+  pub failing_instances: usize,
+  /// Federation workers which are running in this process, one for each instance which activities
+  /// are sent to. This is 0 if activities are sent by a separate process.
+  pub worker_count: usize,
+  /// Activities which were received by this process during the last full minute
+  pub received_last_minute: u64,
+}
+
+/// Received activities are counted in memory, so that reading the rate doesn't need a query.
+static RECEIVED_ACTIVITIES: Lazy<Mutex<MinuteCounter>> = Lazy::new(Default::default);
+
+#[derive(Default)]
+struct MinuteCounter {
+  minute: i64,
+  current: u64,
+  previous: u64,
+}
+
+impl MinuteCounter {
+  fn add(&mut self, minute: i64) {
+    self.advance(minute);
+    self.current += 1;
+  }
+
+  /// Count for the full minute before `minute`.
+  fn last_minute(&mut self, minute: i64) -> u64 {
+    self.advance(minute);
+    self.previous
+  }
+
+  fn advance(&mut self, minute: i64) {
+    if minute == self.minute {
+      return;
+    }
+    // if no activity was received for a whole minute, the previous count is outdated
+    self.previous = if minute == self.minute + 1 {
+      self.current
+    } else {
+      0
+    };
+    self.current = 0;
+    self.minute = minute;
+  }
+}
+
+fn current_minute() -> i64 {
+  Utc::now().timestamp() / 60
+}
+
+/// Counts an activity which was successfully received in one of the inboxes.
+pub(crate) fn count_received_activity() {
+  RECEIVED_ACTIVITIES
+    .lock()
+    .expect("Failed to lock received activities mutex")
+    .add(current_minute());
+}
+
+/// Returns the [FederationStats] as JSON. This only needs cheap queries, so it can be polled often.
+/// Only admins can read them, as they reveal which instances are failing.
+#[tracing::instrument(skip_all)]
+pub(crate) async fn get_federation_stats(
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> Result<HttpResponse, LemmyError> {
+  is_admin(&local_user_view)?;
+  let queues = SentActivity::queue_stats(&mut context.pool()).await?;
+  let received_last_minute = RECEIVED_ACTIVITIES
+    .lock()
+    .expect("Failed to lock received activities mutex")
+    .last_minute(current_minute());
+  Ok(HttpResponse::Ok().json(FederationStats {
+    pending_outgoing: queues.pending,
+    instances: queues.instances,
+    failing_instances: queues.failing_instances,
+    worker_count: ACTIVE_FEDERATION_WORKERS.load(Ordering::Relaxed),
+    received_last_minute,
+  }))
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::objects::tests::init_context;
+  use actix_web::body::to_bytes;
+  use lemmy_db_schema::{
+    source::{
+      instance::Instance,
+      local_user::{LocalUser, LocalUserInsertForm},
+      person::{Person, PersonInsertForm},
+    },
+    traits::Crud,
+  };
+  use lemmy_utils::error::LemmyErrorType;
+  use serde_json::Value;
+  use serial_test::serial;
+
+  async fn create_user(admin: bool, context: &Data<LemmyContext>) -> LocalUserView {
+    let instance = Instance::read_or_create(&mut context.pool(), "example.com".to_string())
+      .await
+      .unwrap();
+    let person_form = PersonInsertForm::builder()
+      .name(format!("stats_user_{admin}"))
+      .public_key("pubkey".to_string())
+      .instance_id(instance.id)
+      .build();
+    let person = Person::create(&mut context.pool(), &person_form)
+      .await
+      .unwrap();
+    let user_form = LocalUserInsertForm::builder()
+      .person_id(person.id)
+      .password_encrypted("pass".to_string())
+      .admin(Some(admin))
+      .build();
+    let local_user = LocalUser::create(&mut context.pool(), &user_form)
+      .await
+      .unwrap();
+    LocalUserView::read(&mut context.pool(), local_user.id)
+      .await
+      .unwrap()
+  }
+
+  #[test]
+  fn test_minute_counter() {
+    let mut counter = MinuteCounter::default();
+    counter.add(100);
+    counter.add(100);
+    assert_eq!(0, counter.last_minute(100));
+    counter.add(101);
+    assert_eq!(2, counter.last_minute(101));
+    // nothing received in minute 102
+    assert_eq!(0, counter.last_minute(103));
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_get_federation_stats() {
+    let context = init_context().await;
+    let admin = create_user(true, &context).await;
+    let user = create_user(false, &context).await;
+    count_received_activity();
+
+    // other users can't read the stats
+    let err = get_federation_stats(context.reset_request_count(), user.clone())
+      .await
+      .unwrap_err();
+    assert_eq!(LemmyErrorType::NotAnAdmin, err.error_type);
+
+    let res = get_federation_stats(context.reset_request_count(), admin.clone())
+      .await
+      .unwrap();
+    assert!(res.status().is_success());
+    let body = to_bytes(res.into_body()).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    for field in [
+      "pending_outgoing",
+      "instances",
+      "failing_instances",
+      "worker_count",
+      "received_last_minute",
+    ] {
+      assert!(json[field].is_u64(), "{field} is missing in {json}");
+    }
+    // the shape matches the struct
+    serde_json::from_value::<FederationStats>(json).unwrap();
+
+    Person::delete(&mut context.pool(), admin.person.id)
+      .await
+      .unwrap();
+    Person::delete(&mut context.pool(), user.person.id)
+      .await
+      .unwrap();
+  }
+}
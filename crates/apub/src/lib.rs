@@ -1,16 +1,32 @@
-use crate::fetcher::post_or_comment::PostOrComment;
+use crate::fetcher::{nodeinfo::detect_instance_software, post_or_comment::PostOrComment};
 use activitypub_federation::config::{Data, UrlVerifier};
 use anyhow::anyhow;
 use async_trait::async_trait;
+use diesel::result::{DatabaseErrorKind::UniqueViolation, Error::DatabaseError};
 use lemmy_api_common::context::LemmyContext;
 use lemmy_db_schema::{
-  source::{activity::ReceivedActivity, instance::Instance, local_site::LocalSite},
-  utils::{ActualDbPool, DbPool},
+  impls::instance::domain_matches,
+  newtypes::DbUrl,
+  source::{
+    activity::ReceivedActivity,
+    community::Community,
+    federation_community_blocklist::FederationCommunityBlockList,
+    instance::Instance,
+    local_site::LocalSite,
+  },
+};
+use lemmy_utils::{
+  error::{LemmyError, LemmyErrorType, LemmyResult},
+  settings::structs::Settings,
 };
-use lemmy_utils::error::{LemmyError, LemmyErrorType, LemmyResult};
 use moka::future::Cache;
 use once_cell::sync::Lazy;
-use std::{sync::Arc, time::Duration};
+use std::{
+  fmt::{Display, Formatter},
+  sync::Arc,
+  time::Duration,
+};
+use tracing::debug;
 use url::Url;
 
 pub mod activities;
@@ -23,7 +39,6 @@ pub(crate) mod mentions;
 pub mod objects;
 pub mod protocol;
 
-pub const FEDERATION_HTTP_FETCH_LIMIT: u32 = 50;
 /// All incoming and outgoing federation actions read the blocklist/allowlist and slur filters
 /// multiple times. This causes a huge number of database reads if we hit the db directly. So we
 /// cache these values for a short time, which will already make a huge difference and ensures that
@@ -35,33 +50,77 @@ static CONTEXT: Lazy<Vec<serde_json::Value>> = Lazy::new(|| {
 });
 
 #[derive(Clone)]
-pub struct VerifyUrlData(pub ActualDbPool);
+pub struct VerifyUrlData(pub LemmyContext);
 
 #[async_trait]
 impl UrlVerifier for VerifyUrlData {
   async fn verify(&self, url: &Url) -> Result<(), anyhow::Error> {
-    let local_site_data = local_site_data_cached(&mut (&self.0).into())
+    let local_site_data = local_site_data_cached(&self.0)
       .await
       .expect("read local site data");
-    check_apub_id_valid(url, &local_site_data).map_err(|err| match err {
-      LemmyError {
-        error_type: LemmyErrorType::FederationDisabled,
-        ..
-      } => anyhow!("Federation disabled"),
-      LemmyError {
-        error_type: LemmyErrorType::DomainBlocked(domain),
-        ..
-      } => anyhow!("Domain {domain:?} is blocked"),
-      LemmyError {
-        error_type: LemmyErrorType::DomainNotInAllowList(domain),
-        ..
-      } => anyhow!("Domain {domain:?} is not in allowlist"),
-      _ => anyhow!("Failed validating apub id"),
-    })?;
+    check_apub_id_valid_detecting_software(url, &local_site_data, self.0.settings(), &self.0)
+      .await
+      .map_err(|e| anyhow!("{e}"))?;
     Ok(())
   }
 }
 
+/// Reason why [check_apub_id_valid] rejects a URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApubValidationError {
+  FederationDisabled,
+  DomainBlocked(String),
+  DomainNotInAllowList(String),
+  FederationPaused(String),
+  InstanceVersionNotAllowed(String),
+  CommunityFederationBlocked(String),
+  UrlWithoutDomain,
+}
+
+impl Display for ApubValidationError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    // remote instances recognize blocks by these messages, so they must not change
+    match self {
+      ApubValidationError::FederationDisabled => write!(f, "Federation disabled"),
+      ApubValidationError::DomainBlocked(domain) => write!(f, "Domain {domain:?} is blocked"),
+      ApubValidationError::DomainNotInAllowList(domain) => {
+        write!(f, "Domain {domain:?} is not in allowlist")
+      }
+      ApubValidationError::FederationPaused(domain) => {
+        write!(f, "Federation paused for this instance: {domain:?}")
+      }
+      ApubValidationError::InstanceVersionNotAllowed(domain) => {
+        write!(f, "Instance {domain:?} runs a version below the minimum")
+      }
+      ApubValidationError::CommunityFederationBlocked(community) => {
+        write!(f, "Community {community:?} is blocked")
+      }
+      ApubValidationError::UrlWithoutDomain => write!(f, "URL has no domain"),
+    }
+  }
+}
+
+impl From<ApubValidationError> for LemmyError {
+  fn from(err: ApubValidationError) -> Self {
+    let error_type = match err {
+      ApubValidationError::FederationDisabled => LemmyErrorType::FederationDisabled,
+      ApubValidationError::DomainBlocked(domain) => LemmyErrorType::DomainBlocked(domain),
+      ApubValidationError::DomainNotInAllowList(domain) => {
+        LemmyErrorType::DomainNotInAllowList(domain)
+      }
+      ApubValidationError::FederationPaused(domain) => LemmyErrorType::FederationPaused(domain),
+      ApubValidationError::InstanceVersionNotAllowed(domain) => {
+        LemmyErrorType::InstanceVersionNotAllowed(domain)
+      }
+      ApubValidationError::CommunityFederationBlocked(community) => {
+        LemmyErrorType::CommunityFederationBlocked(community)
+      }
+      ApubValidationError::UrlWithoutDomain => LemmyErrorType::UrlWithoutDomain,
+    };
+    error_type.into()
+  }
+}
+
 /// Checks if the ID is allowed for sending or receiving.
 ///
 /// In particular, it checks for:
@@ -69,9 +128,27 @@ impl UrlVerifier for VerifyUrlData {
 /// - the correct scheme (either http or https)
 /// - URL being in the allowlist (if it is active)
 /// - URL not being in the blocklist (if it is active)
-#[tracing::instrument(skip(local_site_data))]
-fn check_apub_id_valid(apub_id: &Url, local_site_data: &LocalSiteData) -> Result<(), LemmyError> {
-  let domain = apub_id.domain().expect("apud id has domain").to_string();
+/// - federation with the instance not being paused
+/// - the instance not running a software version below the configured minimum
+/// - the URL not being a blocked community
+///
+/// Entries of the allowlist and blocklist starting with `*.` cover all subdomains, see
+/// [domain_matches].
+#[tracing::instrument(skip(local_site_data, settings))]
+fn check_apub_id_valid(
+  apub_id: &Url,
+  local_site_data: &LocalSiteData,
+  settings: &Settings,
+) -> Result<(), ApubValidationError> {
+  let domain = apub_id
+    .domain()
+    .ok_or(ApubValidationError::UrlWithoutDomain)?
+    .to_string();
+
+  // a wildcard entry must never block the local instance
+  if settings.get_hostname_without_port().ok().as_deref() == Some(domain.as_str()) {
+    return Ok(());
+  }
 
   if !local_site_data
     .local_site
@@ -79,118 +156,873 @@ fn check_apub_id_valid(apub_id: &Url, local_site_data: &LocalSiteData) -> Result
     .map(|l| l.federation_enabled)
     .unwrap_or(true)
   {
-    Err(LemmyErrorType::FederationDisabled)?
+    Err(ApubValidationError::FederationDisabled)?
+  }
+
+  if instance_list_contains(&local_site_data.blocked_instances, &domain) {
+    Err(ApubValidationError::DomainBlocked(domain.clone()))?
+  }
+
+  // Only check this if there are instances in the allowlist
+  if !local_site_data.allowed_instances.is_empty()
+    && !instance_list_contains(&local_site_data.allowed_instances, &domain)
+  {
+    Err(ApubValidationError::DomainNotInAllowList(domain))?
   }
 
   if local_site_data
-    .blocked_instances
+    .paused_instances
     .iter()
-    .any(|i| domain.to_lowercase().eq(&i.domain.to_lowercase()))
+    .any(|i| i.domain.eq_ignore_ascii_case(&domain))
   {
-    Err(LemmyErrorType::DomainBlocked(domain.clone()))?
+    Err(ApubValidationError::FederationPaused(domain))?
   }
 
-  // Only check this if there are instances in the allowlist
-  if !local_site_data.allowed_instances.is_empty()
-    && !local_site_data
-      .allowed_instances
-      .iter()
-      .any(|i| domain.to_lowercase().eq(&i.domain.to_lowercase()))
+  check_instance_version(&domain, &local_site_data.instances, settings)?;
+
+  check_community_not_blocked(apub_id, local_site_data)?;
+
+  Ok(())
+}
+
+/// Same as [check_apub_id_valid], but if the URL is only rejected because the software of its
+/// instance isn't known yet, the software is detected first. Otherwise nothing would ever be
+/// accepted from a new instance when `federation.allow_unknown_versions` is disabled, as the
+/// software is only stored after the first object was received from it.
+async fn check_apub_id_valid_detecting_software(
+  apub_id: &Url,
+  local_site_data: &LocalSiteData,
+  settings: &Settings,
+  context: &LemmyContext,
+) -> Result<(), ApubValidationError> {
+  let res = check_apub_id_valid(apub_id, local_site_data, settings);
+  let Err(ApubValidationError::InstanceVersionNotAllowed(domain)) = &res else {
+    return res;
+  };
+  let software_known = local_site_data
+    .instances
+    .iter()
+    .any(|i| i.domain.eq_ignore_ascii_case(domain) && i.software.is_some());
+  if software_known {
+    return res;
+  }
+  let instance = match detect_instance_software(domain, context).await {
+    Ok(instance) => instance,
+    Err(e) => {
+      debug!("Failed to detect software of {domain}: {e}");
+      return res;
+    }
+  };
+  // the cached list doesn't contain the detected software yet
+  LOCAL_SITE_DATA_CACHE.invalidate(&()).await;
+  let mut local_site_data = local_site_data.clone();
+  local_site_data
+    .instances
+    .retain(|i| !i.domain.eq_ignore_ascii_case(domain));
+  local_site_data.instances.push(instance);
+  check_apub_id_valid(apub_id, &local_site_data, settings)
+}
+
+/// Rejects communities which are in the community blocklist. Posts and comments don't include
+/// their community in the URL, so they need to be checked separately with
+/// [check_apub_id_valid_in_community].
+fn check_community_not_blocked(
+  community_id: &Url,
+  local_site_data: &LocalSiteData,
+) -> Result<(), ApubValidationError> {
+  if local_site_data
+    .blocked_communities
+    .iter()
+    .any(|c| c.inner() == community_id)
   {
-    Err(LemmyErrorType::DomainNotInAllowList(domain))?
+    Err(ApubValidationError::CommunityFederationBlocked(
+      community_id.to_string(),
+    ))?
+  }
+  Ok(())
+}
+
+fn instance_list_contains(instances: &[Instance], domain: &str) -> bool {
+  instances.iter().any(|i| domain_matches(domain, &i.domain))
+}
+
+/// Rejects instances which run a version of their software that is older than the minimum
+/// configured in `federation.minimum_versions`. Instances whose software or version is not known
+/// yet are handled according to `federation.allow_unknown_versions`.
+fn check_instance_version(
+  domain: &str,
+  instances: &[Instance],
+  settings: &Settings,
+) -> Result<(), ApubValidationError> {
+  let config = &settings.federation;
+  let local_domain = settings.get_hostname_without_port().ok();
+  if config.minimum_versions.is_empty() || local_domain.as_deref() == Some(domain) {
+    return Ok(());
   }
+  let instance = instances
+    .iter()
+    .find(|i| i.domain.eq_ignore_ascii_case(domain));
+  let software = instance.and_then(|i| i.software.as_ref());
+  let version = instance
+    .and_then(|i| i.version.as_deref())
+    .and_then(parse_version);
 
+  let allowed = match software {
+    Some(software) => match minimum_version(settings, software) {
+      Some(minimum) => match (version, parse_version(minimum)) {
+        (Some(version), Some(minimum)) => version >= minimum,
+        _ => config.allow_unknown_versions,
+      },
+      // no minimum configured for this software
+      None => true,
+    },
+    None => config.allow_unknown_versions,
+  };
+  if !allowed {
+    Err(ApubValidationError::InstanceVersionNotAllowed(
+      domain.to_string(),
+    ))?
+  }
   Ok(())
 }
 
+/// The minimum version configured for the given software. Software names are compared ignoring
+/// case, both in the config keys and in the software reported by remote instances.
+fn minimum_version<'a>(settings: &'a Settings, software: &str) -> Option<&'a str> {
+  settings
+    .federation
+    .minimum_versions
+    .iter()
+    .find(|(s, _)| s.eq_ignore_ascii_case(software))
+    .map(|(_, minimum)| minimum.as_str())
+}
+
+/// Maximum number of http requests for resolving an object from the given domain, taken from
+/// `federation.http_fetch_limit_overrides` if there is an override for the domain. Otherwise the
+/// limit set in the local site is used, falling back to `federation.http_fetch_limit`.
+fn http_fetch_limit(domain: &str, local_site: Option<&LocalSite>, settings: &Settings) -> u32 {
+  let config = &settings.federation;
+  config
+    .http_fetch_limit_overrides
+    .iter()
+    .find(|(d, _)| d.eq_ignore_ascii_case(domain))
+    .map(|(_, limit)| *limit)
+    .unwrap_or_else(|| default_http_fetch_limit(local_site, settings))
+}
+
+fn default_http_fetch_limit(local_site: Option<&LocalSite>, settings: &Settings) -> u32 {
+  local_site
+    .and_then(|l| l.federation_http_fetch_limit)
+    .and_then(|l| u32::try_from(l).ok())
+    .filter(|l| *l > 0)
+    .unwrap_or(settings.federation.http_fetch_limit)
+}
+
+/// The highest fetch limit for any domain, which needs to be passed to the federation library.
+/// Lower limits for individual domains are enforced in [check_apub_id_valid_with_strictness].
+///
+/// The federation config is only built once at startup, so this is the upper bound for the whole
+/// runtime. Limits which are lowered in the local site take effect right away (after the
+/// [BLOCKLIST_CACHE_DURATION]), but raising the limit above this value requires a restart.
+pub fn max_http_fetch_limit(local_site: Option<&LocalSite>, settings: &Settings) -> u32 {
+  settings
+    .federation
+    .http_fetch_limit_overrides
+    .values()
+    .copied()
+    .fold(default_http_fetch_limit(local_site, settings), u32::max)
+}
+
+/// Parses the leading `major.minor.patch` part of a version string like `0.19.0-rc.1`.
+fn parse_version(version: &str) -> Option<[u64; 3]> {
+  let numeric = version
+    .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+    .next()?;
+  let mut parts = numeric.split('.').map(str::parse::<u64>);
+  let major = parts.next()?.ok()?;
+  let minor = parts.next().and_then(Result::ok).unwrap_or(0);
+  let patch = parts.next().and_then(Result::ok).unwrap_or(0);
+  Some([major, minor, patch])
+}
+
 #[derive(Clone)]
 pub(crate) struct LocalSiteData {
   local_site: Option<LocalSite>,
   allowed_instances: Vec<Instance>,
   blocked_instances: Vec<Instance>,
+  paused_instances: Vec<Instance>,
+  /// Actor ids of remote communities which are blocked, while their instance may still be allowed
+  blocked_communities: Vec<DbUrl>,
+  /// All known instances, only loaded if minimum versions are configured
+  instances: Vec<Instance>,
 }
 
+pub(crate) static LOCAL_SITE_DATA_CACHE: Lazy<Cache<(), Arc<LocalSiteData>>> = Lazy::new(|| {
+  Cache::builder()
+    .max_capacity(1)
+    .time_to_live(BLOCKLIST_CACHE_DURATION)
+    .build()
+});
+
 pub(crate) async fn local_site_data_cached(
-  pool: &mut DbPool<'_>,
+  context: &LemmyContext,
 ) -> LemmyResult<Arc<LocalSiteData>> {
-  static CACHE: Lazy<Cache<(), Arc<LocalSiteData>>> = Lazy::new(|| {
-    Cache::builder()
-      .max_capacity(1)
-      .time_to_live(BLOCKLIST_CACHE_DURATION)
-      .build()
-  });
+  let load_instances = !context.settings().federation.minimum_versions.is_empty();
   Ok(
-    CACHE
+    LOCAL_SITE_DATA_CACHE
       .try_get_with((), async {
-        let (local_site, allowed_instances, blocked_instances) =
-          lemmy_db_schema::try_join_with_pool!(pool => (
-            // LocalSite may be missing
-            |pool| async {
-              Ok(LocalSite::read(pool).await.ok())
-            },
-            Instance::allowlist,
-            Instance::blocklist
-          ))?;
+        let pool = &mut context.pool();
+        let (
+          local_site,
+          allowed_instances,
+          blocked_instances,
+          paused_instances,
+          blocked_communities,
+          instances,
+        ) = lemmy_db_schema::try_join_with_pool!(pool => (
+          // LocalSite may be missing
+          |pool| async {
+            Ok(LocalSite::read(pool).await.ok())
+          },
+          Instance::allowlist,
+          Instance::blocklist,
+          Instance::paused_list,
+          FederationCommunityBlockList::actor_ids,
+          |pool| async {
+            if load_instances {
+              Instance::read_all(pool).await
+            } else {
+              Ok(vec![])
+            }
+          }
+        ))?;
 
         Ok::<_, diesel::result::Error>(Arc::new(LocalSiteData {
           local_site,
           allowed_instances,
           blocked_instances,
+          paused_instances,
+          blocked_communities,
+          instances,
         }))
       })
       .await?,
   )
 }
 
+/// The local site from [local_site_data_cached], or `None` if the site isn't set up yet. Changes
+/// take effect once the cache expires, after at most [BLOCKLIST_CACHE_DURATION].
+pub(crate) async fn local_site_cached(context: &LemmyContext) -> LemmyResult<Option<LocalSite>> {
+  Ok(local_site_data_cached(context).await?.local_site.clone())
+}
+
 pub(crate) async fn check_apub_id_valid_with_strictness(
   apub_id: &Url,
   is_strict: bool,
-  context: &LemmyContext,
+  context: &Data<LemmyContext>,
 ) -> Result<(), LemmyError> {
-  let domain = apub_id.domain().expect("apud id has domain").to_string();
-  let local_instance = context
-    .settings()
-    .get_hostname_without_port()
-    .expect("local hostname is valid");
+  let domain = apub_id
+    .domain()
+    .ok_or(LemmyErrorType::UrlWithoutDomain)?
+    .to_string();
+  let local_instance = context.settings().get_hostname_without_port()?;
   if domain == local_instance {
     return Ok(());
   }
 
-  let local_site_data = local_site_data_cached(&mut context.pool()).await?;
-  check_apub_id_valid(apub_id, &local_site_data)?;
-
-  // Only check allowlist if this is a community, and there are instances in the allowlist
-  if is_strict && !local_site_data.allowed_instances.is_empty() {
-    // need to allow this explicitly because apub receive might contain objects from our local
-    // instance.
-    let mut allowed_and_local = local_site_data
-      .allowed_instances
-      .iter()
-      .map(|i| i.domain.clone())
-      .collect::<Vec<String>>();
-    let local_instance = context
-      .settings()
-      .get_hostname_without_port()
-      .expect("local hostname is valid");
-    allowed_and_local.push(local_instance);
-
-    let domain = apub_id.domain().expect("apud id has domain").to_string();
-    if !allowed_and_local.contains(&domain) {
-      Err(LemmyErrorType::FederationDisabledByStrictAllowList)?
-    }
+  let local_site_data = local_site_data_cached(context).await?;
+  let limit = http_fetch_limit(
+    &domain,
+    local_site_data.local_site.as_ref(),
+    context.settings(),
+  );
+  if context.request_count() > limit {
+    Err(LemmyErrorType::HttpFetchLimitExceeded(domain.clone()))?
+  }
+
+  check_apub_id_valid_detecting_software(apub_id, &local_site_data, context.settings(), context)
+    .await?;
+
+  // Only check allowlist if this is a community, and there are instances in the allowlist. Objects
+  // from our local instance were already allowed above.
+  if is_strict
+    && !local_site_data.allowed_instances.is_empty()
+    && !instance_list_contains(&local_site_data.allowed_instances, &domain)
+  {
+    Err(LemmyErrorType::FederationDisabledByStrictAllowList)?
+  }
+  Ok(())
+}
+
+/// Same as [check_apub_id_valid_with_strictness] for posts and comments, which are additionally
+/// rejected if their community is blocked.
+pub(crate) async fn check_apub_id_valid_in_community(
+  apub_id: &Url,
+  community: &Community,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<()> {
+  check_apub_id_valid_with_strictness(apub_id, community.local, context).await?;
+  if !community.local {
+    let local_site_data = local_site_data_cached(context).await?;
+    check_community_not_blocked(community.actor_id.inner(), &local_site_data)?;
   }
   Ok(())
 }
 
+/// Checks if federation with the instance of the given URL is allowed, for example so that an
+/// admin can check a domain before federating with it. This is the same check as for incoming
+/// objects, without the strict allowlist for communities.
+pub async fn is_apub_id_valid(url: &Url, context: &LemmyContext) -> Result<(), LemmyError> {
+  let local_site_data = local_site_data_cached(context).await?;
+  check_apub_id_valid_detecting_software(url, &local_site_data, context.settings(), context)
+    .await?;
+  Ok(())
+}
+
 /// Store received activities in the database.
 ///
 /// This ensures that the same activity doesnt get received and processed more than once, which
-/// would be a waste of resources.
+/// would be a waste of resources. Recently received ids are also kept in memory, so that
+/// duplicates can be rejected without a database roundtrip.
 #[tracing::instrument(skip(data))]
 async fn insert_received_activity(
   ap_id: &Url,
   data: &Data<LemmyContext>,
 ) -> Result<(), LemmyError> {
-  ReceivedActivity::create(&mut data.pool(), &ap_id.clone().into()).await?;
+  if data.received_activities().contains_key(ap_id) {
+    Err(LemmyErrorType::ActivityAlreadyReceived)?
+  }
+  let res = ReceivedActivity::create(&mut data.pool(), &ap_id.clone().into()).await;
+  // also remember duplicates which were only found in the database, eg after a restart, but not
+  // ids whose insert failed for other reasons, so that a retry can still be processed
+  if matches!(res, Ok(()) | Err(DatabaseError(UniqueViolation, _))) {
+    data.received_activities().insert(ap_id.clone(), ()).await;
+  }
+  res?;
   Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::{
+    fetcher::nodeinfo::UNKNOWN_SOFTWARE,
+    objects::{community::tests::parse_lemmy_community, tests::init_context},
+  };
+  use chrono::Utc;
+  use lemmy_db_schema::{
+    newtypes::InstanceId,
+    source::federation_blocklist::FederationBlockList,
+    traits::Crud,
+    ListingType,
+    RegistrationMode,
+  };
+  use lemmy_utils::settings::SETTINGS;
+  use serial_test::serial;
+
+  fn instance(domain: &str, software: Option<&str>, version: Option<&str>) -> Instance {
+    Instance {
+      id: InstanceId::default(),
+      domain: domain.to_string(),
+      published: Utc::now(),
+      updated: None,
+      software: software.map(ToString::to_string),
+      version: version.map(ToString::to_string),
+      paused: false,
+      blocked_by_remote: None,
+    }
+  }
+
+  fn settings(allow_unknown_versions: bool) -> Settings {
+    let mut settings = SETTINGS.clone();
+    settings
+      .federation
+      .minimum_versions
+      .insert("lemmy".to_string(), "0.18.0".to_string());
+    settings.federation.allow_unknown_versions = allow_unknown_versions;
+    settings
+  }
+
+  #[test]
+  fn test_parse_version() {
+    assert_eq!(Some([0, 19, 0]), parse_version("0.19.0-rc.1"));
+    assert_eq!(Some([0, 18, 0]), parse_version("0.18"));
+    assert_eq!(
+      Some([2, 5, 3]),
+      parse_version("2.5.3 (compatible; Pleroma)")
+    );
+    assert_eq!(None, parse_version("unknown"));
+  }
+
+  #[test]
+  fn test_check_instance_version_below_floor() {
+    let instances = vec![
+      instance("old.example", Some("lemmy"), Some("0.17.4")),
+      instance("new.example", Some("lemmy"), Some("0.19.0-rc.1")),
+      instance("other.example", Some("mastodon"), Some("1.0.0")),
+    ];
+    let settings = settings(true);
+    let res = check_instance_version("old.example", &instances, &settings);
+    assert_eq!(
+      Err(ApubValidationError::InstanceVersionNotAllowed(
+        "old.example".to_string()
+      )),
+      res
+    );
+    assert!(check_instance_version("new.example", &instances, &settings).is_ok());
+    assert!(check_instance_version("other.example", &instances, &settings).is_ok());
+  }
+
+  #[test]
+  fn test_check_instance_version_ignores_case() {
+    let instances = vec![instance("old.example", Some("LEMMY"), Some("0.17.4"))];
+    assert!(check_instance_version("old.example", &instances, &settings(true)).is_err());
+
+    let mut settings = SETTINGS.clone();
+    settings
+      .federation
+      .minimum_versions
+      .insert("Lemmy".to_string(), "0.18.0".to_string());
+    let instances = vec![instance("old.example", Some("lemmy"), Some("0.17.4"))];
+    assert!(check_instance_version("old.example", &instances, &settings).is_err());
+  }
+
+  #[test]
+  fn test_check_instance_version_unknown() {
+    let instances = vec![
+      instance("unknown-version.example", Some("lemmy"), None),
+      instance("unknown-software.example", None, None),
+    ];
+    for domain in ["unknown-version.example", "unknown-software.example"] {
+      assert!(check_instance_version(domain, &instances, &settings(true)).is_ok());
+      assert!(check_instance_version(domain, &instances, &settings(false)).is_err());
+    }
+
+    // without configured minimum versions, nothing is rejected
+    assert!(check_instance_version("unknown-software.example", &instances, &SETTINGS).is_ok());
+  }
+
+  fn local_site_data(allowed: &[&str], blocked: &[&str]) -> LocalSiteData {
+    let to_instances = |domains: &[&str]| domains.iter().map(|d| instance(d, None, None)).collect();
+    LocalSiteData {
+      local_site: None,
+      allowed_instances: to_instances(allowed),
+      blocked_instances: to_instances(blocked),
+      paused_instances: vec![],
+      blocked_communities: vec![],
+      instances: vec![],
+    }
+  }
+
+  fn local_site() -> LocalSite {
+    LocalSite {
+      id: Default::default(),
+      site_id: Default::default(),
+      site_setup: true,
+      enable_downvotes: true,
+      enable_nsfw: false,
+      community_creation_admin_only: false,
+      require_email_verification: false,
+      application_question: None,
+      private_instance: false,
+      default_theme: String::new(),
+      default_post_listing_type: ListingType::All,
+      legal_information: None,
+      hide_modlog_mod_names: true,
+      application_email_admins: false,
+      slur_filter_regex: None,
+      actor_name_max_length: 20,
+      federation_enabled: true,
+      captcha_enabled: false,
+      captcha_difficulty: String::new(),
+      published: Utc::now(),
+      updated: None,
+      registration_mode: RegistrationMode::Open,
+      reports_email_admins: false,
+      federation_http_fetch_limit: None,
+      federate_votes: true,
+    }
+  }
+
+  #[test]
+  fn test_domain_matches_wildcard() {
+    assert!(domain_matches("example.org", "example.org"));
+    assert!(domain_matches("Example.org", "example.ORG"));
+    assert!(domain_matches("sub.example.org", "*.example.org"));
+    assert!(domain_matches("a.b.example.org", "*.Example.org"));
+    assert!(!domain_matches("notexample.org", "*.example.org"));
+    assert!(!domain_matches("example.org", "*.example.org"));
+    assert!(!domain_matches("sub.example.org", "example.org"));
+    // only a leading `*.` is a wildcard
+    assert!(!domain_matches("sub.example.org", "*example.org"));
+  }
+
+  #[test]
+  fn test_check_apub_id_valid_wildcard() {
+    let sub = Url::parse("https://sub.example.org/u/alice").unwrap();
+    let other = Url::parse("https://notexample.org/u/bob").unwrap();
+
+    let blocked = local_site_data(&[], &["*.example.org"]);
+    assert_eq!(
+      Err(ApubValidationError::DomainBlocked(
+        "sub.example.org".to_string()
+      )),
+      check_apub_id_valid(&sub, &blocked, &SETTINGS)
+    );
+    assert!(check_apub_id_valid(&other, &blocked, &SETTINGS).is_ok());
+
+    let allowed = local_site_data(&["*.example.org"], &[]);
+    assert!(check_apub_id_valid(&sub, &allowed, &SETTINGS).is_ok());
+    assert_eq!(
+      Err(ApubValidationError::DomainNotInAllowList(
+        "notexample.org".to_string()
+      )),
+      check_apub_id_valid(&other, &allowed, &SETTINGS)
+    );
+
+    // the local instance is never blocked, even if a wildcard covers it
+    let local_domain = SETTINGS.get_hostname_without_port().unwrap();
+    let local = Url::parse(&format!("https://{local_domain}/u/carol")).unwrap();
+    let blocked = local_site_data(&[], &[&format!("*.{local_domain}"), &local_domain]);
+    assert!(check_apub_id_valid(&local, &blocked, &SETTINGS).is_ok());
+  }
+
+  #[test]
+  fn test_check_apub_id_valid_federation_disabled() {
+    let allowed = Url::parse("https://allowed.example/u/alice").unwrap();
+    let mut data = local_site_data(&["allowed.example"], &[]);
+    assert!(check_apub_id_valid(&allowed, &data, &SETTINGS).is_ok());
+
+    let mut local_site = local_site();
+    local_site.federation_enabled = false;
+    data.local_site = Some(local_site);
+    assert_eq!(
+      Err(ApubValidationError::FederationDisabled),
+      check_apub_id_valid(&allowed, &data, &SETTINGS)
+    );
+
+    // the local instance is still valid
+    let local_domain = SETTINGS.get_hostname_without_port().unwrap();
+    let local = Url::parse(&format!("https://{local_domain}/u/carol")).unwrap();
+    assert!(check_apub_id_valid(&local, &data, &SETTINGS).is_ok());
+  }
+
+  #[test]
+  fn test_check_apub_id_valid_without_domain() {
+    let ip = Url::parse("http://192.0.2.1/actor").unwrap();
+    assert_eq!(
+      Err(ApubValidationError::UrlWithoutDomain),
+      check_apub_id_valid(&ip, &local_site_data(&[], &[]), &SETTINGS)
+    );
+  }
+
+  #[test]
+  fn test_apub_validation_error_messages() {
+    let domain = "example.org".to_string();
+    let cases = [
+      (
+        ApubValidationError::FederationDisabled,
+        "Federation disabled",
+      ),
+      (
+        ApubValidationError::DomainBlocked(domain.clone()),
+        "Domain \"example.org\" is blocked",
+      ),
+      (
+        ApubValidationError::DomainNotInAllowList(domain.clone()),
+        "Domain \"example.org\" is not in allowlist",
+      ),
+      (
+        ApubValidationError::FederationPaused(domain.clone()),
+        "Federation paused for this instance: \"example.org\"",
+      ),
+      (
+        ApubValidationError::CommunityFederationBlocked("https://example.org/c/main".to_string()),
+        "Community \"https://example.org/c/main\" is blocked",
+      ),
+      (ApubValidationError::UrlWithoutDomain, "URL has no domain"),
+    ];
+    for (err, message) in cases {
+      assert_eq!(message, err.to_string());
+    }
+
+    let err: LemmyError = ApubValidationError::DomainBlocked(domain.clone()).into();
+    assert_eq!(
+      LemmyErrorType::DomainBlocked(domain.clone()),
+      err.error_type
+    );
+    let err: LemmyError = ApubValidationError::DomainNotInAllowList(domain.clone()).into();
+    assert_eq!(LemmyErrorType::DomainNotInAllowList(domain), err.error_type);
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_check_apub_id_valid_with_strictness_without_domain() {
+    let context = init_context().await;
+    let ip = Url::parse("http://192.0.2.1/actor").unwrap();
+    let mailto = Url::parse("mailto:alice@example.com").unwrap();
+    for url in [ip, mailto] {
+      let err = check_apub_id_valid_with_strictness(&url, false, &context)
+        .await
+        .unwrap_err();
+      assert_eq!(LemmyErrorType::UrlWithoutDomain, err.error_type);
+    }
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_is_apub_id_valid() {
+    let context = init_context().await;
+    let blocked = Url::parse("https://blocked.example/u/alice").unwrap();
+    let allowed = Url::parse("https://allowed.example/u/bob").unwrap();
+    FederationBlockList::replace(
+      &mut context.pool(),
+      Some(vec!["blocked.example".to_string()]),
+    )
+    .await
+    .unwrap();
+    LOCAL_SITE_DATA_CACHE.invalidate_all();
+
+    let err = is_apub_id_valid(&blocked, &context).await.unwrap_err();
+    assert_eq!(
+      LemmyErrorType::DomainBlocked("blocked.example".to_string()),
+      err.error_type
+    );
+    assert!(is_apub_id_valid(&allowed, &context).await.is_ok());
+
+    FederationBlockList::replace(&mut context.pool(), Some(vec![]))
+      .await
+      .unwrap();
+    LOCAL_SITE_DATA_CACHE.invalidate_all();
+    assert!(is_apub_id_valid(&blocked, &context).await.is_ok());
+    let instance = Instance::read_or_create(&mut context.pool(), "blocked.example".to_string())
+      .await
+      .unwrap();
+    Instance::delete(&mut context.pool(), instance.id)
+      .await
+      .unwrap();
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_check_apub_id_valid_first_contact() {
+    let context = init_context().await;
+    let url = Url::parse("https://first-contact.example/u/alice").unwrap();
+    let settings = settings(false);
+    let data = local_site_data(&[], &[]);
+    assert_eq!(
+      Err(ApubValidationError::InstanceVersionNotAllowed(
+        "first-contact.example".to_string()
+      )),
+      check_apub_id_valid(&url, &data, &settings)
+    );
+
+    // nodeinfo can't be fetched in tests, so the software is stored as unknown, for which no
+    // minimum version is configured
+    check_apub_id_valid_detecting_software(&url, &data, &settings, &context)
+      .await
+      .unwrap();
+    let instance =
+      Instance::read_or_create(&mut context.pool(), "first-contact.example".to_string())
+        .await
+        .unwrap();
+    assert_eq!(Some(UNKNOWN_SOFTWARE.to_string()), instance.software);
+
+    Instance::delete(&mut context.pool(), instance.id)
+      .await
+      .unwrap();
+  }
+
+  #[test]
+  fn test_check_apub_id_valid_paused() {
+    let paused = Url::parse("https://paused.example/u/alice").unwrap();
+    let active = Url::parse("https://active.example/u/bob").unwrap();
+    let mut data = local_site_data(&["paused.example", "active.example"], &[]);
+    data.paused_instances = vec![instance("Paused.example", None, None)];
+
+    assert_eq!(
+      Err(ApubValidationError::FederationPaused(
+        "paused.example".to_string()
+      )),
+      check_apub_id_valid(&paused, &data, &SETTINGS)
+    );
+    assert!(check_apub_id_valid(&active, &data, &SETTINGS).is_ok());
+
+    // resuming federation makes the instance valid again
+    data.paused_instances.clear();
+    assert!(check_apub_id_valid(&paused, &data, &SETTINGS).is_ok());
+  }
+
+  #[test]
+  fn test_check_apub_id_valid_blocked_community() {
+    let blocked = Url::parse("https://allowed.example/c/blocked").unwrap();
+    let other = Url::parse("https://allowed.example/c/other").unwrap();
+    let mut data = local_site_data(&["allowed.example"], &[]);
+    data.blocked_communities = vec![blocked.clone().into()];
+
+    assert_eq!(
+      Err(ApubValidationError::CommunityFederationBlocked(
+        blocked.to_string()
+      )),
+      check_apub_id_valid(&blocked, &data, &SETTINGS)
+    );
+    assert!(check_apub_id_valid(&other, &data, &SETTINGS).is_ok());
+
+    // instance rules are checked first
+    data.blocked_instances = vec![instance("allowed.example", None, None)];
+    assert_eq!(
+      Err(ApubValidationError::DomainBlocked(
+        "allowed.example".to_string()
+      )),
+      check_apub_id_valid(&blocked, &data, &SETTINGS)
+    );
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_check_apub_id_valid_in_blocked_community() {
+    let context = init_context().await;
+    let community = parse_lemmy_community(&context).await;
+    let post_id = Url::parse("https://enterprise.lemmy.ml/post/55143").unwrap();
+    LOCAL_SITE_DATA_CACHE.invalidate_all();
+    check_apub_id_valid_in_community(&post_id, &community, &context)
+      .await
+      .unwrap();
+
+    FederationCommunityBlockList::block(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    LOCAL_SITE_DATA_CACHE.invalidate_all();
+    let expected = LemmyErrorType::CommunityFederationBlocked(community.actor_id.to_string());
+    let err = check_apub_id_valid_in_community(&post_id, &community, &context)
+      .await
+      .unwrap_err();
+    assert_eq!(expected, err.error_type);
+    // the strict allowlist check for communities also rejects it
+    let err = check_apub_id_valid_with_strictness(community.actor_id.inner(), true, &context)
+      .await
+      .unwrap_err();
+    assert_eq!(expected, err.error_type);
+    // other content from the instance is still allowed
+    let person_id = Url::parse("https://enterprise.lemmy.ml/u/picard").unwrap();
+    check_apub_id_valid_with_strictness(&person_id, false, &context)
+      .await
+      .unwrap();
+
+    FederationCommunityBlockList::unblock(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    LOCAL_SITE_DATA_CACHE.invalidate_all();
+    check_apub_id_valid_in_community(&post_id, &community, &context)
+      .await
+      .unwrap();
+    Community::delete(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+  }
+
+  #[test]
+  fn test_http_fetch_limit_overrides() {
+    let mut settings = SETTINGS.clone();
+    settings.federation.http_fetch_limit = 25;
+    settings
+      .federation
+      .http_fetch_limit_overrides
+      .insert("trusted.example".to_string(), 200);
+
+    assert_eq!(200, http_fetch_limit("trusted.example", None, &settings));
+    assert_eq!(200, http_fetch_limit("Trusted.Example", None, &settings));
+    assert_eq!(25, http_fetch_limit("other.example", None, &settings));
+    assert_eq!(200, max_http_fetch_limit(None, &settings));
+
+    // an override can also lower the limit for a domain
+    settings
+      .federation
+      .http_fetch_limit_overrides
+      .insert("slow.example".to_string(), 5);
+    assert_eq!(5, http_fetch_limit("slow.example", None, &settings));
+    assert_eq!(200, max_http_fetch_limit(None, &settings));
+  }
+
+  #[test]
+  fn test_http_fetch_limit_from_local_site() {
+    let mut settings = SETTINGS.clone();
+    settings.federation.http_fetch_limit = 25;
+    settings
+      .federation
+      .http_fetch_limit_overrides
+      .insert("trusted.example".to_string(), 200);
+    let mut local_site = local_site();
+    local_site.federation_http_fetch_limit = Some(400);
+
+    // the local site replaces the default from the config, but not the overrides
+    assert_eq!(
+      400,
+      http_fetch_limit("other.example", Some(&local_site), &settings)
+    );
+    assert_eq!(
+      200,
+      http_fetch_limit("trusted.example", Some(&local_site), &settings)
+    );
+    assert_eq!(400, max_http_fetch_limit(Some(&local_site), &settings));
+
+    // invalid values fall back to the config
+    local_site.federation_http_fetch_limit = Some(0);
+    assert_eq!(
+      25,
+      http_fetch_limit("other.example", Some(&local_site), &settings)
+    );
+    local_site.federation_http_fetch_limit = None;
+    assert_eq!(
+      25,
+      http_fetch_limit("other.example", Some(&local_site), &settings)
+    );
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_insert_received_activity_cached() {
+    let context = init_context().await;
+    let ap_id = Url::parse("http://lemmy-alpha/activities/create/dedup-cache").unwrap();
+
+    insert_received_activity(&ap_id, &context).await.unwrap();
+    // the second attempt is rejected from memory, the database would return a different error
+    let err = insert_received_activity(&ap_id, &context)
+      .await
+      .unwrap_err();
+    assert_eq!(LemmyErrorType::ActivityAlreadyReceived, err.error_type);
+
+    // an id which is only cached never reaches the database
+    let cached_id = Url::parse("http://lemmy-alpha/activities/create/dedup-cache-only").unwrap();
+    context
+      .received_activities()
+      .insert(cached_id.clone(), ())
+      .await;
+    assert!(insert_received_activity(&cached_id, &context)
+      .await
+      .is_err());
+    ReceivedActivity::create(&mut context.pool(), &cached_id.clone().into())
+      .await
+      .unwrap();
+
+    // a duplicate which is only found in the database is remembered as well
+    let stored_id = Url::parse("http://lemmy-alpha/activities/create/dedup-db-only").unwrap();
+    ReceivedActivity::create(&mut context.pool(), &stored_id.clone().into())
+      .await
+      .unwrap();
+    assert!(!context.received_activities().contains_key(&stored_id));
+    assert!(insert_received_activity(&stored_id, &context)
+      .await
+      .is_err());
+    assert!(context.received_activities().contains_key(&stored_id));
+
+    for id in [ap_id, cached_id, stored_id] {
+      ReceivedActivity::delete(&mut context.pool(), &id.into())
+        .await
+        .unwrap();
+    }
+  }
+}
@@ -1,7 +1,10 @@
-use crate::objects::{comment::ApubComment, community::ApubCommunity, person::ApubPerson};
+use crate::{
+  fetcher::webfinger_resolve_actor_cached,
+  objects::{comment::ApubComment, community::ApubCommunity, person::ApubPerson},
+};
 use activitypub_federation::{
   config::Data,
-  fetch::{object_id::ObjectId, webfinger::webfinger_resolve_actor},
+  fetch::object_id::ObjectId,
   kinds::link::MentionType,
   traits::Actor,
 };
@@ -68,7 +71,7 @@ pub async fn collect_non_local_mentions(
 
   for mention in mentions {
     let identifier = format!("{}@{}", mention.name, mention.domain);
-    let person = webfinger_resolve_actor::<LemmyContext, ApubPerson>(&identifier, context).await;
+    let person = webfinger_resolve_actor_cached::<ApubPerson>(&identifier, context).await;
     if let Ok(person) = person {
       addressed_ccs.push(person.actor_id.to_string().parse()?);
 
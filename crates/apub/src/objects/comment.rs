@@ -1,8 +1,8 @@
 use crate::{
-  activities::{verify_is_public, verify_person_in_community},
-  check_apub_id_valid_with_strictness,
+  activities::{verify_community_member, verify_is_public, verify_person_in_community},
+  check_apub_id_valid_in_community,
   mentions::collect_non_local_mentions,
-  objects::{read_from_string_or_source, verify_is_remote_object},
+  objects::{inline_emojis, read_from_string_or_source, verify_is_remote_object},
   protocol::{
     objects::{note::Note, LanguageTag},
     InCommunity,
@@ -16,8 +16,12 @@ use activitypub_federation::{
   traits::Object,
 };
 use chrono::{DateTime, Utc};
-use lemmy_api_common::{context::LemmyContext, utils::local_site_opt_to_slur_regex};
+use lemmy_api_common::{
+  context::LemmyContext,
+  utils::{check_reply_policy, local_site_opt_to_slur_regex},
+};
 use lemmy_db_schema::{
+  aggregates::structs::CommentAggregates,
   source::{
     comment::{Comment, CommentInsertForm, CommentUpdateForm},
     community::Community,
@@ -29,7 +33,7 @@ use lemmy_db_schema::{
 };
 use lemmy_utils::{
   error::{LemmyError, LemmyErrorType},
-  utils::{markdown::markdown_to_html, slurs::remove_slurs},
+  utils::{markdown::markdown_to_html, slurs::remove_slurs, validation::check_renderable_content},
 };
 use std::ops::Deref;
 use url::Url;
@@ -102,6 +106,7 @@ impl Object for ApubComment {
     };
     let language = LanguageTag::new_single(self.language_id, &mut context.pool()).await?;
     let maa = collect_non_local_mentions(&self, community.actor_id.clone().into(), context).await?;
+    let aggregates = CommentAggregates::read(&mut context.pool(), self.id).await?;
 
     let note = Note {
       r#type: NoteType::Note,
@@ -117,8 +122,11 @@ impl Object for ApubComment {
       updated: self.updated,
       tag: maa.tags,
       distinguished: Some(self.distinguished),
+      reply_policy: Some(self.reply_policy),
       language,
       audience: Some(community.actor_id.into()),
+      upvotes: Some(aggregates.upvotes),
+      downvotes: Some(aggregates.downvotes),
     };
 
     Ok(note)
@@ -135,15 +143,33 @@ impl Object for ApubComment {
     verify_is_public(&note.to, &note.cc)?;
     let community = note.community(context).await?;
 
-    check_apub_id_valid_with_strictness(note.id.inner(), community.local, context).await?;
+    check_apub_id_valid_in_community(note.id.inner(), &community, context).await?;
     verify_is_remote_object(note.id.inner(), context.settings())?;
     verify_person_in_community(&note.attributed_to, &community, context).await?;
-    let (post, _) = note.get_parents(context).await?;
+    verify_community_member(&note.attributed_to, &community, context).await?;
+    let (post, parent_comment) = note.get_parents(context).await?;
     if post.locked {
       Err(LemmyErrorType::PostIsLocked)?
-    } else {
-      Ok(())
+    } else if post.archived {
+      Err(LemmyErrorType::PostIsArchived)?
     }
+
+    // Only enforce the reply policy of local content, for remote content this is up to the origin
+    let (parent_local, parent_creator_id, reply_policy) = match parent_comment {
+      Some(c) => (c.local, c.creator_id, c.reply_policy),
+      None => (post.local, post.creator_id, post.reply_policy),
+    };
+    if parent_local {
+      let creator = note.attributed_to.dereference(context).await?;
+      check_reply_policy(
+        creator.id,
+        parent_creator_id,
+        reply_policy,
+        &mut context.pool(),
+      )
+      .await?;
+    }
+    Ok(())
   }
 
   /// Converts a `Note` to `Comment`.
@@ -155,6 +181,8 @@ impl Object for ApubComment {
     let (post, parent_comment) = note.get_parents(context).await?;
 
     let content = read_from_string_or_source(&note.content, &note.media_type, &note.source);
+    let content = inline_emojis(&content, &note.emojis());
+    check_renderable_content(&content, context.settings())?;
 
     let local_site = LocalSite::read(&mut context.pool()).await.ok();
     let slur_regex = &local_site_opt_to_slur_regex(&local_site);
@@ -174,9 +202,22 @@ impl Object for ApubComment {
       distinguished: note.distinguished,
       local: Some(false),
       language_id,
+      reply_policy: note.reply_policy,
     };
     let parent_comment_path = parent_comment.map(|t| t.0.path);
     let comment = Comment::create(&mut context.pool(), &form, parent_comment_path.as_ref()).await?;
+
+    // use the vote counts of the origin instance, so that ranking is accurate even if not all
+    // votes were federated to us
+    if let (Some(upvotes), Some(downvotes)) = (note.upvotes, note.downvotes) {
+      CommentAggregates::update_federated_votes(
+        &mut context.pool(),
+        comment.id,
+        upvotes,
+        downvotes,
+      )
+      .await?;
+    }
     Ok(comment.into())
   }
 }
@@ -195,11 +236,20 @@ pub(crate) mod tests {
       post::ApubPost,
       tests::init_context,
     },
-    protocol::tests::file_to_json_object,
+    protocol::{objects::person::Person as ApubPersonJson, tests::file_to_json_object},
   };
   use assert_json_diff::assert_json_include;
   use html2md::parse_html;
-  use lemmy_db_schema::source::site::Site;
+  use lemmy_db_schema::{
+    source::{
+      instance::Instance,
+      person::{PersonFollower, PersonFollowerForm, PersonInsertForm},
+      post::PostInsertForm,
+      site::Site,
+    },
+    traits::Followable,
+    ReplyPolicy,
+  };
   use serial_test::serial;
 
   async fn prepare_comment_test(
@@ -256,6 +306,33 @@ pub(crate) mod tests {
     cleanup(data, &context).await;
   }
 
+  #[tokio::test]
+  #[serial]
+  async fn test_parse_comment_votes() {
+    let context = init_context().await;
+    let url = Url::parse("https://enterprise.lemmy.ml/comment/38741").unwrap();
+    let data = prepare_comment_test(&url, &context).await;
+
+    let mut json: Note = file_to_json_object("assets/lemmy/objects/note.json").unwrap();
+    json.upvotes = Some(12);
+    json.downvotes = Some(9);
+    ApubComment::verify(&json, &url, &context).await.unwrap();
+    let comment = ApubComment::from_json(json, &context).await.unwrap();
+
+    let aggregates = CommentAggregates::read(&mut context.pool(), comment.id)
+      .await
+      .unwrap();
+    assert_eq!(12, aggregates.upvotes);
+    assert_eq!(9, aggregates.downvotes);
+    assert_eq!(3, aggregates.score);
+    assert!(aggregates.controversy_rank > 0.0);
+
+    Comment::delete(&mut context.pool(), comment.id)
+      .await
+      .unwrap();
+    cleanup(data, &context).await;
+  }
+
   #[tokio::test]
   #[serial]
   async fn test_parse_pleroma_comment() {
@@ -266,7 +343,10 @@ pub(crate) mod tests {
     let pleroma_url =
       Url::parse("https://queer.hacktivis.me/objects/8d4973f4-53de-49cd-8c27-df160e16a9c2")
         .unwrap();
-    let person_json = file_to_json_object("assets/pleroma/objects/person.json").unwrap();
+    let mut person_json: ApubPersonJson =
+      file_to_json_object("assets/pleroma/objects/person.json").unwrap();
+    // change these links so they dont fetch over the network
+    person_json.featured = None;
     ApubPerson::verify(&person_json, &pleroma_url, &context)
       .await
       .unwrap();
@@ -288,6 +368,83 @@ pub(crate) mod tests {
     cleanup(data, &context).await;
   }
 
+  #[tokio::test]
+  #[serial]
+  async fn test_round_trip_reply_policy() {
+    let context = init_context().await;
+    let url = Url::parse("https://enterprise.lemmy.ml/comment/38741").unwrap();
+    let data = prepare_comment_test(&url, &context).await;
+
+    let mut json: Note = file_to_json_object("assets/lemmy/objects/note.json").unwrap();
+    json.reply_policy = Some(ReplyPolicy::Followers);
+    ApubComment::verify(&json, &url, &context).await.unwrap();
+    let comment = ApubComment::from_json(json, &context).await.unwrap();
+    assert_eq!(ReplyPolicy::Followers, comment.reply_policy);
+
+    let comment_id = comment.id;
+    let to_apub = comment.into_json(&context).await.unwrap();
+    assert_eq!(Some(ReplyPolicy::Followers), to_apub.reply_policy);
+
+    Comment::delete(&mut context.pool(), comment_id)
+      .await
+      .unwrap();
+    cleanup(data, &context).await;
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_reject_reply_to_followers_only_post() {
+    let context = init_context().await;
+    let url = Url::parse("https://enterprise.lemmy.ml/comment/38741").unwrap();
+    let data = prepare_comment_test(&url, &context).await;
+
+    let instance = Instance::read_or_create(&mut context.pool(), "example.com".to_string())
+      .await
+      .unwrap();
+    let person_form = PersonInsertForm::builder()
+      .name("reply_policy_author".to_string())
+      .public_key("pubkey".to_string())
+      .instance_id(instance.id)
+      .local(Some(true))
+      .build();
+    let author = Person::create(&mut context.pool(), &person_form)
+      .await
+      .unwrap();
+    let post_form = PostInsertForm::builder()
+      .name("followers only".to_string())
+      .creator_id(author.id)
+      .community_id(data.1.id)
+      .local(Some(true))
+      .reply_policy(Some(ReplyPolicy::Followers))
+      .build();
+    let post = Post::create(&mut context.pool(), &post_form).await.unwrap();
+
+    // picard doesn't follow the author, so the reply is rejected
+    let mut json: Note = file_to_json_object("assets/lemmy/objects/note.json").unwrap();
+    json.in_reply_to = post.ap_id.clone().into();
+    let res = ApubComment::verify(&json, &url, &context).await;
+    assert_eq!(
+      res.unwrap_err().error_type,
+      LemmyErrorType::RepliesNotAllowed
+    );
+
+    let follower_form = PersonFollowerForm {
+      person_id: author.id,
+      follower_id: data.0.id,
+      pending: false,
+    };
+    PersonFollower::follow(&mut context.pool(), &follower_form)
+      .await
+      .unwrap();
+    ApubComment::verify(&json, &url, &context).await.unwrap();
+
+    Post::delete(&mut context.pool(), post.id).await.unwrap();
+    Person::delete(&mut context.pool(), author.id)
+      .await
+      .unwrap();
+    cleanup(data, &context).await;
+  }
+
   #[tokio::test]
   #[serial]
   async fn test_html_to_markdown_sanitize() {
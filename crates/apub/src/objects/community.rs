@@ -2,9 +2,13 @@ use crate::{
   activities::GetActorType,
   check_apub_id_valid,
   local_site_data_cached,
-  objects::instance::fetch_instance_actor_for_object,
+  objects::{generate_summary, instance::fetch_instance_actor_for_object},
   protocol::{
-    objects::{group::Group, Endpoints, LanguageTag},
+    objects::{
+      group::{ActiveUsers, Group},
+      Endpoints,
+      LanguageTag,
+    },
     ImageObject,
     Source,
   },
@@ -20,6 +24,7 @@ use lemmy_api_common::{
   utils::{generate_featured_url, generate_moderators_url, generate_outbox_url},
 };
 use lemmy_db_schema::{
+  aggregates::structs::CommunityAggregates,
   source::{
     activity::ActorType,
     actor_language::CommunityLanguage,
@@ -28,7 +33,7 @@ use lemmy_db_schema::{
   traits::{ApubActor, Crud},
 };
 use lemmy_db_views_actor::structs::CommunityFollowerView;
-use lemmy_utils::{error::LemmyError, utils::markdown::markdown_to_html};
+use lemmy_utils::error::LemmyError;
 use std::ops::Deref;
 use tracing::debug;
 use url::Url;
@@ -86,13 +91,29 @@ impl Object for ApubCommunity {
     let community_id = self.id;
     let langs = CommunityLanguage::read(&mut data.pool(), community_id).await?;
     let language = LanguageTag::new_multiple(langs, &mut data.pool()).await?;
+    let primary_language =
+      LanguageTag::new_single(self.primary_language_id, &mut data.pool()).await?;
+    // only counts of our own communities are accurate, so don't pass on those of remote ones
+    let active_users = if self.local {
+      let counts = CommunityAggregates::read(&mut data.pool(), community_id).await?;
+      Some(ActiveUsers {
+        day: counts.users_active_day,
+        week: counts.users_active_week,
+        month: counts.users_active_month,
+      })
+    } else {
+      None
+    };
 
     let group = Group {
       kind: GroupType::Group,
       id: self.id().into(),
       preferred_username: self.name.clone(),
       name: Some(self.title.clone()),
-      summary: self.description.as_ref().map(|b| markdown_to_html(b)),
+      summary: self
+        .description
+        .as_ref()
+        .map(|d| generate_summary(d, data.settings())),
       source: self.description.clone().map(Source::new),
       icon: self.icon.clone().map(ImageObject::new),
       image: self.banner.clone().map(ImageObject::new),
@@ -109,6 +130,11 @@ impl Object for ApubCommunity {
       published: Some(self.published),
       updated: self.updated,
       posting_restricted_to_mods: Some(self.posting_restricted_to_mods),
+      membership_mode: Some(self.membership_mode),
+      default_sort_type: self.default_sort_type,
+      default_post_nsfw: Some(self.default_post_nsfw),
+      primary_language,
+      active_users,
       attributed_to: Some(generate_moderators_url(&self.actor_id)?.into()),
     };
     Ok(group)
@@ -131,12 +157,16 @@ impl Object for ApubCommunity {
   ) -> Result<ApubCommunity, LemmyError> {
     let instance_id = fetch_instance_actor_for_object(&group.id, context).await?;
 
-    let form = Group::into_insert_form(group.clone(), instance_id);
+    let primary_language_id = group.primary_language_id(&mut context.pool()).await?;
+    let form = Group::into_insert_form(group.clone(), instance_id, primary_language_id);
     let languages =
       LanguageTag::to_language_id_multiple(group.language, &mut context.pool()).await?;
 
     let community = Community::create(&mut context.pool(), &form).await?;
     CommunityLanguage::update(&mut context.pool(), languages, community.id).await?;
+    group
+      .store_active_users(&community, &mut context.pool())
+      .await?;
 
     let community: ApubCommunity = community.into();
 
@@ -199,7 +229,7 @@ impl ApubCommunity {
   ) -> Result<Vec<Url>, LemmyError> {
     let id = self.id;
 
-    let local_site_data = local_site_data_cached(&mut context.pool()).await?;
+    let local_site_data = local_site_data_cached(context).await?;
     let follows =
       CommunityFollowerView::get_community_follower_inboxes(&mut context.pool(), id).await?;
     let inboxes: Vec<Url> = follows
@@ -207,7 +237,7 @@ impl ApubCommunity {
       .map(Into::into)
       .filter(|inbox: &Url| inbox.host_str() != Some(&context.settings().hostname))
       // Don't send to blocked instances
-      .filter(|inbox| check_apub_id_valid(inbox, &local_site_data).is_ok())
+      .filter(|inbox| check_apub_id_valid(inbox, &local_site_data, context.settings()).is_ok())
       .collect();
 
     Ok(inboxes)
@@ -225,7 +255,14 @@ pub(crate) mod tests {
     protocol::tests::file_to_json_object,
   };
   use activitypub_federation::fetch::collection_id::CollectionId;
-  use lemmy_db_schema::{source::site::Site, traits::Crud};
+  use lemmy_db_schema::{
+    impls::actor_language::UNDETERMINED_ID,
+    source::{language::Language, site::Site},
+    traits::Crud,
+    CommunityMembershipMode,
+    SortType,
+  };
+  use lemmy_db_views_actor::community_view::CommunityQuery;
   use serial_test::serial;
 
   pub(crate) async fn parse_lemmy_community(context: &Data<LemmyContext>) -> ApubCommunity {
@@ -263,4 +300,193 @@ pub(crate) mod tests {
       .unwrap();
     Site::delete(&mut context.pool(), site.id).await.unwrap();
   }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_parse_community_membership_mode() {
+    let context = init_context().await;
+    let site = parse_lemmy_instance(&context).await;
+    let community = parse_lemmy_community(&context).await;
+    assert_eq!(community.membership_mode, CommunityMembershipMode::Open);
+
+    // federate a change of the membership mode
+    let mut json = community.clone().into_json(&context).await.unwrap();
+    assert_eq!(json.membership_mode, Some(CommunityMembershipMode::Open));
+    json.membership_mode = Some(CommunityMembershipMode::RequireApplication);
+    json.attributed_to = None;
+    json.featured = None;
+    let context2 = context.reset_request_count();
+    let updated = ApubCommunity::from_json(json, &context2).await.unwrap();
+    assert_eq!(
+      updated.membership_mode,
+      CommunityMembershipMode::RequireApplication
+    );
+
+    let json = updated.into_json(&context).await.unwrap();
+    assert_eq!(
+      json.membership_mode,
+      Some(CommunityMembershipMode::RequireApplication)
+    );
+
+    Community::delete(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_community_published_round_trip() {
+    let context = init_context().await;
+    let site = parse_lemmy_instance(&context).await;
+    let community = parse_lemmy_community(&context).await;
+    let published = DateTime::parse_from_rfc3339("2019-06-02T16:43:50.799554Z")
+      .unwrap()
+      .with_timezone(&Utc);
+    assert_eq!(community.published, published);
+
+    // federate the community again, the original creation time is kept
+    let mut json = community.clone().into_json(&context).await.unwrap();
+    assert_eq!(json.published, Some(published));
+    json.attributed_to = None;
+    json.featured = None;
+    let context2 = context.reset_request_count();
+    let updated = ApubCommunity::from_json(json.clone(), &context2)
+      .await
+      .unwrap();
+    assert_eq!(updated.published, published);
+
+    // a creation time in the future is clamped
+    json.published = Some(Utc::now() + chrono::Duration::days(365));
+    let updated = ApubCommunity::from_json(json, &context2).await.unwrap();
+    assert!(updated.published <= Utc::now());
+
+    Community::delete(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_parse_community_default_sort_type() {
+    let context = init_context().await;
+    let site = parse_lemmy_instance(&context).await;
+    let community = parse_lemmy_community(&context).await;
+    assert_eq!(community.default_sort_type, Some(SortType::Hot));
+
+    // the default sort is federated back out
+    let json = community.clone().into_json(&context).await.unwrap();
+    assert_eq!(json.default_sort_type, Some(SortType::Hot));
+
+    // an unknown value falls back to the global default
+    let mut json = serde_json::to_value(json).unwrap();
+    json["defaultSortType"] = "SomeFutureSort".into();
+    let mut json: Group = serde_json::from_value(json).unwrap();
+    assert_eq!(json.default_sort_type, None);
+    json.attributed_to = None;
+    json.featured = None;
+    let context2 = context.reset_request_count();
+    let updated = ApubCommunity::from_json(json, &context2).await.unwrap();
+    assert_eq!(updated.default_sort_type, None);
+
+    Community::delete(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_community_primary_language_round_trip() {
+    let context = init_context().await;
+    let site = parse_lemmy_instance(&context).await;
+    let community = parse_lemmy_community(&context).await;
+    let french_id = Language::read_id_from_code(&mut context.pool(), Some("fr"))
+      .await
+      .unwrap()
+      .unwrap();
+    assert_eq!(community.primary_language_id, french_id);
+
+    // the community can be found by its primary language
+    let communities = CommunityQuery {
+      primary_language_id: Some(french_id),
+      is_mod_or_admin: true,
+      ..Default::default()
+    }
+    .list(&mut context.pool())
+    .await
+    .unwrap();
+    assert!(communities.iter().any(|c| c.community.id == community.id));
+
+    let mut json = community.clone().into_json(&context).await.unwrap();
+    assert_eq!(
+      Some("fr"),
+      json
+        .primary_language
+        .as_ref()
+        .map(|l| l.identifier.as_str())
+    );
+
+    // an unknown language falls back to undetermined, which is not federated
+    json.primary_language = Some(LanguageTag {
+      identifier: "xx-unknown".to_string(),
+      name: "Unknown".to_string(),
+    });
+    json.attributed_to = None;
+    json.featured = None;
+    let context2 = context.reset_request_count();
+    let updated = ApubCommunity::from_json(json, &context2).await.unwrap();
+    assert_eq!(updated.primary_language_id, UNDETERMINED_ID);
+    let json = updated.into_json(&context).await.unwrap();
+    assert!(json.primary_language.is_none());
+
+    Community::delete(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_community_active_users_round_trip() {
+    let context = init_context().await;
+    let site = parse_lemmy_instance(&context).await;
+    let community = parse_lemmy_community(&context).await;
+
+    // counts of remote communities are not passed on
+    let mut json = community.clone().into_json(&context).await.unwrap();
+    assert_eq!(json.active_users, None);
+
+    let active_users = ActiveUsers {
+      day: 12,
+      week: 80,
+      month: 250,
+    };
+    json.active_users = Some(active_users.clone());
+    json.attributed_to = None;
+    json.featured = None;
+    let context2 = context.reset_request_count();
+    ApubCommunity::from_json(json, &context2).await.unwrap();
+
+    let counts = CommunityAggregates::read(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    assert_eq!(active_users.day, counts.users_active_day);
+    assert_eq!(active_users.week, counts.users_active_week);
+    assert_eq!(active_users.month, counts.users_active_month);
+    assert!(counts.federated_users_active);
+
+    // a malformed hint is ignored
+    let mut json =
+      serde_json::to_value(community.clone().into_json(&context).await.unwrap()).unwrap();
+    json["activeUsers"] = "many".into();
+    let json: Group = serde_json::from_value(json).unwrap();
+    assert_eq!(json.active_users, None);
+
+    Community::delete(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
 }
@@ -1,6 +1,7 @@
 use crate::{
   activities::GetActorType,
   check_apub_id_valid_with_strictness,
+  fetcher::nodeinfo::spawn_update_instance_software,
   local_site_data_cached,
   objects::read_from_string_or_source_opt,
   protocol::{
@@ -117,7 +118,7 @@ impl Object for ApubSite {
     check_apub_id_valid_with_strictness(apub.id.inner(), true, data).await?;
     verify_domains_match(expected_domain, apub.id.inner())?;
 
-    let local_site_data = local_site_data_cached(&mut data.pool()).await?;
+    let local_site_data = local_site_data_cached(data).await?;
     let slur_regex = &local_site_opt_to_slur_regex(&local_site_data.local_site);
     check_slurs(&apub.name, slur_regex)?;
     check_slurs_opt(&apub.summary, slur_regex)?;
@@ -129,6 +130,7 @@ impl Object for ApubSite {
   async fn from_json(apub: Self::Kind, data: &Data<Self::DataType>) -> Result<Self, LemmyError> {
     let domain = apub.id.inner().domain().expect("group id has domain");
     let instance = DbInstance::read_or_create(&mut data.pool(), domain.to_string()).await?;
+    spawn_update_instance_software(&instance, data);
 
     let sidebar = read_from_string_or_source_opt(&apub.content, &None, &apub.source);
 
@@ -193,11 +195,9 @@ pub(in crate::objects) async fn fetch_instance_actor_for_object<T: Into<Url> + C
       // Failed to fetch instance actor, its probably not a lemmy instance
       debug!("Failed to dereference site for {}: {}", &instance_id, e);
       let domain = instance_id.domain().expect("has domain");
-      Ok(
-        DbInstance::read_or_create(&mut context.pool(), domain.to_string())
-          .await?
-          .id,
-      )
+      let instance = DbInstance::read_or_create(&mut context.pool(), domain.to_string()).await?;
+      spawn_update_instance_software(&instance, context);
+      Ok(instance.id)
     }
   }
 }
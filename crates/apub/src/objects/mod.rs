@@ -1,14 +1,21 @@
 use crate::protocol::Source;
 use activitypub_federation::protocol::values::MediaTypeMarkdownOrHtml;
 use anyhow::anyhow;
+use chrono::{DateTime, Utc};
 use html2md::parse_html;
-use lemmy_utils::{error::LemmyError, settings::structs::Settings};
+use lemmy_utils::{
+  error::LemmyError,
+  settings::structs::Settings,
+  utils::markdown::markdown_to_html_with_max_len,
+};
+use std::collections::HashMap;
 use url::Url;
 
 pub mod comment;
 pub mod community;
 pub mod instance;
 pub mod person;
+pub mod poll;
 pub mod post;
 pub mod private_message;
 
@@ -29,6 +36,19 @@ pub(crate) fn read_from_string_or_source(
   }
 }
 
+/// Turns custom emojis of remote content into inline images, as only the markdown is stored and
+/// not the emoji definitions from the tags. Unknown shortcodes are kept as text.
+pub(crate) fn inline_emojis(markdown: &str, emojis: &HashMap<String, String>) -> String {
+  emojis
+    .iter()
+    .fold(markdown.to_string(), |markdown, (shortcode, url)| {
+      markdown.replace(
+        &format!(":{shortcode}:"),
+        &format!("![:{shortcode}:](<{url}>)"),
+      )
+    })
+}
+
 pub(crate) fn read_from_string_or_source_opt(
   content: &Option<String>,
   media_type: &Option<MediaTypeMarkdownOrHtml>,
@@ -39,6 +59,24 @@ pub(crate) fn read_from_string_or_source_opt(
     .map(|content| read_from_string_or_source(content, media_type, source))
 }
 
+/// Renders markdown as html for the `summary` field, truncated to the configured maximum length.
+/// The rendered html is truncated rather than the markdown, so that no formatting is cut in half.
+/// The full text is federated separately in `source`.
+pub(crate) fn generate_summary(markdown: &str, settings: &Settings) -> String {
+  markdown_to_html_with_max_len(markdown, settings.federation.summary_max_length)
+}
+
+/// Clamps the `published` time received from a remote instance to a sane range. Times in the
+/// future are replaced by the current time, times before the unix epoch are discarded.
+pub(crate) fn clamp_published(published: Option<DateTime<Utc>>) -> Option<DateTime<Utc>> {
+  let now = Utc::now();
+  match published {
+    Some(p) if p > now => Some(now),
+    Some(p) if p.timestamp() < 0 => None,
+    p => p,
+  }
+}
+
 /// When for example a Post is made in a remote community, the community will send it back,
 /// wrapped in Announce. If we simply receive this like any other federated object, overwrite the
 /// existing, local Post. In particular, it will set the field local = false, so that the object
@@ -57,13 +95,16 @@ pub(crate) mod tests {
   #![allow(clippy::unwrap_used)]
   #![allow(clippy::indexing_slicing)]
 
+  use super::inline_emojis;
+  use crate::protocol::{objects::note::Note, tests::file_to_json_object};
   use activitypub_federation::config::{Data, FederationConfig};
   use anyhow::anyhow;
   use lemmy_api_common::{context::LemmyContext, request::client_builder};
   use lemmy_db_schema::{source::secret::Secret, utils::build_db_pool_for_tests};
   use lemmy_utils::{rate_limit::RateLimitCell, settings::SETTINGS};
   use reqwest::{Request, Response};
-  use reqwest_middleware::{ClientBuilder, Middleware, Next};
+  use reqwest_middleware::{ClientBuilder, ClientWithMiddleware, Middleware, Next};
+  use serde_json::json;
   use task_local_extensions::Extensions;
 
   struct BlockedMiddleware;
@@ -83,6 +124,13 @@ pub(crate) mod tests {
 
   // TODO: would be nice if we didnt have to use a full context for tests.
   pub(crate) async fn init_context() -> Data<LemmyContext> {
+    init_context_with_client(reqwest::Client::default().into()).await
+  }
+
+  /// Same as [init_context], but requests by the federation library go through the given client.
+  pub(crate) async fn init_context_with_client(
+    federation_client: ClientWithMiddleware,
+  ) -> Data<LemmyContext> {
     // call this to run migrations
     let pool = build_db_pool_for_tests().await;
 
@@ -100,9 +148,42 @@ pub(crate) mod tests {
     let config = FederationConfig::builder()
       .domain("example.com")
       .app_data(context)
+      .client(federation_client)
       .build()
       .await
       .unwrap();
     config.to_request_data()
   }
+
+  #[test]
+  fn test_inline_emojis() {
+    let mut note: Note = file_to_json_object("assets/mastodon/objects/note.json").unwrap();
+    note.tag.push(
+      serde_json::from_value(json!({
+        "type": "Emoji",
+        "name": ":blobcat:",
+        "icon": {
+          "type": "Image",
+          "mediaType": "image/png",
+          "url": "https://mastodon.example/emoji/blobcat.png"
+        }
+      }))
+      .unwrap(),
+    );
+    note.tag.push(
+      serde_json::from_value(json!({
+        "type": "Emoji",
+        "name": ":evil](https://evil.example):",
+        "icon": { "type": "Image", "url": "https://evil.example/x.png" }
+      }))
+      .unwrap(),
+    );
+    let emojis = note.emojis();
+    assert_eq!(1, emojis.len());
+
+    assert_eq!(
+      "hi ![:blobcat:](<https://mastodon.example/emoji/blobcat.png>) and :unknown:",
+      inline_emojis("hi :blobcat: and :unknown:", &emojis)
+    );
+  }
 }
@@ -1,8 +1,12 @@
 use crate::{
-  activities::GetActorType,
+  activities::{block::apply_remote_ban_policy, GetActorType},
   check_apub_id_valid_with_strictness,
   local_site_data_cached,
-  objects::{instance::fetch_instance_actor_for_object, read_from_string_or_source_opt},
+  objects::{
+    generate_summary,
+    instance::fetch_instance_actor_for_object,
+    read_from_string_or_source_opt,
+  },
   protocol::{
     objects::{
       person::{Person, UserTypes},
@@ -20,7 +24,7 @@ use activitypub_federation::{
 use chrono::{DateTime, Utc};
 use lemmy_api_common::{
   context::LemmyContext,
-  utils::{generate_outbox_url, local_site_opt_to_slur_regex},
+  utils::{generate_featured_url, generate_outbox_url, local_site_opt_to_slur_regex},
 };
 use lemmy_db_schema::{
   source::{
@@ -32,14 +36,26 @@ use lemmy_db_schema::{
 };
 use lemmy_utils::{
   error::LemmyError,
-  utils::{
-    markdown::markdown_to_html,
-    slurs::{check_slurs, check_slurs_opt},
-  },
+  utils::slurs::{check_slurs, check_slurs_opt},
 };
-use std::ops::Deref;
+use moka::future::Cache;
+use once_cell::sync::Lazy;
+use std::{ops::Deref, time::Duration};
+use tracing::debug;
 use url::Url;
 
+/// Persons are also parsed when they are resolved through search or a signed activity. Their
+/// featured collection is only dereferenced again once it is older than this.
+const FEATURED_REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// Featured collections which were dereferenced recently.
+static RECENT_FEATURED: Lazy<Cache<Url, ()>> = Lazy::new(|| {
+  Cache::builder()
+    .max_capacity(10_000)
+    .time_to_live(FEATURED_REFRESH_INTERVAL)
+    .build()
+});
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ApubPerson(pub(crate) DbPerson);
 
@@ -89,19 +105,29 @@ impl Object for ApubPerson {
   }
 
   #[tracing::instrument(skip_all)]
-  async fn into_json(self, _context: &Data<Self::DataType>) -> Result<Person, LemmyError> {
+  async fn into_json(self, context: &Data<Self::DataType>) -> Result<Person, LemmyError> {
     let kind = if self.bot_account {
       UserTypes::Service
     } else {
       UserTypes::Person
     };
+    let also_known_as: Vec<_> = self
+      .also_known_as
+      .iter()
+      .flatten()
+      .cloned()
+      .map(Into::into)
+      .collect();
 
     let person = Person {
       kind,
       id: self.actor_id.clone().into(),
       preferred_username: self.name.clone(),
       name: self.display_name.clone(),
-      summary: self.bio.as_ref().map(|b| markdown_to_html(b)),
+      summary: self
+        .bio
+        .as_ref()
+        .map(|b| generate_summary(b, context.settings())),
       source: self.bio.clone().map(Source::new),
       icon: self.avatar.clone().map(ImageObject::new),
       image: self.banner.clone().map(ImageObject::new),
@@ -111,6 +137,11 @@ impl Object for ApubPerson {
       endpoints: self.shared_inbox_url.clone().map(|s| Endpoints {
         shared_inbox: s.into(),
       }),
+      featured: Some(generate_featured_url(&self.actor_id)?.into()),
+      also_known_as: Some(also_known_as).filter(|a| !a.is_empty()),
+      // for local users this is kept in sync with local_user.admin by a database trigger
+      instance_admin: Some(self.instance_admin),
+      banned: Some(self.banned),
       public_key: self.public_key(),
       updated: self.updated,
       inbox: self.inbox_url.clone().into(),
@@ -124,7 +155,7 @@ impl Object for ApubPerson {
     expected_domain: &Url,
     context: &Data<Self::DataType>,
   ) -> Result<(), LemmyError> {
-    let local_site_data = local_site_data_cached(&mut context.pool()).await?;
+    let local_site_data = local_site_data_cached(context).await?;
     let slur_regex = &local_site_opt_to_slur_regex(&local_site_data.local_site);
     check_slurs(&person.preferred_username, slur_regex)?;
     check_slurs_opt(&person.name, slur_regex)?;
@@ -150,9 +181,14 @@ impl Object for ApubPerson {
     // https://github.com/mastodon/mastodon/issues/25233
     let display_name = person.name.filter(|n| !n.is_empty());
 
+    // A ban by the home instance is applied, but the flag is never cleared here as the user may
+    // also be banned locally. Unbans are federated with `Undo(Block)` instead.
+    let banned = person.banned.unwrap_or(false);
+
     let person_form = PersonInsertForm {
       name: person.preferred_username,
       display_name,
+      // left unchanged by the upsert, so that the previous value is returned
       banned: None,
       ban_expires: None,
       deleted: Some(false),
@@ -171,10 +207,50 @@ impl Object for ApubPerson {
       shared_inbox_url: person.endpoints.map(|e| e.shared_inbox.into()),
       matrix_user_id: person.matrix_user_id,
       instance_id,
+      also_known_as: Some(
+        person
+          .also_known_as
+          .unwrap_or_default()
+          .into_iter()
+          .map(|a| Some(a.into()))
+          .collect(),
+      ),
+      // only used for display, this doesnt give any permissions on the local instance
+      instance_admin: Some(person.instance_admin.unwrap_or(false)),
     };
-    let person = DbPerson::upsert(&mut context.pool(), &person_form).await?;
+    let featured = person.featured;
+    let mut person: ApubPerson = DbPerson::upsert(&mut context.pool(), &person_form)
+      .await?
+      .into();
+
+    if banned && !person.banned {
+      let form = PersonUpdateForm {
+        banned: Some(true),
+        ..Default::default()
+      };
+      person = DbPerson::update(&mut context.pool(), person.id, &form)
+        .await?
+        .into();
+      apply_remote_ban_policy(
+        person.id,
+        false,
+        context.settings().federation.remote_ban_content_policy,
+        context,
+      )
+      .await?;
+    }
+
+    // Featured posts are not necessary for Lemmy to work, so ignore errors.
+    if let Some(featured) = featured.filter(|f| !RECENT_FEATURED.contains_key(f.inner())) {
+      RECENT_FEATURED.insert(featured.inner().clone(), ()).await;
+      featured
+        .dereference(&person, context)
+        .await
+        .map_err(|e| debug!("{}", e))
+        .ok();
+    }
 
-    Ok(person.into())
+    Ok(person)
   }
 }
 
@@ -214,18 +290,36 @@ pub(crate) mod tests {
   use super::*;
   use crate::{
     objects::{
+      community::tests::parse_lemmy_community,
       instance::{tests::parse_lemmy_instance, ApubSite},
+      post::ApubPost,
       tests::init_context,
     },
-    protocol::{objects::instance::Instance, tests::file_to_json_object},
+    protocol::{
+      objects::{instance::Instance, page::Page},
+      tests::file_to_json_object,
+    },
+  };
+  use activitypub_federation::fetch::{collection_id::CollectionId, object_id::ObjectId};
+  use lemmy_db_schema::{
+    source::{
+      community::Community,
+      instance::Instance as DbInstance,
+      local_user::{LocalUser, LocalUserInsertForm, LocalUserUpdateForm},
+      post::Post,
+      site::Site,
+    },
+    traits::Crud,
   };
-  use activitypub_federation::fetch::object_id::ObjectId;
-  use lemmy_db_schema::{source::site::Site, traits::Crud};
+  use lemmy_db_views_actor::structs::PersonView;
+  use lemmy_utils::settings::structs::RemoteBanContentPolicy;
   use serial_test::serial;
 
   pub(crate) async fn parse_lemmy_person(context: &Data<LemmyContext>) -> (ApubPerson, ApubSite) {
     let site = parse_lemmy_instance(context).await;
-    let json = file_to_json_object("assets/lemmy/objects/person.json").unwrap();
+    let mut json: Person = file_to_json_object("assets/lemmy/objects/person.json").unwrap();
+    // change these links so they dont fetch over the network
+    json.featured = None;
     let url = Url::parse("https://enterprise.lemmy.ml/u/picard").unwrap();
     ApubPerson::verify(&json, &url, context).await.unwrap();
     let person = ApubPerson::from_json(json, context).await.unwrap();
@@ -258,7 +352,8 @@ pub(crate) mod tests {
     ApubSite::verify(&json, &url, &context).await.unwrap();
     let site = ApubSite::from_json(json, &context).await.unwrap();
 
-    let json = file_to_json_object("assets/pleroma/objects/person.json").unwrap();
+    let mut json: Person = file_to_json_object("assets/pleroma/objects/person.json").unwrap();
+    json.featured = None;
     ApubPerson::verify(&json, &url, &context).await.unwrap();
     let person = ApubPerson::from_json(json, &context).await.unwrap();
 
@@ -267,7 +362,223 @@ pub(crate) mod tests {
     assert!(!person.local);
     assert_eq!(context.request_count(), 0);
     assert_eq!(person.bio.as_ref().unwrap().len(), 873);
+    assert!(person.also_known_as.is_empty());
+
+    cleanup((person, site), &context).await;
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_parse_person_also_known_as() {
+    let context = init_context().await;
+    let site = parse_lemmy_instance(&context).await;
+    let mut json: Person = file_to_json_object("assets/lemmy/objects/person.json").unwrap();
+    json.featured = None;
+    let aliases = vec![
+      ObjectId::parse("https://enterprise.lemmy.ml/u/locutus").unwrap(),
+      ObjectId::parse("https://mastodon.example/users/picard").unwrap(),
+    ];
+    json.also_known_as = Some(aliases.clone());
+    let url = Url::parse("https://enterprise.lemmy.ml/u/picard").unwrap();
+    ApubPerson::verify(&json, &url, &context).await.unwrap();
+    let person = ApubPerson::from_json(json, &context).await.unwrap();
+    assert_eq!(context.request_count(), 0);
+
+    let read_person: ApubPerson = DbPerson::read(&mut context.pool(), person.id)
+      .await
+      .unwrap()
+      .into();
+    let expected: Vec<_> = aliases.iter().cloned().map(|a| Some(a.into())).collect();
+    assert_eq!(read_person.also_known_as, expected);
+
+    let json = read_person.into_json(&context).await.unwrap();
+    assert_eq!(json.also_known_as, Some(aliases.clone()));
+    // other platforms expect the field name from the Mastodon extension
+    let value = serde_json::to_value(&json).unwrap();
+    let serialized: Vec<_> = aliases.iter().map(|a| a.inner().as_str()).collect();
+    assert_eq!(value["alsoKnownAs"], serde_json::json!(serialized));
+
+    cleanup((person, site), &context).await;
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_person_summary_max_length() {
+    let context = init_context().await;
+    let (mut person, site) = parse_lemmy_person(&context).await;
+    let max_length = context.settings().federation.summary_max_length;
+
+    // short bios are federated completely
+    person.0.bio = Some("Captain of the starship **Enterprise**.".to_string());
+    let json = person.clone().into_json(&context).await.unwrap();
+    assert_eq!(
+      Some("<p>Captain of the starship <strong>Enterprise</strong>.</p>\n".to_string()),
+      json.summary
+    );
+
+    // long bios are cut off without leaving any formatting open
+    let bio = "Captain of the starship **Enterprise**. ".repeat(1000);
+    person.0.bio = Some(bio.clone());
+    let json = person.clone().into_json(&context).await.unwrap();
+    let summary = json.summary.unwrap();
+    assert!(summary.len() <= max_length);
+    assert!(summary.starts_with("<p>Captain of the starship <strong>Enterprise</strong>."));
+    assert_eq!(
+      summary.matches("<strong>").count(),
+      summary.matches("</strong>").count()
+    );
+    assert!(summary.ends_with("</p>\n"));
+    // the full text is still available
+    assert_eq!(bio, json.source.unwrap().content);
+
+    cleanup((person, site), &context).await;
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_parse_remote_instance_admin() {
+    let context = init_context().await;
+    let site = parse_lemmy_instance(&context).await;
+    let mut json: Person = file_to_json_object("assets/lemmy/objects/person.json").unwrap();
+    json.featured = None;
+    json.instance_admin = Some(true);
+    let url = Url::parse("https://enterprise.lemmy.ml/u/picard").unwrap();
+    ApubPerson::verify(&json, &url, &context).await.unwrap();
+    let person = ApubPerson::from_json(json, &context).await.unwrap();
+    assert!(person.instance_admin);
+
+    let json = person.clone().into_json(&context).await.unwrap();
+    assert_eq!(json.instance_admin, Some(true));
+
+    // the remote admin has no permissions on the local instance
+    let is_admin = PersonView::is_admin(&mut context.pool(), person.id).await;
+    assert!(!is_admin.unwrap_or(false));
+
+    cleanup((person, site), &context).await;
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_local_instance_admin() {
+    let context = init_context().await;
+    let instance = DbInstance::read_or_create(&mut context.pool(), "example.com".to_string())
+      .await
+      .unwrap();
+    let person_form = PersonInsertForm::builder()
+      .name("local_admin".to_string())
+      .public_key("pubkey".to_string())
+      .instance_id(instance.id)
+      .local(Some(true))
+      .build();
+    let person = DbPerson::create(&mut context.pool(), &person_form)
+      .await
+      .unwrap();
+    let user_form = LocalUserInsertForm::builder()
+      .person_id(person.id)
+      .password_encrypted("pass".to_string())
+      .admin(Some(true))
+      .build();
+    let local_user = LocalUser::create(&mut context.pool(), &user_form)
+      .await
+      .unwrap();
+
+    // the flag is taken from the person row, which is kept in sync with local_user.admin
+    let person: ApubPerson = DbPerson::read(&mut context.pool(), person.id)
+      .await
+      .unwrap()
+      .into();
+    let json = person.clone().into_json(&context).await.unwrap();
+    assert_eq!(json.instance_admin, Some(true));
+
+    let user_form = LocalUserUpdateForm {
+      admin: Some(false),
+      ..Default::default()
+    };
+    LocalUser::update(&mut context.pool(), local_user.id, &user_form)
+      .await
+      .unwrap();
+    let person: ApubPerson = DbPerson::read(&mut context.pool(), person.id)
+      .await
+      .unwrap()
+      .into();
+    let json = person.clone().into_json(&context).await.unwrap();
+    assert_eq!(json.instance_admin, Some(false));
+
+    DbPerson::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    DbInstance::delete(&mut context.pool(), instance.id)
+      .await
+      .unwrap();
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_parse_person_featured_only_when_stale() {
+    let context = init_context().await;
+    let site = parse_lemmy_instance(&context).await;
+    let mut json: Person = file_to_json_object("assets/lemmy/objects/person.json").unwrap();
+    json.featured =
+      Some(CollectionId::parse("https://enterprise.lemmy.ml/u/picard/not_featured").unwrap());
+    let url = Url::parse("https://enterprise.lemmy.ml/u/picard").unwrap();
+    ApubPerson::verify(&json, &url, &context).await.unwrap();
 
+    // this makes a request to the (intentionally broken) featured collection
+    ApubPerson::from_json(json.clone(), &context).await.unwrap();
+    assert_eq!(context.request_count(), 1);
+
+    // parsing the person again doesnt fetch the collection again
+    let person = ApubPerson::from_json(json, &context).await.unwrap();
+    assert_eq!(context.request_count(), 1);
+
+    cleanup((person, site), &context).await;
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_federated_person_ban() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    assert!(!person.banned);
+    let community = parse_lemmy_community(&context).await;
+    let page: Page = file_to_json_object("assets/lemmy/objects/page.json").unwrap();
+    let post = ApubPost::from_json(page, &context).await.unwrap();
+
+    // the home instance marks the user as banned
+    let mut json: Person = file_to_json_object("assets/lemmy/objects/person.json").unwrap();
+    json.featured = None;
+    json.banned = Some(true);
+    let url = Url::parse("https://enterprise.lemmy.ml/u/picard").unwrap();
+    ApubPerson::verify(&json, &url, &context).await.unwrap();
+    let person = ApubPerson::from_json(json.clone(), &context).await.unwrap();
+    assert!(person.banned);
+    assert_eq!(context.request_count(), 0);
+
+    // by default, content is only removed if the home instance asks for it
+    let read_post = Post::read(&mut context.pool(), post.id).await.unwrap();
+    assert!(!read_post.removed);
+
+    apply_remote_ban_policy(person.id, false, RemoteBanContentPolicy::Keep, &context)
+      .await
+      .unwrap();
+    let read_post = Post::read(&mut context.pool(), post.id).await.unwrap();
+    assert!(!read_post.removed);
+
+    apply_remote_ban_policy(person.id, false, RemoteBanContentPolicy::Remove, &context)
+      .await
+      .unwrap();
+    let read_post = Post::read(&mut context.pool(), post.id).await.unwrap();
+    assert!(read_post.removed);
+
+    // a missing or false flag doesn't lift the ban, that requires an explicit undo
+    json.banned = Some(false);
+    let person = ApubPerson::from_json(json, &context).await.unwrap();
+    assert!(person.banned);
+
+    Post::delete(&mut context.pool(), post.id).await.unwrap();
+    Community::delete(&mut context.pool(), community.id)
+      .await
+      .unwrap();
     cleanup((person, site), &context).await;
   }
 
@@ -0,0 +1,238 @@
+use crate::{
+  local_site_data_cached,
+  objects::post::ApubPost,
+  protocol::objects::question::{Question, QuestionOption, QuestionOptionReplies, QuestionType},
+};
+use activitypub_federation::{config::Data, traits::Object};
+use chrono::{DateTime, Utc};
+use lemmy_api_common::{context::LemmyContext, utils::local_site_opt_to_slur_regex};
+use lemmy_db_schema::source::poll::{Poll, PollForm, PollOption};
+use lemmy_utils::{
+  error::{LemmyError, LemmyErrorType},
+  utils::slurs::check_slurs,
+};
+use url::Url;
+
+/// A post together with its poll.
+#[derive(Clone, Debug)]
+pub struct ApubPoll {
+  pub(crate) post: ApubPost,
+  pub(crate) poll: Poll,
+  pub(crate) options: Vec<PollOption>,
+}
+
+#[async_trait::async_trait]
+impl Object for ApubPoll {
+  type DataType = LemmyContext;
+  type Kind = Question;
+  type Error = LemmyError;
+
+  fn last_refreshed_at(&self) -> Option<DateTime<Utc>> {
+    None
+  }
+
+  #[tracing::instrument(skip_all)]
+  async fn read_from_id(
+    object_id: Url,
+    context: &Data<Self::DataType>,
+  ) -> Result<Option<Self>, LemmyError> {
+    let Some(post) = ApubPost::read_from_id(object_id, context).await? else {
+      return Ok(None);
+    };
+    let Some(poll) = Poll::read_for_post(&mut context.pool(), post.id).await? else {
+      return Ok(None);
+    };
+    let options = Poll::read_options(&mut context.pool(), poll.id).await?;
+    Ok(Some(ApubPoll {
+      post,
+      poll,
+      options,
+    }))
+  }
+
+  #[tracing::instrument(skip_all)]
+  async fn delete(self, context: &Data<Self::DataType>) -> Result<(), LemmyError> {
+    self.post.delete(context).await
+  }
+
+  #[tracing::instrument(skip_all)]
+  async fn into_json(self, context: &Data<Self::DataType>) -> Result<Question, LemmyError> {
+    let page = self.post.into_json(context).await?;
+    let options = self
+      .options
+      .into_iter()
+      .map(|o| QuestionOption {
+        kind: Default::default(),
+        name: o.name,
+        replies: Some(QuestionOptionReplies {
+          kind: Default::default(),
+          total_items: o.vote_count,
+        }),
+      })
+      .collect();
+    let (one_of, any_of) = if self.poll.multiple_choice {
+      (None, Some(options))
+    } else {
+      (Some(options), None)
+    };
+    Ok(Question {
+      kind: QuestionType::Question,
+      id: page.id.inner().clone().into(),
+      attributed_to: page.creator()?,
+      to: page.to,
+      cc: page.cc,
+      in_reply_to: None,
+      name: page.name,
+      content: page.content,
+      media_type: page.media_type,
+      source: page.source,
+      one_of,
+      any_of,
+      end_time: self.poll.end_time,
+      sensitive: page.sensitive,
+      published: page.published,
+      updated: page.updated,
+      audience: page.audience,
+    })
+  }
+
+  #[tracing::instrument(skip_all)]
+  async fn verify(
+    question: &Question,
+    expected_domain: &Url,
+    context: &Data<Self::DataType>,
+  ) -> Result<(), LemmyError> {
+    ApubPost::verify(&question.page(), expected_domain, context).await?;
+
+    let (_, options) = question.options();
+    if options.is_empty() {
+      Err(LemmyErrorType::InvalidPoll)?
+    }
+    let local_site_data = local_site_data_cached(context).await?;
+    let slur_regex = &local_site_opt_to_slur_regex(&local_site_data.local_site);
+    for option in options {
+      check_slurs(&option.name, slur_regex)?;
+    }
+    Ok(())
+  }
+
+  #[tracing::instrument(skip_all)]
+  async fn from_json(
+    question: Question,
+    context: &Data<Self::DataType>,
+  ) -> Result<Self, LemmyError> {
+    let post = ApubPost::from_json(question.page(), context).await?;
+
+    let (multiple_choice, options) = question.options();
+    let options = options
+      .iter()
+      .map(|o| {
+        let votes = o.replies.as_ref().map(|r| r.total_items).unwrap_or(0);
+        (o.name.clone(), votes)
+      })
+      .collect();
+    let form = PollForm {
+      post_id: post.id,
+      multiple_choice,
+      end_time: question.end_time,
+      updated: question.updated,
+    };
+    let (poll, options) = Poll::upsert(&mut context.pool(), &form, options).await?;
+    Ok(ApubPoll {
+      post,
+      poll,
+      options,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::{
+    objects::{
+      community::tests::parse_lemmy_community,
+      person::tests::parse_lemmy_person,
+      tests::init_context,
+    },
+    protocol::tests::file_to_json_object,
+  };
+  use lemmy_db_schema::{
+    source::{community::Community, person::Person, post::Post, site::Site},
+    traits::Crud,
+  };
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_parse_mastodon_question() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+
+    let mut json: Question = file_to_json_object("assets/mastodon/objects/question.json").unwrap();
+    // use the local test user as author, so that nothing is fetched over the network
+    let url = Url::parse("https://enterprise.lemmy.ml/post/55144").unwrap();
+    json.id = url.clone().into();
+    json.attributed_to = person.actor_id.clone().into();
+    ApubPoll::verify(&json, &url, &context).await.unwrap();
+    let poll = ApubPoll::from_json(json, &context).await.unwrap();
+
+    // the community mention is removed from the title
+    assert_eq!(poll.post.name, "Best captain?");
+    assert_eq!(poll.post.community_id, community.id);
+    assert!(!poll.poll.multiple_choice);
+    assert_eq!(
+      poll.poll.end_time,
+      Some("2021-11-06T12:01:12Z".parse().unwrap())
+    );
+    let options: Vec<_> = poll
+      .options
+      .iter()
+      .map(|o| (o.name.as_str(), o.vote_count))
+      .collect();
+    assert_eq!(vec![("Picard", 5), ("Kirk", 2)], options);
+
+    // convert back, and read again from the database
+    let question = poll.clone().into_json(&context).await.unwrap();
+    assert_eq!(question.id.inner(), &url);
+    assert!(question.any_of.is_none());
+    let names: Vec<_> = question
+      .options()
+      .1
+      .iter()
+      .map(|o| (o.name.as_str(), o.replies.as_ref().unwrap().total_items))
+      .collect();
+    assert_eq!(vec![("Picard", 5), ("Kirk", 2)], names);
+    assert_eq!(question.end_time, poll.poll.end_time);
+    let read = ApubPoll::read_from_id(url, &context)
+      .await
+      .unwrap()
+      .unwrap();
+    assert_eq!(read.poll, poll.poll);
+    assert_eq!(read.options, poll.options);
+
+    // updated options replace the previous ones
+    let mut question = question;
+    question.any_of = question.one_of.take();
+    question.any_of.as_mut().unwrap().truncate(1);
+    let updated = ApubPoll::from_json(question, &context).await.unwrap();
+    assert_eq!(updated.poll.id, poll.poll.id);
+    assert!(updated.poll.multiple_choice);
+    assert_eq!(1, updated.options.len());
+
+    Post::delete(&mut context.pool(), poll.post.id)
+      .await
+      .unwrap();
+    Person::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Community::delete(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+}
@@ -1,6 +1,7 @@
 use crate::{
-  activities::{verify_is_public, verify_person_in_community},
-  check_apub_id_valid_with_strictness,
+  activities::{verify_community_member, verify_is_public, verify_person_in_community},
+  check_apub_id_valid_in_community,
+  is_apub_id_valid,
   local_site_data_cached,
   objects::{read_from_string_or_source_opt, verify_is_remote_object},
   protocol::{
@@ -41,9 +42,9 @@ use lemmy_db_schema::{
 use lemmy_utils::{
   error::LemmyError,
   utils::{
-    markdown::markdown_to_html,
+    markdown::{markdown_to_html, sanitize_html},
     slurs::{check_slurs_opt, remove_slurs},
-    validation::check_url_scheme,
+    validation::{check_renderable_content, check_url_scheme},
   },
 };
 use std::ops::Deref;
@@ -51,6 +52,8 @@ use stringreader::StringReader;
 use url::Url;
 
 const MAX_TITLE_LENGTH: usize = 200;
+/// Same as the length of the `quote_url` column
+const MAX_QUOTE_URL_LENGTH: usize = 512;
 
 #[derive(Clone, Debug)]
 pub struct ApubPost(pub(crate) Post);
@@ -110,6 +113,11 @@ impl Object for ApubPost {
     let community_id = self.community_id;
     let community = Community::read(&mut context.pool(), community_id).await?;
     let language = LanguageTag::new_single(self.language_id, &mut context.pool()).await?;
+    let content = self.body.as_ref().map(|b| markdown_to_html(b));
+    let content = match render_quote(&self, context).await? {
+      Some(quote) => Some(format!("{}{quote}", content.unwrap_or_default())),
+      None => content,
+    };
 
     let page = Page {
       kind: PageType::Page,
@@ -118,12 +126,16 @@ impl Object for ApubPost {
       to: vec![community.actor_id.clone().into(), public()],
       cc: vec![],
       name: Some(self.name.clone()),
-      content: self.body.as_ref().map(|b| markdown_to_html(b)),
+      content,
       media_type: Some(MediaTypeMarkdownOrHtml::Html),
       source: self.body.clone().map(Source::new),
       attachment: self.url.clone().map(Attachment::new).into_iter().collect(),
       image: self.thumbnail_url.clone().map(ImageObject::new),
-      comments_enabled: Some(!self.locked),
+      comments_enabled: Some(!self.locked && !self.archived),
+      archived: Some(self.archived),
+      reply_policy: Some(self.reply_policy),
+      default_comment_sort_type: self.default_comment_sort_type,
+      quote_url: self.quote_url.clone().map(Into::into),
       sensitive: Some(self.nsfw),
       language,
       published: Some(self.published),
@@ -148,10 +160,11 @@ impl Object for ApubPost {
     };
 
     let community = page.community(context).await?;
-    check_apub_id_valid_with_strictness(page.id.inner(), community.local, context).await?;
+    check_apub_id_valid_in_community(page.id.inner(), &community, context).await?;
     verify_person_in_community(&page.creator()?, &community, context).await?;
+    verify_community_member(&page.creator()?, &community, context).await?;
 
-    let local_site_data = local_site_data_cached(&mut context.pool()).await?;
+    let local_site_data = local_site_data_cached(context).await?;
     let slur_regex = &local_site_opt_to_slur_regex(&local_site_data.local_site);
     check_slurs_opt(&page.name, slur_regex)?;
 
@@ -196,6 +209,12 @@ impl Object for ApubPost {
     let old_post = page.id.dereference_local(context).await;
 
     let form = if !page.is_mod_action(context).await? {
+      let image_attachment = page
+        .attachment
+        .iter()
+        .find(|a| a.is_image())
+        .cloned()
+        .map(Attachment::url);
       let first_attachment = page.attachment.into_iter().map(Attachment::url).next();
       let url = if first_attachment.is_some() {
         first_attachment
@@ -206,10 +225,12 @@ impl Object for ApubPost {
         None
       };
       check_url_scheme(&url)?;
+      check_renderable_content(&name, context.settings())?;
 
       let local_site = LocalSite::read(&mut context.pool()).await.ok();
       let allow_sensitive = local_site_opt_to_sensitive(&local_site);
-      let page_is_sensitive = page.sensitive.unwrap_or(false);
+      // posts which don't say whether they are sensitive get the community default
+      let page_is_sensitive = page.sensitive.unwrap_or(community.default_post_nsfw);
       let include_image = allow_sensitive || !page_is_sensitive;
 
       // Only fetch metadata if the post has a url and was not seen previously. We dont want to
@@ -227,8 +248,11 @@ impl Object for ApubPost {
         }
         _ => (None, None),
       };
-      // If no image was included with metadata, use post image instead when available.
-      let thumbnail_url = thumbnail.or_else(|| page.image.map(|i| i.url.into()));
+      // If no image was included with metadata, use post image or the first image attachment
+      // instead when available.
+      let thumbnail_url = thumbnail
+        .or_else(|| page.image.map(|i| i.url.into()))
+        .or_else(|| image_attachment.map(Into::into));
 
       let (embed_title, embed_description, embed_video_url) = metadata_res
         .map(|u| (u.title, u.description, u.embed_video_url))
@@ -239,6 +263,20 @@ impl Object for ApubPost {
         .map(|s| remove_slurs(&s, slur_regex));
       let language_id =
         LanguageTag::to_language_id_single(page.language, &mut context.pool()).await?;
+      // an invalid quote is ignored instead of rejecting the whole post
+      let mut quote_url = page.quote_url.filter(is_valid_quote_url);
+      if let Some(url) = &quote_url {
+        if is_apub_id_valid(url, context).await.is_err() {
+          quote_url = None;
+        }
+      }
+      // only link the quoted post if we know it already, there is no need to fetch it
+      let quoted_post_id = match &quote_url {
+        Some(quote_url) => Post::read_from_apub_id(&mut context.pool(), quote_url.clone())
+          .await?
+          .map(|p| p.id),
+        None => None,
+      };
 
       PostInsertForm {
         name,
@@ -247,11 +285,16 @@ impl Object for ApubPost {
         creator_id: creator.id,
         community_id: community.id,
         removed: None,
-        locked: page.comments_enabled.map(|e| !e),
+        locked: page.locked(),
+        archived: page.archived,
+        reply_policy: page.reply_policy,
+        default_comment_sort_type: page.default_comment_sort_type,
+        quote_url: quote_url.map(Into::into),
+        quoted_post_id,
         published: page.published.map(Into::into),
         updated: page.updated.map(Into::into),
         deleted: Some(false),
-        nsfw: page.sensitive,
+        nsfw: Some(page_is_sensitive),
         embed_title,
         embed_description,
         embed_video_url,
@@ -261,15 +304,17 @@ impl Object for ApubPost {
         language_id,
         featured_community: None,
         featured_local: None,
+        featured_profile: None,
       }
     } else {
-      // if is mod action, only update locked/stickied fields, nothing else
+      // if is mod action, only update locked/archived/stickied fields, nothing else
       PostInsertForm::builder()
         .name(name)
         .creator_id(creator.id)
         .community_id(community.id)
         .ap_id(Some(page.id.clone().into()))
-        .locked(page.comments_enabled.map(|e| !e))
+        .locked(page.locked())
+        .archived(page.archived)
         .updated(page.updated.map(Into::into))
         .build()
     };
@@ -277,7 +322,7 @@ impl Object for ApubPost {
     let post = Post::create(&mut context.pool(), &form).await?;
 
     // write mod log entry for lock
-    if Page::is_locked_changed(&old_post, &page.comments_enabled) {
+    if Page::is_locked_changed(&old_post, &page.locked()) {
       let form = ModLockPostForm {
         mod_person_id: creator.id,
         post_id: post.id,
@@ -290,6 +335,38 @@ impl Object for ApubPost {
   }
 }
 
+/// Renders the post quoted by this one as html, to be shown below the content. A quoted post which
+/// is known locally is embedded as a blockquote with its title, otherwise a plain link is used.
+async fn render_quote(
+  post: &Post,
+  context: &Data<LemmyContext>,
+) -> Result<Option<String>, LemmyError> {
+  let Some(quote_url) = post
+    .quote_url
+    .as_ref()
+    .filter(|u| is_valid_quote_url(u.inner()))
+  else {
+    return Ok(None);
+  };
+  let quoted = match post.quoted_post_id {
+    Some(id) => Post::read(&mut context.pool(), id).await.ok(),
+    None => None,
+  };
+  Ok(Some(match quoted {
+    Some(quoted) => format!(
+      "<blockquote><p><a href=\"{}\">{}</a></p></blockquote>\n",
+      quoted.ap_id,
+      sanitize_html(&quoted.name)
+    ),
+    None => format!("<p><a href=\"{quote_url}\">{quote_url}</a></p>\n"),
+  }))
+}
+
+/// Quotes can only link to http(s) urls which fit into the database.
+fn is_valid_quote_url(url: &Url) -> bool {
+  matches!(url.scheme(), "http" | "https") && url.as_str().len() <= MAX_QUOTE_URL_LENGTH
+}
+
 #[cfg(test)]
 mod tests {
   #![allow(clippy::unwrap_used)]
@@ -297,16 +374,27 @@ mod tests {
 
   use super::*;
   use crate::{
+    activities::deletion::verify_object_not_deleted,
     objects::{
+      comment::ApubComment,
       community::{tests::parse_lemmy_community, ApubCommunity},
       instance::ApubSite,
       person::{tests::parse_lemmy_person, ApubPerson},
       post::ApubPost,
       tests::init_context,
     },
-    protocol::tests::file_to_json_object,
+    protocol::{objects::note::Note, tests::file_to_json_object},
+  };
+  use lemmy_db_schema::{
+    source::{
+      community::{CommunityFollower, CommunityFollowerForm, CommunityUpdateForm},
+      site::Site,
+    },
+    traits::Followable,
+    CommentSortType,
+    CommunityMembershipMode,
   };
-  use lemmy_db_schema::source::site::Site;
+  use lemmy_utils::error::LemmyErrorType;
   use serial_test::serial;
 
   #[tokio::test]
@@ -347,6 +435,330 @@ mod tests {
     cleanup(&context, person, site, community, post).await;
   }
 
+  #[tokio::test]
+  #[serial]
+  async fn test_parse_article_with_image_attachment() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+
+    let mut json: Page = file_to_json_object("assets/wordpress/objects/article.json").unwrap();
+    assert_eq!(PageType::Article, json.kind);
+    // the attachment with unknown type is skipped
+    assert_eq!(1, json.attachment.len());
+    json.attributed_to = AttributedTo::Lemmy(person.actor_id.clone().into());
+    let post = ApubPost::from_json(json, &context).await.unwrap();
+
+    let image =
+      Url::parse("https://blog.example.com/wp-content/uploads/2023/11/warp-core.jpg").unwrap();
+    assert_eq!("Warp core maintenance", post.name);
+    assert_eq!(Some(image.clone().into()), post.url);
+    assert_eq!(Some(image.into()), post.thumbnail_url);
+    assert_eq!(community.id, post.community_id);
+
+    cleanup(&context, person, site, community, post).await;
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_reject_post_from_non_member() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+
+    // pretend the community is local and requires an application
+    let form = CommunityUpdateForm {
+      local: Some(true),
+      membership_mode: Some(CommunityMembershipMode::RequireApplication),
+      ..Default::default()
+    };
+    Community::update(&mut context.pool(), community.id, &form)
+      .await
+      .unwrap();
+
+    let json = file_to_json_object("assets/lemmy/objects/page.json").unwrap();
+    let url = Url::parse("https://enterprise.lemmy.ml/post/55143").unwrap();
+    let res = ApubPost::verify(&json, &url, &context).await;
+    assert_eq!(
+      res.unwrap_err().error_type,
+      LemmyErrorType::CommunityMembershipRequired
+    );
+
+    // once the follow is accepted, posting is allowed
+    let form = CommunityFollowerForm {
+      community_id: community.id,
+      person_id: person.id,
+      pending: false,
+    };
+    CommunityFollower::follow(&mut context.pool(), &form)
+      .await
+      .unwrap();
+    ApubPost::verify(&json, &url, &context).await.unwrap();
+    assert_eq!(context.request_count(), 0);
+
+    CommunityFollower::unfollow(&mut context.pool(), &form)
+      .await
+      .unwrap();
+    Person::delete(&mut context.pool(), person.id)
+      .await
+      .unwrap();
+    Community::delete(&mut context.pool(), community.id)
+      .await
+      .unwrap();
+    Site::delete(&mut context.pool(), site.id).await.unwrap();
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_reject_update_of_deleted_post() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+
+    let json = file_to_json_object("assets/lemmy/objects/page.json").unwrap();
+    let url = Url::parse("https://enterprise.lemmy.ml/post/55143").unwrap();
+    ApubPost::verify(&json, &url, &context).await.unwrap();
+    let post = ApubPost::from_json(json, &context).await.unwrap();
+
+    // updating a live post is allowed
+    verify_object_not_deleted(&url, &context).await.unwrap();
+
+    let form = PostUpdateForm {
+      deleted: Some(true),
+      ..Default::default()
+    };
+    Post::update(&mut context.pool(), post.id, &form)
+      .await
+      .unwrap();
+    let res = verify_object_not_deleted(&url, &context).await;
+    assert_eq!(res.unwrap_err().error_type, LemmyErrorType::Deleted);
+
+    cleanup(&context, person, site, community, post).await;
+  }
+
+  /// Sets the given flags on the post, federates it out and back in again with the flags reset
+  /// in between, and returns the resulting post along with the error for a new comment on it.
+  async fn round_trip_closed_post(
+    context: &Data<LemmyContext>,
+    post: &ApubPost,
+    form: PostUpdateForm,
+  ) -> (ApubPost, LemmyErrorType) {
+    let closed: ApubPost = Post::update(&mut context.pool(), post.id, &form)
+      .await
+      .unwrap()
+      .into();
+    let page = closed.into_json(context).await.unwrap();
+    assert_eq!(Some(false), page.comments_enabled);
+
+    let form = PostUpdateForm {
+      locked: Some(false),
+      archived: Some(false),
+      ..Default::default()
+    };
+    Post::update(&mut context.pool(), post.id, &form)
+      .await
+      .unwrap();
+    let post = ApubPost::from_json(page, context).await.unwrap();
+
+    let note: Note = file_to_json_object("assets/lemmy/objects/note.json").unwrap();
+    let url = Url::parse("https://enterprise.lemmy.ml/comment/38741").unwrap();
+    let err = ApubComment::verify(&note, &url, context).await.unwrap_err();
+    (post, err.error_type)
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_round_trip_locked_post() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+    let json = file_to_json_object("assets/lemmy/objects/page.json").unwrap();
+    let post = ApubPost::from_json(json, &context).await.unwrap();
+
+    let form = PostUpdateForm {
+      locked: Some(true),
+      ..Default::default()
+    };
+    let (post, err) = round_trip_closed_post(&context, &post, form).await;
+    assert!(post.locked);
+    assert!(!post.archived);
+    assert_eq!(LemmyErrorType::PostIsLocked, err);
+    assert_eq!(context.request_count(), 0);
+
+    cleanup(&context, person, site, community, post).await;
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_round_trip_archived_post() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+    let json = file_to_json_object("assets/lemmy/objects/page.json").unwrap();
+    let post = ApubPost::from_json(json, &context).await.unwrap();
+
+    let form = PostUpdateForm {
+      archived: Some(true),
+      ..Default::default()
+    };
+    let (post, err) = round_trip_closed_post(&context, &post, form).await;
+    assert!(!post.locked);
+    assert!(post.archived);
+    assert_eq!(LemmyErrorType::PostIsArchived, err);
+    assert_eq!(context.request_count(), 0);
+
+    cleanup(&context, person, site, community, post).await;
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_community_default_post_nsfw() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+    assert!(!community.default_post_nsfw);
+
+    // the community default is federated
+    let mut group = community.clone().into_json(&context).await.unwrap();
+    assert_eq!(Some(false), group.default_post_nsfw);
+    group.default_post_nsfw = Some(true);
+    group.attributed_to = None;
+    group.featured = None;
+    let community = ApubCommunity::from_json(group, &context.reset_request_count())
+      .await
+      .unwrap();
+    assert!(community.default_post_nsfw);
+
+    // a post which doesn't say if it is sensitive gets the community default
+    let mut json: Page = file_to_json_object("assets/lemmy/objects/page.json").unwrap();
+    json.sensitive = None;
+    let post = ApubPost::from_json(json.clone(), &context).await.unwrap();
+    assert!(post.nsfw);
+    let page = post.into_json(&context).await.unwrap();
+    assert_eq!(Some(true), page.sensitive);
+
+    // an explicit flag on the post takes precedence
+    json.sensitive = Some(false);
+    let post = ApubPost::from_json(json, &context).await.unwrap();
+    assert!(!post.nsfw);
+    assert_eq!(context.request_count(), 0);
+
+    cleanup(&context, person, site, community, post).await;
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_round_trip_default_comment_sort_type() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+
+    // unknown values are ignored, so that the local default is used
+    let mut json: serde_json::Value =
+      file_to_json_object("assets/lemmy/objects/page.json").unwrap();
+    json["defaultCommentSortType"] = "SomeFutureSort".into();
+    let json: Page = serde_json::from_value(json).unwrap();
+    assert!(json.default_comment_sort_type.is_none());
+    let post = ApubPost::from_json(json.clone(), &context).await.unwrap();
+    assert_eq!(None, post.default_comment_sort_type);
+
+    let mut json = json;
+    json.default_comment_sort_type = Some(CommentSortType::New);
+    let post = ApubPost::from_json(json, &context).await.unwrap();
+    assert_eq!(Some(CommentSortType::New), post.default_comment_sort_type);
+    let page = post.clone().into_json(&context).await.unwrap();
+    assert_eq!(Some(CommentSortType::New), page.default_comment_sort_type);
+    assert_eq!(context.request_count(), 0);
+
+    cleanup(&context, person, site, community, post).await;
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_round_trip_quote_of_known_post() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+    let mut json: Page = file_to_json_object("assets/lemmy/objects/page.json").unwrap();
+    let quoted = ApubPost::from_json(json.clone(), &context).await.unwrap();
+
+    json.id = Url::parse("https://enterprise.lemmy.ml/post/55144")
+      .unwrap()
+      .into();
+    json.quote_url = Some(quoted.ap_id.clone().into());
+    let post = ApubPost::from_json(json, &context).await.unwrap();
+    assert_eq!(Some(quoted.id), post.quoted_post_id);
+
+    let post_id = post.id;
+    let page = post.into_json(&context).await.unwrap();
+    assert_eq!(Some(quoted.ap_id.clone().into()), page.quote_url);
+    let content = page.content.unwrap();
+    assert!(content.ends_with(&format!(
+      "<blockquote><p><a href=\"{}\">Post title</a></p></blockquote>\n",
+      quoted.ap_id
+    )));
+    assert_eq!(context.request_count(), 0);
+
+    Post::delete(&mut context.pool(), post_id).await.unwrap();
+    cleanup(&context, person, site, community, quoted).await;
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_round_trip_quote_of_unknown_post() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+
+    // use the Misskey name for the quote
+    let mut json: serde_json::Value =
+      file_to_json_object("assets/lemmy/objects/page.json").unwrap();
+    json["_misskey_quote"] = "https://misskey.example/notes/9k2xk".into();
+    let json: Page = serde_json::from_value(json).unwrap();
+    let post = ApubPost::from_json(json, &context).await.unwrap();
+    assert_eq!(None, post.quoted_post_id);
+
+    let page = post.clone().into_json(&context).await.unwrap();
+    let quote_url = Url::parse("https://misskey.example/notes/9k2xk").unwrap();
+    assert_eq!(Some(quote_url), page.quote_url);
+    let content = page.content.unwrap();
+    assert!(content.ends_with(
+      "<p><a href=\"https://misskey.example/notes/9k2xk\">https://misskey.example/notes/9k2xk</a></p>\n"
+    ));
+    assert_eq!(context.request_count(), 0);
+
+    cleanup(&context, person, site, community, post).await;
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_invalid_quote_is_ignored() {
+    let context = init_context().await;
+    let (person, site) = parse_lemmy_person(&context).await;
+    let community = parse_lemmy_community(&context).await;
+    let mut json: Page = file_to_json_object("assets/lemmy/objects/page.json").unwrap();
+
+    let too_long = format!("https://misskey.example/notes/{}", "a".repeat(500));
+    for quote_url in ["javascript:alert(1)", &too_long] {
+      json.quote_url = Some(Url::parse(quote_url).unwrap());
+      let post = ApubPost::from_json(json.clone(), &context).await.unwrap();
+      assert_eq!(None, post.quote_url);
+
+      let page = post.into_json(&context).await.unwrap();
+      assert_eq!(None, page.quote_url);
+      assert!(!page.content.unwrap().contains(quote_url));
+    }
+    assert!(is_valid_quote_url(
+      &Url::parse("https://misskey.example/notes/9k2xk").unwrap()
+    ));
+
+    let post = Post::read_from_apub_id(&mut context.pool(), json.id.inner().clone())
+      .await
+      .unwrap()
+      .unwrap();
+    cleanup(&context, person, site, community, post.into()).await;
+  }
+
   async fn cleanup(
     context: &Data<LemmyContext>,
     person: ApubPerson,
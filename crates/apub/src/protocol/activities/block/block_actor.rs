@@ -0,0 +1,35 @@
+use crate::{
+  fetcher::site_or_community_or_user::SiteOrCommunityOrUser,
+  objects::person::ApubPerson,
+};
+use activitypub_federation::{
+  fetch::object_id::ObjectId,
+  kinds::activity::BlockType,
+  protocol::helpers::deserialize_skip_error,
+};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// A user blocking an instance, or another user or a community. Unlike
+/// [BlockUser](super::block_user::BlockUser), which is a ban by a moderator, this is only sent to
+/// the blocked actor. Lemmy only sends it for instance blocks, the other targets are blocks
+/// federated by other software.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockActor {
+  pub(crate) actor: ObjectId<ApubPerson>,
+  /// Optional, for compatibility with platforms that always expect recipient field
+  #[serde(deserialize_with = "deserialize_skip_error", default)]
+  pub(crate) to: Option<[ObjectId<SiteOrCommunityOrUser>; 1]>,
+  pub(crate) object: ObjectId<SiteOrCommunityOrUser>,
+  #[serde(rename = "type")]
+  pub(crate) kind: BlockType,
+  pub(crate) id: Url,
+  /// Bans always have a target, so that they can't be mistaken for a [BlockActor] in the inbox.
+  #[serde(default, skip_serializing)]
+  pub(crate) target: Option<NoTarget>,
+}
+
+/// Can't be deserialized from any value.
+#[derive(Clone, Debug, Deserialize)]
+pub enum NoTarget {}
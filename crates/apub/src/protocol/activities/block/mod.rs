@@ -1,4 +1,6 @@
+pub mod block_actor;
 pub mod block_user;
+pub mod undo_block_actor;
 pub mod undo_block_user;
 
 #[cfg(test)]
@@ -7,8 +9,13 @@ mod tests {
   #![allow(clippy::indexing_slicing)]
 
   use crate::protocol::{
-    activities::block::{block_user::BlockUser, undo_block_user::UndoBlockUser},
-    tests::test_parse_lemmy_item,
+    activities::block::{
+      block_actor::BlockActor,
+      block_user::BlockUser,
+      undo_block_actor::UndoBlockActor,
+      undo_block_user::UndoBlockUser,
+    },
+    tests::{file_to_json_object, test_parse_lemmy_item},
   };
 
   #[test]
@@ -16,5 +23,19 @@ mod tests {
     test_parse_lemmy_item::<BlockUser>("assets/lemmy/activities/block/block_user.json").unwrap();
     test_parse_lemmy_item::<UndoBlockUser>("assets/lemmy/activities/block/undo_block_user.json")
       .unwrap();
+    test_parse_lemmy_item::<BlockActor>("assets/lemmy/activities/block/block_actor.json").unwrap();
+    test_parse_lemmy_item::<UndoBlockActor>("assets/lemmy/activities/block/undo_block_actor.json")
+      .unwrap();
+  }
+
+  #[test]
+  fn test_ban_is_not_block_actor() {
+    assert!(
+      file_to_json_object::<BlockActor>("assets/lemmy/activities/block/block_user.json").is_err()
+    );
+    assert!(file_to_json_object::<UndoBlockActor>(
+      "assets/lemmy/activities/block/undo_block_user.json"
+    )
+    .is_err());
   }
 }
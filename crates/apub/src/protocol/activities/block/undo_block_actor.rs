@@ -0,0 +1,25 @@
+use crate::{
+  fetcher::site_or_community_or_user::SiteOrCommunityOrUser,
+  objects::person::ApubPerson,
+  protocol::activities::block::block_actor::BlockActor,
+};
+use activitypub_federation::{
+  fetch::object_id::ObjectId,
+  kinds::activity::UndoType,
+  protocol::helpers::deserialize_skip_error,
+};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoBlockActor {
+  pub(crate) actor: ObjectId<ApubPerson>,
+  /// Optional, for compatibility with platforms that always expect recipient field
+  #[serde(deserialize_with = "deserialize_skip_error", default)]
+  pub(crate) to: Option<[ObjectId<SiteOrCommunityOrUser>; 1]>,
+  pub(crate) object: BlockActor,
+  #[serde(rename = "type")]
+  pub(crate) kind: UndoType,
+  pub(crate) id: Url,
+}
@@ -1,6 +1,7 @@
 pub mod chat_message;
 pub mod note;
 pub mod page;
+pub mod poll_vote;
 
 #[cfg(test)]
 mod tests {
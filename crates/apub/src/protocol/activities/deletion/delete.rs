@@ -40,6 +40,9 @@ pub struct Delete {
   /// If summary is present, this is a mod action (Remove in Lemmy terms). Otherwise, its a user
   /// deleting their own content.
   pub(crate) summary: Option<String>,
+  /// Optional note from a user deleting their own content. Unlike summary, this doesnt make it a
+  /// mod action.
+  pub(crate) delete_reason: Option<String>,
 }
 
 #[async_trait::async_trait]
@@ -0,0 +1,21 @@
+use crate::{
+  objects::{community::ApubCommunity, instance::ApubSite, person::ApubPerson},
+  protocol::activities::following::batch_follow::BatchFollow,
+};
+use activitypub_federation::{fetch::object_id::ObjectId, kinds::activity::AcceptType};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// Accepts the follows of a [BatchFollow] at once. Only the users in `accepted` are subscribed,
+/// follows which wait for a mod to approve them are accepted individually later.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AcceptBatchFollow {
+  pub(crate) actor: ObjectId<ApubCommunity>,
+  pub(crate) to: [ObjectId<ApubSite>; 1],
+  pub(crate) object: BatchFollow,
+  pub(crate) accepted: Vec<ObjectId<ApubPerson>>,
+  #[serde(rename = "type")]
+  pub(crate) kind: AcceptType,
+  pub(crate) id: Url,
+}
@@ -0,0 +1,30 @@
+use crate::objects::{community::ApubCommunity, instance::ApubSite, person::ApubPerson};
+use activitypub_federation::fetch::object_id::ObjectId;
+use serde::{Deserialize, Serialize};
+use strum_macros::Display;
+use url::Url;
+
+#[derive(Clone, Debug, Deserialize, Serialize, Display)]
+pub enum BatchFollowType {
+  BatchFollow,
+}
+
+/// Follows and unfollows a community for many users of the sending instance at once, for example
+/// during a migration. The actor is the instance actor, all entries need to be users of that
+/// instance, and the whole batch is applied atomically by the receiving community. The follows
+/// which don't need approval by a mod are confirmed with a single
+/// [AcceptBatchFollow](crate::protocol::activities::following::accept_batch_follow::AcceptBatchFollow).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchFollow {
+  pub(crate) actor: ObjectId<ApubSite>,
+  pub(crate) to: [ObjectId<ApubCommunity>; 1],
+  pub(crate) object: ObjectId<ApubCommunity>,
+  #[serde(default)]
+  pub(crate) follow: Vec<ObjectId<ApubPerson>>,
+  #[serde(default)]
+  pub(crate) unfollow: Vec<ObjectId<ApubPerson>>,
+  #[serde(rename = "type")]
+  pub(crate) kind: BatchFollowType,
+  pub(crate) id: Url,
+}
@@ -1,4 +1,6 @@
 pub(crate) mod accept;
+pub(crate) mod accept_batch_follow;
+pub mod batch_follow;
 pub mod follow;
 pub mod undo_follow;
 
@@ -8,7 +10,13 @@ mod tests {
   #![allow(clippy::indexing_slicing)]
 
   use crate::protocol::{
-    activities::following::{accept::AcceptFollow, follow::Follow, undo_follow::UndoFollow},
+    activities::following::{
+      accept::AcceptFollow,
+      accept_batch_follow::AcceptBatchFollow,
+      batch_follow::BatchFollow,
+      follow::Follow,
+      undo_follow::UndoFollow,
+    },
     tests::test_parse_lemmy_item,
   };
 
@@ -18,5 +26,11 @@ mod tests {
     test_parse_lemmy_item::<AcceptFollow>("assets/lemmy/activities/following/accept.json").unwrap();
     test_parse_lemmy_item::<UndoFollow>("assets/lemmy/activities/following/undo_follow.json")
       .unwrap();
+    test_parse_lemmy_item::<BatchFollow>("assets/lemmy/activities/following/batch_follow.json")
+      .unwrap();
+    test_parse_lemmy_item::<AcceptBatchFollow>(
+      "assets/lemmy/activities/following/accept_batch_follow.json",
+    )
+    .unwrap();
   }
 }
@@ -6,6 +6,7 @@ pub mod community;
 pub mod create_or_update;
 pub mod deletion;
 pub mod following;
+pub mod reaction;
 pub mod voting;
 
 #[derive(Clone, Debug, Display, Deserialize, Serialize, PartialEq, Eq)]
@@ -22,7 +23,11 @@ mod tests {
   use crate::protocol::{
     activities::{
       community::announce::AnnounceActivity,
-      create_or_update::{note::CreateOrUpdateNote, page::CreateOrUpdatePage},
+      create_or_update::{
+        note::CreateOrUpdateNote,
+        page::CreateOrUpdatePage,
+        poll_vote::CreatePollVote,
+      },
       deletion::delete::Delete,
       following::{follow::Follow, undo_follow::UndoFollow},
       voting::{undo_vote::UndoVote, vote::Vote},
@@ -50,6 +55,7 @@ mod tests {
     test_json::<UndoFollow>("assets/mastodon/activities/undo_follow.json").unwrap();
     test_json::<Vote>("assets/mastodon/activities/like_page.json").unwrap();
     test_json::<UndoVote>("assets/mastodon/activities/undo_like_page.json").unwrap();
+    test_json::<CreatePollVote>("assets/mastodon/activities/create_poll_vote.json").unwrap();
   }
 
   #[test]
@@ -0,0 +1,47 @@
+use crate::{
+  activities::verify_community_matches,
+  fetcher::post_or_comment::PostOrComment,
+  objects::{community::ApubCommunity, person::ApubPerson},
+  protocol::InCommunity,
+};
+use activitypub_federation::{config::Data, fetch::object_id::ObjectId};
+use lemmy_api_common::context::LemmyContext;
+use lemmy_utils::error::LemmyError;
+use serde::{Deserialize, Serialize};
+use strum_macros::Display;
+use url::Url;
+
+#[derive(Clone, Debug, Display, Deserialize, Serialize, PartialEq, Eq)]
+pub enum EmojiReactType {
+  EmojiReact,
+}
+
+/// Emoji reaction to a post or comment, as sent by Pleroma and Misskey. `content` is a unicode
+/// emoji or the shortcode of a custom emoji like `:blobcat:`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmojiReact {
+  pub(crate) actor: ObjectId<ApubPerson>,
+  pub(crate) object: ObjectId<PostOrComment>,
+  pub(crate) content: String,
+  #[serde(rename = "type")]
+  pub(crate) kind: EmojiReactType,
+  pub(crate) id: Url,
+  pub(crate) audience: Option<ObjectId<ApubCommunity>>,
+}
+
+#[async_trait::async_trait]
+impl InCommunity for EmojiReact {
+  async fn community(&self, context: &Data<LemmyContext>) -> Result<ApubCommunity, LemmyError> {
+    let community = self
+      .object
+      .dereference(context)
+      .await?
+      .community(context)
+      .await?;
+    if let Some(audience) = &self.audience {
+      verify_community_matches(audience, community.actor_id.clone())?;
+    }
+    Ok(community)
+  }
+}
@@ -0,0 +1,23 @@
+pub mod emoji_react;
+pub mod undo_emoji_react;
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use crate::protocol::{
+    activities::reaction::{emoji_react::EmojiReact, undo_emoji_react::UndoEmojiReact},
+    tests::test_parse_lemmy_item,
+  };
+
+  #[test]
+  fn test_parse_lemmy_reaction() {
+    test_parse_lemmy_item::<EmojiReact>("assets/lemmy/activities/reaction/emoji_react.json")
+      .unwrap();
+    test_parse_lemmy_item::<UndoEmojiReact>(
+      "assets/lemmy/activities/reaction/undo_emoji_react.json",
+    )
+    .unwrap();
+  }
+}
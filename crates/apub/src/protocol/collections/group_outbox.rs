@@ -1,5 +1,8 @@
 use crate::protocol::activities::community::announce::AnnounceActivity;
-use activitypub_federation::kinds::collection::OrderedCollectionType;
+use activitypub_federation::{
+  kinds::collection::{OrderedCollectionPageType, OrderedCollectionType},
+  protocol::helpers::deserialize_skip_error,
+};
 use serde::{Deserialize, Serialize};
 use url::Url;
 
@@ -9,5 +12,20 @@ pub struct GroupOutbox {
   pub(crate) r#type: OrderedCollectionType,
   pub(crate) id: Url,
   pub(crate) total_items: i32,
+  /// Lemmy includes all items directly, other software may only provide them via `first`.
+  #[serde(default)]
   pub(crate) ordered_items: Vec<AnnounceActivity>,
+  #[serde(deserialize_with = "deserialize_skip_error", default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) first: Option<Url>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupOutboxPage {
+  pub(crate) r#type: OrderedCollectionPageType,
+  pub(crate) id: Url,
+  pub(crate) ordered_items: Vec<AnnounceActivity>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) next: Option<Url>,
 }
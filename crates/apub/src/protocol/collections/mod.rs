@@ -15,7 +15,7 @@ mod tests {
       group_featured::GroupFeatured,
       group_followers::GroupFollowers,
       group_moderators::GroupModerators,
-      group_outbox::GroupOutbox,
+      group_outbox::{GroupOutbox, GroupOutboxPage},
     },
     tests::{test_json, test_parse_lemmy_item},
   };
@@ -27,6 +27,14 @@ mod tests {
     let outbox =
       test_parse_lemmy_item::<GroupOutbox>("assets/lemmy/collections/group_outbox.json").unwrap();
     assert_eq!(outbox.ordered_items.len() as i32, outbox.total_items);
+    let paginated =
+      test_parse_lemmy_item::<GroupOutbox>("assets/lemmy/collections/group_outbox_paginated.json")
+        .unwrap();
+    assert!(paginated.first.is_some());
+    test_parse_lemmy_item::<GroupOutboxPage>("assets/lemmy/collections/group_outbox_page_1.json")
+      .unwrap();
+    test_parse_lemmy_item::<GroupOutboxPage>("assets/lemmy/collections/group_outbox_page_2.json")
+      .unwrap();
     test_parse_lemmy_item::<GroupFeatured>("assets/lemmy/collections/group_featured_posts.json")
       .unwrap();
     test_parse_lemmy_item::<GroupModerators>("assets/lemmy/collections/group_moderators.json")
@@ -93,9 +93,10 @@ pub(crate) mod tests {
   #![allow(clippy::indexing_slicing)]
 
   use activitypub_federation::protocol::context::WithContext;
-  use assert_json_diff::assert_json_include;
+  use assert_json_diff::{assert_json_eq, assert_json_include};
   use lemmy_utils::error::LemmyError;
   use serde::{de::DeserializeOwned, Serialize};
+  use serde_json::{json, Value};
   use std::{collections::HashMap, fs::File, io::BufReader};
 
   pub(crate) fn file_to_json_object<T: DeserializeOwned>(path: &str) -> Result<T, LemmyError> {
@@ -122,4 +123,84 @@ pub(crate) mod tests {
     assert_json_include!(actual: &parsed, expected: raw);
     Ok(parsed)
   }
+
+  /// Asserts that two activities or objects are equivalent once serialized, otherwise panics with a
+  /// diff of the json. Key order is ignored, as is the difference between null and absent fields.
+  pub(crate) fn assert_activity_json_eq<A: Serialize, E: Serialize>(actual: &A, expected: &E) {
+    let actual = normalize_json(serde_json::to_value(actual).unwrap());
+    let expected = normalize_json(serde_json::to_value(expected).unwrap());
+    assert_json_eq!(actual, expected);
+  }
+
+  /// Sorts object keys and removes fields which are null, recursively.
+  fn normalize_json(value: Value) -> Value {
+    match value {
+      Value::Object(map) => {
+        let mut fields: Vec<_> = map
+          .into_iter()
+          .filter(|(_, v)| !v.is_null())
+          .map(|(k, v)| (k, normalize_json(v)))
+          .collect();
+        fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Value::Object(fields.into_iter().collect())
+      }
+      Value::Array(values) => Value::Array(values.into_iter().map(normalize_json).collect()),
+      other => other,
+    }
+  }
+
+  #[test]
+  fn test_assert_activity_json_eq_key_order() {
+    let actual = json!({
+      "type": "Like",
+      "object": { "id": "https://example.com/post/1", "type": "Page" },
+      "to": ["https://example.com/c/main"]
+    });
+    let expected = json!({
+      "to": ["https://example.com/c/main"],
+      "object": { "type": "Page", "id": "https://example.com/post/1" },
+      "type": "Like"
+    });
+    assert_activity_json_eq(&actual, &expected);
+
+    let keys: Vec<_> = normalize_json(expected)
+      .as_object()
+      .unwrap()
+      .keys()
+      .cloned()
+      .collect();
+    assert_eq!(vec!["object", "to", "type"], keys);
+  }
+
+  #[test]
+  fn test_assert_activity_json_eq_null_and_absent() {
+    let actual = json!({
+      "type": "Page",
+      "summary": null,
+      "attachment": [{ "type": "Link", "name": null }]
+    });
+    let expected = json!({
+      "type": "Page",
+      "attachment": [{ "type": "Link" }]
+    });
+    assert_activity_json_eq(&actual, &expected);
+    assert_activity_json_eq(&expected, &actual);
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_assert_activity_json_eq_mismatch() {
+    let actual = json!({ "type": "Like", "object": "https://example.com/post/1" });
+    let expected = json!({ "type": "Dislike", "object": "https://example.com/post/1" });
+    assert_activity_json_eq(&actual, &expected);
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_assert_activity_json_eq_array_order() {
+    // unlike keys, the order of array items is significant
+    let actual = json!({ "to": ["https://a.example", "https://b.example"] });
+    let expected = json!({ "to": ["https://b.example", "https://a.example"] });
+    assert_activity_json_eq(&actual, &expected);
+  }
 }
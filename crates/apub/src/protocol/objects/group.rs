@@ -7,7 +7,7 @@ use crate::{
     community_outbox::ApubCommunityOutbox,
   },
   local_site_data_cached,
-  objects::{community::ApubCommunity, read_from_string_or_source_opt},
+  objects::{clamp_published, community::ApubCommunity, read_from_string_or_source_opt},
   protocol::{
     objects::{Endpoints, LanguageTag},
     ImageObject,
@@ -15,6 +15,7 @@ use crate::{
   },
 };
 use activitypub_federation::{
+  config::Data,
   fetch::{collection_id::CollectionId, object_id::ObjectId},
   kinds::actor::GroupType,
   protocol::{
@@ -26,9 +27,13 @@ use activitypub_federation::{
 use chrono::{DateTime, Utc};
 use lemmy_api_common::{context::LemmyContext, utils::local_site_opt_to_slur_regex};
 use lemmy_db_schema::{
-  newtypes::InstanceId,
-  source::community::{CommunityInsertForm, CommunityUpdateForm},
-  utils::naive_now,
+  aggregates::structs::CommunityAggregates,
+  impls::actor_language::UNDETERMINED_ID,
+  newtypes::{InstanceId, LanguageId},
+  source::community::{Community, CommunityInsertForm, CommunityUpdateForm},
+  utils::{naive_now, DbPool},
+  CommunityMembershipMode,
+  SortType,
 };
 use lemmy_utils::{
   error::LemmyError,
@@ -66,6 +71,20 @@ pub struct Group {
   pub(crate) attributed_to: Option<CollectionId<ApubCommunityModerators>>,
   // lemmy extension
   pub(crate) posting_restricted_to_mods: Option<bool>,
+  // lemmy extension
+  #[serde(deserialize_with = "deserialize_skip_error", default)]
+  pub(crate) membership_mode: Option<CommunityMembershipMode>,
+  // lemmy extension, unknown values are ignored
+  #[serde(deserialize_with = "deserialize_skip_error", default)]
+  pub(crate) default_sort_type: Option<SortType>,
+  // lemmy extension
+  pub(crate) default_post_nsfw: Option<bool>,
+  // lemmy extension
+  #[serde(deserialize_with = "deserialize_skip_error", default)]
+  pub(crate) primary_language: Option<LanguageTag>,
+  // lemmy extension, only trusted if it comes from the community's own instance
+  #[serde(deserialize_with = "deserialize_skip_error", default)]
+  pub(crate) active_users: Option<ActiveUsers>,
   pub(crate) outbox: CollectionId<ApubCommunityOutbox>,
   pub(crate) endpoints: Option<Endpoints>,
   pub(crate) featured: Option<CollectionId<ApubCommunityFeatured>>,
@@ -75,16 +94,24 @@ pub struct Group {
   pub(crate) updated: Option<DateTime<Utc>>,
 }
 
+/// Number of users which were active in the community, as counted by its instance.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ActiveUsers {
+  pub(crate) day: i64,
+  pub(crate) week: i64,
+  pub(crate) month: i64,
+}
+
 impl Group {
   pub(crate) async fn verify(
     &self,
     expected_domain: &Url,
-    context: &LemmyContext,
+    context: &Data<LemmyContext>,
   ) -> Result<(), LemmyError> {
     check_apub_id_valid_with_strictness(self.id.inner(), true, context).await?;
     verify_domains_match(expected_domain, self.id.inner())?;
 
-    let local_site_data = local_site_data_cached(&mut context.pool()).await?;
+    let local_site_data = local_site_data_cached(context).await?;
     let slur_regex = &local_site_opt_to_slur_regex(&local_site_data.local_site);
 
     check_slurs(&self.preferred_username, slur_regex)?;
@@ -94,7 +121,40 @@ impl Group {
     Ok(())
   }
 
-  pub(crate) fn into_insert_form(self, instance_id: InstanceId) -> CommunityInsertForm {
+  /// The primary language of the community, undetermined if it is missing or unknown.
+  pub(crate) async fn primary_language_id(
+    &self,
+    pool: &mut DbPool<'_>,
+  ) -> Result<LanguageId, LemmyError> {
+    let language = LanguageTag::to_language_id_single(self.primary_language.clone(), pool).await?;
+    Ok(language.unwrap_or(UNDETERMINED_ID))
+  }
+
+  /// Stores the active user counts of a remote community, replacing the counts based on the
+  /// activity which is known locally. Must only be called with data from the community's instance.
+  pub(crate) async fn store_active_users(
+    &self,
+    community: &Community,
+    pool: &mut DbPool<'_>,
+  ) -> Result<(), LemmyError> {
+    if let (Some(active_users), false) = (&self.active_users, community.local) {
+      CommunityAggregates::update_federated_users_active(
+        pool,
+        community.id,
+        active_users.day,
+        active_users.week,
+        active_users.month,
+      )
+      .await?;
+    }
+    Ok(())
+  }
+
+  pub(crate) fn into_insert_form(
+    self,
+    instance_id: InstanceId,
+    primary_language_id: LanguageId,
+  ) -> CommunityInsertForm {
     let description = read_from_string_or_source_opt(&self.summary, &None, &self.source);
 
     CommunityInsertForm {
@@ -102,7 +162,7 @@ impl Group {
       title: self.name.unwrap_or(self.preferred_username.clone()),
       description,
       removed: None,
-      published: self.published,
+      published: clamp_published(self.published),
       updated: self.updated,
       deleted: Some(false),
       nsfw: Some(self.sensitive.unwrap_or(false)),
@@ -121,10 +181,14 @@ impl Group {
       posting_restricted_to_mods: self.posting_restricted_to_mods,
       instance_id,
       featured_url: self.featured.map(Into::into),
+      membership_mode: self.membership_mode,
+      default_sort_type: self.default_sort_type,
+      default_post_nsfw: self.default_post_nsfw,
+      primary_language_id: Some(primary_language_id),
     }
   }
 
-  pub(crate) fn into_update_form(self) -> CommunityUpdateForm {
+  pub(crate) fn into_update_form(self, primary_language_id: LanguageId) -> CommunityUpdateForm {
     CommunityUpdateForm {
       title: Some(self.name.unwrap_or(self.preferred_username)),
       description: Some(read_from_string_or_source_opt(
@@ -133,7 +197,7 @@ impl Group {
         &self.source,
       )),
       removed: None,
-      published: self.published.map(Into::into),
+      published: clamp_published(self.published),
       updated: Some(self.updated.map(Into::into)),
       deleted: None,
       nsfw: Some(self.sensitive.unwrap_or(false)),
@@ -151,6 +215,10 @@ impl Group {
       moderators_url: self.attributed_to.map(Into::into),
       posting_restricted_to_mods: self.posting_restricted_to_mods,
       featured_url: self.featured.map(Into::into),
+      membership_mode: self.membership_mode,
+      default_sort_type: Some(self.default_sort_type),
+      default_post_nsfw: self.default_post_nsfw,
+      primary_language_id: Some(primary_language_id),
     }
   }
 }
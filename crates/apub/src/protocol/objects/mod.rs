@@ -1,3 +1,4 @@
+use crate::protocol::ImageObject;
 use lemmy_db_schema::{
   impls::actor_language::UNDETERMINED_ID,
   newtypes::LanguageId,
@@ -14,6 +15,7 @@ pub(crate) mod instance;
 pub(crate) mod note;
 pub(crate) mod page;
 pub(crate) mod person;
+pub(crate) mod question;
 pub(crate) mod tombstone;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -22,6 +24,23 @@ pub struct Endpoints {
   pub shared_inbox: Url,
 }
 
+/// Custom emoji which is used in the content as `:shortcode:`, as sent by Mastodon and others.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct EmojiTag {
+  #[serde(rename = "type")]
+  pub(crate) kind: EmojiType,
+  /// the shortcode including colons, eg `:blobcat:`
+  pub(crate) name: String,
+  pub(crate) icon: ImageObject,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum EmojiType {
+  #[default]
+  Emoji,
+}
+
 /// As specified in https://schema.org/Language
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -3,7 +3,11 @@ use crate::{
   fetcher::post_or_comment::PostOrComment,
   mentions::MentionOrValue,
   objects::{comment::ApubComment, community::ApubCommunity, person::ApubPerson, post::ApubPost},
-  protocol::{objects::LanguageTag, InCommunity, Source},
+  protocol::{
+    objects::{EmojiTag, LanguageTag},
+    InCommunity,
+    Source,
+  },
 };
 use activitypub_federation::{
   config::Data,
@@ -19,11 +23,12 @@ use lemmy_api_common::context::LemmyContext;
 use lemmy_db_schema::{
   source::{community::Community, post::Post},
   traits::Crud,
+  ReplyPolicy,
 };
 use lemmy_utils::error::LemmyError;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
-use std::ops::Deref;
+use std::{collections::HashMap, ops::Deref};
 use url::Url;
 
 #[skip_serializing_none]
@@ -49,11 +54,38 @@ pub struct Note {
   pub(crate) tag: Vec<MentionOrValue>,
   // lemmy extension
   pub(crate) distinguished: Option<bool>,
+  // lemmy extension, unknown values are ignored
+  #[serde(deserialize_with = "deserialize_skip_error", default)]
+  pub(crate) reply_policy: Option<ReplyPolicy>,
   pub(crate) language: Option<LanguageTag>,
   pub(crate) audience: Option<ObjectId<ApubCommunity>>,
+  /// Vote counts on the origin instance, used for ranking comments
+  pub(crate) upvotes: Option<i64>,
+  pub(crate) downvotes: Option<i64>,
 }
 
 impl Note {
+  /// Custom emojis which are defined in the tags, mapped from shortcode without colons to the
+  /// image url. Shortcodes which could interfere with markdown are ignored.
+  pub(crate) fn emojis(&self) -> HashMap<String, String> {
+    self
+      .tag
+      .iter()
+      .filter_map(|tag| match tag {
+        MentionOrValue::Value(value) => serde_json::from_value::<EmojiTag>(value.clone()).ok(),
+        MentionOrValue::Mention(_) => None,
+      })
+      .filter(|emoji| ["http", "https"].contains(&emoji.icon.url.scheme()))
+      .map(|emoji| {
+        let shortcode = emoji.name.trim_matches(':').to_string();
+        (shortcode, emoji.icon.url.to_string())
+      })
+      .filter(|(shortcode, _)| {
+        !shortcode.is_empty() && shortcode.chars().all(|c| c.is_alphanumeric() || c == '_')
+      })
+      .collect()
+  }
+
   pub(crate) async fn get_parents(
     &self,
     context: &Data<LemmyContext>,
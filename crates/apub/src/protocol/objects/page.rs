@@ -20,9 +20,10 @@ use activitypub_federation::{
 use chrono::{DateTime, Utc};
 use itertools::Itertools;
 use lemmy_api_common::context::LemmyContext;
-use lemmy_db_schema::newtypes::DbUrl;
+use lemmy_db_schema::{newtypes::DbUrl, CommentSortType, ReplyPolicy};
 use lemmy_utils::error::{LemmyError, LemmyErrorType};
 use serde::{de::Error, Deserialize, Deserializer, Serialize};
+use serde_json::Value;
 use serde_with::skip_serializing_none;
 use url::Url;
 
@@ -58,10 +59,21 @@ pub struct Page {
   pub(crate) source: Option<Source>,
   /// most software uses array type for attachment field, so we do the same. nevertheless, we only
   /// use the first item
-  #[serde(default)]
+  #[serde(deserialize_with = "deserialize_attachments", default)]
   pub(crate) attachment: Vec<Attachment>,
   pub(crate) image: Option<ImageObject>,
   pub(crate) comments_enabled: Option<bool>,
+  /// Set if comments are disabled because the post is old, rather than locked by a mod
+  pub(crate) archived: Option<bool>,
+  // lemmy extension, unknown values are ignored
+  #[serde(deserialize_with = "deserialize_skip_error", default)]
+  pub(crate) reply_policy: Option<ReplyPolicy>,
+  // lemmy extension, unknown values are ignored
+  #[serde(deserialize_with = "deserialize_skip_error", default)]
+  pub(crate) default_comment_sort_type: Option<CommentSortType>,
+  /// Another post which is quoted by this one. Misskey and Fedibird use different names for it.
+  #[serde(alias = "_misskey_quote", alias = "quoteUri")]
+  pub(crate) quote_url: Option<Url>,
   pub(crate) sensitive: Option<bool>,
   pub(crate) published: Option<DateTime<Utc>>,
   pub(crate) updated: Option<DateTime<Utc>>,
@@ -90,6 +102,8 @@ pub(crate) struct Document {
   #[serde(rename = "type")]
   pub(crate) kind: DocumentType,
   pub(crate) url: Url,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) media_type: Option<String>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -111,6 +125,18 @@ impl Attachment {
       Attachment::Document(d) => d.url,
     }
   }
+
+  /// Images are sent with the `Image` type, or as document with an image media type (Mastodon).
+  pub(crate) fn is_image(&self) -> bool {
+    match self {
+      Attachment::Image(_) => true,
+      Attachment::Document(d) => d
+        .media_type
+        .as_deref()
+        .is_some_and(|m| m.starts_with("image/")),
+      Attachment::Link(_) => false,
+    }
+  }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -129,8 +155,8 @@ pub(crate) struct AttributedToPeertube {
 }
 
 impl Page {
-  /// Only mods can change the post's locked status. So if it is changed from the default value,
-  /// it is a mod action and needs to be verified as such.
+  /// Only mods can change the post's locked or archived status. So if it is changed from the
+  /// default value, it is a mod action and needs to be verified as such.
   ///
   /// Locked needs to be false on a newly created post (verified in [[CreatePost]].
   pub(crate) async fn is_mod_action(
@@ -138,16 +164,42 @@ impl Page {
     context: &Data<LemmyContext>,
   ) -> Result<bool, LemmyError> {
     let old_post = self.id.clone().dereference_local(context).await;
-    Ok(Page::is_locked_changed(&old_post, &self.comments_enabled))
+    Ok(
+      Page::is_locked_changed(&old_post, &self.locked())
+        || Page::is_archived_changed(&old_post, &self.archived),
+    )
+  }
+
+  /// Whether the post was locked by a mod. Archived posts also have comments disabled, so for
+  /// those the locked status can't be derived from `commentsEnabled`.
+  pub(crate) fn locked(&self) -> Option<bool> {
+    if self.archived == Some(true) {
+      None
+    } else {
+      self.comments_enabled.map(|e| !e)
+    }
   }
 
   pub(crate) fn is_locked_changed<E>(
     old_post: &Result<ApubPost, E>,
-    new_comments_enabled: &Option<bool>,
+    new_locked: &Option<bool>,
   ) -> bool {
-    if let Some(new_comments_enabled) = new_comments_enabled {
+    if let Some(new_locked) = new_locked {
       if let Ok(old_post) = old_post {
-        return new_comments_enabled != &!old_post.locked;
+        return new_locked != &old_post.locked;
+      }
+    }
+
+    false
+  }
+
+  pub(crate) fn is_archived_changed<E>(
+    old_post: &Result<ApubPost, E>,
+    new_archived: &Option<bool>,
+  ) -> bool {
+    if let Some(new_archived) = new_archived {
+      if let Ok(old_post) = old_post {
+        return new_archived != &old_post.archived;
       }
     }
 
@@ -240,6 +292,21 @@ where
   }
 }
 
+/// Attachments of unknown types, like the `PropertyValue` which some software sends, are skipped
+/// instead of failing to parse the whole object.
+fn deserialize_attachments<'de, D>(deserializer: D) -> Result<Vec<Attachment>, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  let attachments: Vec<Value> = deserialize_one_or_many(deserializer)?;
+  Ok(
+    attachments
+      .into_iter()
+      .filter_map(|a| serde_json::from_value(a).ok())
+      .collect(),
+  )
+}
+
 #[cfg(test)]
 mod tests {
   #![allow(clippy::unwrap_used)]
@@ -1,9 +1,10 @@
 use crate::{
+  collections::person_featured::ApubPersonFeatured,
   objects::person::ApubPerson,
   protocol::{objects::Endpoints, ImageObject, Source},
 };
 use activitypub_federation::{
-  fetch::object_id::ObjectId,
+  fetch::{collection_id::CollectionId, object_id::ObjectId},
   protocol::{helpers::deserialize_skip_error, public_key::PublicKey},
 };
 use chrono::{DateTime, Utc};
@@ -43,6 +44,16 @@ pub struct Person {
   pub(crate) image: Option<ImageObject>,
   pub(crate) matrix_user_id: Option<String>,
   pub(crate) endpoints: Option<Endpoints>,
+  /// posts which the user pinned to their profile
+  #[serde(deserialize_with = "deserialize_skip_error", default)]
+  pub(crate) featured: Option<CollectionId<ApubPersonFeatured>>,
+  /// previous accounts of the user, which were moved to this one
+  #[serde(deserialize_with = "deserialize_skip_error", default)]
+  pub(crate) also_known_as: Option<Vec<ObjectId<ApubPerson>>>,
+  /// whether the user is an admin of their home instance, only used for display
+  pub(crate) instance_admin: Option<bool>,
+  /// whether the user is banned by their home instance
+  pub(crate) banned: Option<bool>,
   pub(crate) published: Option<DateTime<Utc>>,
   pub(crate) updated: Option<DateTime<Utc>>,
 }
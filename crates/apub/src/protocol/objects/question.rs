@@ -0,0 +1,140 @@
+use crate::{
+  objects::{community::ApubCommunity, person::ApubPerson, poll::ApubPoll},
+  protocol::{
+    objects::page::{deserialize_not_present, AttributedTo, Page, PageType},
+    InCommunity,
+    Source,
+  },
+};
+use activitypub_federation::{
+  config::Data,
+  fetch::object_id::ObjectId,
+  kinds::{collection::CollectionType, object::NoteType},
+  protocol::{
+    helpers::{deserialize_one_or_many, deserialize_skip_error},
+    values::MediaTypeMarkdownOrHtml,
+  },
+};
+use chrono::{DateTime, Utc};
+use lemmy_api_common::context::LemmyContext;
+use lemmy_utils::error::LemmyError;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+use url::Url;
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum QuestionType {
+  #[default]
+  Question,
+}
+
+/// A poll, as sent by Mastodon and others. Besides the options it is the same as a post, so it is
+/// stored as a post with an attached poll.
+#[skip_serializing_none]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Question {
+  #[serde(rename = "type")]
+  pub(crate) kind: QuestionType,
+  pub(crate) id: ObjectId<ApubPoll>,
+  pub(crate) attributed_to: ObjectId<ApubPerson>,
+  #[serde(deserialize_with = "deserialize_one_or_many")]
+  pub(crate) to: Vec<Url>,
+  #[serde(deserialize_with = "deserialize_one_or_many", default)]
+  pub(crate) cc: Vec<Url>,
+  // Polls in reply to other objects would be comments, which can't have polls
+  #[serde(deserialize_with = "deserialize_not_present", default)]
+  pub(crate) in_reply_to: Option<String>,
+  pub(crate) name: Option<String>,
+  pub(crate) content: Option<String>,
+  pub(crate) media_type: Option<MediaTypeMarkdownOrHtml>,
+  #[serde(deserialize_with = "deserialize_skip_error", default)]
+  pub(crate) source: Option<Source>,
+  /// Options of a single choice poll
+  pub(crate) one_of: Option<Vec<QuestionOption>>,
+  /// Options of a multiple choice poll
+  pub(crate) any_of: Option<Vec<QuestionOption>>,
+  pub(crate) end_time: Option<DateTime<Utc>>,
+  pub(crate) sensitive: Option<bool>,
+  pub(crate) published: Option<DateTime<Utc>>,
+  pub(crate) updated: Option<DateTime<Utc>>,
+  pub(crate) audience: Option<ObjectId<ApubCommunity>>,
+}
+
+#[skip_serializing_none]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuestionOption {
+  #[serde(rename = "type")]
+  pub(crate) kind: NoteType,
+  pub(crate) name: String,
+  pub(crate) replies: Option<QuestionOptionReplies>,
+}
+
+/// Only used for the number of votes of the option.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuestionOptionReplies {
+  #[serde(rename = "type")]
+  pub(crate) kind: CollectionType,
+  pub(crate) total_items: i32,
+}
+
+/// A vote in a poll. Mastodon sends these as a note whose name is the chosen option, one for
+/// each option in multiple choice polls.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuestionVote {
+  #[serde(rename = "type")]
+  pub(crate) kind: NoteType,
+  pub(crate) id: Url,
+  pub(crate) attributed_to: ObjectId<ApubPerson>,
+  pub(crate) name: String,
+  pub(crate) in_reply_to: ObjectId<ApubPoll>,
+}
+
+impl Question {
+  /// Whether multiple options can be chosen, and the options.
+  pub(crate) fn options(&self) -> (bool, &[QuestionOption]) {
+    match (&self.one_of, &self.any_of) {
+      (_, Some(any_of)) if !any_of.is_empty() => (true, any_of.as_slice()),
+      (Some(one_of), _) => (false, one_of.as_slice()),
+      _ => (false, &[]),
+    }
+  }
+
+  /// The question without its options, to be handled like any other post.
+  pub(crate) fn page(&self) -> Page {
+    Page {
+      kind: PageType::Note,
+      id: self.id.inner().clone().into(),
+      attributed_to: AttributedTo::Lemmy(self.attributed_to.clone()),
+      to: self.to.clone(),
+      in_reply_to: None,
+      name: self.name.clone(),
+      cc: self.cc.clone(),
+      content: self.content.clone(),
+      media_type: self.media_type.clone(),
+      source: self.source.clone(),
+      attachment: vec![],
+      image: None,
+      comments_enabled: None,
+      archived: None,
+      reply_policy: None,
+      default_comment_sort_type: None,
+      quote_url: None,
+      sensitive: self.sensitive,
+      published: self.published,
+      updated: self.updated,
+      language: None,
+      audience: self.audience.clone(),
+    }
+  }
+}
+
+#[async_trait::async_trait]
+impl InCommunity for Question {
+  async fn community(&self, context: &Data<LemmyContext>) -> Result<ApubCommunity, LemmyError> {
+    self.page().community(context).await
+  }
+}
@@ -1,5 +1,6 @@
 use crate::protocol::Id;
 use activitypub_federation::kinds::object::TombstoneType;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use url::Url;
@@ -11,6 +12,11 @@ pub struct Tombstone {
   pub(crate) id: Url,
   #[serde(rename = "type")]
   pub(crate) kind: TombstoneType,
+  /// Type of the object before it was deleted, like `Page` or `Note`
+  #[serde(alias = "former_type")]
+  pub(crate) former_type: Option<String>,
+  #[serde(alias = "deletedAt")]
+  pub(crate) deleted: Option<DateTime<Utc>>,
 }
 
 impl Tombstone {
@@ -18,6 +24,8 @@ impl Tombstone {
     Tombstone {
       id,
       kind: TombstoneType::Tombstone,
+      former_type: None,
+      deleted: None,
     }
   }
 }
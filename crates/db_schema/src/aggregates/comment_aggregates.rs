@@ -2,7 +2,11 @@ use crate::{
   aggregates::structs::CommentAggregates,
   newtypes::CommentId,
   schema::comment_aggregates,
-  utils::{functions::hot_rank, get_conn, DbPool},
+  utils::{
+    functions::{controversy_rank, hot_rank},
+    get_conn,
+    DbPool,
+  },
 };
 use diesel::{result::Error, ExpressionMethods, QueryDsl};
 use diesel_async::RunQueryDsl;
@@ -31,6 +35,32 @@ impl CommentAggregates {
       .get_result::<Self>(conn)
       .await
   }
+
+  /// Overwrite the vote counts with the values from the origin instance of a federated comment,
+  /// and recalculate the ranks based on them.
+  pub async fn update_federated_votes(
+    pool: &mut DbPool<'_>,
+    comment_id: CommentId,
+    upvotes: i64,
+    downvotes: i64,
+  ) -> Result<Self, Error> {
+    let conn = &mut get_conn(pool).await?;
+    let upvotes = upvotes.max(0);
+    let downvotes = downvotes.max(0);
+    let score = upvotes - downvotes;
+
+    diesel::update(comment_aggregates::table)
+      .filter(comment_aggregates::comment_id.eq(comment_id))
+      .set((
+        comment_aggregates::upvotes.eq(upvotes),
+        comment_aggregates::downvotes.eq(downvotes),
+        comment_aggregates::score.eq(score),
+        comment_aggregates::hot_rank.eq(hot_rank(score, comment_aggregates::published)),
+        comment_aggregates::controversy_rank.eq(controversy_rank(upvotes, downvotes, score)),
+      ))
+      .get_result::<Self>(conn)
+      .await
+  }
 }
 
 #[cfg(test)]
@@ -3,7 +3,14 @@ use crate::{
   newtypes::CommunityId,
   schema::{
     community_aggregates,
-    community_aggregates::{community_id, subscribers},
+    community_aggregates::{
+      community_id,
+      federated_users_active,
+      subscribers,
+      users_active_day,
+      users_active_month,
+      users_active_week,
+    },
   },
   utils::{get_conn, DbPool},
 };
@@ -31,6 +38,27 @@ impl CommunityAggregates {
       .get_result::<Self>(conn)
       .await
   }
+
+  /// Stores the active user counts of a remote community as reported by its instance. These are
+  /// then no longer overwritten with the locally known activity.
+  pub async fn update_federated_users_active(
+    pool: &mut DbPool<'_>,
+    for_community_id: CommunityId,
+    day: i64,
+    week: i64,
+    month: i64,
+  ) -> Result<Self, Error> {
+    let conn = &mut get_conn(pool).await?;
+    diesel::update(community_aggregates::table.filter(community_id.eq(for_community_id)))
+      .set((
+        users_active_day.eq(day.max(0)),
+        users_active_week.eq(week.max(0)),
+        users_active_month.eq(month.max(0)),
+        federated_users_active.eq(true),
+      ))
+      .get_result::<Self>(conn)
+      .await
+  }
 }
 
 #[cfg(test)]
@@ -59,6 +59,10 @@ pub struct CommunityAggregates {
   pub users_active_half_year: i64,
   #[serde(skip)]
   pub hot_rank: f64,
+  /// Whether the daily, weekly and monthly active users were received from the community's
+  /// instance, instead of being counted locally.
+  #[serde(skip)]
+  pub federated_users_active: bool,
 }
 
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone, Default)]
@@ -1,12 +1,22 @@
 use crate::{
-  diesel::OptionalExtension,
+  diesel::{dsl::IntervalDsl, OptionalExtension},
+  impls::instance::BLOCKED_BY_REMOTE_PAUSE_DAYS,
   newtypes::DbUrl,
-  source::activity::{ReceivedActivity, SentActivity, SentActivityForm},
-  utils::{get_conn, DbPool},
+  source::activity::{
+    FederationQueueStats,
+    ReceivedActivity,
+    SentActivity,
+    SentActivityDelivery,
+    SentActivityDeliveryForm,
+    SentActivityForm,
+  },
+  utils::{functions::coalesce, get_conn, limit_and_offset, now, DbPool},
 };
 use diesel::{
-  dsl::insert_into,
+  dsl::{insert_into, max, sql},
   result::{DatabaseErrorKind, Error, Error::DatabaseError},
+  sql_types::{Bool, Text},
+  BoolExpressionMethods,
   ExpressionMethods,
   QueryDsl,
 };
@@ -35,6 +45,125 @@ impl SentActivity {
     let conn = &mut get_conn(pool).await?;
     sent_activity.find(object_id).first::<Self>(conn).await
   }
+
+  /// Lists the stored activities of the given actor, newest first. Besides the activities which the
+  /// actor sent itself, this includes activities of a remote actor which were announced by a local
+  /// community. Sensitive activities, like private messages, are left out.
+  pub async fn list_for_actor(
+    pool: &mut DbPool<'_>,
+    actor: &DbUrl,
+    page: Option<i64>,
+    limit: Option<i64>,
+  ) -> Result<Vec<Self>, Error> {
+    use crate::schema::sent_activity::dsl::{
+      actor_apub_id,
+      id,
+      published,
+      sensitive,
+      sent_activity,
+    };
+    let conn = &mut get_conn(pool).await?;
+    let (limit, offset) = limit_and_offset(page, limit)?;
+    // the data column is json, so the actor of an announced activity can't be compared with diesel
+    // operators. Both conditions are covered by an index.
+    let announced_by_community =
+      sql::<Bool>("data -> 'object' ->> 'actor' = ").bind::<Text, _>(actor.to_string());
+    sent_activity
+      .filter(sensitive.eq(false))
+      .filter(actor_apub_id.eq(actor).or(announced_by_community))
+      .order_by((published.desc(), id.desc()))
+      .limit(limit)
+      .offset(offset)
+      .load::<Self>(conn)
+      .await
+  }
+
+  /// Counts the activities which are waiting to be sent to other instances. Dead and paused
+  /// instances, and those which recently blocked this instance, are left out, as nothing is sent
+  /// to them. The pending count is an upper bound, as all activities after the last successful
+  /// one are counted for each instance, regardless of whether they are addressed to it.
+  pub async fn queue_stats(pool: &mut DbPool<'_>) -> Result<FederationQueueStats, Error> {
+    use crate::schema::{federation_queue_state, instance, sent_activity};
+    let conn = &mut get_conn(pool).await?;
+    let newest_id = sent_activity::table
+      .select(max(sent_activity::id))
+      .get_result::<Option<i64>>(conn)
+      .await?
+      .unwrap_or(0);
+    let queues = federation_queue_state::table
+      .inner_join(instance::table)
+      .filter(instance::paused.eq(false))
+      .filter(
+        instance::blocked_by_remote
+          .is_null()
+          .or(instance::blocked_by_remote.lt(now() - BLOCKED_BY_REMOTE_PAUSE_DAYS.days())),
+      )
+      .filter(coalesce(instance::updated, instance::published).ge(now() - 3.days()))
+      .select((
+        federation_queue_state::last_successful_id,
+        federation_queue_state::fail_count,
+      ))
+      .load::<(i64, i32)>(conn)
+      .await?;
+    Ok(FederationQueueStats {
+      pending: queues
+        .iter()
+        .map(|(last_successful_id, _)| (newest_id - last_successful_id).max(0))
+        .sum(),
+      instances: queues.len(),
+      failing_instances: queues
+        .iter()
+        .filter(|(_, fail_count)| *fail_count > 0)
+        .count(),
+    })
+  }
+}
+
+impl SentActivityDelivery {
+  /// Stores the outcome of a delivery attempt. Only the latest attempt per inbox is kept.
+  pub async fn upsert(
+    pool: &mut DbPool<'_>,
+    form: &SentActivityDeliveryForm,
+  ) -> Result<Self, Error> {
+    use crate::schema::sent_activity_delivery::dsl::{
+      error,
+      inbox,
+      sent_activity_delivery,
+      sent_activity_id,
+      success,
+      updated,
+    };
+    let conn = &mut get_conn(pool).await?;
+    // set columns explicitly, so that the error of a previous attempt is cleared on success
+    insert_into(sent_activity_delivery)
+      .values(form)
+      .on_conflict((sent_activity_id, inbox))
+      .do_update()
+      .set((
+        success.eq(form.success),
+        error.eq(&form.error),
+        updated.eq(form.updated),
+      ))
+      .get_result::<Self>(conn)
+      .await
+  }
+
+  pub async fn list_for_activity(
+    pool: &mut DbPool<'_>,
+    for_sent_activity_id: i64,
+  ) -> Result<Vec<Self>, Error> {
+    use crate::schema::sent_activity_delivery::dsl::{
+      id,
+      sent_activity_delivery,
+      sent_activity_id,
+    };
+    let conn = &mut get_conn(pool).await?;
+    sent_activity_delivery
+      .filter(sent_activity_id.eq(for_sent_activity_id))
+      .order_by(id)
+      .load::<Self>(conn)
+      .await
+  }
 }
 
 impl ReceivedActivity {
@@ -59,6 +188,37 @@ impl ReceivedActivity {
       ))
     }
   }
+
+  pub async fn read_from_apub_id(pool: &mut DbPool<'_>, object_id: &DbUrl) -> Result<Self, Error> {
+    use crate::schema::received_activity::dsl::{ap_id, received_activity};
+    let conn = &mut get_conn(pool).await?;
+    received_activity
+      .filter(ap_id.eq(object_id))
+      .first::<Self>(conn)
+      .await
+  }
+
+  /// Records which HTTP signature scheme was used to deliver the activity.
+  pub async fn set_signature_algorithm(
+    pool: &mut DbPool<'_>,
+    object_id: &DbUrl,
+    algorithm: &str,
+  ) -> Result<usize, Error> {
+    use crate::schema::received_activity::dsl::{ap_id, received_activity, signature_algorithm};
+    let conn = &mut get_conn(pool).await?;
+    diesel::update(received_activity.filter(ap_id.eq(object_id)))
+      .set(signature_algorithm.eq(algorithm))
+      .execute(conn)
+      .await
+  }
+
+  pub async fn delete(pool: &mut DbPool<'_>, object_id: &DbUrl) -> Result<usize, Error> {
+    use crate::schema::received_activity::dsl::{ap_id, received_activity};
+    let conn = &mut get_conn(pool).await?;
+    diesel::delete(received_activity.filter(ap_id.eq(object_id)))
+      .execute(conn)
+      .await
+  }
 }
 
 #[cfg(test)]
@@ -68,7 +228,8 @@ mod tests {
 
   use super::*;
   use crate::{source::activity::ActorType, utils::build_db_pool_for_tests};
-  use serde_json::json;
+  use chrono::Utc;
+  use serde_json::{json, Value};
   use serial_test::serial;
   use url::Url;
 
@@ -123,4 +284,117 @@ mod tests {
     assert_eq!(res.data, data);
     assert_eq!(res.sensitive, sensitive);
   }
+
+  #[tokio::test]
+  #[serial]
+  async fn sent_activity_list_for_actor() {
+    let pool = &build_db_pool_for_tests().await;
+    let pool = &mut pool.into();
+    let alice: DbUrl = Url::parse("http://example.com/u/alice").unwrap().into();
+    let bob: DbUrl = Url::parse("http://example.com/u/bob").unwrap().into();
+    let community: DbUrl = Url::parse("http://example.com/c/main").unwrap().into();
+    let activity = |num: i32, actor: &DbUrl, data: Value, sensitive: bool| SentActivityForm {
+      ap_id: Url::parse(&format!("http://example.com/activities/list/{num}"))
+        .unwrap()
+        .into(),
+      data,
+      sensitive,
+      actor_apub_id: actor.clone(),
+      actor_type: ActorType::Person,
+      send_all_instances: false,
+      send_community_followers_of: None,
+      send_inboxes: vec![],
+    };
+    let like = |actor: &DbUrl| json!({"actor": actor, "type": "Like"});
+    for form in [
+      activity(1, &alice, like(&alice), false),
+      activity(2, &bob, like(&bob), false),
+      activity(3, &alice, json!({"actor": alice, "type": "Create"}), true),
+      activity(
+        4,
+        &community,
+        json!({"actor": community, "type": "Announce", "object": like(&alice)}),
+        false,
+      ),
+      activity(5, &bob, like(&bob), false),
+    ] {
+      SentActivity::create(pool, form).await.unwrap();
+    }
+
+    // newest first, without the sensitive activity and the ones of bob
+    let ap_ids = |list: Vec<SentActivity>| {
+      list
+        .into_iter()
+        .map(|a| a.ap_id.to_string())
+        .collect::<Vec<_>>()
+    };
+    let list = SentActivity::list_for_actor(pool, &alice, None, None)
+      .await
+      .unwrap();
+    assert_eq!(
+      vec![
+        "http://example.com/activities/list/4",
+        "http://example.com/activities/list/1"
+      ],
+      ap_ids(list)
+    );
+
+    let list = SentActivity::list_for_actor(pool, &bob, Some(2), Some(1))
+      .await
+      .unwrap();
+    assert_eq!(vec!["http://example.com/activities/list/2"], ap_ids(list));
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn sent_activity_delivery_per_inbox() {
+    let pool = &build_db_pool_for_tests().await;
+    let pool = &mut pool.into();
+    let form = SentActivityForm {
+      ap_id: Url::parse("http://example.com/activity/413")
+        .unwrap()
+        .into(),
+      data: json!({}),
+      sensitive: false,
+      actor_apub_id: Url::parse("http://example.com/u/exampleuser")
+        .unwrap()
+        .into(),
+      actor_type: ActorType::Person,
+      send_all_instances: false,
+      send_community_followers_of: None,
+      send_inboxes: vec![],
+    };
+    let activity = SentActivity::create(pool, form).await.unwrap();
+
+    let inbox_a: DbUrl = Url::parse("http://a.com/inbox").unwrap().into();
+    let inbox_b: DbUrl = Url::parse("http://b.com/inbox").unwrap().into();
+    let delivery = |inbox: &DbUrl, error: Option<&str>| SentActivityDeliveryForm {
+      sent_activity_id: activity.id,
+      inbox: inbox.clone(),
+      success: error.is_none(),
+      error: error.map(ToString::to_string),
+      updated: Utc::now(),
+    };
+    SentActivityDelivery::upsert(pool, &delivery(&inbox_a, None))
+      .await
+      .unwrap();
+    SentActivityDelivery::upsert(pool, &delivery(&inbox_b, Some("timeout")))
+      .await
+      .unwrap();
+    // a retry overwrites the previous outcome instead of adding another row
+    SentActivityDelivery::upsert(pool, &delivery(&inbox_b, Some("status 500")))
+      .await
+      .unwrap();
+
+    let deliveries = SentActivityDelivery::list_for_activity(pool, activity.id)
+      .await
+      .unwrap();
+    assert_eq!(2, deliveries.len());
+    assert_eq!(inbox_a, deliveries[0].inbox);
+    assert!(deliveries[0].success);
+    assert_eq!(None, deliveries[0].error);
+    assert_eq!(inbox_b, deliveries[1].inbox);
+    assert!(!deliveries[1].success);
+    assert_eq!(Some("status 500".to_string()), deliveries[1].error);
+  }
 }
@@ -0,0 +1,125 @@
+use crate::{
+  schema::{actor_name_alias, community, person},
+  source::{
+    actor_name_alias::{ActorNameAlias, ActorNameAliasForm},
+    community::Community,
+    person::Person,
+  },
+  utils::{functions::lower, get_conn, DbPool},
+};
+use diesel::{dsl::insert_into, result::Error, ExpressionMethods, QueryDsl};
+use diesel_async::RunQueryDsl;
+
+impl ActorNameAlias {
+  pub async fn create(pool: &mut DbPool<'_>, form: &ActorNameAliasForm) -> Result<Self, Error> {
+    let conn = &mut get_conn(pool).await?;
+    insert_into(actor_name_alias::table)
+      .values(form)
+      .get_result::<Self>(conn)
+      .await
+  }
+
+  /// Reads the local person which was previously called `name`. Like
+  /// [crate::traits::ApubActor::read_from_name], deleted persons are not returned.
+  pub async fn read_person(pool: &mut DbPool<'_>, name: &str) -> Result<Person, Error> {
+    let conn = &mut get_conn(pool).await?;
+    actor_name_alias::table
+      .inner_join(person::table)
+      .filter(lower(actor_name_alias::name).eq(name.to_lowercase()))
+      .filter(person::local.eq(true))
+      .filter(person::deleted.eq(false))
+      .select(person::all_columns)
+      .first::<Person>(conn)
+      .await
+  }
+
+  /// Reads the local community which was previously called `name`, unless it is deleted or
+  /// removed.
+  pub async fn read_community(pool: &mut DbPool<'_>, name: &str) -> Result<Community, Error> {
+    let conn = &mut get_conn(pool).await?;
+    actor_name_alias::table
+      .inner_join(community::table)
+      .filter(lower(actor_name_alias::name).eq(name.to_lowercase()))
+      .filter(community::local.eq(true))
+      .filter(community::deleted.eq(false))
+      .filter(community::removed.eq(false))
+      .select(community::all_columns)
+      .first::<Community>(conn)
+      .await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::{
+    source::{community::CommunityInsertForm, instance::Instance, person::PersonInsertForm},
+    traits::Crud,
+    utils::build_db_pool_for_tests,
+  };
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_actor_name_alias() {
+    let pool = &build_db_pool_for_tests().await;
+    let pool = &mut pool.into();
+
+    let instance = Instance::read_or_create(pool, "my_domain.tld".to_string())
+      .await
+      .unwrap();
+    let person_form = PersonInsertForm::builder()
+      .name("renamed_person".into())
+      .public_key("pubkey".to_string())
+      .instance_id(instance.id)
+      .build();
+    let person = Person::create(pool, &person_form).await.unwrap();
+    let community_form = CommunityInsertForm::builder()
+      .name("renamed_community".to_string())
+      .title("nada".to_owned())
+      .public_key("pubkey".to_string())
+      .instance_id(instance.id)
+      .build();
+    let community = Community::create(pool, &community_form).await.unwrap();
+
+    // the same old name can belong to a person and a community
+    let form = ActorNameAliasForm {
+      name: "old_name".to_string(),
+      person_id: Some(person.id),
+      community_id: None,
+    };
+    ActorNameAlias::create(pool, &form).await.unwrap();
+    let form = ActorNameAliasForm {
+      name: "old_name".to_string(),
+      person_id: None,
+      community_id: Some(community.id),
+    };
+    ActorNameAlias::create(pool, &form).await.unwrap();
+
+    // an alias must point to exactly one actor
+    let form = ActorNameAliasForm {
+      name: "nobody".to_string(),
+      person_id: None,
+      community_id: None,
+    };
+    assert!(ActorNameAlias::create(pool, &form).await.is_err());
+
+    let read_person = ActorNameAlias::read_person(pool, "Old_Name").await.unwrap();
+    assert_eq!(person.actor_id, read_person.actor_id);
+    let read_community = ActorNameAlias::read_community(pool, "old_name")
+      .await
+      .unwrap();
+    assert_eq!(community.actor_id, read_community.actor_id);
+    let err = ActorNameAlias::read_person(pool, "unknown")
+      .await
+      .unwrap_err();
+    assert_eq!(Error::NotFound, err);
+
+    Person::delete(pool, person.id).await.unwrap();
+    Community::delete(pool, community.id).await.unwrap();
+    Instance::delete(pool, instance.id).await.unwrap();
+  }
+}
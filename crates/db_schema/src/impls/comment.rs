@@ -264,6 +264,7 @@ mod tests {
     },
     traits::{Crud, Likeable, Saveable},
     utils::build_db_pool_for_tests,
+    ReplyPolicy,
   };
   use diesel_ltree::Ltree;
   use serial_test::serial;
@@ -325,6 +326,8 @@ mod tests {
       distinguished: false,
       local: true,
       language_id: LanguageId::default(),
+      delete_reason: None,
+      reply_policy: ReplyPolicy::Everyone,
     };
 
     let child_comment_form = CommentInsertForm::builder()
@@ -19,6 +19,7 @@ use crate::{
   utils::{functions::lower, get_conn, DbPool},
   SubscribedType,
 };
+use chrono::{DateTime, Utc};
 use diesel::{
   deserialize,
   dsl,
@@ -137,6 +138,24 @@ impl Community {
     }
     Err(diesel::NotFound)
   }
+
+  /// Remote communities which were last fetched before `older_than`, least recently refreshed
+  /// first.
+  pub async fn list_stale_remote(
+    pool: &mut DbPool<'_>,
+    older_than: DateTime<Utc>,
+    limit: i64,
+  ) -> Result<Vec<Self>, Error> {
+    let conn = &mut get_conn(pool).await?;
+    community::table
+      .filter(community::local.eq(false))
+      .filter(community::deleted.eq(false))
+      .filter(community::last_refreshed_at.lt(older_than))
+      .order_by(community::last_refreshed_at.asc())
+      .limit(limit)
+      .load::<Self>(conn)
+      .await
+  }
 }
 
 impl CommunityModerator {
@@ -230,6 +249,41 @@ impl CommunityFollower {
     community_follower::pending.nullable()
   }
 
+  /// Adds and removes followers of a community in a single transaction, so that either the whole
+  /// batch is applied or none of it. Existing follows are left unchanged.
+  pub async fn apply_batch(
+    pool: &mut DbPool<'_>,
+    for_community_id: CommunityId,
+    follow_forms: Vec<CommunityFollowerForm>,
+    unfollow_ids: Vec<PersonId>,
+  ) -> Result<(), Error> {
+    use crate::schema::community_follower::dsl::{community_follower, community_id, person_id};
+    let conn = &mut get_conn(pool).await?;
+    conn
+      .build_transaction()
+      .run(|conn| {
+        Box::pin(async move {
+          if !follow_forms.is_empty() {
+            insert_into(community_follower)
+              .values(follow_forms)
+              .on_conflict((community_id, person_id))
+              .do_nothing()
+              .execute(conn)
+              .await?;
+          }
+          diesel::delete(
+            community_follower
+              .filter(community_id.eq(for_community_id))
+              .filter(person_id.eq_any(unfollow_ids)),
+          )
+          .execute(conn)
+          .await?;
+          Ok(())
+        }) as _
+      })
+      .await
+  }
+
   /// Check if a remote instance has any followers on local instance. For this it is enough to check
   /// if any follow relation is stored. Dont use this for local community.
   pub async fn has_local_followers(
@@ -245,6 +299,30 @@ impl CommunityFollower {
     .get_result(conn)
     .await
   }
+
+  /// Check if the person follows the community, and the follow was accepted.
+  pub async fn is_accepted_follower(
+    pool: &mut DbPool<'_>,
+    for_community_id: CommunityId,
+    for_person_id: PersonId,
+  ) -> Result<bool, Error> {
+    use crate::schema::community_follower::dsl::{
+      community_follower,
+      community_id,
+      pending,
+      person_id,
+    };
+    use diesel::dsl::{exists, select};
+    let conn = &mut get_conn(pool).await?;
+    select(exists(
+      community_follower
+        .filter(community_id.eq(for_community_id))
+        .filter(person_id.eq(for_person_id))
+        .filter(pending.eq(false)),
+    ))
+    .get_result(conn)
+    .await
+  }
 }
 
 impl Queryable<sql_types::Nullable<sql_types::Bool>, Pg> for SubscribedType {
@@ -363,6 +441,7 @@ mod tests {
   #![allow(clippy::indexing_slicing)]
 
   use crate::{
+    impls::actor_language::UNDETERMINED_ID,
     source::{
       community::{
         Community,
@@ -379,8 +458,11 @@ mod tests {
       person::{Person, PersonInsertForm},
     },
     traits::{Bannable, Crud, Followable, Joinable},
-    utils::build_db_pool_for_tests,
+    utils::{build_db_pool_for_tests, get_conn},
+    CommunityMembershipMode,
   };
+  use diesel::{ExpressionMethods, QueryDsl};
+  use diesel_async::RunQueryDsl;
   use serial_test::serial;
 
   #[tokio::test]
@@ -434,6 +516,10 @@ mod tests {
       featured_url: None,
       hidden: false,
       posting_restricted_to_mods: false,
+      membership_mode: CommunityMembershipMode::Open,
+      default_sort_type: None,
+      default_post_nsfw: false,
+      primary_language_id: UNDETERMINED_ID,
       instance_id: inserted_instance.id,
     };
 
@@ -526,4 +612,79 @@ mod tests {
     // assert_eq!(2, loaded_count);
     assert_eq!(1, num_deleted);
   }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_apply_follower_batch() {
+    use crate::schema::community_follower::dsl::{community_follower, community_id, person_id};
+    let pool = &build_db_pool_for_tests().await;
+    let pool = &mut pool.into();
+
+    let inserted_instance = Instance::read_or_create(pool, "my_domain.tld".to_string())
+      .await
+      .unwrap();
+    let mut persons = vec![];
+    for name in ["batch_a", "batch_b", "batch_c"] {
+      let form = PersonInsertForm::builder()
+        .name(name.into())
+        .public_key("pubkey".to_string())
+        .instance_id(inserted_instance.id)
+        .build();
+      persons.push(Person::create(pool, &form).await.unwrap());
+    }
+    let new_community = CommunityInsertForm::builder()
+      .name("batch".into())
+      .title("nada".to_owned())
+      .public_key("pubkey".to_string())
+      .instance_id(inserted_instance.id)
+      .build();
+    let inserted_community = Community::create(pool, &new_community).await.unwrap();
+
+    // a already follows, b has a pending follow
+    for (person, pending) in [(&persons[0], false), (&persons[1], true)] {
+      let form = CommunityFollowerForm {
+        community_id: inserted_community.id,
+        person_id: person.id,
+        pending,
+      };
+      CommunityFollower::follow(pool, &form).await.unwrap();
+    }
+
+    let follow_forms = [&persons[0], &persons[2]]
+      .iter()
+      .map(|p| CommunityFollowerForm {
+        community_id: inserted_community.id,
+        person_id: p.id,
+        pending: true,
+      })
+      .collect();
+    CommunityFollower::apply_batch(
+      pool,
+      inserted_community.id,
+      follow_forms,
+      vec![persons[1].id],
+    )
+    .await
+    .unwrap();
+
+    let mut followers: Vec<CommunityFollower> = community_follower
+      .filter(community_id.eq(inserted_community.id))
+      .load(&mut get_conn(pool).await.unwrap())
+      .await
+      .unwrap();
+    followers.sort_by_key(|f| f.person_id.0);
+    let follower_ids: Vec<_> = followers.iter().map(|f| f.person_id).collect();
+    assert_eq!(vec![persons[0].id, persons[2].id], follower_ids);
+    // the existing follow stays accepted, the new one waits for the community to accept it
+    let pending: Vec<_> = followers.iter().map(|f| f.pending).collect();
+    assert_eq!(vec![false, true], pending);
+
+    Community::delete(pool, inserted_community.id)
+      .await
+      .unwrap();
+    for person in persons {
+      Person::delete(pool, person.id).await.unwrap();
+    }
+    Instance::delete(pool, inserted_instance.id).await.unwrap();
+  }
 }
@@ -0,0 +1,49 @@
+use crate::{
+  newtypes::{CommunityId, DbUrl},
+  schema::{community, federation_community_blocklist},
+  source::federation_community_blocklist::{
+    FederationCommunityBlockList,
+    FederationCommunityBlockListForm,
+  },
+  utils::{get_conn, DbPool},
+};
+use diesel::{dsl::insert_into, result::Error, ExpressionMethods, QueryDsl};
+use diesel_async::RunQueryDsl;
+
+impl FederationCommunityBlockList {
+  /// Blocks federation with the community. Blocking it again doesn't change anything.
+  pub async fn block(pool: &mut DbPool<'_>, community_id: CommunityId) -> Result<(), Error> {
+    let conn = &mut get_conn(pool).await?;
+    let form = FederationCommunityBlockListForm {
+      community_id,
+      updated: None,
+    };
+    insert_into(federation_community_blocklist::table)
+      .values(form)
+      .on_conflict(federation_community_blocklist::community_id)
+      .do_nothing()
+      .execute(conn)
+      .await?;
+    Ok(())
+  }
+
+  pub async fn unblock(pool: &mut DbPool<'_>, community_id: CommunityId) -> Result<usize, Error> {
+    let conn = &mut get_conn(pool).await?;
+    diesel::delete(
+      federation_community_blocklist::table
+        .filter(federation_community_blocklist::community_id.eq(community_id)),
+    )
+    .execute(conn)
+    .await
+  }
+
+  /// Actor ids of all blocked communities.
+  pub async fn actor_ids(pool: &mut DbPool<'_>) -> Result<Vec<DbUrl>, Error> {
+    let conn = &mut get_conn(pool).await?;
+    federation_community_blocklist::table
+      .inner_join(community::table)
+      .select(community::actor_id)
+      .get_results(conn)
+      .await
+  }
+}
@@ -5,17 +5,21 @@ use crate::{
   source::instance::{Instance, InstanceForm},
   utils::{functions::lower, get_conn, naive_now, now, DbPool},
 };
+use chrono::{DateTime, Utc};
 use diesel::{
-  dsl::{count_star, insert_into},
+  dsl::insert_into,
   result::Error,
   sql_types::{Nullable, Timestamptz},
   ExpressionMethods,
-  NullableExpressionMethods,
   QueryDsl,
   SelectableHelper,
 };
 use diesel_async::RunQueryDsl;
 
+/// How long deliveries to an instance are paused after it rejected one because it blocked this
+/// instance.
+pub const BLOCKED_BY_REMOTE_PAUSE_DAYS: i64 = 3;
+
 impl Instance {
   /// Attempt to read Instance column for the given domain. If it doesnt exist, insert a new one.
   /// There is no need for update as the domain of an existing instance cant change.
@@ -49,6 +53,17 @@ impl Instance {
       e => e,
     }
   }
+  pub async fn update(
+    pool: &mut DbPool<'_>,
+    instance_id: InstanceId,
+    form: &InstanceForm,
+  ) -> Result<usize, Error> {
+    let conn = &mut get_conn(pool).await?;
+    diesel::update(instance::table.find(instance_id))
+      .set(form)
+      .execute(conn)
+      .await
+  }
   pub async fn delete(pool: &mut DbPool<'_>, instance_id: InstanceId) -> Result<usize, Error> {
     let conn = &mut get_conn(pool).await?;
     diesel::delete(instance::table.find(instance_id))
@@ -78,6 +93,51 @@ impl Instance {
       .await
   }
 
+  /// Pauses or resumes federation with the instance. Unlike the blocklist, this keeps all data of
+  /// the instance and activities for it are only delayed.
+  pub async fn set_paused(
+    pool: &mut DbPool<'_>,
+    instance_id: InstanceId,
+    paused: bool,
+  ) -> Result<Self, Error> {
+    let conn = &mut get_conn(pool).await?;
+    diesel::update(instance::table.find(instance_id))
+      .set(instance::paused.eq(paused))
+      .get_result::<Self>(conn)
+      .await
+  }
+
+  /// Marks the instance as having blocked this instance, which pauses deliveries to it for
+  /// [BLOCKED_BY_REMOTE_PAUSE_DAYS]. Passing `None` clears the mark once deliveries succeed again.
+  pub async fn set_blocked_by_remote(
+    pool: &mut DbPool<'_>,
+    instance_id: InstanceId,
+    blocked_by_remote: Option<DateTime<Utc>>,
+  ) -> Result<Self, Error> {
+    let conn = &mut get_conn(pool).await?;
+    diesel::update(instance::table.find(instance_id))
+      .set(instance::blocked_by_remote.eq(blocked_by_remote))
+      .get_result::<Self>(conn)
+      .await
+  }
+
+  /// Whether deliveries to the instance are currently paused because it blocked this instance.
+  /// Afterwards they are attempted again, in case the block was lifted.
+  pub fn is_blocked_by_remote(&self) -> bool {
+    self
+      .blocked_by_remote
+      .is_some_and(|t| Utc::now() - t < chrono::Duration::days(BLOCKED_BY_REMOTE_PAUSE_DAYS))
+  }
+
+  pub async fn paused_list(pool: &mut DbPool<'_>) -> Result<Vec<Self>, Error> {
+    let conn = &mut get_conn(pool).await?;
+    instance::table
+      .filter(instance::paused.eq(true))
+      .select(instance::all_columns)
+      .get_results(conn)
+      .await
+  }
+
   pub async fn blocklist(pool: &mut DbPool<'_>) -> Result<Vec<Self>, Error> {
     let conn = &mut get_conn(pool).await?;
     instance::table
@@ -92,52 +152,135 @@ impl Instance {
   pub async fn read_all_with_blocked_and_dead(
     pool: &mut DbPool<'_>,
   ) -> Result<Vec<(Self, bool, bool)>, Error> {
+    // the meaning of "allowed" depends on the existence of any value at all in the allowlist, and
+    // entries can cover subdomains with `*.`, so a join on the instance id wouldn't work
+    let allowlist = Self::allowlist(pool).await?;
+    let blocklist = Self::blocklist(pool).await?;
     let conn = &mut get_conn(pool).await?;
     let is_dead_expr = coalesce(instance::updated, instance::published).lt(now() - 3.days());
-    // this needs to be done in two steps because the meaning of the "blocked" column depends on the existence
-    // of any value at all in the allowlist. (so a normal join wouldn't work)
-    let use_allowlist = federation_allowlist::table
-      .select(count_star().gt(0))
-      .get_result::<bool>(conn)
+    let instances = instance::table
+      .select((Self::as_select(), is_dead_expr))
+      .order_by(instance::id)
+      .get_results::<(Self, bool)>(conn)
       .await?;
-    if use_allowlist {
-      instance::table
-        .left_join(federation_allowlist::table)
-        .select((
-          Self::as_select(),
-          federation_allowlist::id.nullable().is_not_null(),
-          is_dead_expr,
-        ))
-        .order_by(instance::id)
-        .get_results::<(Self, bool, bool)>(conn)
-        .await
-    } else {
-      instance::table
-        .left_join(federation_blocklist::table)
-        .select((
-          Self::as_select(),
-          federation_blocklist::id.nullable().is_null(),
-          is_dead_expr,
-        ))
-        .order_by(instance::id)
-        .get_results::<(Self, bool, bool)>(conn)
-        .await
-    }
+    Ok(
+      instances
+        .into_iter()
+        .map(|(instance, is_dead)| {
+          let allowed = if allowlist.is_empty() {
+            !list_contains(&blocklist, &instance.domain)
+          } else {
+            list_contains(&allowlist, &instance.domain)
+          };
+          (instance, allowed, is_dead)
+        })
+        .collect(),
+    )
   }
 
   pub async fn linked(pool: &mut DbPool<'_>) -> Result<Vec<Self>, Error> {
+    let blocklist = Self::blocklist(pool).await?;
     let conn = &mut get_conn(pool).await?;
-    instance::table
+    let instances = instance::table
       // omit instance representing the local site
       .left_join(site::table.inner_join(local_site::table))
       .filter(local_site::id.is_null())
-      // omit instances in the blocklist
-      .left_join(federation_blocklist::table)
-      .filter(federation_blocklist::id.is_null())
       .select(instance::all_columns)
-      .get_results(conn)
-      .await
+      .get_results::<Self>(conn)
+      .await?;
+    // omit instances in the blocklist
+    Ok(
+      instances
+        .into_iter()
+        .filter(|i| !list_contains(&blocklist, &i.domain))
+        .collect(),
+    )
+  }
+}
+
+fn list_contains(instances: &[Instance], domain: &str) -> bool {
+  instances.iter().any(|i| domain_matches(domain, &i.domain))
+}
+
+/// Compares a domain against an entry of the allowlist or blocklist, ignoring case. An entry like
+/// `*.example.org` matches all subdomains such as `sub.example.org`, but not `example.org` itself.
+pub fn domain_matches(domain: &str, entry: &str) -> bool {
+  let domain = domain.to_lowercase();
+  let entry = entry.to_lowercase();
+  match entry.strip_prefix('*') {
+    Some(suffix) if suffix.starts_with('.') => domain.ends_with(suffix),
+    _ => domain == entry,
   }
 }
 
 sql_function! { fn coalesce(x: Nullable<Timestamptz>, y: Timestamptz) -> Timestamptz; }
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::{
+    source::{
+      federation_allowlist::FederationAllowList,
+      federation_blocklist::FederationBlockList,
+    },
+    utils::build_db_pool_for_tests,
+  };
+  use serial_test::serial;
+
+  async fn allowed(pool: &mut DbPool<'_>, domain: &str) -> bool {
+    Instance::read_all_with_blocked_and_dead(pool)
+      .await
+      .unwrap()
+      .into_iter()
+      .find(|(i, _, _)| i.domain == domain)
+      .map(|(_, allowed, _)| allowed)
+      .unwrap()
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_wildcard_lists_for_delivery() {
+    let pool = &build_db_pool_for_tests().await;
+    let pool = &mut pool.into();
+
+    let sub = Instance::read_or_create(pool, "sub.example.org".to_string())
+      .await
+      .unwrap();
+    let other = Instance::read_or_create(pool, "notexample.org".to_string())
+      .await
+      .unwrap();
+
+    // a wildcard entry in the blocklist stops delivery to all subdomains
+    FederationBlockList::replace(pool, Some(vec!["*.example.org".to_string()]))
+      .await
+      .unwrap();
+    assert!(!allowed(pool, "sub.example.org").await);
+    assert!(allowed(pool, "notexample.org").await);
+    let linked = Instance::linked(pool).await.unwrap();
+    assert!(!linked.iter().any(|i| i.id == sub.id));
+    assert!(linked.iter().any(|i| i.id == other.id));
+
+    // the same entry in the allowlist allows delivery to them
+    FederationBlockList::replace(pool, Some(vec![]))
+      .await
+      .unwrap();
+    FederationAllowList::replace(pool, Some(vec!["*.example.org".to_string()]))
+      .await
+      .unwrap();
+    assert!(allowed(pool, "sub.example.org").await);
+    assert!(!allowed(pool, "notexample.org").await);
+
+    FederationAllowList::replace(pool, Some(vec![]))
+      .await
+      .unwrap();
+    let wildcard = Instance::read_or_create(pool, "*.example.org".to_string())
+      .await
+      .unwrap();
+    for instance in [sub, other, wildcard] {
+      Instance::delete(pool, instance.id).await.unwrap();
+    }
+  }
+}
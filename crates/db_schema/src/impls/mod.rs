@@ -1,5 +1,6 @@
 pub mod activity;
 pub mod actor_language;
+pub mod actor_name_alias;
 pub mod captcha_answer;
 pub mod comment;
 pub mod comment_reply;
@@ -10,6 +11,7 @@ pub mod custom_emoji;
 pub mod email_verification;
 pub mod federation_allowlist;
 pub mod federation_blocklist;
+pub mod federation_community_blocklist;
 pub mod image_upload;
 pub mod instance;
 pub mod instance_block;
@@ -23,10 +25,12 @@ pub mod password_reset_request;
 pub mod person;
 pub mod person_block;
 pub mod person_mention;
+pub mod poll;
 pub mod post;
 pub mod post_report;
 pub mod private_message;
 pub mod private_message_report;
+pub mod reaction;
 pub mod registration_application;
 pub mod secret;
 pub mod site;
@@ -11,7 +11,15 @@ use crate::{
   traits::{ApubActor, Crud, Followable},
   utils::{functions::lower, get_conn, naive_now, DbPool},
 };
-use diesel::{dsl::insert_into, result::Error, ExpressionMethods, JoinOnDsl, QueryDsl};
+use chrono::{DateTime, Utc};
+use diesel::{
+  dsl::{exists, insert_into},
+  result::Error,
+  select,
+  ExpressionMethods,
+  JoinOnDsl,
+  QueryDsl,
+};
 use diesel_async::RunQueryDsl;
 
 #[async_trait]
@@ -84,6 +92,23 @@ impl Person {
       .get_result::<Self>(conn)
       .await
   }
+
+  /// Remote users which were last fetched before `older_than`, least recently refreshed first.
+  pub async fn list_stale_remote(
+    pool: &mut DbPool<'_>,
+    older_than: DateTime<Utc>,
+    limit: i64,
+  ) -> Result<Vec<Self>, Error> {
+    let conn = &mut get_conn(pool).await?;
+    person::table
+      .filter(person::local.eq(false))
+      .filter(person::deleted.eq(false))
+      .filter(person::last_refreshed_at.lt(older_than))
+      .order_by(person::last_refreshed_at.asc())
+      .limit(limit)
+      .load::<Self>(conn)
+      .await
+  }
 }
 
 #[async_trait]
@@ -180,6 +205,23 @@ impl PersonFollower {
       .load(conn)
       .await
   }
+
+  /// Returns true if `follower_id` has an accepted follow of `for_person_id`.
+  pub async fn is_follower(
+    pool: &mut DbPool<'_>,
+    for_person_id: PersonId,
+    for_follower_id: PersonId,
+  ) -> Result<bool, Error> {
+    let conn = &mut get_conn(pool).await?;
+    select(exists(
+      person_follower::table
+        .filter(person_follower::person_id.eq(for_person_id))
+        .filter(person_follower::follower_id.eq(for_follower_id))
+        .filter(person_follower::pending.eq(false)),
+    ))
+    .get_result(conn)
+    .await
+  }
 }
 
 #[cfg(test)]
@@ -236,6 +278,8 @@ mod tests {
       shared_inbox_url: None,
       matrix_user_id: None,
       ban_expires: None,
+      also_known_as: vec![],
+      instance_admin: false,
       instance_id: inserted_instance.id,
     };
 
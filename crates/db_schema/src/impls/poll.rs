@@ -0,0 +1,233 @@
+use crate::{
+  newtypes::{PollId, PollOptionId, PostId},
+  schema::{poll, poll_option, poll_vote},
+  source::poll::{Poll, PollForm, PollOption, PollOptionForm, PollVoteForm},
+  utils::{get_conn, DbPool},
+};
+use diesel::{dsl::insert_into, result::Error, ExpressionMethods, OptionalExtension, QueryDsl};
+use diesel_async::RunQueryDsl;
+
+impl Poll {
+  /// Creates the poll of a post or updates it if it already exists. Options which are not in
+  /// `options` anymore are removed together with their votes, the vote counts of all others are
+  /// set to the given values.
+  pub async fn upsert(
+    pool: &mut DbPool<'_>,
+    form: &PollForm,
+    options: Vec<(String, i32)>,
+  ) -> Result<(Self, Vec<PollOption>), Error> {
+    let conn = &mut get_conn(pool).await?;
+    let form = form.clone();
+    conn
+      .build_transaction()
+      .run(|conn| {
+        Box::pin(async move {
+          let poll = insert_into(poll::table)
+            .values(&form)
+            .on_conflict(poll::post_id)
+            .do_update()
+            .set(&form)
+            .get_result::<Self>(conn)
+            .await?;
+
+          let names: Vec<_> = options.iter().map(|(name, _)| name.clone()).collect();
+          diesel::delete(
+            poll_option::table
+              .filter(poll_option::poll_id.eq(poll.id))
+              .filter(poll_option::name.ne_all(names)),
+          )
+          .execute(conn)
+          .await?;
+
+          for (name, vote_count) in options {
+            let form = PollOptionForm {
+              poll_id: poll.id,
+              name,
+              vote_count,
+            };
+            insert_into(poll_option::table)
+              .values(&form)
+              .on_conflict((poll_option::poll_id, poll_option::name))
+              .do_update()
+              .set(poll_option::vote_count.eq(vote_count))
+              .execute(conn)
+              .await?;
+          }
+          let options = Self::read_options(&mut conn.into(), poll.id).await?;
+          Ok((poll, options))
+        }) as _
+      })
+      .await
+  }
+
+  pub async fn read_for_post(
+    pool: &mut DbPool<'_>,
+    post_id: PostId,
+  ) -> Result<Option<Self>, Error> {
+    let conn = &mut get_conn(pool).await?;
+    poll::table
+      .filter(poll::post_id.eq(post_id))
+      .first::<Self>(conn)
+      .await
+      .optional()
+  }
+
+  /// Options of the poll, in the order in which they were created.
+  pub async fn read_options(
+    pool: &mut DbPool<'_>,
+    poll_id: PollId,
+  ) -> Result<Vec<PollOption>, Error> {
+    let conn = &mut get_conn(pool).await?;
+    poll_option::table
+      .filter(poll_option::poll_id.eq(poll_id))
+      .order_by(poll_option::id)
+      .get_results::<PollOption>(conn)
+      .await
+  }
+
+  /// Records the vote of a person for an option, and updates the vote count of the option.
+  /// In single choice polls, any previous vote of the person is replaced. Returns the number of
+  /// new votes, which is zero if the person already voted for the option.
+  pub async fn vote(&self, pool: &mut DbPool<'_>, form: &PollVoteForm) -> Result<usize, Error> {
+    let conn = &mut get_conn(pool).await?;
+    let form = form.clone();
+    let poll_id = self.id;
+    let multiple_choice = self.multiple_choice;
+    conn
+      .build_transaction()
+      .run(|conn| {
+        Box::pin(async move {
+          if !multiple_choice {
+            let poll_options = poll_option::table
+              .filter(poll_option::poll_id.eq(poll_id))
+              .select(poll_option::id);
+            let removed = diesel::delete(
+              poll_vote::table
+                .filter(poll_vote::person_id.eq(form.person_id))
+                .filter(poll_vote::poll_option_id.ne(form.poll_option_id))
+                .filter(poll_vote::poll_option_id.eq_any(poll_options)),
+            )
+            .returning(poll_vote::poll_option_id)
+            .get_results::<PollOptionId>(conn)
+            .await?;
+            diesel::update(poll_option::table.filter(poll_option::id.eq_any(removed)))
+              .set(poll_option::vote_count.eq(poll_option::vote_count - 1))
+              .execute(conn)
+              .await?;
+          }
+
+          let inserted = insert_into(poll_vote::table)
+            .values(&form)
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .await?;
+          if inserted > 0 {
+            diesel::update(poll_option::table.find(form.poll_option_id))
+              .set(poll_option::vote_count.eq(poll_option::vote_count + 1))
+              .execute(conn)
+              .await?;
+          }
+          Ok(inserted)
+        }) as _
+      })
+      .await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::{
+    source::{
+      community::{Community, CommunityInsertForm},
+      instance::Instance,
+      person::{Person, PersonInsertForm},
+      post::{Post, PostInsertForm},
+    },
+    traits::Crud,
+    utils::build_db_pool_for_tests,
+  };
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_poll_votes() {
+    let pool = &build_db_pool_for_tests().await;
+    let pool = &mut pool.into();
+
+    let instance = Instance::read_or_create(pool, "my_domain.tld".to_string())
+      .await
+      .unwrap();
+    let person_form = PersonInsertForm::builder()
+      .name("poll_voter".into())
+      .public_key("pubkey".to_string())
+      .instance_id(instance.id)
+      .build();
+    let person = Person::create(pool, &person_form).await.unwrap();
+    let community_form = CommunityInsertForm::builder()
+      .name("test_polls".to_string())
+      .title("nada".to_owned())
+      .public_key("pubkey".to_string())
+      .instance_id(instance.id)
+      .build();
+    let community = Community::create(pool, &community_form).await.unwrap();
+    let post_form = PostInsertForm::builder()
+      .name("A poll".into())
+      .creator_id(person.id)
+      .community_id(community.id)
+      .build();
+    let post = Post::create(pool, &post_form).await.unwrap();
+
+    let form = PollForm {
+      post_id: post.id,
+      ..Default::default()
+    };
+    let options = vec![("yes".to_string(), 0), ("no".to_string(), 0)];
+    let (poll, options) = Poll::upsert(pool, &form, options).await.unwrap();
+    assert_eq!(2, options.len());
+    assert_eq!(
+      Some(&poll),
+      Poll::read_for_post(pool, post.id).await.unwrap().as_ref()
+    );
+
+    let vote = |option: &PollOption| PollVoteForm {
+      poll_option_id: option.id,
+      person_id: person.id,
+    };
+    let counts = |options: Vec<PollOption>| {
+      options
+        .into_iter()
+        .map(|o| o.vote_count)
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(1, poll.vote(pool, &vote(&options[0])).await.unwrap());
+    assert_eq!(0, poll.vote(pool, &vote(&options[0])).await.unwrap());
+    assert_eq!(
+      vec![1, 0],
+      counts(Poll::read_options(pool, poll.id).await.unwrap())
+    );
+
+    // single choice, so voting again replaces the previous vote
+    assert_eq!(1, poll.vote(pool, &vote(&options[1])).await.unwrap());
+    assert_eq!(
+      vec![0, 1],
+      counts(Poll::read_options(pool, poll.id).await.unwrap())
+    );
+
+    // updating keeps existing options and removes missing ones
+    let options = vec![("no".to_string(), 5), ("maybe".to_string(), 2)];
+    let (_, options) = Poll::upsert(pool, &form, options).await.unwrap();
+    let names: Vec<_> = options.iter().map(|o| o.name.as_str()).collect();
+    assert_eq!(vec!["no", "maybe"], names);
+    assert_eq!(vec![5, 2], counts(options));
+
+    Post::delete(pool, post.id).await.unwrap();
+    assert_eq!(None, Poll::read_for_post(pool, post.id).await.unwrap());
+    Community::delete(pool, community.id).await.unwrap();
+    Person::delete(pool, person.id).await.unwrap();
+    Instance::delete(pool, instance.id).await.unwrap();
+  }
+}
@@ -8,6 +8,7 @@ use crate::{
     creator_id,
     deleted,
     featured_community,
+    featured_profile,
     local,
     name,
     post,
@@ -100,6 +101,50 @@ impl Post {
       .await
   }
 
+  pub async fn list_featured_for_person(
+    pool: &mut DbPool<'_>,
+    the_creator_id: PersonId,
+  ) -> Result<Vec<Self>, Error> {
+    let conn = &mut get_conn(pool).await?;
+    post
+      .filter(creator_id.eq(the_creator_id))
+      .filter(deleted.eq(false))
+      .filter(removed.eq(false))
+      .filter(featured_profile.eq(true))
+      .then_order_by(published.desc())
+      .limit(FETCH_LIMIT_MAX)
+      .load::<Self>(conn)
+      .await
+  }
+
+  /// Marks exactly the given posts as featured on the profile of their creator, and unfeatures
+  /// all other posts of the same creator.
+  pub async fn update_featured_for_person(
+    pool: &mut DbPool<'_>,
+    for_creator_id: PersonId,
+    featured_post_ids: Vec<PostId>,
+  ) -> Result<(), Error> {
+    use crate::schema::post::dsl::id;
+    let conn = &mut get_conn(pool).await?;
+    diesel::update(
+      post
+        .filter(creator_id.eq(for_creator_id))
+        .filter(id.ne_all(featured_post_ids.clone())),
+    )
+    .set(featured_profile.eq(false))
+    .execute(conn)
+    .await?;
+    diesel::update(
+      post
+        .filter(creator_id.eq(for_creator_id))
+        .filter(id.eq_any(featured_post_ids)),
+    )
+    .set(featured_profile.eq(true))
+    .execute(conn)
+    .await?;
+    Ok(())
+  }
+
   pub async fn list_for_sitemap(
     pool: &mut DbPool<'_>,
   ) -> Result<Vec<(DbUrl, chrono::DateTime<Utc>)>, Error> {
@@ -364,6 +409,7 @@ mod tests {
     },
     traits::{Crud, Likeable, Saveable},
     utils::build_db_pool_for_tests,
+    ReplyPolicy,
   };
   use serial_test::serial;
   use std::collections::HashSet;
@@ -432,6 +478,13 @@ mod tests {
       language_id: Default::default(),
       featured_community: false,
       featured_local: false,
+      featured_profile: false,
+      archived: false,
+      delete_reason: None,
+      reply_policy: ReplyPolicy::Everyone,
+      quote_url: None,
+      quoted_post_id: None,
+      default_comment_sort_type: None,
     };
 
     // Post Like
@@ -0,0 +1,143 @@
+use crate::{
+  newtypes::{CommentId, PostId},
+  schema::reaction,
+  source::reaction::{Reaction, ReactionForm},
+  utils::{get_conn, DbPool},
+};
+use diesel::{dsl::insert_into, result::Error, ExpressionMethods, PgExpressionMethods, QueryDsl};
+use diesel_async::RunQueryDsl;
+
+impl Reaction {
+  /// Stores the reaction. Returns the number of new reactions, which is zero if the person already
+  /// reacted with the same emoji.
+  pub async fn react(pool: &mut DbPool<'_>, form: &ReactionForm) -> Result<usize, Error> {
+    let conn = &mut get_conn(pool).await?;
+    insert_into(reaction::table)
+      .values(form)
+      .on_conflict_do_nothing()
+      .execute(conn)
+      .await
+  }
+
+  pub async fn remove(pool: &mut DbPool<'_>, form: &ReactionForm) -> Result<usize, Error> {
+    let conn = &mut get_conn(pool).await?;
+    diesel::delete(
+      reaction::table
+        .filter(reaction::person_id.eq(form.person_id))
+        .filter(reaction::post_id.eq(form.post_id))
+        .filter(reaction::comment_id.is_not_distinct_from(form.comment_id))
+        .filter(reaction::shortcode.eq(&form.shortcode)),
+    )
+    .execute(conn)
+    .await
+  }
+
+  /// Reactions to the post itself if `comment_id` is none, otherwise to the comment. Ordered by
+  /// the time they were made.
+  pub async fn list(
+    pool: &mut DbPool<'_>,
+    post_id: PostId,
+    comment_id: Option<CommentId>,
+  ) -> Result<Vec<Self>, Error> {
+    let conn = &mut get_conn(pool).await?;
+    reaction::table
+      .filter(reaction::post_id.eq(post_id))
+      .filter(reaction::comment_id.is_not_distinct_from(comment_id))
+      .order_by(reaction::published)
+      .then_order_by(reaction::id)
+      .get_results(conn)
+      .await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::{
+    source::{
+      comment::{Comment, CommentInsertForm},
+      community::{Community, CommunityInsertForm},
+      instance::Instance,
+      person::{Person, PersonInsertForm},
+      post::{Post, PostInsertForm},
+    },
+    traits::Crud,
+    utils::build_db_pool_for_tests,
+  };
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_reactions() {
+    let pool = &build_db_pool_for_tests().await;
+    let pool = &mut pool.into();
+
+    let instance = Instance::read_or_create(pool, "my_domain.tld".to_string())
+      .await
+      .unwrap();
+    let person_form = PersonInsertForm::builder()
+      .name("reacting_person".into())
+      .public_key("pubkey".to_string())
+      .instance_id(instance.id)
+      .build();
+    let person = Person::create(pool, &person_form).await.unwrap();
+    let community_form = CommunityInsertForm::builder()
+      .name("test_reactions".to_string())
+      .title("nada".to_owned())
+      .public_key("pubkey".to_string())
+      .instance_id(instance.id)
+      .build();
+    let community = Community::create(pool, &community_form).await.unwrap();
+    let post_form = PostInsertForm::builder()
+      .name("A post".into())
+      .creator_id(person.id)
+      .community_id(community.id)
+      .build();
+    let post = Post::create(pool, &post_form).await.unwrap();
+    let comment_form = CommentInsertForm::builder()
+      .content("A comment".into())
+      .creator_id(person.id)
+      .post_id(post.id)
+      .build();
+    let comment = Comment::create(pool, &comment_form, None).await.unwrap();
+
+    let post_reaction = ReactionForm {
+      person_id: person.id,
+      post_id: post.id,
+      comment_id: None,
+      shortcode: "👍".to_string(),
+    };
+    let comment_reaction = ReactionForm {
+      comment_id: Some(comment.id),
+      ..post_reaction.clone()
+    };
+    assert_eq!(1, Reaction::react(pool, &post_reaction).await.unwrap());
+    // the same reaction again is ignored
+    assert_eq!(0, Reaction::react(pool, &post_reaction).await.unwrap());
+    assert_eq!(1, Reaction::react(pool, &comment_reaction).await.unwrap());
+
+    let reactions = Reaction::list(pool, post.id, None).await.unwrap();
+    assert_eq!(1, reactions.len());
+    assert_eq!("👍", reactions[0].shortcode);
+    assert_eq!(None, reactions[0].comment_id);
+    let reactions = Reaction::list(pool, post.id, Some(comment.id))
+      .await
+      .unwrap();
+    assert_eq!(1, reactions.len());
+
+    // removing the reaction to the post keeps the one to the comment
+    assert_eq!(1, Reaction::remove(pool, &post_reaction).await.unwrap());
+    assert_eq!(0, Reaction::remove(pool, &post_reaction).await.unwrap());
+    let reactions = Reaction::list(pool, post.id, None).await.unwrap();
+    assert!(reactions.is_empty());
+    let reactions = Reaction::list(pool, post.id, Some(comment.id))
+      .await
+      .unwrap();
+    assert_eq!(1, reactions.len());
+
+    Instance::delete(pool, instance.id).await.unwrap();
+  }
+}
@@ -5,6 +5,14 @@ pub mod sql_types {
     #[diesel(postgres_type(name = "actor_type_enum"))]
     pub struct ActorTypeEnum;
 
+    #[derive(diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "comment_sort_type_enum"))]
+    pub struct CommentSortTypeEnum;
+
+    #[derive(diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "community_membership_mode_enum"))]
+    pub struct CommunityMembershipModeEnum;
+
     #[derive(diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "listing_type_enum"))]
     pub struct ListingTypeEnum;
@@ -21,11 +29,26 @@ pub mod sql_types {
     #[diesel(postgres_type(name = "registration_mode_enum"))]
     pub struct RegistrationModeEnum;
 
+    #[derive(diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "reply_policy_enum"))]
+    pub struct ReplyPolicyEnum;
+
     #[derive(diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "sort_type_enum"))]
     pub struct SortTypeEnum;
 }
 
+diesel::table! {
+    actor_name_alias (id) {
+        id -> Int4,
+        #[max_length = 255]
+        name -> Varchar,
+        person_id -> Nullable<Int4>,
+        community_id -> Nullable<Int4>,
+        published -> Timestamptz,
+    }
+}
+
 diesel::table! {
     admin_purge_comment (id) {
         id -> Int4,
@@ -76,6 +99,7 @@ diesel::table! {
 diesel::table! {
     use diesel::sql_types::*;
     use diesel_ltree::sql_types::Ltree;
+    use super::sql_types::ReplyPolicyEnum;
 
     comment (id) {
         id -> Int4,
@@ -92,6 +116,8 @@ diesel::table! {
         path -> Ltree,
         distinguished -> Bool,
         language_id -> Int4,
+        delete_reason -> Nullable<Text>,
+        reply_policy -> ReplyPolicyEnum,
     }
 }
 
@@ -154,6 +180,10 @@ diesel::table! {
 }
 
 diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::CommunityMembershipModeEnum;
+    use super::sql_types::SortTypeEnum;
+
     community (id) {
         id -> Int4,
         #[max_length = 255]
@@ -187,6 +217,10 @@ diesel::table! {
         moderators_url -> Nullable<Varchar>,
         #[max_length = 255]
         featured_url -> Nullable<Varchar>,
+        membership_mode -> CommunityMembershipModeEnum,
+        default_sort_type -> Nullable<SortTypeEnum>,
+        default_post_nsfw -> Bool,
+        primary_language_id -> Int4,
     }
 }
 
@@ -203,6 +237,7 @@ diesel::table! {
         users_active_month -> Int8,
         users_active_half_year -> Int8,
         hot_rank -> Float8,
+        federated_users_active -> Bool,
     }
 }
 
@@ -303,6 +338,26 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    federation_community_blocklist (id) {
+        id -> Int4,
+        community_id -> Int4,
+        published -> Timestamptz,
+        updated -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    federation_queue (id) {
+        id -> Int8,
+        instance_id -> Int4,
+        sent_activity_id -> Int8,
+        inbox -> Text,
+        attempts -> Int4,
+        next_retry -> Timestamptz,
+    }
+}
+
 diesel::table! {
     federation_queue_state (id) {
         id -> Int4,
@@ -334,6 +389,8 @@ diesel::table! {
         software -> Nullable<Varchar>,
         #[max_length = 255]
         version -> Nullable<Varchar>,
+        paused -> Bool,
+        blocked_by_remote -> Nullable<Timestamptz>,
     }
 }
 
@@ -385,6 +442,8 @@ diesel::table! {
         updated -> Nullable<Timestamptz>,
         registration_mode -> RegistrationModeEnum,
         reports_email_admins -> Bool,
+        federation_http_fetch_limit -> Nullable<Int4>,
+        federate_votes -> Bool,
     }
 }
 
@@ -625,6 +684,8 @@ diesel::table! {
         bot_account -> Bool,
         ban_expires -> Nullable<Timestamptz>,
         instance_id -> Int4,
+        also_known_as -> Array<Nullable<Text>>,
+        instance_admin -> Bool,
     }
 }
 
@@ -687,6 +748,38 @@ diesel::table! {
 }
 
 diesel::table! {
+    poll (id) {
+        id -> Int4,
+        post_id -> Int4,
+        multiple_choice -> Bool,
+        end_time -> Nullable<Timestamptz>,
+        published -> Timestamptz,
+        updated -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    poll_option (id) {
+        id -> Int4,
+        poll_id -> Int4,
+        name -> Text,
+        vote_count -> Int4,
+    }
+}
+
+diesel::table! {
+    poll_vote (poll_option_id, person_id) {
+        poll_option_id -> Int4,
+        person_id -> Int4,
+        published -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::ReplyPolicyEnum;
+    use super::sql_types::CommentSortTypeEnum;
+
     post (id) {
         id -> Int4,
         #[max_length = 200]
@@ -712,6 +805,14 @@ diesel::table! {
         language_id -> Int4,
         featured_community -> Bool,
         featured_local -> Bool,
+        featured_profile -> Bool,
+        archived -> Bool,
+        delete_reason -> Nullable<Text>,
+        reply_policy -> ReplyPolicyEnum,
+        #[max_length = 512]
+        quote_url -> Nullable<Varchar>,
+        quoted_post_id -> Nullable<Int4>,
+        default_comment_sort_type -> Nullable<CommentSortTypeEnum>,
     }
 }
 
@@ -813,11 +914,23 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    reaction (id) {
+        id -> Int4,
+        person_id -> Int4,
+        post_id -> Int4,
+        comment_id -> Nullable<Int4>,
+        shortcode -> Text,
+        published -> Timestamptz,
+    }
+}
+
 diesel::table! {
     received_activity (id) {
         id -> Int8,
         ap_id -> Text,
         published -> Timestamptz,
+        signature_algorithm -> Nullable<Text>,
     }
 }
 
@@ -857,6 +970,17 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    sent_activity_delivery (id) {
+        id -> Int8,
+        sent_activity_id -> Int8,
+        inbox -> Text,
+        success -> Bool,
+        error -> Nullable<Text>,
+        updated -> Timestamptz,
+    }
+}
+
 diesel::table! {
     site (id) {
         id -> Int4,
@@ -913,6 +1037,8 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(actor_name_alias -> community (community_id));
+diesel::joinable!(actor_name_alias -> person (person_id));
 diesel::joinable!(admin_purge_comment -> person (admin_person_id));
 diesel::joinable!(admin_purge_comment -> post (post_id));
 diesel::joinable!(admin_purge_community -> person (admin_person_id));
@@ -932,6 +1058,7 @@ diesel::joinable!(comment_report -> comment (comment_id));
 diesel::joinable!(comment_saved -> comment (comment_id));
 diesel::joinable!(comment_saved -> person (person_id));
 diesel::joinable!(community -> instance (instance_id));
+diesel::joinable!(community -> language (primary_language_id));
 diesel::joinable!(community_aggregates -> community (community_id));
 diesel::joinable!(community_block -> community (community_id));
 diesel::joinable!(community_block -> person (person_id));
@@ -948,6 +1075,9 @@ diesel::joinable!(custom_emoji_keyword -> custom_emoji (custom_emoji_id));
 diesel::joinable!(email_verification -> local_user (local_user_id));
 diesel::joinable!(federation_allowlist -> instance (instance_id));
 diesel::joinable!(federation_blocklist -> instance (instance_id));
+diesel::joinable!(federation_community_blocklist -> community (community_id));
+diesel::joinable!(federation_queue -> instance (instance_id));
+diesel::joinable!(federation_queue -> sent_activity (sent_activity_id));
 diesel::joinable!(federation_queue_state -> instance (instance_id));
 diesel::joinable!(image_upload -> local_user (local_user_id));
 diesel::joinable!(instance_block -> instance (instance_id));
@@ -981,6 +1111,10 @@ diesel::joinable!(person_mention -> comment (comment_id));
 diesel::joinable!(person_mention -> person (recipient_id));
 diesel::joinable!(person_post_aggregates -> person (person_id));
 diesel::joinable!(person_post_aggregates -> post (post_id));
+diesel::joinable!(poll -> post (post_id));
+diesel::joinable!(poll_option -> poll (poll_id));
+diesel::joinable!(poll_vote -> person (person_id));
+diesel::joinable!(poll_vote -> poll_option (poll_option_id));
 diesel::joinable!(post -> community (community_id));
 diesel::joinable!(post -> language (language_id));
 diesel::joinable!(post -> person (creator_id));
@@ -996,8 +1130,12 @@ diesel::joinable!(post_report -> post (post_id));
 diesel::joinable!(post_saved -> person (person_id));
 diesel::joinable!(post_saved -> post (post_id));
 diesel::joinable!(private_message_report -> private_message (private_message_id));
+diesel::joinable!(reaction -> comment (comment_id));
+diesel::joinable!(reaction -> person (person_id));
+diesel::joinable!(reaction -> post (post_id));
 diesel::joinable!(registration_application -> local_user (local_user_id));
 diesel::joinable!(registration_application -> person (admin_id));
+diesel::joinable!(sent_activity_delivery -> sent_activity (sent_activity_id));
 diesel::joinable!(site -> instance (instance_id));
 diesel::joinable!(site_aggregates -> site (site_id));
 diesel::joinable!(site_language -> language (language_id));
@@ -1005,6 +1143,7 @@ diesel::joinable!(site_language -> site (site_id));
 diesel::joinable!(tagline -> local_site (local_site_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    actor_name_alias,
     admin_purge_comment,
     admin_purge_community,
     admin_purge_person,
@@ -1028,6 +1167,8 @@ diesel::allow_tables_to_appear_in_same_query!(
     email_verification,
     federation_allowlist,
     federation_blocklist,
+    federation_community_blocklist,
+    federation_queue,
     federation_queue_state,
     image_upload,
     instance,
@@ -1057,6 +1198,9 @@ diesel::allow_tables_to_appear_in_same_query!(
     person_follower,
     person_mention,
     person_post_aggregates,
+    poll,
+    poll_option,
+    poll_vote,
     post,
     post_aggregates,
     post_like,
@@ -1065,10 +1209,12 @@ diesel::allow_tables_to_appear_in_same_query!(
     post_saved,
     private_message,
     private_message_report,
+    reaction,
     received_activity,
     registration_application,
     secret,
     sent_activity,
+    sent_activity_delivery,
     site,
     site_aggregates,
     site_language,
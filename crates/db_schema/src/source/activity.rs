@@ -1,6 +1,6 @@
 use crate::{
   newtypes::{CommunityId, DbUrl},
-  schema::sent_activity,
+  schema::{sent_activity, sent_activity_delivery},
 };
 use chrono::{DateTime, Utc};
 use diesel::{sql_types::Nullable, Queryable};
@@ -79,6 +79,29 @@ pub struct SentActivityForm {
   pub actor_apub_id: DbUrl,
 }
 
+/// Outcome of the latest attempt to deliver a sent activity to one inbox.
+#[derive(PartialEq, Eq, Debug, Queryable)]
+#[diesel(table_name = sent_activity_delivery)]
+pub struct SentActivityDelivery {
+  pub id: i64,
+  pub sent_activity_id: i64,
+  pub inbox: DbUrl,
+  pub success: bool,
+  /// Why the delivery failed, if it did
+  pub error: Option<String>,
+  pub updated: DateTime<Utc>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = sent_activity_delivery)]
+pub struct SentActivityDeliveryForm {
+  pub sent_activity_id: i64,
+  pub inbox: DbUrl,
+  pub success: bool,
+  pub error: Option<String>,
+  pub updated: DateTime<Utc>,
+}
+
 #[derive(Clone, Copy, Debug, diesel_derive_enum::DbEnum, PartialEq, Eq)]
 #[ExistingTypePath = "crate::schema::sql_types::ActorTypeEnum"]
 pub enum ActorType {
@@ -87,10 +110,24 @@ pub enum ActorType {
   Person,
 }
 
+/// Summary of the queues of activities which are sent to other instances.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FederationQueueStats {
+  /// Upper bound for the activities which are waiting to be sent, summed up over all instances
+  pub pending: i64,
+  /// Instances which activities are sent to
+  pub instances: usize,
+  /// Instances where the last delivery failed, these are retried with increasing delay
+  pub failing_instances: usize,
+}
+
 #[derive(PartialEq, Eq, Debug, Queryable)]
 #[diesel(table_name = received_activity)]
 pub struct ReceivedActivity {
   pub id: i64,
   pub ap_id: DbUrl,
   pub published: DateTime<Utc>,
+  /// HTTP signature scheme of the request which delivered the activity, like
+  /// `draft-cavage/rsa-sha256` or `rfc9421`
+  pub signature_algorithm: Option<String>,
 }
@@ -0,0 +1,27 @@
+use crate::newtypes::{CommunityId, PersonId};
+#[cfg(feature = "full")]
+use crate::schema::actor_name_alias;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A previous name of a local person or community, which is still resolved by webfinger after the
+/// actor was renamed. Exactly one of `person_id` and `community_id` is set.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "full", derive(Queryable, Selectable, Identifiable))]
+#[cfg_attr(feature = "full", diesel(table_name = actor_name_alias))]
+pub struct ActorNameAlias {
+  pub id: i32,
+  pub name: String,
+  pub person_id: Option<PersonId>,
+  pub community_id: Option<CommunityId>,
+  pub published: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "full", derive(Insertable))]
+#[cfg_attr(feature = "full", diesel(table_name = actor_name_alias))]
+pub struct ActorNameAliasForm {
+  pub name: String,
+  pub person_id: Option<PersonId>,
+  pub community_id: Option<CommunityId>,
+}
@@ -1,8 +1,11 @@
 #[cfg(feature = "full")]
 use crate::newtypes::LtreeDef;
-use crate::newtypes::{CommentId, DbUrl, LanguageId, PersonId, PostId};
 #[cfg(feature = "full")]
 use crate::schema::{comment, comment_like, comment_saved};
+use crate::{
+  newtypes::{CommentId, DbUrl, LanguageId, PersonId, PostId},
+  ReplyPolicy,
+};
 use chrono::{DateTime, Utc};
 #[cfg(feature = "full")]
 use diesel_ltree::Ltree;
@@ -44,6 +47,10 @@ pub struct Comment {
   /// Whether the comment has been distinguished(speaking officially) by a mod.
   pub distinguished: bool,
   pub language_id: LanguageId,
+  /// An optional note left by the creator when deleting the comment.
+  pub delete_reason: Option<String>,
+  /// Who is allowed to reply to the comment.
+  pub reply_policy: ReplyPolicy,
 }
 
 #[derive(Debug, Clone, TypedBuilder)]
@@ -65,6 +72,7 @@ pub struct CommentInsertForm {
   pub local: Option<bool>,
   pub distinguished: Option<bool>,
   pub language_id: Option<LanguageId>,
+  pub reply_policy: Option<ReplyPolicy>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -80,6 +88,8 @@ pub struct CommentUpdateForm {
   pub local: Option<bool>,
   pub distinguished: Option<bool>,
   pub language_id: Option<LanguageId>,
+  pub delete_reason: Option<Option<String>>,
+  pub reply_policy: Option<ReplyPolicy>,
 }
 
 #[derive(PartialEq, Eq, Debug, Clone)]
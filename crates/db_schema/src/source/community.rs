@@ -1,8 +1,10 @@
 #[cfg(feature = "full")]
 use crate::schema::{community, community_follower, community_moderator, community_person_ban};
 use crate::{
-  newtypes::{CommunityId, DbUrl, InstanceId, PersonId},
+  newtypes::{CommunityId, DbUrl, InstanceId, LanguageId, PersonId},
   source::placeholder_apub_url,
+  CommunityMembershipMode,
+  SortType,
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -65,6 +67,14 @@ pub struct Community {
   /// Url where featured posts collection is served over Activitypub
   #[serde(skip)]
   pub featured_url: Option<DbUrl>,
+  /// Whether users need an approved application to participate in the community.
+  pub membership_mode: CommunityMembershipMode,
+  /// The sort which is used by default when browsing the community.
+  pub default_sort_type: Option<SortType>,
+  /// Whether posts in the community are marked as NSFW, unless the creator specifies otherwise.
+  pub default_post_nsfw: bool,
+  /// The main language of the community, used for discovery. Undetermined if not set.
+  pub primary_language_id: LanguageId,
 }
 
 #[derive(Debug, Clone, TypedBuilder)]
@@ -98,6 +108,10 @@ pub struct CommunityInsertForm {
   pub posting_restricted_to_mods: Option<bool>,
   #[builder(!default)]
   pub instance_id: InstanceId,
+  pub membership_mode: Option<CommunityMembershipMode>,
+  pub default_sort_type: Option<SortType>,
+  pub default_post_nsfw: Option<bool>,
+  pub primary_language_id: Option<LanguageId>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -125,6 +139,10 @@ pub struct CommunityUpdateForm {
   pub featured_url: Option<DbUrl>,
   pub hidden: Option<bool>,
   pub posting_restricted_to_mods: Option<bool>,
+  pub membership_mode: Option<CommunityMembershipMode>,
+  pub default_sort_type: Option<Option<SortType>>,
+  pub default_post_nsfw: Option<bool>,
+  pub primary_language_id: Option<LanguageId>,
 }
 
 #[derive(PartialEq, Eq, Debug)]
@@ -0,0 +1,30 @@
+use crate::newtypes::CommunityId;
+#[cfg(feature = "full")]
+use crate::schema::federation_community_blocklist;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+/// A remote community which doesn't federate with the local instance, even though its instance is
+/// allowed.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "full", derive(Queryable, Associations, Identifiable))]
+#[cfg_attr(
+  feature = "full",
+  diesel(belongs_to(crate::source::community::Community))
+)]
+#[cfg_attr(feature = "full", diesel(table_name = federation_community_blocklist))]
+pub struct FederationCommunityBlockList {
+  pub id: i32,
+  pub community_id: CommunityId,
+  pub published: DateTime<Utc>,
+  pub updated: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "full", derive(Insertable, AsChangeset))]
+#[cfg_attr(feature = "full", diesel(table_name = federation_community_blocklist))]
+pub struct FederationCommunityBlockListForm {
+  pub community_id: CommunityId,
+  pub updated: Option<DateTime<Utc>>,
+}
@@ -22,6 +22,11 @@ pub struct Instance {
   pub updated: Option<DateTime<Utc>>,
   pub software: Option<String>,
   pub version: Option<String>,
+  /// Federation with the instance is temporarily stopped, without blocking it.
+  pub paused: bool,
+  /// When a delivery to the instance was last rejected because it blocked this instance.
+  /// Deliveries are paused for a while afterwards.
+  pub blocked_by_remote: Option<DateTime<Utc>>,
 }
 
 #[derive(Clone, TypedBuilder)]
@@ -34,4 +39,5 @@ pub struct InstanceForm {
   pub software: Option<String>,
   pub version: Option<String>,
   pub updated: Option<DateTime<Utc>>,
+  pub paused: Option<bool>,
 }
@@ -60,6 +60,12 @@ pub struct LocalSite {
   pub registration_mode: RegistrationMode,
   /// Whether to email admins on new reports.
   pub reports_email_admins: bool,
+  /// The max number of http requests to resolve a federated object. If not set, the value from
+  /// the config file is used.
+  pub federation_http_fetch_limit: Option<i32>,
+  /// Whether votes are sent to and accepted from other instances. Posts and comments federate
+  /// either way.
+  pub federate_votes: bool,
 }
 
 #[derive(Clone, TypedBuilder)]
@@ -88,6 +94,8 @@ pub struct LocalSiteInsertForm {
   pub captcha_difficulty: Option<String>,
   pub registration_mode: Option<RegistrationMode>,
   pub reports_email_admins: Option<bool>,
+  pub federation_http_fetch_limit: Option<i32>,
+  pub federate_votes: Option<bool>,
 }
 
 #[derive(Clone, Default)]
@@ -113,5 +121,7 @@ pub struct LocalSiteUpdateForm {
   pub captcha_difficulty: Option<String>,
   pub registration_mode: Option<RegistrationMode>,
   pub reports_email_admins: Option<bool>,
+  pub federation_http_fetch_limit: Option<Option<i32>>,
+  pub federate_votes: Option<bool>,
   pub updated: Option<Option<DateTime<Utc>>>,
 }
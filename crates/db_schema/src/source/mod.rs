@@ -4,6 +4,7 @@ use url::Url;
 #[cfg(feature = "full")]
 pub mod activity;
 pub mod actor_language;
+pub mod actor_name_alias;
 pub mod captcha_answer;
 pub mod comment;
 pub mod comment_reply;
@@ -15,6 +16,7 @@ pub mod custom_emoji_keyword;
 pub mod email_verification;
 pub mod federation_allowlist;
 pub mod federation_blocklist;
+pub mod federation_community_blocklist;
 pub mod image_upload;
 pub mod instance;
 pub mod instance_block;
@@ -28,10 +30,12 @@ pub mod password_reset_request;
 pub mod person;
 pub mod person_block;
 pub mod person_mention;
+pub mod poll;
 pub mod post;
 pub mod post_report;
 pub mod private_message;
 pub mod private_message_report;
+pub mod reaction;
 pub mod registration_application;
 pub mod secret;
 pub mod site;
@@ -56,6 +56,12 @@ pub struct Person {
   /// When their ban, if it exists, expires, if at all.
   pub ban_expires: Option<DateTime<Utc>>,
   pub instance_id: InstanceId,
+  /// Other actor ids of this person, from accounts which were moved here.
+  #[serde(skip)]
+  pub also_known_as: Vec<Option<DbUrl>>,
+  /// Whether the person is an admin of their home instance. This is only for display, local
+  /// permissions are determined by `LocalUser::admin`.
+  pub instance_admin: bool,
 }
 
 #[derive(Clone, TypedBuilder)]
@@ -86,6 +92,8 @@ pub struct PersonInsertForm {
   pub matrix_user_id: Option<String>,
   pub bot_account: Option<bool>,
   pub ban_expires: Option<DateTime<Utc>>,
+  pub also_known_as: Option<Vec<Option<DbUrl>>>,
+  pub instance_admin: Option<bool>,
 }
 
 #[derive(Clone, Default)]
@@ -109,6 +117,8 @@ pub struct PersonUpdateForm {
   pub matrix_user_id: Option<Option<String>>,
   pub bot_account: Option<bool>,
   pub ban_expires: Option<Option<DateTime<Utc>>>,
+  pub also_known_as: Option<Vec<Option<DbUrl>>>,
+  pub instance_admin: Option<bool>,
 }
 
 #[derive(PartialEq, Eq, Debug)]
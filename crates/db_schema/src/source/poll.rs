@@ -0,0 +1,73 @@
+use crate::newtypes::{PersonId, PollId, PollOptionId, PostId};
+#[cfg(feature = "full")]
+use crate::schema::{poll, poll_option, poll_vote};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+#[cfg(feature = "full")]
+use ts_rs::TS;
+
+#[skip_serializing_none]
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "full", derive(Queryable, Selectable, Identifiable, TS))]
+#[cfg_attr(feature = "full", diesel(table_name = poll))]
+#[cfg_attr(feature = "full", ts(export))]
+/// A poll which is attached to a post.
+pub struct Poll {
+  pub id: PollId,
+  pub post_id: PostId,
+  /// Whether voters may choose more than one option.
+  pub multiple_choice: bool,
+  /// After this time no more votes are accepted.
+  pub end_time: Option<DateTime<Utc>>,
+  pub published: DateTime<Utc>,
+  pub updated: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "full", derive(Insertable, AsChangeset))]
+#[cfg_attr(feature = "full", diesel(table_name = poll))]
+pub struct PollForm {
+  pub post_id: PostId,
+  pub multiple_choice: bool,
+  pub end_time: Option<DateTime<Utc>>,
+  pub updated: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "full", derive(Queryable, Selectable, Identifiable, TS))]
+#[cfg_attr(feature = "full", diesel(table_name = poll_option))]
+#[cfg_attr(feature = "full", ts(export))]
+/// One of the options which can be chosen in a poll.
+pub struct PollOption {
+  pub id: PollOptionId,
+  pub poll_id: PollId,
+  pub name: String,
+  pub vote_count: i32,
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "full", derive(Insertable, AsChangeset))]
+#[cfg_attr(feature = "full", diesel(table_name = poll_option))]
+pub struct PollOptionForm {
+  pub poll_id: PollId,
+  pub name: String,
+  pub vote_count: i32,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "full", derive(Queryable, Selectable))]
+#[cfg_attr(feature = "full", diesel(table_name = poll_vote))]
+pub struct PollVote {
+  pub poll_option_id: PollOptionId,
+  pub person_id: PersonId,
+  pub published: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "full", derive(Insertable))]
+#[cfg_attr(feature = "full", diesel(table_name = poll_vote))]
+pub struct PollVoteForm {
+  pub poll_option_id: PollOptionId,
+  pub person_id: PersonId,
+}
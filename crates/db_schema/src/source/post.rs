@@ -1,6 +1,10 @@
-use crate::newtypes::{CommunityId, DbUrl, LanguageId, PersonId, PostId};
 #[cfg(feature = "full")]
 use crate::schema::{post, post_like, post_read, post_saved};
+use crate::{
+  newtypes::{CommunityId, DbUrl, LanguageId, PersonId, PostId},
+  CommentSortType,
+  ReplyPolicy,
+};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
@@ -54,6 +58,22 @@ pub struct Post {
   pub featured_community: bool,
   /// Whether the post is featured to its site.
   pub featured_local: bool,
+  /// Whether the post is featured on the creator's profile.
+  pub featured_profile: bool,
+  /// Whether the post was archived because of its age. Unlike a mod lock, this doesn't imply
+  /// any wrongdoing, but new comments are rejected all the same.
+  pub archived: bool,
+  /// An optional note left by the creator when deleting the post.
+  pub delete_reason: Option<String>,
+  /// Who is allowed to comment on the post.
+  pub reply_policy: ReplyPolicy,
+  #[cfg_attr(feature = "full", ts(type = "string"))]
+  /// The url of another post which this post quotes.
+  pub quote_url: Option<DbUrl>,
+  /// The quoted post, if it is known locally.
+  pub quoted_post_id: Option<PostId>,
+  /// The sort which is used by default when viewing the comments of the post.
+  pub default_comment_sort_type: Option<CommentSortType>,
 }
 
 #[derive(Debug, Clone, TypedBuilder)]
@@ -84,6 +104,12 @@ pub struct PostInsertForm {
   pub language_id: Option<LanguageId>,
   pub featured_community: Option<bool>,
   pub featured_local: Option<bool>,
+  pub featured_profile: Option<bool>,
+  pub archived: Option<bool>,
+  pub reply_policy: Option<ReplyPolicy>,
+  pub quote_url: Option<DbUrl>,
+  pub quoted_post_id: Option<PostId>,
+  pub default_comment_sort_type: Option<CommentSortType>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -108,6 +134,13 @@ pub struct PostUpdateForm {
   pub language_id: Option<LanguageId>,
   pub featured_community: Option<bool>,
   pub featured_local: Option<bool>,
+  pub featured_profile: Option<bool>,
+  pub archived: Option<bool>,
+  pub delete_reason: Option<Option<String>>,
+  pub reply_policy: Option<ReplyPolicy>,
+  pub quote_url: Option<Option<DbUrl>>,
+  pub quoted_post_id: Option<Option<PostId>>,
+  pub default_comment_sort_type: Option<Option<CommentSortType>>,
 }
 
 #[derive(PartialEq, Eq, Debug)]
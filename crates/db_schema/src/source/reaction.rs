@@ -0,0 +1,33 @@
+use crate::newtypes::{CommentId, PersonId, PostId};
+#[cfg(feature = "full")]
+use crate::schema::reaction;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "full")]
+use ts_rs::TS;
+
+/// An emoji reaction of a person to a post or comment.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "full", derive(Queryable, Selectable, Identifiable, TS))]
+#[cfg_attr(feature = "full", diesel(table_name = reaction))]
+#[cfg_attr(feature = "full", ts(export))]
+pub struct Reaction {
+  pub id: i32,
+  pub person_id: PersonId,
+  /// For reactions to a comment, this is the post of the comment.
+  pub post_id: PostId,
+  pub comment_id: Option<CommentId>,
+  /// Unicode emoji or the shortcode of a custom emoji, like `:blobcat:`.
+  pub shortcode: String,
+  pub published: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "full", derive(Insertable))]
+#[cfg_attr(feature = "full", diesel(table_name = reaction))]
+pub struct ReactionForm {
+  pub person_id: PersonId,
+  pub post_id: PostId,
+  pub comment_id: Option<CommentId>,
+  pub shortcode: String,
+}
@@ -220,6 +220,7 @@ mod tests {
   };
   use lemmy_db_schema::{
     aggregates::structs::CommentAggregates,
+    impls::actor_language::UNDETERMINED_ID,
     source::{
       comment::{Comment, CommentInsertForm},
       comment_report::{CommentReport, CommentReportForm},
@@ -231,6 +232,7 @@ mod tests {
     },
     traits::{Crud, Joinable, Reportable},
     utils::build_db_pool_for_tests,
+    CommunityMembershipMode,
   };
   use serial_test::serial;
 
@@ -366,6 +368,10 @@ mod tests {
         banner: None,
         hidden: false,
         posting_restricted_to_mods: false,
+        membership_mode: CommunityMembershipMode::Open,
+        default_sort_type: None,
+        default_post_nsfw: false,
+        primary_language_id: UNDETERMINED_ID,
         published: inserted_community.published,
         private_key: inserted_community.private_key,
         public_key: inserted_community.public_key,
@@ -395,6 +401,8 @@ mod tests {
         shared_inbox_url: None,
         matrix_user_id: None,
         ban_expires: None,
+        also_known_as: vec![],
+        instance_admin: false,
         instance_id: inserted_instance.id,
         private_key: inserted_jessica.private_key,
         public_key: inserted_jessica.public_key,
@@ -418,6 +426,8 @@ mod tests {
         shared_inbox_url: None,
         matrix_user_id: None,
         ban_expires: None,
+        also_known_as: vec![],
+        instance_admin: false,
         instance_id: inserted_instance.id,
         private_key: inserted_timmy.private_key.clone(),
         public_key: inserted_timmy.public_key.clone(),
@@ -461,6 +471,8 @@ mod tests {
       shared_inbox_url: None,
       matrix_user_id: None,
       ban_expires: None,
+      also_known_as: vec![],
+      instance_admin: false,
       instance_id: inserted_instance.id,
       private_key: inserted_sara.private_key,
       public_key: inserted_sara.public_key,
@@ -529,6 +541,8 @@ mod tests {
       shared_inbox_url: None,
       matrix_user_id: None,
       ban_expires: None,
+      also_known_as: vec![],
+      instance_admin: false,
       instance_id: inserted_instance.id,
     });
 
@@ -364,6 +364,8 @@ mod tests {
     },
     traits::{Blockable, Crud, Joinable, Likeable},
     utils::build_db_pool_for_tests,
+    CommunityMembershipMode,
+    ReplyPolicy,
     SubscribedType,
   };
   use serial_test::serial;
@@ -873,6 +875,8 @@ mod tests {
         distinguished: false,
         path: data.inserted_comment_0.clone().path,
         language_id: LanguageId(37),
+        delete_reason: None,
+        reply_policy: ReplyPolicy::Everyone,
       },
       creator: Person {
         id: data.local_user_view.person.id,
@@ -892,6 +896,8 @@ mod tests {
         shared_inbox_url: None,
         matrix_user_id: None,
         ban_expires: None,
+        also_known_as: vec![],
+        instance_admin: false,
         instance_id: data.inserted_instance.id,
         private_key: data.local_user_view.person.private_key.clone(),
         public_key: data.local_user_view.person.public_key.clone(),
@@ -919,6 +925,13 @@ mod tests {
         language_id: Default::default(),
         featured_community: false,
         featured_local: false,
+        featured_profile: false,
+        archived: false,
+        delete_reason: None,
+        reply_policy: ReplyPolicy::Everyone,
+        quote_url: None,
+        quoted_post_id: None,
+        default_comment_sort_type: None,
       },
       community: Community {
         id: data.inserted_community.id,
@@ -935,6 +948,10 @@ mod tests {
         banner: None,
         hidden: false,
         posting_restricted_to_mods: false,
+        membership_mode: CommunityMembershipMode::Open,
+        default_sort_type: None,
+        default_post_nsfw: false,
+        primary_language_id: UNDETERMINED_ID,
         published: data.inserted_community.published,
         instance_id: data.inserted_instance.id,
         private_key: data.inserted_community.private_key.clone(),
@@ -725,6 +725,8 @@ mod tests {
     },
     traits::{Blockable, Crud, Joinable, Likeable},
     utils::{build_db_pool_for_tests, DbPool},
+    CommunityMembershipMode,
+    ReplyPolicy,
     SortType,
     SubscribedType,
   };
@@ -1407,6 +1409,13 @@ mod tests {
         language_id: LanguageId(47),
         featured_community: false,
         featured_local: false,
+        featured_profile: false,
+        archived: false,
+        delete_reason: None,
+        reply_policy: ReplyPolicy::Everyone,
+        quote_url: None,
+        quoted_post_id: None,
+        default_comment_sort_type: None,
       },
       my_vote: None,
       unread_comments: 0,
@@ -1428,6 +1437,8 @@ mod tests {
         shared_inbox_url: None,
         matrix_user_id: None,
         ban_expires: None,
+        also_known_as: vec![],
+        instance_admin: false,
         instance_id: data.inserted_instance.id,
         private_key: inserted_person.private_key.clone(),
         public_key: inserted_person.public_key.clone(),
@@ -1450,6 +1461,10 @@ mod tests {
         banner: None,
         hidden: false,
         posting_restricted_to_mods: false,
+        membership_mode: CommunityMembershipMode::Open,
+        default_sort_type: None,
+        default_post_nsfw: false,
+        primary_language_id: UNDETERMINED_ID,
         published: inserted_community.published,
         instance_id: data.inserted_instance.id,
         private_key: inserted_community.private_key.clone(),
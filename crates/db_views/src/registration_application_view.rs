@@ -279,6 +279,8 @@ mod tests {
         local: true,
         banned: false,
         ban_expires: None,
+        also_known_as: vec![],
+        instance_admin: false,
         deleted: false,
         bot_account: false,
         bio: None,
@@ -357,6 +359,8 @@ mod tests {
       local: true,
       banned: false,
       ban_expires: None,
+      also_known_as: vec![],
+      instance_admin: false,
       deleted: false,
       bot_account: false,
       bio: None,
@@ -11,7 +11,7 @@ use diesel::{
 };
 use diesel_async::RunQueryDsl;
 use lemmy_db_schema::{
-  newtypes::{CommunityId, PersonId},
+  newtypes::{CommunityId, LanguageId, PersonId},
   schema::{
     community,
     community_aggregates,
@@ -109,6 +109,10 @@ fn queries<'a>() -> Queries<
         .or_filter(community::title.ilike(searcher))
     }
 
+    if let Some(primary_language_id) = options.primary_language_id {
+      query = query.filter(community::primary_language_id.eq(primary_language_id));
+    }
+
     // Hide deleted and removed for non-admins or mods
     if !options.is_mod_or_admin {
       query = query.filter(not_removed_or_deleted).filter(
@@ -204,6 +208,7 @@ pub struct CommunityQuery<'a> {
   pub search_term: Option<String>,
   pub is_mod_or_admin: bool,
   pub show_nsfw: bool,
+  pub primary_language_id: Option<LanguageId>,
   pub page: Option<i64>,
   pub limit: Option<i64>,
 }
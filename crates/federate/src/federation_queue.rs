@@ -0,0 +1,338 @@
+use crate::util::{retry_sleep_duration, ActivityId};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use lemmy_db_schema::{
+  newtypes::{DbUrl, InstanceId},
+  utils::{get_conn, DbPool},
+};
+use reqwest::Url;
+use std::future::Future;
+
+/// Number of queued deliveries which are retried at once.
+const RETRY_BATCH_SIZE: i64 = 100;
+
+/// A delivery of an activity to a single inbox which failed with a retriable error (network
+/// error, timeout or 5xx response). It is retried with exponential backoff by the worker of the
+/// instance, until it succeeds or the activity is removed from `sent_activity`. Permanent
+/// failures (4xx responses) are dropped by the federation library and never end up here.
+#[derive(Queryable, Selectable, Clone, Debug)]
+#[diesel(table_name = lemmy_db_schema::schema::federation_queue)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct QueuedDelivery {
+  pub id: i64,
+  pub sent_activity_id: ActivityId,
+  pub inbox: DbUrl,
+  pub attempts: i32,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = lemmy_db_schema::schema::federation_queue)]
+struct QueuedDeliveryForm {
+  instance_id: InstanceId,
+  sent_activity_id: ActivityId,
+  inbox: DbUrl,
+  attempts: i32,
+  next_retry: DateTime<Utc>,
+}
+
+/// Outcome of retrying the queued deliveries of an instance.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct RetryOutcome {
+  pub delivered: usize,
+  pub failed: bool,
+}
+
+impl QueuedDelivery {
+  /// Queue the delivery of the activity to the inbox for retry, after the first attempt failed.
+  /// If it is already queued, nothing changes.
+  pub async fn enqueue(
+    pool: &mut DbPool<'_>,
+    instance_id: InstanceId,
+    sent_activity_id: ActivityId,
+    inbox: &Url,
+  ) -> Result<()> {
+    use lemmy_db_schema::schema::federation_queue::dsl::federation_queue;
+    let conn = &mut get_conn(pool).await?;
+    let form = QueuedDeliveryForm {
+      instance_id,
+      sent_activity_id,
+      inbox: inbox.clone().into(),
+      attempts: 1,
+      next_retry: Utc::now() + backoff(1),
+    };
+    diesel::insert_into(federation_queue)
+      .values(form)
+      .on_conflict_do_nothing()
+      .execute(conn)
+      .await?;
+    Ok(())
+  }
+
+  /// Deliveries to the instance which are due for a retry at the given time, oldest first.
+  pub async fn read_due(
+    pool: &mut DbPool<'_>,
+    instance_id_: InstanceId,
+    now: DateTime<Utc>,
+  ) -> Result<Vec<Self>> {
+    use lemmy_db_schema::schema::federation_queue::dsl::{
+      federation_queue,
+      instance_id,
+      next_retry,
+      sent_activity_id,
+    };
+    let conn = &mut get_conn(pool).await?;
+    Ok(
+      federation_queue
+        .filter(instance_id.eq(instance_id_))
+        .filter(next_retry.le(now))
+        .order_by(sent_activity_id.asc())
+        .limit(RETRY_BATCH_SIZE)
+        .select(Self::as_select())
+        .load(conn)
+        .await?,
+    )
+  }
+
+  /// Remove the delivery from the queue, after it succeeded.
+  async fn delete(&self, pool: &mut DbPool<'_>) -> Result<()> {
+    use lemmy_db_schema::schema::federation_queue::dsl::federation_queue;
+    let conn = &mut get_conn(pool).await?;
+    diesel::delete(federation_queue.find(self.id))
+      .execute(conn)
+      .await?;
+    Ok(())
+  }
+
+  /// Register another failed attempt, which increases the delay before the next one.
+  async fn reschedule(&self, pool: &mut DbPool<'_>, now: DateTime<Utc>) -> Result<()> {
+    use lemmy_db_schema::schema::federation_queue::dsl::{attempts, federation_queue, next_retry};
+    let conn = &mut get_conn(pool).await?;
+    let attempts_ = self.attempts + 1;
+    diesel::update(federation_queue.find(self.id))
+      .set((
+        attempts.eq(attempts_),
+        next_retry.eq(now + backoff(attempts_)),
+      ))
+      .execute(conn)
+      .await?;
+    Ok(())
+  }
+}
+
+fn backoff(attempts: i32) -> chrono::Duration {
+  chrono::Duration::from_std(retry_sleep_duration(attempts)).expect("delay is capped")
+}
+
+/// Retry the deliveries to the instance which are due at `now`. Deliveries which succeed are
+/// removed from the queue, and the others are rescheduled. As a failure most likely means that the
+/// instance is still unreachable, the remaining deliveries are left for later.
+pub(crate) async fn retry_queued_deliveries<F, Fut>(
+  pool: &mut DbPool<'_>,
+  instance_id: InstanceId,
+  now: DateTime<Utc>,
+  mut deliver: F,
+) -> Result<RetryOutcome>
+where
+  F: FnMut(QueuedDelivery) -> Fut,
+  Fut: Future<Output = Result<()>>,
+{
+  let mut outcome = RetryOutcome::default();
+  for queued in QueuedDelivery::read_due(pool, instance_id, now).await? {
+    match deliver(queued.clone()).await {
+      Ok(()) => {
+        queued.delete(pool).await?;
+        outcome.delivered += 1;
+      }
+      Err(e) => {
+        tracing::info!(
+          "retrying {} to {} failed on attempt {}. ({e})",
+          queued.sent_activity_id,
+          queued.inbox,
+          queued.attempts + 1
+        );
+        queued.reschedule(pool, now).await?;
+        outcome.failed = true;
+        break;
+      }
+    }
+  }
+  Ok(outcome)
+}
+
+#[cfg(test)]
+mod test {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use anyhow::anyhow;
+  use lemmy_db_schema::{
+    source::{
+      activity::{ActorType, SentActivity, SentActivityForm},
+      instance::Instance,
+    },
+    utils::build_db_pool_for_tests,
+  };
+  use reqwest::{Request, Response};
+  use reqwest_middleware::{ClientBuilder, ClientWithMiddleware, Middleware, Next};
+  use serde_json::json;
+  use serial_test::serial;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  use task_local_extensions::Extensions;
+
+  /// Answers the first request with 503, like an instance which is temporarily down, and all
+  /// further ones with 200.
+  #[derive(Default)]
+  struct UnavailableOnceMiddleware {
+    requests: AtomicUsize,
+  }
+
+  #[async_trait::async_trait]
+  impl Middleware for UnavailableOnceMiddleware {
+    async fn handle(
+      &self,
+      _req: Request,
+      _extensions: &mut Extensions,
+      _next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+      let status = if self.requests.fetch_add(1, Ordering::SeqCst) == 0 {
+        503
+      } else {
+        200
+      };
+      let res = http::Response::builder()
+        .status(status)
+        .body(String::new())
+        .unwrap();
+      Ok(res.into())
+    }
+  }
+
+  /// Posts to the inbox. Like the federation library, server errors fail the delivery.
+  async fn post(client: &ClientWithMiddleware, inbox: Url) -> Result<()> {
+    let res = client.post(inbox.clone()).send().await?;
+    if res.status().is_server_error() {
+      return Err(anyhow!("{inbox} responded with {}", res.status()));
+    }
+    Ok(())
+  }
+
+  async fn create_activity(pool: &mut DbPool<'_>) -> SentActivity {
+    let id = Utc::now().timestamp_nanos_opt().unwrap();
+    let ap_id = Url::parse(&format!("http://ds9.lemmy.ml/activities/like/{id}")).unwrap();
+    let form = SentActivityForm {
+      ap_id: ap_id.clone().into(),
+      data: json!({ "id": ap_id, "type": "Like" }),
+      sensitive: false,
+      actor_apub_id: Url::parse("http://ds9.lemmy.ml/u/lemmy_alpha")
+        .unwrap()
+        .into(),
+      actor_type: ActorType::Person,
+      send_all_instances: true,
+      send_community_followers_of: None,
+      send_inboxes: vec![],
+    };
+    SentActivity::create(pool, form).await.unwrap()
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_retry_after_unavailable() {
+    let pool = &build_db_pool_for_tests().await;
+    let pool = &mut pool.into();
+
+    let instance = Instance::read_or_create(pool, "retry.example.com".to_string())
+      .await
+      .unwrap();
+    let activity = create_activity(pool).await;
+    let inbox = Url::parse("https://retry.example.com/inbox").unwrap();
+
+    let client = ClientBuilder::new(reqwest::Client::default())
+      .with(UnavailableOnceMiddleware::default())
+      .build();
+    // the first delivery fails with 503, so it is queued
+    post(&client, inbox.clone()).await.unwrap_err();
+    QueuedDelivery::enqueue(pool, instance.id, activity.id, &inbox)
+      .await
+      .unwrap();
+    // enqueuing again doesn't add a second retry
+    QueuedDelivery::enqueue(pool, instance.id, activity.id, &inbox)
+      .await
+      .unwrap();
+
+    // nothing is retried before the backoff passed
+    let outcome = retry_queued_deliveries(pool, instance.id, Utc::now(), |q| {
+      post(&client, q.inbox.into())
+    })
+    .await
+    .unwrap();
+    assert_eq!(RetryOutcome::default(), outcome);
+
+    let later = Utc::now() + backoff(1);
+    let queued = QueuedDelivery::read_due(pool, instance.id, later)
+      .await
+      .unwrap();
+    assert_eq!(1, queued.len());
+    assert_eq!(activity.id, queued[0].sent_activity_id);
+    assert_eq!(inbox, *queued[0].inbox.inner());
+    assert_eq!(1, queued[0].attempts);
+
+    // the retry succeeds and removes the delivery from the queue
+    let outcome =
+      retry_queued_deliveries(pool, instance.id, later, |q| post(&client, q.inbox.into()))
+        .await
+        .unwrap();
+    assert_eq!(
+      RetryOutcome {
+        delivered: 1,
+        failed: false
+      },
+      outcome
+    );
+    let remaining = QueuedDelivery::read_due(pool, instance.id, later)
+      .await
+      .unwrap();
+    assert!(remaining.is_empty());
+
+    Instance::delete(pool, instance.id).await.unwrap();
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_failed_retry_is_rescheduled() {
+    let pool = &build_db_pool_for_tests().await;
+    let pool = &mut pool.into();
+
+    let instance = Instance::read_or_create(pool, "down.example.com".to_string())
+      .await
+      .unwrap();
+    let activity = create_activity(pool).await;
+    let inbox = Url::parse("https://down.example.com/inbox").unwrap();
+    QueuedDelivery::enqueue(pool, instance.id, activity.id, &inbox)
+      .await
+      .unwrap();
+
+    let now = Utc::now() + backoff(1);
+    let outcome = retry_queued_deliveries(pool, instance.id, now, |_| async {
+      Err(anyhow!("connection refused"))
+    })
+    .await
+    .unwrap();
+    assert!(outcome.failed);
+    assert_eq!(0, outcome.delivered);
+
+    // the delay before the next attempt grew
+    assert!(QueuedDelivery::read_due(pool, instance.id, now)
+      .await
+      .unwrap()
+      .is_empty());
+    let queued = QueuedDelivery::read_due(pool, instance.id, now + backoff(2))
+      .await
+      .unwrap();
+    assert_eq!(2, queued[0].attempts);
+
+    Instance::delete(pool, instance.id).await.unwrap();
+  }
+}
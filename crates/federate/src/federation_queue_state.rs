@@ -1,4 +1,4 @@
-use crate::util::ActivityId;
+use crate::util::{retry_sleep_duration, ActivityId};
 use anyhow::Result;
 use chrono::{DateTime, TimeZone, Utc};
 use diesel::prelude::*;
@@ -7,6 +7,12 @@ use lemmy_db_schema::{
   newtypes::InstanceId,
   utils::{get_conn, DbPool},
 };
+use std::time::Duration;
+
+/// Number of consecutive failed deliveries after which an instance is considered dead. Until then
+/// failed deliveries are only queued for retry, afterwards nothing is sent to the instance until
+/// the cooldown passed.
+pub(crate) const DEAD_INSTANCE_FAILURES: i32 = 3;
 
 #[derive(Queryable, Selectable, Insertable, AsChangeset, Clone)]
 #[diesel(table_name = lemmy_db_schema::schema::federation_queue_state)]
@@ -44,6 +50,35 @@ impl FederationQueueState {
         }),
     )
   }
+
+  /// Register a failed delivery attempt, which increases the backoff before the next attempt.
+  pub fn record_failure(&mut self) {
+    self.fail_count += 1;
+    self.last_retry = Utc::now();
+  }
+
+  /// Register a successful delivery, which resets the backoff.
+  pub fn record_success(&mut self) {
+    self.fail_count = 0;
+  }
+
+  /// How long to wait after the last failed attempt before sending anything to the instance again.
+  /// This is zero unless the instance is considered dead, and grows exponentially with every
+  /// further failure.
+  pub fn cooldown(&self) -> Duration {
+    if self.fail_count < DEAD_INSTANCE_FAILURES {
+      return Duration::ZERO;
+    }
+    retry_sleep_duration(self.fail_count)
+  }
+
+  /// Earliest time at which the next delivery to this instance should be attempted. Unless the
+  /// instance is in cooldown, this is in the past so sending can continue immediately.
+  pub fn next_attempt(&self) -> DateTime<Utc> {
+    let delay = chrono::Duration::from_std(self.cooldown()).expect("delay is capped");
+    self.last_retry + delay
+  }
+
   pub async fn upsert(pool: &mut DbPool<'_>, state: &FederationQueueState) -> Result<()> {
     use lemmy_db_schema::schema::federation_queue_state::dsl::{
       federation_queue_state,
@@ -61,3 +96,81 @@ impl FederationQueueState {
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod test {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::util::MAX_RETRY_SLEEP_DURATION;
+
+  fn empty_state() -> FederationQueueState {
+    FederationQueueState {
+      instance_id: InstanceId::default(),
+      last_successful_id: 0,
+      fail_count: 0,
+      last_retry: Utc.timestamp_nanos(0),
+    }
+  }
+
+  #[test]
+  fn test_backoff_escalates() {
+    let mut state = empty_state();
+    for _ in 0..DEAD_INSTANCE_FAILURES {
+      state.record_failure();
+    }
+    assert!(state.next_attempt() > Utc::now());
+
+    let mut previous_delay = state.next_attempt() - state.last_retry;
+    for _ in 0..5 {
+      state.record_failure();
+      let delay = state.next_attempt() - state.last_retry;
+      assert!(delay > previous_delay);
+      assert!(state.next_attempt() > Utc::now());
+      previous_delay = delay;
+    }
+    assert_eq!(DEAD_INSTANCE_FAILURES + 5, state.fail_count);
+  }
+
+  #[test]
+  fn test_backoff_is_capped() {
+    let mut state = empty_state();
+    for _ in 0..100 {
+      state.record_failure();
+    }
+    let delay = (state.next_attempt() - state.last_retry).to_std().unwrap();
+    assert_eq!(MAX_RETRY_SLEEP_DURATION, delay);
+  }
+
+  #[test]
+  fn test_fourth_attempt_waits_after_three_failures() {
+    let mut state = empty_state();
+    assert!(state.next_attempt() <= Utc::now());
+    // the second and third attempt are made right away
+    for _ in 0..2 {
+      state.record_failure();
+      assert_eq!(Duration::ZERO, state.cooldown());
+      assert!(state.next_attempt() <= Utc::now());
+    }
+    state.record_failure();
+    // no attempt is made until the cooldown passed, which grows with each failure
+    assert!(state.next_attempt() > Utc::now());
+    assert_eq!(retry_sleep_duration(3), state.cooldown());
+    state.record_failure();
+    assert!(state.cooldown() > retry_sleep_duration(3));
+  }
+
+  #[test]
+  fn test_backoff_reset_on_success() {
+    let mut state = empty_state();
+    for _ in 0..DEAD_INSTANCE_FAILURES {
+      state.record_failure();
+    }
+    assert!(state.next_attempt() > Utc::now());
+
+    state.record_success();
+    assert_eq!(0, state.fail_count);
+    assert!(state.next_attempt() <= Utc::now());
+  }
+}
@@ -1,27 +1,30 @@
-use crate::{
-  util::{retry_sleep_duration, CancellableTask},
-  worker::InstanceWorker,
-};
+use crate::{util::CancellableTask, worker::InstanceWorker};
 use activitypub_federation::config::FederationConfig;
 use chrono::{Local, Timelike};
 use federation_queue_state::FederationQueueState;
-use lemmy_api_common::context::LemmyContext;
+use lemmy_api_common::{context::LemmyContext, send_activity::ACTIVE_FEDERATION_WORKERS};
 use lemmy_db_schema::{
   newtypes::InstanceId,
   source::instance::Instance,
   utils::{ActualDbPool, DbPool},
 };
-use std::{collections::HashMap, time::Duration};
+use std::{collections::HashMap, sync::atomic::Ordering, time::Duration};
 use tokio::{
   sync::mpsc::{unbounded_channel, UnboundedReceiver},
   time::sleep,
 };
 use tokio_util::sync::CancellationToken;
 
+mod federation_queue;
 mod federation_queue_state;
+mod remote_block;
+mod resend;
 mod util;
 mod worker;
 
+pub use remote_block::RemoteBlockMiddleware;
+pub use resend::resend_activity;
+
 static WORKER_EXIT_TIMEOUT: Duration = Duration::from_secs(30);
 #[cfg(debug_assertions)]
 static INSTANCES_RECHECK_DELAY: Duration = Duration::from_secs(5);
@@ -52,6 +55,7 @@ async fn start_stop_federation_workers(
     let mut total_count = 0;
     let mut dead_count = 0;
     let mut disallowed_count = 0;
+    let mut blocked_by_remote_count = 0;
     for (instance, allowed, is_dead) in Instance::read_all_with_blocked_and_dead(pool2).await? {
       if instance.domain == local_domain {
         continue;
@@ -66,7 +70,13 @@ async fn start_stop_federation_workers(
       if is_dead {
         dead_count += 1;
       }
-      let should_federate = allowed && !is_dead;
+      let blocked_by_remote = instance.is_blocked_by_remote();
+      if blocked_by_remote {
+        blocked_by_remote_count += 1;
+      }
+      // activities for paused instances are sent once federation is resumed, and for instances
+      // which blocked us once the pause after the rejection is over
+      let should_federate = allowed && !is_dead && !instance.paused && !blocked_by_remote;
       if should_federate {
         if workers.contains_key(&instance.id) {
           if workers
@@ -114,7 +124,8 @@ async fn start_stop_federation_workers(
       }
     }
     let worker_count = workers.len();
-    tracing::info!("Federating to {worker_count}/{total_count} instances ({dead_count} dead, {disallowed_count} disallowed)");
+    ACTIVE_FEDERATION_WORKERS.store(worker_count, Ordering::Relaxed);
+    tracing::info!("Federating to {worker_count}/{total_count} instances ({dead_count} dead, {disallowed_count} disallowed, {blocked_by_remote_count} blocked us)");
     tokio::select! {
       () = sleep(INSTANCES_RECHECK_DELAY) => {},
       _ = cancel.cancelled() => { break; }
@@ -128,6 +139,7 @@ async fn start_stop_federation_workers(
   );
   // the cancel futures need to be awaited concurrently for the shutdown processes to be triggered concurrently
   futures::future::join_all(workers.into_values().map(util::CancellableTask::cancel)).await;
+  ACTIVE_FEDERATION_WORKERS.store(0, Ordering::Relaxed);
   exit_print.await?;
   Ok(())
 }
@@ -191,11 +203,11 @@ async fn print_stats(pool: &mut DbPool<'_>, stats: &HashMap<String, FederationQu
     let behind = last_id - stat.last_successful_id;
     if stat.fail_count > 0 {
       tracing::info!(
-        "{}: Warning. {} behind, {} consecutive fails, current retry delay {:.2?}",
+        "{}: Warning. {} behind, {} consecutive fails, current cooldown {:.2?}",
         domain,
         behind,
         stat.fail_count,
-        retry_sleep_duration(stat.fail_count)
+        stat.cooldown()
       );
     } else if behind > 0 {
       tracing::info!("{}: Ok. {} behind", domain, behind);
@@ -0,0 +1,232 @@
+use anyhow::Result;
+use chrono::Utc;
+use lemmy_db_schema::{source::instance::Instance, utils::DbPool};
+use lemmy_utils::error::LemmyErrorType;
+use once_cell::sync::Lazy;
+use reqwest::{Method, Request, Response};
+use reqwest_middleware::{Middleware, Next};
+use std::{collections::HashSet, sync::Mutex};
+use task_local_extensions::Extensions;
+
+/// Only the start of a rejection body is checked, Lemmy's error responses are much shorter.
+const MAX_REJECTION_BODY: usize = 64 * 1024;
+
+/// Domains which rejected a delivery because they blocked this instance, until the worker for the
+/// instance picks them up.
+static REJECTED_BY_REMOTE: Lazy<Mutex<HashSet<String>>> = Lazy::new(Default::default);
+
+/// Watches the responses to outgoing activities for signs that the receiving instance blocked this
+/// instance. The federation library drops rejected activities without returning an error, so this
+/// is the only place where the response can be inspected. Only add this to the client which
+/// delivers activities, not to the one used for fetching.
+pub struct RemoteBlockMiddleware;
+
+#[async_trait::async_trait]
+impl Middleware for RemoteBlockMiddleware {
+  async fn handle(
+    &self,
+    req: Request,
+    extensions: &mut Extensions,
+    next: Next<'_>,
+  ) -> reqwest_middleware::Result<Response> {
+    let domain = (req.method() == Method::POST)
+      .then(|| req.url().domain().map(ToString::to_string))
+      .flatten();
+    let mut res = next.run(req, extensions).await?;
+    let Some(domain) = domain.filter(|_| res.status().is_client_error()) else {
+      return Ok(res);
+    };
+
+    // the body is consumed here, so the response needs to be rebuilt afterwards
+    let status = res.status();
+    let headers = res.headers().clone();
+    let mut body = Vec::new();
+    while let Some(chunk) = res.chunk().await? {
+      body.extend_from_slice(&chunk);
+      if body.len() >= MAX_REJECTION_BODY {
+        break;
+      }
+    }
+    if is_block_rejection(&body) {
+      tracing::debug!("{domain} rejected an activity with status {status}, it blocked us");
+      REJECTED_BY_REMOTE
+        .lock()
+        .expect("lock remote rejections")
+        .insert(domain);
+    }
+    let mut rebuilt = http::Response::new(body);
+    *rebuilt.status_mut() = status;
+    *rebuilt.headers_mut() = headers;
+    Ok(rebuilt.into())
+  }
+}
+
+/// Checks if the body is one of the errors which Lemmy returns for activities from blocked
+/// domains. Other rejections, even with status 403, don't mean that the instance blocked us.
+///
+/// The inbox checks the domain through the URL verifier of the federation library, which only
+/// passes on the message of the error (see `VerifyUrlData` in lemmy_apub). So the response is an
+/// unknown error, and the block can only be recognized by its message.
+fn is_block_rejection(body: &[u8]) -> bool {
+  match serde_json::from_slice(body) {
+    Ok(LemmyErrorType::DomainBlocked(_) | LemmyErrorType::DomainNotInAllowList(_)) => true,
+    Ok(LemmyErrorType::Unknown(message)) => {
+      message.contains("Domain \"")
+        && (message.contains("\" is blocked") || message.contains("\" is not in allowlist"))
+    }
+    _ => false,
+  }
+}
+
+/// Updates the blocked-by-remote mark of the instance after a round of deliveries to it. Returns
+/// true if the instance rejected one of them because it blocked this instance, in which case
+/// deliveries need to be paused. The mark is cleared again once a delivery succeeds.
+pub(crate) async fn update_blocked_by_remote(
+  pool: &mut DbPool<'_>,
+  instance: &mut Instance,
+  delivered: bool,
+) -> Result<bool> {
+  let rejected = REJECTED_BY_REMOTE
+    .lock()
+    .expect("lock remote rejections")
+    .remove(&instance.domain);
+  if rejected {
+    tracing::warn!(
+      "{} blocked this instance, pausing deliveries",
+      instance.domain
+    );
+    *instance = Instance::set_blocked_by_remote(pool, instance.id, Some(Utc::now())).await?;
+  } else if delivered && instance.blocked_by_remote.is_some() {
+    tracing::info!(
+      "{} accepts activities again, it is no longer marked as blocking us",
+      instance.domain
+    );
+    *instance = Instance::set_blocked_by_remote(pool, instance.id, None).await?;
+  }
+  Ok(rejected)
+}
+
+#[cfg(test)]
+mod test {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use lemmy_db_schema::utils::build_db_pool_for_tests;
+  use lemmy_utils::error::LemmyError;
+  use reqwest::StatusCode;
+  use reqwest_middleware::ClientBuilder;
+  use serial_test::serial;
+
+  const DOMAIN_BLOCKED: &str = r#"{"error":"domain_blocked","message":"lemmy.test"}"#;
+
+  /// The body which a Lemmy inbox sends when our domain is blocked, built the same way as there.
+  fn inbox_rejection(message: &str) -> String {
+    let err =
+      activitypub_federation::error::Error::UrlVerificationError(anyhow::anyhow!("{message}"));
+    serde_json::to_string(&LemmyError::from(err).error_type).unwrap()
+  }
+
+  /// Answers like a remote instance, with the status and body given by the path.
+  struct RemoteInboxMiddleware;
+
+  #[async_trait::async_trait]
+  impl Middleware for RemoteInboxMiddleware {
+    async fn handle(
+      &self,
+      req: Request,
+      _extensions: &mut Extensions,
+      _next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+      let res = match req.url().path() {
+        "/forbidden" => http::Response::builder()
+          .status(403)
+          .body("forbidden".to_string()),
+        "/domain_blocked" => http::Response::builder()
+          .status(400)
+          .body(DOMAIN_BLOCKED.to_string()),
+        "/inbox_blocked" => http::Response::builder()
+          .status(400)
+          .body(inbox_rejection("Domain \"lemmy.test\" is blocked")),
+        "/inbox_not_allowed" => http::Response::builder()
+          .status(400)
+          .body(inbox_rejection("Domain \"lemmy.test\" is not in allowlist")),
+        "/community_blocked" => http::Response::builder()
+          .status(400)
+          .body(inbox_rejection("Community \"main\" is blocked")),
+        "/invalid" => http::Response::builder()
+          .status(400)
+          .body(r#"{"error":"invalid_url"}"#.to_string()),
+        _ => http::Response::builder().body(String::new()),
+      };
+      Ok(res.unwrap().into())
+    }
+  }
+
+  async fn deliver(url: &str) -> Response {
+    let client = ClientBuilder::new(reqwest::Client::default())
+      .with(RemoteBlockMiddleware)
+      .with(RemoteInboxMiddleware)
+      .build();
+    client.post(url).send().await.unwrap()
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_block_rejection_sets_blocked_by_remote() {
+    let pool = &build_db_pool_for_tests().await;
+    let pool = &mut pool.into();
+    let mut instance = Instance::read_or_create(pool, "blocking.example".to_string())
+      .await
+      .unwrap();
+    assert!(!instance.is_blocked_by_remote());
+
+    // the body of the response is still available after it was inspected
+    let res = deliver("https://blocking.example/domain_blocked").await;
+    assert_eq!(StatusCode::BAD_REQUEST, res.status());
+    assert_eq!(DOMAIN_BLOCKED, res.text().await.unwrap());
+    assert!(update_blocked_by_remote(pool, &mut instance, false)
+      .await
+      .unwrap());
+    let stored = Instance::read_or_create(pool, "blocking.example".to_string())
+      .await
+      .unwrap();
+    assert!(stored.is_blocked_by_remote());
+    assert_eq!(instance, stored);
+
+    // the rejection is only handled once
+    assert!(!update_blocked_by_remote(pool, &mut instance, false)
+      .await
+      .unwrap());
+    assert!(instance.is_blocked_by_remote());
+
+    // a successful delivery clears the mark
+    deliver("https://blocking.example/inbox").await;
+    assert!(!update_blocked_by_remote(pool, &mut instance, true)
+      .await
+      .unwrap());
+    assert_eq!(None, instance.blocked_by_remote);
+
+    Instance::delete(pool, instance.id).await.unwrap();
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_detect_block_rejection() {
+    deliver("https://lemmy.example/domain_blocked").await;
+    deliver("https://other.example/invalid").await;
+    deliver("https://forbidden.example/forbidden").await;
+    // responses of an actual inbox, where the domain check only passes on the message
+    deliver("https://blocking.example/inbox_blocked").await;
+    deliver("https://allowlist.example/inbox_not_allowed").await;
+    deliver("https://community.example/community_blocked").await;
+    let mut rejected = REJECTED_BY_REMOTE.lock().unwrap();
+    assert!(rejected.remove("lemmy.example"));
+    assert!(rejected.remove("blocking.example"));
+    assert!(rejected.remove("allowlist.example"));
+    // other rejections don't mean that the instance blocked us
+    assert!(!rejected.contains("other.example"));
+    assert!(!rejected.contains("forbidden.example"));
+    assert!(!rejected.contains("community.example"));
+  }
+}
@@ -0,0 +1,117 @@
+use crate::util::get_actor_cached;
+use activitypub_federation::{
+  activity_sending::SendActivityTask,
+  config::Data,
+  traits::ActivityHandler,
+};
+use lemmy_api_common::context::LemmyContext;
+use lemmy_apub::activity_lists::SharedInboxActivities;
+use lemmy_db_schema::{
+  source::activity::{ActorType, SentActivity},
+  utils::DbPool,
+};
+use lemmy_utils::error::{LemmyErrorType, LemmyResult};
+use reqwest::Url;
+
+/// Sends an activity which was stored when it was first sent to the given inbox once more, for
+/// example because the remote instance missed it. It is signed with the key of the original actor,
+/// like a regular delivery. Activities which are marked as sensitive are never resent.
+///
+/// Sending the same activity again is safe, as receivers ignore activities with an id they already
+/// processed.
+pub async fn resend_activity(
+  ap_id: &Url,
+  inbox: Url,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<()> {
+  let (activity, actor_type) = read_resendable(&mut context.pool(), ap_id).await?;
+  let actor = get_actor_cached(&mut context.pool(), actor_type, activity.actor()).await?;
+  let tasks = SendActivityTask::prepare(&activity, actor.as_ref(), vec![inbox], context).await?;
+  // local and blocked inboxes are skipped by the library without an error
+  if tasks.is_empty() {
+    Err(LemmyErrorType::InvalidUrl)?
+  }
+  for task in tasks {
+    tracing::info!("resending {}", task);
+    task.sign_and_send(context).await?;
+  }
+  Ok(())
+}
+
+/// Reads the stored activity with the type of its actor, unless it is sensitive. The json is
+/// parsed the same way as for regular deliveries.
+async fn read_resendable(
+  pool: &mut DbPool<'_>,
+  ap_id: &Url,
+) -> LemmyResult<(SharedInboxActivities, ActorType)> {
+  let row = SentActivity::read_from_apub_id(pool, &ap_id.clone().into()).await?;
+  if row.sensitive {
+    Err(LemmyErrorType::SensitiveActivityNotResendable)?
+  }
+  let activity: SharedInboxActivities = serde_json::from_value(row.data)?;
+  Ok((activity, row.actor_type))
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use chrono::Utc;
+  use lemmy_db_schema::{source::activity::SentActivityForm, utils::build_db_pool_for_tests};
+  use serde_json::json;
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_read_resendable() {
+    let pool = &build_db_pool_for_tests().await;
+    let pool = &mut pool.into();
+
+    let actor = Url::parse("http://ds9.lemmy.ml/u/lemmy_alpha").unwrap();
+    let store = |sensitive: bool| {
+      let id = Utc::now().timestamp_nanos_opt().unwrap();
+      let ap_id = Url::parse(&format!("http://ds9.lemmy.ml/activities/like/{id}")).unwrap();
+      let data = json!({
+        "actor": actor,
+        "object": "http://ds9.lemmy.ml/comment/1",
+        "audience": "https://enterprise.lemmy.ml/c/tenforward",
+        "type": "Like",
+        "id": ap_id,
+      });
+      let form = SentActivityForm {
+        ap_id: ap_id.clone().into(),
+        data,
+        sensitive,
+        actor_apub_id: actor.clone().into(),
+        actor_type: ActorType::Person,
+        send_all_instances: false,
+        send_community_followers_of: None,
+        send_inboxes: vec![],
+      };
+      (ap_id, form)
+    };
+
+    let (ap_id, form) = store(false);
+    let data = form.data.clone();
+    SentActivity::create(pool, form).await.unwrap();
+    let (activity, actor_type) = read_resendable(pool, &ap_id).await.unwrap();
+    assert_eq!(&ap_id, activity.id());
+    assert_eq!(&actor, activity.actor());
+    assert_eq!(ActorType::Person, actor_type);
+    // the stored json is sent unchanged
+    assert_eq!(data, serde_json::to_value(&activity).unwrap());
+
+    let (ap_id, form) = store(true);
+    SentActivity::create(pool, form).await.unwrap();
+    let err = read_resendable(pool, &ap_id).await.unwrap_err();
+    assert_eq!(
+      LemmyErrorType::SensitiveActivityNotResendable,
+      err.error_type
+    );
+
+    let unknown = Url::parse("http://example.com/activities/unknown").unwrap();
+    assert!(read_resendable(pool, &unknown).await.is_err());
+  }
+}
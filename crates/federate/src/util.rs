@@ -1,4 +1,5 @@
 use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
 use diesel::prelude::*;
 use diesel_async::RunQueryDsl;
 use lemmy_apub::{
@@ -7,7 +8,7 @@ use lemmy_apub::{
 };
 use lemmy_db_schema::{
   source::{
-    activity::{ActorType, SentActivity},
+    activity::{ActorType, SentActivity, SentActivityDelivery, SentActivityDeliveryForm},
     community::Community,
     person::Person,
     site::Site,
@@ -15,11 +16,14 @@ use lemmy_db_schema::{
   traits::ApubActor,
   utils::{get_conn, DbPool},
 };
+use lemmy_utils::request::exponential_backoff;
 use moka::future::Cache;
 use once_cell::sync::Lazy;
 use reqwest::Url;
 use serde_json::Value;
 use std::{
+  collections::HashSet,
+  fmt::Display,
   future::Future,
   pin::Pin,
   sync::{Arc, RwLock},
@@ -199,7 +203,211 @@ pub(crate) async fn get_latest_activity_id(pool: &mut DbPool<'_>) -> Result<Acti
     .map_err(|e| anyhow::anyhow!("err getting id: {e:?}"))
 }
 
+/// Upper bound for the delay between two delivery attempts to the same instance. Without this the
+/// exponential backoff grows so large that an instance which comes back online after a longer
+/// outage would not receive anything for days.
+pub(crate) static MAX_RETRY_SLEEP_DURATION: Duration = Duration::from_secs(60 * 60 * 24);
+
 /// how long to sleep based on how many retries have already happened
 pub(crate) fn retry_sleep_duration(retry_count: i32) -> Duration {
-  Duration::from_secs_f64(10.0 * 2.0_f64.powf(f64::from(retry_count)))
+  exponential_backoff(
+    Duration::from_secs(10),
+    retry_count,
+    MAX_RETRY_SLEEP_DURATION,
+  )
+}
+
+/// Split the inboxes of an activity into delivery passes of at most `max_recipients` each, so
+/// that huge follower sets are not all sent out in a single operation.
+pub(crate) fn delivery_passes(inbox_urls: HashSet<Url>, max_recipients: usize) -> Vec<Vec<Url>> {
+  let mut inbox_urls: Vec<Url> = inbox_urls.into_iter().collect();
+  // sort so that passes are stable across retries
+  inbox_urls.sort();
+  inbox_urls
+    .chunks(max_recipients.max(1))
+    .map(<[Url]>::to_vec)
+    .collect()
+}
+
+/// Attempts the delivery to each inbox once. The outcome is returned separately for each inbox,
+/// so that one failing inbox doesn't hold up the others and only failed ones are retried.
+pub(crate) async fn deliver_to_inboxes<'a, T, F, Fut>(
+  targets: &'a [(Url, T)],
+  deliver: F,
+) -> Vec<(Url, Result<()>)>
+where
+  F: Fn(&'a T) -> Fut,
+  Fut: Future<Output = Result<()>>,
+{
+  let mut results = Vec::with_capacity(targets.len());
+  for (inbox, target) in targets {
+    results.push((inbox.clone(), deliver(target).await));
+  }
+  results
+}
+
+/// Fails the delivery if it takes longer than `timeout`. The inbox is then retried like after any
+/// other failure.
+pub(crate) async fn with_delivery_timeout<Fut>(timeout: Duration, delivery: Fut) -> Result<()>
+where
+  Fut: Future<Output = Result<()>>,
+{
+  tokio::time::timeout(timeout, delivery)
+    .await
+    .map_err(|_| anyhow!("delivery timed out after {timeout:?}"))?
+}
+
+/// Store the outcome of a delivery attempt. Failing to do so only gets logged, as it must not
+/// hold up federation.
+pub(crate) async fn record_delivery<E: Display>(
+  pool: &mut DbPool<'_>,
+  activity: &SentActivity,
+  inbox: &Url,
+  res: &Result<(), E>,
+) {
+  let form = SentActivityDeliveryForm {
+    sent_activity_id: activity.id,
+    inbox: inbox.clone().into(),
+    success: res.is_ok(),
+    error: res.as_ref().err().map(ToString::to_string),
+    updated: Utc::now(),
+  };
+  if let Err(e) = SentActivityDelivery::upsert(pool, &form).await {
+    tracing::warn!(
+      "failed to record delivery of {} to {inbox}: {e}",
+      activity.ap_id
+    );
+  }
+}
+
+#[cfg(test)]
+mod test {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use std::collections::HashMap;
+
+  #[test]
+  fn test_delivery_passes() {
+    let inbox_urls: HashSet<Url> = (0..25)
+      .map(|i| Url::parse(&format!("https://example.com/u/user{i}/inbox")).unwrap())
+      .collect();
+    let passes = delivery_passes(inbox_urls.clone(), 10);
+    assert_eq!(3, passes.len());
+    assert_eq!(10, passes[0].len());
+    assert_eq!(10, passes[1].len());
+    assert_eq!(5, passes[2].len());
+
+    // every inbox is delivered to exactly once
+    let delivered: Vec<Url> = passes.into_iter().flatten().collect();
+    assert_eq!(inbox_urls.len(), delivered.len());
+    let delivered: HashSet<Url> = delivered.into_iter().collect();
+    assert_eq!(inbox_urls, delivered);
+  }
+
+  #[tokio::test]
+  async fn test_deliver_to_inboxes() {
+    let ok = Url::parse("https://example.com/inbox").unwrap();
+    let failing = Url::parse("https://down.example.com/inbox").unwrap();
+    let targets = vec![(ok.clone(), true), (failing.clone(), false)];
+
+    let results = deliver_to_inboxes(&targets, |reachable| async move {
+      if *reachable {
+        Ok(())
+      } else {
+        Err(anyhow!("connection refused"))
+      }
+    })
+    .await;
+    assert_eq!(2, results.len());
+    assert_eq!(ok, results[0].0);
+    assert!(results[0].1.is_ok());
+    assert_eq!(failing, results[1].0);
+    assert_eq!(
+      "connection refused",
+      results[1].1.as_ref().unwrap_err().to_string()
+    );
+  }
+
+  #[tokio::test]
+  async fn test_delivery_timeout() {
+    let fast = Url::parse("https://example.com/inbox").unwrap();
+    let slow = Url::parse("https://slow.example.com/inbox").unwrap();
+    let targets = vec![
+      (fast.clone(), Duration::ZERO),
+      (slow.clone(), Duration::from_secs(3600)),
+    ];
+
+    let timeout = Duration::from_millis(50);
+    let results = deliver_to_inboxes(&targets, |delay| {
+      with_delivery_timeout(timeout, async move {
+        sleep(*delay).await;
+        Ok(())
+      })
+    })
+    .await;
+    assert!(results[0].1.is_ok());
+    // the slow inbox failed, so it is retried
+    assert_eq!(slow, results[1].0);
+    let err = results[1].1.as_ref().unwrap_err().to_string();
+    assert!(err.contains("timed out"), "{err}");
+  }
+
+  #[tokio::test]
+  async fn test_delivery_passes_with_retries() {
+    let inbox_urls: HashSet<Url> = (0..25)
+      .map(|i| Url::parse(&format!("https://example.com/u/user{i}/inbox")).unwrap())
+      .collect();
+    // some inboxes fail on the first attempt
+    let failing: HashSet<Url> = inbox_urls.iter().step_by(3).cloned().collect();
+    let attempts = std::sync::Mutex::new(HashMap::<Url, u32>::new());
+    let delivered = std::sync::Mutex::new(Vec::<Url>::new());
+
+    let passes = delivery_passes(inbox_urls.clone(), 10);
+    assert_eq!(3, passes.len());
+    // same as in the worker, each pass is retried until all of its inboxes succeeded
+    for pass in passes {
+      let mut pending: Vec<(Url, Url)> = pass.into_iter().map(|i| (i.clone(), i)).collect();
+      while !pending.is_empty() {
+        let results = deliver_to_inboxes(&pending, |inbox| {
+          let mut attempts = attempts.lock().unwrap();
+          let count = attempts.entry(inbox.clone()).or_default();
+          *count += 1;
+          let fails = *count == 1 && failing.contains(inbox);
+          if !fails {
+            delivered.lock().unwrap().push(inbox.clone());
+          }
+          async move {
+            if fails {
+              Err(anyhow!("connection refused"))
+            } else {
+              Ok(())
+            }
+          }
+        })
+        .await;
+        let failed: HashSet<Url> = results
+          .into_iter()
+          .filter_map(|(inbox, res)| res.err().map(|_| inbox))
+          .collect();
+        pending.retain(|(inbox, _)| failed.contains(inbox));
+      }
+    }
+
+    // no inbox is missed, and none receives the activity twice
+    let delivered = delivered.into_inner().unwrap();
+    assert_eq!(inbox_urls.len(), delivered.len());
+    assert_eq!(inbox_urls, delivered.into_iter().collect());
+    let retried = attempts.into_inner().unwrap();
+    assert_eq!(failing.len(), retried.values().filter(|a| **a == 2).count());
+  }
+
+  #[test]
+  fn test_delivery_passes_below_cap() {
+    let inbox_urls: HashSet<Url> = [Url::parse("https://example.com/inbox").unwrap()].into();
+    let passes = delivery_passes(inbox_urls, 0);
+    assert_eq!(1, passes.len());
+    assert!(delivery_passes(HashSet::new(), 10).is_empty());
+  }
 }
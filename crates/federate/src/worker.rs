@@ -1,10 +1,15 @@
 use crate::{
+  federation_queue::{retry_queued_deliveries, QueuedDelivery},
   federation_queue_state::FederationQueueState,
+  remote_block::update_blocked_by_remote,
   util::{
+    deliver_to_inboxes,
+    delivery_passes,
     get_activity_cached,
     get_actor_cached,
     get_latest_activity_id,
-    retry_sleep_duration,
+    record_delivery,
+    with_delivery_timeout,
     LEMMY_TEST_FAST_FEDERATION,
     WORK_FINISHED_RECHECK_DELAY,
   },
@@ -103,8 +108,12 @@ impl InstanceWorker {
     let save_state_every = chrono::Duration::from_std(SAVE_STATE_EVERY_TIME).expect("not negative");
 
     self.update_communities(pool).await?;
-    self.initial_fail_sleep().await?;
     while !self.stop.is_cancelled() {
+      self.wait_for_next_attempt().await?;
+      if self.stop.is_cancelled() {
+        break;
+      }
+      self.retry_queued(pool).await?;
       self.loop_batch(pool).await?;
       if self.stop.is_cancelled() {
         break;
@@ -119,15 +128,12 @@ impl InstanceWorker {
     Ok(())
   }
 
-  async fn initial_fail_sleep(&mut self) -> Result<()> {
-    // before starting queue, sleep remaining duration if last request failed
-    if self.state.fail_count > 0 {
-      let elapsed = (Utc::now() - self.state.last_retry).to_std()?;
-      let required = retry_sleep_duration(self.state.fail_count);
-      if elapsed >= required {
-        return Ok(());
-      }
-      let remaining = required - elapsed;
+  async fn wait_for_next_attempt(&mut self) -> Result<()> {
+    // before sending anything, sleep remaining cooldown if the instance is considered dead
+    let now = Utc::now();
+    let next_attempt = self.state.next_attempt();
+    if next_attempt > now {
+      let remaining = (next_attempt - now).to_std()?;
       tokio::select! {
         () = sleep(remaining) => {},
         () = self.stop.cancelled() => {}
@@ -135,6 +141,52 @@ impl InstanceWorker {
     }
     Ok(())
   }
+
+  /// retry the deliveries of earlier activities which failed and are due again
+  async fn retry_queued(&mut self, pool: &mut DbPool<'_>) -> Result<()> {
+    let context = &self.context;
+    let record_deliveries = context.settings().federation.record_deliveries;
+    let timeout = Duration::from_secs(context.settings().federation.delivery_timeout_seconds);
+    let outcome =
+      retry_queued_deliveries(pool, self.instance.id, Utc::now(), |queued| async move {
+        let Some(ele) = get_activity_cached(&mut context.pool(), queued.sent_activity_id).await?
+        else {
+          return Ok(()); // the activity was removed in the meantime
+        };
+        let (activity, object) = ele.as_ref();
+        let Some(actor_apub_id) = &activity.actor_apub_id else {
+          return Ok(());
+        };
+        let actor =
+          get_actor_cached(&mut context.pool(), activity.actor_type, actor_apub_id).await?;
+        let inbox: Url = queued.inbox.into();
+        let requests =
+          SendActivityTask::prepare(object, actor.as_ref(), vec![inbox.clone()], context)
+            .await
+            .into_anyhow()?;
+        let res = with_delivery_timeout(timeout, async {
+          for task in requests {
+            tracing::info!("retrying {}", task);
+            task.sign_and_send(context).await?;
+          }
+          Ok::<_, anyhow::Error>(())
+        })
+        .await;
+        if record_deliveries {
+          record_delivery(&mut context.pool(), activity, &inbox, &res).await;
+        }
+        res
+      })
+      .await?;
+    if outcome.failed {
+      self.state.record_failure();
+      self.save_and_send_state(pool).await?;
+    } else if outcome.delivered > 0 {
+      self.state.record_success();
+    }
+    Ok(())
+  }
+
   /// send out a batch of CHECK_SAVE_STATE_EVERY_IT activities
   async fn loop_batch(&mut self, pool: &mut DbPool<'_>) -> Result<()> {
     let latest_id = get_latest_activity_id(pool).await?;
@@ -168,7 +220,7 @@ impl InstanceWorker {
         self.state.last_successful_id = id;
         continue;
       };
-      if let Err(e) = self.send_retry_loop(pool, &ele.0, &ele.1).await {
+      if let Err(e) = self.send(pool, &ele.0, &ele.1).await {
         tracing::warn!(
           "sending {} errored internally, skipping activity: {:?}",
           ele.0.ap_id,
@@ -178,16 +230,19 @@ impl InstanceWorker {
       if self.stop.is_cancelled() {
         return Ok(());
       }
-      // send success!
+      // sent, or queued for retry
       self.state.last_successful_id = id;
-      self.state.fail_count = 0;
+      if self.state.next_attempt() > Utc::now() {
+        // the instance is considered dead, wait for the cooldown before sending anything else
+        return Ok(());
+      }
     }
     Ok(())
   }
 
-  // this function will return successfully when (a) send succeeded or (b) worker cancelled
-  // and will return an error if an internal error occurred (send errors cause an infinite loop)
-  async fn send_retry_loop(
+  // this function will return successfully when the activity was sent or queued for retry to all
+  // inboxes, and will return an error if an internal error occurred
+  async fn send(
     &mut self,
     pool: &mut DbPool<'_>,
     activity: &SentActivity,
@@ -208,31 +263,63 @@ impl InstanceWorker {
       .await
       .context("failed getting actor instance (was it marked deleted / removed?)")?;
 
-    let inbox_urls = inbox_urls.into_iter().collect();
-    let requests = SendActivityTask::prepare(object, actor.as_ref(), inbox_urls, &self.context)
-      .await
-      .into_anyhow()?;
-    for task in requests {
-      // usually only one due to shared inbox
-      tracing::info!("sending out {}", task);
-      while let Err(e) = task.sign_and_send(&self.context).await {
-        self.state.fail_count += 1;
-        self.state.last_retry = Utc::now();
-        let retry_delay: Duration = retry_sleep_duration(self.state.fail_count);
+    let max_recipients = self.context.settings().federation.max_recipients_per_pass;
+    let record_deliveries = self.context.settings().federation.record_deliveries;
+    let timeout = Duration::from_secs(self.context.settings().federation.delivery_timeout_seconds);
+    for inbox_urls in delivery_passes(inbox_urls, max_recipients) {
+      // prepare separately for each inbox, so that the outcome can be attributed to it
+      let mut pending = Vec::with_capacity(inbox_urls.len());
+      for inbox in inbox_urls {
+        let requests =
+          SendActivityTask::prepare(object, actor.as_ref(), vec![inbox.clone()], &self.context)
+            .await
+            .into_anyhow()?;
+        pending.push((inbox, requests));
+      }
+      let context = &self.context;
+      let results = deliver_to_inboxes(&pending, |requests| async move {
+        for task in requests {
+          tracing::info!("sending out {}", task);
+          with_delivery_timeout(timeout, async {
+            task.sign_and_send(context).await?;
+            Ok::<_, anyhow::Error>(())
+          })
+          .await?;
+        }
+        Ok::<_, anyhow::Error>(())
+      })
+      .await;
+      if record_deliveries {
+        for (inbox, res) in &results {
+          record_delivery(pool, activity, inbox, res).await;
+        }
+      }
+      let delivered = results.iter().any(|(_, res)| res.is_ok());
+      if update_blocked_by_remote(pool, &mut self.instance, delivered).await? {
+        // stop the worker without marking the activity as sent, so that it is delivered once
+        // the pause is over
+        self.stop.cancel();
+        return Ok(());
+      }
+      // the inboxes which failed are retried later, without holding up the following activities
+      let mut failed = false;
+      for (inbox, res) in results {
+        let Err(e) = res else {
+          continue;
+        };
         tracing::info!(
-          "{}: retrying {} attempt {} with delay {retry_delay:.2?}. ({e})",
+          "{}: queueing {} to {inbox} for retry. ({e})",
           self.instance.domain,
           activity.id,
-          self.state.fail_count
         );
+        QueuedDelivery::enqueue(pool, self.instance.id, activity.id, &inbox).await?;
+        failed = true;
+      }
+      if delivered {
+        self.state.record_success();
+      } else if failed {
+        self.state.record_failure();
         self.save_and_send_state(pool).await?;
-        tokio::select! {
-          () = sleep(retry_delay) => {},
-          () = self.stop.cancelled() => {
-            // save state to db and exit
-            return Ok(());
-          }
-        }
       }
     }
     Ok(())
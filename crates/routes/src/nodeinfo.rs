@@ -1,6 +1,16 @@
 use actix_web::{error::ErrorBadRequest, web, Error, HttpResponse, Result};
 use anyhow::anyhow;
-use lemmy_api_common::context::LemmyContext;
+use lemmy_api_common::{
+  context::LemmyContext,
+  nodeinfo::{
+    NodeInfo,
+    NodeInfoSoftware,
+    NodeInfoUsage,
+    NodeInfoUsers,
+    NodeInfoWellKnown,
+    NodeInfoWellKnownLinks,
+  },
+};
 use lemmy_db_schema::RegistrationMode;
 use lemmy_db_views::structs::SiteView;
 use lemmy_utils::{
@@ -8,7 +18,6 @@ use lemmy_utils::{
   error::LemmyError,
   version,
 };
-use serde::{Deserialize, Serialize};
 use url::Url;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
@@ -73,47 +82,3 @@ async fn node_info(context: web::Data<LemmyContext>) -> Result<HttpResponse, Err
 
   Ok(HttpResponse::Ok().json(json))
 }
-
-#[derive(Serialize, Deserialize, Debug)]
-struct NodeInfoWellKnown {
-  pub links: Vec<NodeInfoWellKnownLinks>,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct NodeInfoWellKnownLinks {
-  pub rel: Url,
-  pub href: Url,
-}
-
-#[derive(Serialize, Deserialize, Debug, Default)]
-#[serde(rename_all = "camelCase", default)]
-pub struct NodeInfo {
-  pub version: Option<String>,
-  pub software: Option<NodeInfoSoftware>,
-  pub protocols: Option<Vec<String>>,
-  pub usage: Option<NodeInfoUsage>,
-  pub open_registrations: Option<bool>,
-}
-
-#[derive(Serialize, Deserialize, Debug, Default)]
-#[serde(default)]
-pub struct NodeInfoSoftware {
-  pub name: Option<String>,
-  pub version: Option<String>,
-}
-
-#[derive(Serialize, Deserialize, Debug, Default)]
-#[serde(rename_all = "camelCase", default)]
-pub struct NodeInfoUsage {
-  pub users: Option<NodeInfoUsers>,
-  pub local_posts: Option<i64>,
-  pub local_comments: Option<i64>,
-}
-
-#[derive(Serialize, Deserialize, Debug, Default)]
-#[serde(rename_all = "camelCase", default)]
-pub struct NodeInfoUsers {
-  pub total: Option<i64>,
-  pub active_halfyear: Option<i64>,
-  pub active_month: Option<i64>,
-}
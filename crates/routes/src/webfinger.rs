@@ -5,10 +5,13 @@ use activitypub_federation::{
 use actix_web::{web, web::Query, HttpResponse};
 use lemmy_api_common::context::LemmyContext;
 use lemmy_db_schema::{
-  source::{community::Community, person::Person},
+  source::{actor_name_alias::ActorNameAlias, community::Community, person::Person},
   traits::ApubActor,
 };
-use lemmy_utils::{cache_header::cache_3days, error::LemmyError};
+use lemmy_utils::{
+  cache_header::cache_3days,
+  error::{LemmyError, LemmyErrorExt, LemmyErrorType},
+};
 use serde::Deserialize;
 use std::collections::HashMap;
 use url::Url;
@@ -37,15 +40,22 @@ async fn get_webfinger_response(
 ) -> Result<HttpResponse, LemmyError> {
   let name = extract_webfinger_name(&info.resource, &context)?;
 
-  let name_ = name.clone();
-  let user_id: Option<Url> = Person::read_from_name(&mut context.pool(), &name_, false)
-    .await
-    .ok()
-    .map(|c| c.actor_id.into());
-  let community_id: Option<Url> = Community::read_from_name(&mut context.pool(), &name, false)
-    .await
-    .ok()
-    .map(|c| c.actor_id.into());
+  // Actors which were renamed can still be found under their previous names
+  let person = match Person::read_from_name(&mut context.pool(), &name, false).await {
+    Ok(person) => Ok(person),
+    Err(_) => ActorNameAlias::read_person(&mut context.pool(), &name).await,
+  };
+  let community = match Community::read_from_name(&mut context.pool(), &name, false).await {
+    Ok(community) => Ok(community),
+    Err(_) => ActorNameAlias::read_community(&mut context.pool(), &name).await,
+  };
+  let (user_id, community_id): (Option<Url>, Option<Url>) = match (person, community) {
+    (Err(e), Err(_)) => Err(e).with_lemmy_type(LemmyErrorType::CouldntFindObject)?,
+    (person, community) => (
+      person.ok().map(|p| p.actor_id.into()),
+      community.ok().map(|c| c.actor_id.into()),
+    ),
+  };
 
   // Mastodon seems to prioritize the last webfinger item in case of duplicates. Put
   // community last so that it gets prioritized. For Lemmy the order doesnt matter.
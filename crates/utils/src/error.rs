@@ -142,6 +142,7 @@ pub enum LemmyErrorType {
   InvalidCommunity,
   CannotCreatePostOrCommentInDeletedOrRemovedCommunity,
   CannotReceivePage,
+  ActivityAlreadyReceived,
   NewPostCannotBeLocked,
   OnlyLocalAdminCanRemoveCommunity,
   OnlyLocalAdminCanRestoreCommunity,
@@ -150,8 +151,11 @@ pub enum LemmyErrorType {
   InvalidQuery,
   ObjectNotLocal,
   PostIsLocked,
+  PostIsArchived,
+  RepliesNotAllowed,
   PersonIsBannedFromSite(String),
   InvalidVoteValue,
+  InvalidReaction,
   PageDoesNotSpecifyCreator,
   PageDoesNotSpecifyGroup,
   NoCommunityFoundInCc,
@@ -164,6 +168,7 @@ pub enum LemmyErrorType {
   InvalidMatrixId,
   InvalidPostTitle,
   InvalidBodyField,
+  NoVisibleContent,
   BioLengthOverflow,
   MissingTotpToken,
   MissingTotpSecret,
@@ -200,6 +205,7 @@ pub enum LemmyErrorType {
   CouldntGetComments,
   CouldntGetPosts,
   InvalidUrl,
+  UrlWithoutDomain,
   EmailSendFailed,
   Slurs,
   CouldntFindObject,
@@ -207,6 +213,10 @@ pub enum LemmyErrorType {
   FederationDisabled,
   DomainBlocked(String),
   DomainNotInAllowList(String),
+  FederationPaused(String),
+  CommunityFederationBlocked(String),
+  /// The id of a fetched object doesn't match the url it was fetched from
+  ObjectIdMismatch,
   FederationDisabledByStrictAllowList,
   SiteNameRequired,
   SiteNameLengthOverflow,
@@ -226,6 +236,18 @@ pub enum LemmyErrorType {
   CommunityHasNoFollowers,
   BanExpirationInPast,
   InvalidUnixTime,
+  /// Verifying an incoming activity took longer than the configured timeout
+  ActivityVerificationTimeout,
+  /// Sensitive activities like private messages are never resent manually
+  SensitiveActivityNotResendable,
+  CommunityMembershipRequired,
+  InstanceVersionNotAllowed(String),
+  /// Resolving an object required more http requests than allowed for its domain
+  HttpFetchLimitExceeded(String),
+  /// A federated poll has no options
+  InvalidPoll,
+  PollEnded,
+  PollOptionNotFound,
   Unknown(String),
 }
 
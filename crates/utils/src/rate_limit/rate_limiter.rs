@@ -299,6 +299,66 @@ impl RateLimitState {
   }
 }
 
+/// Rate limiting of incoming federation activities, with one bucket per sending domain. Unlike
+/// [RateLimitState] the config can differ between domains, so it is looked up with `config`.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct DomainRateLimitState {
+  buckets: HashMap<String, Bucket>,
+}
+
+impl DomainRateLimitState {
+  /// Returns true if the request passed the rate limit, false if it failed and should be rejected.
+  pub fn check(
+    &mut self,
+    domain: &str,
+    now: InstantSecs,
+    config: impl Fn(&str) -> BucketConfig,
+  ) -> bool {
+    let domain = domain.to_lowercase();
+    // Same as for IP addresses, there may be arbitrarily many domains
+    if (self.buckets.capacity() == self.buckets.len()) && !self.buckets.contains_key(&domain) {
+      self.remove_full_buckets(now, &config);
+    }
+
+    let bucket_config = config(&domain);
+    let bucket = self.buckets.entry(domain).or_insert(Bucket {
+      last_checked: now,
+      tokens: bucket_config.capacity,
+    });
+    let new_bucket = bucket.update(now, bucket_config);
+    if new_bucket.tokens == 0 {
+      false
+    } else {
+      *bucket = new_bucket;
+      bucket.tokens -= 1;
+      true
+    }
+  }
+
+  /// Returns true if the domain used up its rate limit, without counting another request.
+  pub fn is_limited(
+    &self,
+    domain: &str,
+    now: InstantSecs,
+    config: impl Fn(&str) -> BucketConfig,
+  ) -> bool {
+    let domain = domain.to_lowercase();
+    self
+      .buckets
+      .get(&domain)
+      .is_some_and(|bucket| bucket.update(now, config(&domain)).tokens == 0)
+  }
+
+  /// Remove buckets that are now full
+  pub fn remove_full_buckets(&mut self, now: InstantSecs, config: impl Fn(&str) -> BucketConfig) {
+    self.buckets.retain(|domain, bucket| {
+      let config = config(domain);
+      bucket.update(now, config).tokens != config.capacity
+    });
+    self.buckets.shrink_to_fit();
+  }
+}
+
 fn split_ipv6(ip: Ipv6Addr) -> ([u8; 6], u8, u8) {
   let [a0, a1, a2, a3, a4, a5, b, c, ..] = ip.octets();
   ([a0, a1, a2, a3, a4, a5], b, c)
@@ -309,7 +369,14 @@ mod tests {
   #![allow(clippy::unwrap_used)]
   #![allow(clippy::indexing_slicing)]
 
-  use super::{ActionType, BucketConfig, InstantSecs, RateLimitState, RateLimitedGroup};
+  use super::{
+    ActionType,
+    BucketConfig,
+    DomainRateLimitState,
+    InstantSecs,
+    RateLimitState,
+    RateLimitedGroup,
+  };
 
   #[test]
   fn test_split_ipv6() {
@@ -415,4 +482,37 @@ mod tests {
     assert!(rate_limiter.ipv4_buckets.is_empty());
     assert!(rate_limiter.ipv6_buckets.is_empty());
   }
+
+  #[test]
+  fn test_domain_rate_limiter() {
+    let config = BucketConfig {
+      capacity: 2,
+      secs_to_refill: 10,
+    };
+    let mut rate_limiter = DomainRateLimitState::default();
+    let mut now = InstantSecs::now();
+
+    let mut check = |domain: &str, now| rate_limiter.check(domain, now, |_| config);
+    assert!(check("a.example", now));
+    assert!(check("A.example", now));
+    assert!(!check("a.example", now));
+    // other domains have their own bucket
+    assert!(check("b.example", now));
+
+    // one token is added every 5 seconds
+    now.secs += 5;
+    assert!(check("a.example", now));
+    assert!(!check("a.example", now));
+
+    // checking the limit doesn't use up tokens
+    assert!(rate_limiter.is_limited("a.example", now, |_| config));
+    assert!(!rate_limiter.is_limited("b.example", now, |_| config));
+    assert!(!rate_limiter.is_limited("b.example", now, |_| config));
+    assert!(!rate_limiter.is_limited("c.example", now, |_| config));
+
+    now.secs += 10;
+    assert!(!rate_limiter.is_limited("a.example", now, |_| config));
+    rate_limiter.remove_full_buckets(now, |_| config);
+    assert!(rate_limiter.buckets.is_empty());
+  }
 }
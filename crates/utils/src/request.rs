@@ -1,4 +1,4 @@
-use std::future::Future;
+use std::{future::Future, time::Duration};
 
 #[tracing::instrument(skip_all)]
 pub async fn retry<F, Fut, T>(f: F) -> Result<T, reqwest_middleware::Error>
@@ -35,3 +35,10 @@ where
 
   response.expect("retry http request")
 }
+
+/// Delay before retrying an operation which already failed `retry_count` times. Starts at `base`
+/// and doubles with every attempt, but never exceeds `max`.
+pub fn exponential_backoff(base: Duration, retry_count: i32, max: Duration) -> Duration {
+  let secs = base.as_secs_f64() * 2.0_f64.powf(f64::from(retry_count));
+  Duration::from_secs_f64(secs.min(max.as_secs_f64()))
+}
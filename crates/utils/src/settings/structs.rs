@@ -1,6 +1,9 @@
 use doku::Document;
 use serde::{Deserialize, Serialize};
-use std::net::{IpAddr, Ipv4Addr};
+use std::{
+  collections::BTreeMap,
+  net::{IpAddr, Ipv4Addr},
+};
 use url::Url;
 
 #[derive(Debug, Deserialize, Serialize, Clone, SmartDefault, Document)]
@@ -10,6 +13,8 @@ pub struct Settings {
   #[default(Default::default())]
   pub database: DatabaseConfig,
   /// Settings related to activitypub federation
+  #[default(Default::default())]
+  pub federation: FederationConfig,
   /// Pictrs image server configuration.
   #[default(Some(Default::default()))]
   pub(crate) pictrs: Option<PictrsConfig>,
@@ -45,6 +50,10 @@ pub struct Settings {
   /// The number of activitypub federation retry workers that can be in-flight concurrently
   #[default(0)]
   pub retry_count: usize,
+  /// Reject posts and comments which render to nothing visible, for example because they only
+  /// consist of invisible characters or empty markup. Applies to local and federated content.
+  #[default(false)]
+  pub reject_invisible_content: bool,
   // Prometheus configuration.
   #[default(None)]
   #[doku(example = "Some(Default::default())")]
@@ -68,6 +77,102 @@ pub struct PictrsConfig {
   pub cache_remote_images: bool,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone, SmartDefault, Document)]
+#[serde(default, deny_unknown_fields)]
+pub struct FederationConfig {
+  /// Maximum length in bytes of the html `summary` of federated actors. Longer texts are
+  /// truncated, the full text is still available in `source`.
+  #[default(5000)]
+  pub summary_max_length: usize,
+  /// Maximum time in seconds for verifying an incoming activity, including any remote fetches
+  /// this requires. Activities which take longer are rejected.
+  #[default(60)]
+  pub verify_timeout_seconds: u64,
+  /// Maximum number of inboxes on a single instance which an activity is delivered to at once.
+  /// Remaining inboxes are handled in subsequent passes.
+  #[default(1000)]
+  pub max_recipients_per_pass: usize,
+  /// Maximum time in seconds for delivering an activity to a single inbox. Slower deliveries are
+  /// aborted and retried later, so that one slow inbox doesn't hold up the others.
+  #[default(10)]
+  pub delivery_timeout_seconds: u64,
+  /// Minimum version of remote instances, per software name. Instances running an older version
+  /// of the given software are not federated with, for example `{ lemmy: "0.18.0" }`.
+  pub minimum_versions: BTreeMap<String, String>,
+  /// Whether to federate with instances whose version is unknown while minimum versions are
+  /// configured.
+  #[default(true)]
+  pub allow_unknown_versions: bool,
+  /// Store the outcome of delivering each activity to each inbox, for debugging federation. The
+  /// records are deleted together with the activity.
+  #[default(false)]
+  pub record_deliveries: bool,
+  /// Maximum number of http requests which may be made while resolving a single object or
+  /// activity.
+  #[default(50)]
+  pub http_fetch_limit: u32,
+  /// Per-domain overrides of `http_fetch_limit`, for example a higher limit for trusted instances
+  /// like `{ "lemmy.example": 200 }`.
+  pub http_fetch_limit_overrides: BTreeMap<String, u32>,
+  /// Maximum number of items from a remote collection, like a community outbox, which are
+  /// dereferenced at once.
+  #[default(4)]
+  pub collection_item_concurrency: usize,
+  /// What happens to the posts and comments of remote users who are banned by their home
+  /// instance.
+  pub remote_ban_content_policy: RemoteBanContentPolicy,
+  /// How long in seconds the actor which a webfinger identifier like `user@example.com` resolved
+  /// to is remembered.
+  #[default(3600)]
+  pub webfinger_cache_seconds: u64,
+  /// How long in seconds a failed webfinger lookup is remembered, so that unreachable instances
+  /// are not queried over and over.
+  #[default(300)]
+  pub webfinger_negative_cache_seconds: u64,
+  /// Maximum number of activities per minute which are accepted from a single remote instance,
+  /// identified by the domain of the actor, whose key has to sign them. Every activity with a valid
+  /// signature is counted, even if it is rejected afterwards. Further activities are rejected with
+  /// HTTP 429, so that the sender retries them later. 0 disables the limit.
+  #[default(3000)]
+  pub inbox_rate_limit: u32,
+  /// Per-domain overrides of `inbox_rate_limit`, for example a higher limit for large trusted
+  /// instances like `{ "lemmy.example": 20000 }`.
+  pub inbox_rate_limit_overrides: BTreeMap<String, u32>,
+  /// Activity types which are sent by other platforms, but have no meaning for Lemmy. Once their
+  /// signature is verified, these are stored and acknowledged without processing or logging an
+  /// error.
+  #[default(vec!["View".to_string(), "Listen".to_string()])]
+  pub ignored_activity_types: Vec<String>,
+  /// Require a valid HTTP signature from a remote actor on requests for ActivityPub objects
+  /// ("authorized fetch"), and reject unsigned requests with HTTP 401. This makes scraping harder,
+  /// but instances which don't sign their fetches can't read anything anymore. Actors like users,
+  /// communities and the instance itself are always served, so that others can verify signatures
+  /// made with their keys.
+  #[default(false)]
+  pub signed_fetch: bool,
+  /// Whether activities served under `/activities/` also require a signature when `signed_fetch`
+  /// is enabled.
+  #[default(true)]
+  pub signed_fetch_activities: bool,
+  /// Allow requests from a loopback address without signature when `signed_fetch` is enabled.
+  /// Behind a reverse proxy on the same machine this applies to all requests, so only enable it
+  /// if Lemmy is reached directly.
+  #[default(false)]
+  pub signed_fetch_allow_local: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, SmartDefault, Document)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteBanContentPolicy {
+  /// Remove the content only if the home instance asks for it
+  #[default]
+  FollowHomeInstance,
+  /// Always remove the content
+  Remove,
+  /// Never remove the content, the user is only marked as banned
+  Keep,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, SmartDefault, Document)]
 #[serde(default)]
 pub struct DatabaseConfig {
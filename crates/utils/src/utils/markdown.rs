@@ -1,13 +1,79 @@
-use markdown_it::MarkdownIt;
+use crate::utils::validation::FORBIDDEN_DISPLAY_CHARS;
+use definition_list_rule::{DefinitionDetails, DefinitionTerm};
+use hashtag_rule::Hashtag;
+use markdown_it::{
+  parser::{
+    core::Root,
+    inline::{Text, TextSpecial},
+  },
+  plugins::{
+    cmark::{
+      block::{
+        blockquote::Blockquote,
+        code::CodeBlock,
+        fence::CodeFence,
+        heading::ATXHeading,
+        lheading::SetextHeader,
+        list::{BulletList, ListItem, OrderedList},
+        paragraph::Paragraph,
+      },
+      inline::{
+        autolink::Autolink,
+        backticks::CodeInline,
+        image::Image,
+        link::Link,
+        newline::{Hardbreak, Softbreak},
+      },
+    },
+    extra::linkify::Linkified,
+  },
+  MarkdownIt,
+  Node,
+  NodeValue,
+  Renderer,
+};
 use once_cell::sync::Lazy;
+use regex::{NoExpand, Regex};
+use spoiler_rule::SpoilerBlock;
+use std::{
+  collections::{HashMap, VecDeque},
+  mem::take,
+};
+use url::{form_urlencoded::byte_serialize, Url};
 
+mod definition_list_rule;
+mod hashtag_rule;
+mod inline_spoiler_rule;
+mod math_rule;
+mod mention_rule;
 mod spoiler_rule;
+mod strikethrough_rule;
+mod sup_sub_rule;
+mod task_list_rule;
 
-static MARKDOWN_PARSER: Lazy<MarkdownIt> = Lazy::new(|| {
+/// Markdown parser with all the plugins which are used for rendering.
+fn base_parser() -> MarkdownIt {
   let mut parser = MarkdownIt::new();
   markdown_it::plugins::cmark::add(&mut parser);
   markdown_it::plugins::extra::add(&mut parser);
   spoiler_rule::add(&mut parser);
+  inline_spoiler_rule::add(&mut parser);
+  math_rule::add(&mut parser);
+  strikethrough_rule::add(&mut parser);
+  sup_sub_rule::add(&mut parser);
+  task_list_rule::add(&mut parser);
+  definition_list_rule::add(&mut parser);
+  hashtag_rule::add(&mut parser);
+
+  parser
+}
+
+static MARKDOWN_PARSER: Lazy<MarkdownIt> = Lazy::new(base_parser);
+
+/// Same as [MARKDOWN_PARSER], but also recognizes user and community handles.
+static MARKDOWN_PARSER_WITH_MENTIONS: Lazy<MarkdownIt> = Lazy::new(|| {
+  let mut parser = base_parser();
+  mention_rule::add(&mut parser);
 
   parser
 });
@@ -16,18 +82,766 @@ static MARKDOWN_PARSER: Lazy<MarkdownIt> = Lazy::new(|| {
 ///
 /// Taken from https://github.com/OWASP/CheatSheetSeries/blob/master/cheatsheets/Cross_Site_Scripting_Prevention_Cheat_Sheet.md#output-encoding-for-html-contexts
 ///
-/// `>` is left in place because it is interpreted as markdown quote.
+/// `>` is left in place because it is interpreted as markdown quote. Control characters like NUL
+/// are removed, see [remove_control_chars].
 pub fn sanitize_html(text: &str) -> String {
-  text
+  remove_control_chars(text)
     .replace('&', "&amp;")
     .replace('<', "&lt;")
     .replace('\"', "&quot;")
     .replace('\'', "&#x27;")
 }
 
-/// Converts text from markdown to HTML, while escaping special characters.
+/// Removes control characters other than tab, newline and carriage return, which some clients
+/// mishandle and which could be used to inject fake lines into logs.
+fn remove_control_chars(text: &str) -> String {
+  text
+    .chars()
+    .filter(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r'))
+    .collect()
+}
+
+/// Same as [sanitize_html], but keeps the tags in `allowed_tags` (like `b`, `i` or `code`) for
+/// trusted contexts which may contain simple inline markup. Only bare tags like `<b>` and `</b>`
+/// are kept, tags with attributes are always escaped. Closing tags without a matching opening tag
+/// are escaped, and tags which are still open at the end of the text are closed.
+pub fn sanitize_html_allowing(text: &str, allowed_tags: &[&str]) -> String {
+  let mut out = String::with_capacity(text.len());
+  let mut open_tags: Vec<String> = vec![];
+  let mut rest = text;
+  while let Some((before, after)) = rest.split_once('<') {
+    out.push_str(&sanitize_html(before));
+    let tag = after
+      .split_once('>')
+      .and_then(|(inner, after_tag)| Some((allowed_tag(inner, allowed_tags)?, after_tag)));
+    rest = match tag {
+      Some(((name, false), after_tag)) => {
+        out.push_str(&format!("<{name}>"));
+        open_tags.push(name);
+        after_tag
+      }
+      Some(((name, true), after_tag)) if open_tags.contains(&name) => {
+        // also close any tags which were opened inside of this one
+        while let Some(open) = open_tags.pop() {
+          out.push_str(&format!("</{open}>"));
+          if open == name {
+            break;
+          }
+        }
+        after_tag
+      }
+      _ => {
+        out.push_str("&lt;");
+        after
+      }
+    };
+  }
+  out.push_str(&sanitize_html(rest));
+  for open in open_tags.iter().rev() {
+    out.push_str(&format!("</{open}>"));
+  }
+  out
+}
+
+/// Parses the content between `<` and `>`. Returns the lowercase tag name and whether it is a
+/// closing tag, if it is a bare tag from the allowlist.
+fn allowed_tag(inner: &str, allowed_tags: &[&str]) -> Option<(String, bool)> {
+  let (name, closing) = match inner.strip_prefix('/') {
+    Some(name) => (name, true),
+    None => (inner, false),
+  };
+  let valid = !name.is_empty()
+    && name.chars().all(|c| c.is_ascii_alphanumeric())
+    && allowed_tags.iter().any(|t| t.eq_ignore_ascii_case(name));
+  valid.then(|| (name.to_ascii_lowercase(), closing))
+}
+
+/// Appended to rendered markdown which exceeded the maximum number of nodes.
+const TRUNCATION_NOTICE: &str = "<p><em>[content truncated]</em></p>\n";
+
+/// Limits which keep rendering of huge or malicious documents cheap. The renderers use
+/// [MarkdownLimits::default], [markdown_to_html_with_limits] renders with other limits.
+#[derive(Clone, Debug)]
+pub struct MarkdownLimits {
+  /// Maximum number of elements which are rendered. Anything beyond is cut off with a notice, so
+  /// that huge documents can't exhaust memory.
+  pub max_nodes: usize,
+  /// Maximum nesting depth of blockquotes and lists. Deeper ones are flattened, so that their
+  /// content is still shown but can't blow up rendering.
+  pub max_depth: usize,
+  /// Url schemes which are allowed for links and images. Links with other schemes like
+  /// `javascript:` or `data:` are rendered as plain text. Relative links are always allowed.
+  pub allowed_schemes: Vec<String>,
+}
+
+impl Default for MarkdownLimits {
+  fn default() -> Self {
+    MarkdownLimits {
+      max_nodes: 100_000,
+      max_depth: 10,
+      allowed_schemes: ["http", "https", "mailto", "magnet"]
+        .map(String::from)
+        .to_vec(),
+    }
+  }
+}
+
+static DEFAULT_LIMITS: Lazy<MarkdownLimits> = Lazy::new(MarkdownLimits::default);
+
+/// Converts text from markdown to HTML, while escaping special characters and removing control
+/// characters.
+///
+/// The document is rendered within the default [MarkdownLimits].
 pub fn markdown_to_html(text: &str) -> String {
-  MARKDOWN_PARSER.parse(text).xrender()
+  markdown_to_html_with_limits(text, &DEFAULT_LIMITS)
+}
+
+/// Converts text from markdown to HTML within the given limits. If the document has more than
+/// `max_nodes` elements, the rest is dropped and a notice is appended instead. Blockquotes and
+/// lists nested deeper than `max_depth` are flattened, and links with a scheme which is not in
+/// `allowed_schemes` are shown as text.
+pub fn markdown_to_html_with_limits(text: &str, limits: &MarkdownLimits) -> String {
+  render(parse(&MARKDOWN_PARSER, text), limits)
+}
+
+/// Same as [markdown_to_html], but additionally turns `@user@instance.tld` and
+/// `!community@instance.tld` into links to the profile on the instance at `protocol_and_hostname`.
+/// Hashtags link to the tag pages on that instance instead of the relative `/tag/<name>`.
+pub fn markdown_to_html_with_context(text: &str, protocol_and_hostname: &str) -> String {
+  render_with_context(text, protocol_and_hostname, None)
+}
+
+/// Same as [markdown_to_html_with_context], but mentions of the actors in `display_names`, which
+/// maps handles like `@name@domain` or `!name@domain` to display names, show the display name as
+/// link text and the handle as title. Mentions of other actors show the handle.
+pub fn markdown_to_html_with_display_names(
+  text: &str,
+  protocol_and_hostname: &str,
+  display_names: &HashMap<String, String>,
+) -> String {
+  render_with_context(text, protocol_and_hostname, Some(display_names))
+}
+
+fn render_with_context(
+  text: &str,
+  protocol_and_hostname: &str,
+  display_names: Option<&HashMap<String, String>>,
+) -> String {
+  let mut root = parse(&MARKDOWN_PARSER_WITH_MENTIONS, text);
+  mention_rule::resolve_mentions(&mut root, protocol_and_hostname, display_names);
+  hashtag_rule::set_hashtag_prefix(&mut root, protocol_and_hostname);
+  render(root, &DEFAULT_LIMITS)
+}
+
+/// Same as [markdown_to_html], but images are loaded through the proxy at `proxy_url`, so that
+/// readers don't connect to arbitrary remote hosts. For example with `/image_proxy`, the image
+/// `https://example.com/a.png` becomes `/image_proxy?url=https%3A%2F%2Fexample.com%2Fa.png`.
+/// Relative image urls are kept, images with other schemes like `data:` are replaced by their alt
+/// text.
+pub fn markdown_to_html_with_proxy(text: &str, proxy_url: &str) -> String {
+  let mut root = parse(&MARKDOWN_PARSER, text);
+  proxy_images(&mut root, proxy_url);
+  render(root, &DEFAULT_LIMITS)
+}
+
+/// Same as [markdown_to_html], but custom emojis written as `:shortcode:` are shown as images.
+/// `emojis` maps shortcodes without the colons to image urls, unknown shortcodes are left as text.
+pub fn markdown_to_html_with_emojis(text: &str, emojis: &HashMap<String, String>) -> String {
+  let mut root = parse(&MARKDOWN_PARSER, text);
+  replace_emojis(&mut root, emojis);
+  render(root, &DEFAULT_LIMITS)
+}
+
+/// Same as [markdown_to_html], but the HTML is at most `max_len` bytes long. A few characters of
+/// markdown can result in much longer HTML, for example with deeply nested emphasis. If the
+/// output is longer, the end of the document is dropped so that all tags are still closed, and a
+/// notice is appended instead.
+pub fn markdown_to_html_with_max_len(text: &str, max_len: usize) -> String {
+  render_with_max_len(parse(&MARKDOWN_PARSER, text), &DEFAULT_LIMITS, max_len)
+}
+
+/// Parses text for rendering, after removing control characters and excessive blank lines.
+fn parse(parser: &MarkdownIt, text: &str) -> Node {
+  parser.parse(&collapse_blank_lines(&remove_control_chars(text)))
+}
+
+fn render(root: Node, limits: &MarkdownLimits) -> String {
+  render_with_max_len(root, limits, usize::MAX)
+}
+
+fn render_with_max_len(mut root: Node, limits: &MarkdownLimits, max_len: usize) -> String {
+  remove_blank_paragraphs(&mut root);
+  let source = root
+    .cast::<Root>()
+    .map(|root| root.content.clone())
+    .unwrap_or_default();
+  restrict_linkified(&mut root, &source, false);
+  restrict_link_schemes(&mut root, &limits.allowed_schemes);
+  limit_nesting(&mut root, 0, limits.max_depth);
+  let mut remaining = limits.max_nodes;
+  let mut truncated = truncate_nodes(&mut root, &mut remaining);
+  let mut html = root.xrender();
+  if html.len() > max_len {
+    truncate_output(&mut root, max_len.saturating_sub(TRUNCATION_NOTICE.len()));
+    html = root.xrender();
+    truncated = true;
+  }
+  if truncated {
+    format!("{html}{TRUNCATION_NOTICE}")
+  } else {
+    html
+  }
+}
+
+/// Keeps the first `remaining` descendants of the node in document order and drops everything
+/// after them. Returns true if anything was dropped.
+fn truncate_nodes(node: &mut Node, remaining: &mut usize) -> bool {
+  for i in 0..node.children.len() {
+    if *remaining == 0 {
+      node.children.truncate(i);
+      return true;
+    }
+    *remaining -= 1;
+    if let Some(child) = node.children.get_mut(i) {
+      if truncate_nodes(child, remaining) {
+        node.children.truncate(i + 1);
+        return true;
+      }
+    }
+  }
+  false
+}
+
+/// Drops descendants from the end of the node until its HTML is at most `max_len` bytes long.
+/// Text is cut off in between, other nodes are only dropped as a whole so that no tag is left open.
+fn truncate_output(node: &mut Node, max_len: usize) {
+  let children = take(&mut node.children);
+  // length of the tags around the children
+  let mut remaining = max_len.saturating_sub(node.xrender().len());
+  for mut child in children {
+    let len = child.xrender().len();
+    if len > remaining {
+      if let Some(text) = child.cast_mut::<Text>() {
+        text.content = truncate_escaped(&text.content, remaining);
+      } else {
+        truncate_output(&mut child, remaining);
+      }
+      if child.xrender().len() <= remaining {
+        node.children.push(child);
+      }
+      break;
+    }
+    remaining -= len;
+    node.children.push(child);
+  }
+}
+
+/// Longest prefix of the text which is at most `max_len` bytes long after escaping it for HTML.
+fn truncate_escaped(text: &str, max_len: usize) -> String {
+  let mut len = 0;
+  text
+    .chars()
+    .take_while(|c| {
+      len += match c {
+        '&' => "&amp;".len(),
+        '<' | '>' => "&lt;".len(),
+        '"' => "&quot;".len(),
+        _ => c.len_utf8(),
+      };
+      len <= max_len
+    })
+    .collect()
+}
+
+/// Turns bare URLs which were recognized as links back into text, unless they use the http or
+/// https scheme. Also unwraps them and hashtags inside of other links, as links can't be nested.
+///
+/// The text of recognized URLs lacks the scheme, so it is replaced by the URL as written in
+/// `source`.
+fn restrict_linkified(node: &mut Node, source: &str, inside_link: bool) {
+  let children = take(&mut node.children);
+  for mut child in children {
+    if let Some(linkified) = child.cast::<Linkified>() {
+      let allowed = linkified
+        .url
+        .split_once(':')
+        .is_some_and(|(scheme, _)| ["http", "https"].contains(&scheme.to_lowercase().as_str()));
+      let content = child
+        .srcmap
+        .and_then(|map| {
+          let (start, end) = map.get_byte_offsets();
+          source.get(start..end)
+        })
+        .unwrap_or(&linkified.url)
+        .to_string();
+      let mut text = Node::new(Text { content });
+      text.srcmap = child.srcmap;
+      if inside_link || !allowed {
+        node.children.push(text);
+        continue;
+      }
+      child.children = vec![text];
+    }
+    if inside_link && child.is::<Hashtag>() {
+      node.children.append(&mut child.children);
+      continue;
+    }
+    let is_link = child.is::<Link>()
+      || child.is::<Autolink>()
+      || child.is::<Linkified>()
+      || child.is::<Hashtag>();
+    restrict_linkified(&mut child, source, inside_link || is_link);
+    node.children.push(child);
+  }
+}
+
+/// Replaces links and images whose url has a scheme which is not in `allowed_schemes` by their
+/// content, so that for example `[click](data:text/html,...)` only renders the text `click`.
+/// Relative urls don't have a scheme and are kept.
+fn restrict_link_schemes(node: &mut Node, allowed_schemes: &[String]) {
+  let children = take(&mut node.children);
+  for mut child in children {
+    let url = if let Some(link) = child.cast::<Link>() {
+      Some(&link.url)
+    } else if let Some(link) = child.cast::<Autolink>() {
+      Some(&link.url)
+    } else if let Some(link) = child.cast::<Linkified>() {
+      Some(&link.url)
+    } else {
+      child.cast::<Image>().map(|image| &image.url)
+    };
+    let scheme = url
+      .and_then(|url| Url::parse(url).ok())
+      .map(|url| url.scheme().to_string());
+    if let Some(scheme) = scheme {
+      if !allowed_schemes
+        .iter()
+        .any(|s| s.eq_ignore_ascii_case(&scheme))
+      {
+        node.children.append(&mut child.children);
+        continue;
+      }
+    }
+    restrict_link_schemes(&mut child, allowed_schemes);
+    node.children.push(child);
+  }
+}
+
+/// Rewrites the urls of remote images to go through the proxy, see [markdown_to_html_with_proxy].
+fn proxy_images(node: &mut Node, proxy_url: &str) {
+  let children = take(&mut node.children);
+  for mut child in children {
+    if let Some(image) = child.cast_mut::<Image>() {
+      let scheme = Url::parse(&image.url).ok().map(|u| u.scheme().to_string());
+      match scheme.as_deref() {
+        Some("http" | "https") => {
+          let encoded: String = byte_serialize(image.url.as_bytes()).collect();
+          image.url = format!("{proxy_url}?url={encoded}");
+        }
+        None => {}
+        Some(_) => {
+          node.children.append(&mut child.children);
+          continue;
+        }
+      }
+    }
+    proxy_images(&mut child, proxy_url);
+    node.children.push(child);
+  }
+}
+
+#[derive(Debug)]
+struct CustomEmoji {
+  shortcode: String,
+  url: String,
+}
+
+impl NodeValue for CustomEmoji {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    let name = format!(":{}:", self.shortcode);
+    let mut attrs = node.attrs.clone();
+    attrs.push(("class", "emoji".into()));
+    attrs.push(("src", self.url.clone()));
+    attrs.push(("alt", name.clone()));
+    attrs.push(("title", name));
+    fmt.self_close("img", &attrs);
+  }
+}
+
+/// Splits text nodes at known emoji shortcodes, see [markdown_to_html_with_emojis]. Code is not
+/// affected, like with [apply_word_filters].
+fn replace_emojis(node: &mut Node, emojis: &HashMap<String, String>) {
+  let children = take(&mut node.children);
+  for mut child in children {
+    if child.is::<CodeInline>() || child.is::<CodeBlock>() || child.is::<CodeFence>() {
+      node.children.push(child);
+      continue;
+    }
+    if let Some(text) = child.cast::<Text>() {
+      node
+        .children
+        .append(&mut split_emojis(&text.content, emojis));
+      continue;
+    }
+    replace_emojis(&mut child, emojis);
+    node.children.push(child);
+  }
+}
+
+fn split_emojis(text: &str, emojis: &HashMap<String, String>) -> Vec<Node> {
+  let mut nodes = vec![];
+  let mut plain = String::new();
+  let mut rest = text;
+  while let Some((before, after)) = rest.split_once(':') {
+    plain.push_str(before);
+    let emoji = after
+      .split_once(':')
+      .and_then(|(shortcode, remaining)| Some((shortcode, emojis.get(shortcode)?, remaining)));
+    if let Some((shortcode, url, remaining)) = emoji {
+      if !plain.is_empty() {
+        nodes.push(Node::new(Text {
+          content: take(&mut plain),
+        }));
+      }
+      nodes.push(Node::new(CustomEmoji {
+        shortcode: shortcode.to_string(),
+        url: url.clone(),
+      }));
+      rest = remaining;
+    } else {
+      plain.push(':');
+      rest = after;
+    }
+  }
+  plain.push_str(rest);
+  if !plain.is_empty() {
+    nodes.push(Node::new(Text { content: plain }));
+  }
+  nodes
+}
+
+/// Flattens blockquotes and lists which are nested more than `max_depth` levels deep. Their
+/// content is kept in place of the container, so nothing is lost but deeply nested documents can't
+/// blow up rendering. `depth` is the number of containers around `node`.
+fn limit_nesting(node: &mut Node, depth: usize, max_depth: usize) {
+  let mut pending: VecDeque<Node> = take(&mut node.children).into();
+  while let Some(mut child) = pending.pop_front() {
+    if is_nesting_container(&child) {
+      if depth >= max_depth {
+        for content in container_content(child).into_iter().rev() {
+          pending.push_front(content);
+        }
+        continue;
+      }
+      limit_nesting(&mut child, depth + 1, max_depth);
+    } else {
+      limit_nesting(&mut child, depth, max_depth);
+    }
+    node.children.push(child);
+  }
+}
+
+fn is_nesting_container(node: &Node) -> bool {
+  node.is::<Blockquote>() || node.is::<BulletList>() || node.is::<OrderedList>()
+}
+
+/// Content of a blockquote or list, without the container itself. For lists this is the content
+/// of all items, as list items can't be rendered outside of a list.
+fn container_content(mut node: Node) -> Vec<Node> {
+  let children = take(&mut node.children);
+  if node.is::<Blockquote>() {
+    children
+  } else {
+    children
+      .into_iter()
+      .flat_map(|mut item| {
+        if item.is::<ListItem>() {
+          take(&mut item.children)
+        } else {
+          vec![item]
+        }
+      })
+      .collect()
+  }
+}
+
+/// Collapses runs of three or more blank lines into a single one, as text which is pasted from
+/// editors often contains dozens of them. Blank lines in fenced code blocks are kept, and so are
+/// those before an indented line, which may continue an indented code block.
+fn collapse_blank_lines(text: &str) -> String {
+  let is_blank = |line: &str| line.chars().all(|c| matches!(c, ' ' | '\t' | '\r'));
+  let mut lines: Vec<&str> = Vec::new();
+  let mut blank_run: Vec<&str> = Vec::new();
+  // fence character and length of the open code fence
+  let mut fence: Option<(char, usize)> = None;
+  for line in text.split('\n') {
+    if fence.is_none() && is_blank(line) {
+      blank_run.push(line);
+      continue;
+    }
+    let indented = line.starts_with("    ") || line.starts_with('\t');
+    if blank_run.len() >= 3 && !indented {
+      lines.push("");
+    } else {
+      lines.append(&mut blank_run);
+    }
+    blank_run.clear();
+    lines.push(line);
+
+    if !indented {
+      let trimmed = line.trim_start_matches(' ');
+      let marker = trimmed.chars().next().filter(|c| matches!(c, '`' | '~'));
+      if let Some(c) = marker {
+        let rest = trimmed.trim_start_matches(c);
+        let len = trimmed.len() - rest.len();
+        match fence {
+          None if len >= 3 => fence = Some((c, len)),
+          Some((open, open_len)) if open == c && len >= open_len && rest.trim().is_empty() => {
+            fence = None
+          }
+          _ => {}
+        }
+      }
+    }
+  }
+  if blank_run.len() < 3 {
+    lines.append(&mut blank_run);
+  }
+  lines.join("\n")
+}
+
+/// Removes paragraphs which contain nothing but whitespace. Blank lines themselves never create
+/// paragraphs, but pasted text often contains lines of non-breaking or zero-width spaces (or
+/// `&nbsp;`) which do, and these would render as large gaps. Code blocks contain their text
+/// verbatim rather than paragraphs, so blank lines in them are kept.
+fn remove_blank_paragraphs(node: &mut Node) {
+  node
+    .children
+    .retain(|child| !(child.is::<Paragraph>() && is_blank(child)));
+  for child in &mut node.children {
+    remove_blank_paragraphs(child);
+  }
+}
+
+/// Whether the node only consists of whitespace and line breaks.
+fn is_blank(node: &Node) -> bool {
+  let blank_text = |text: &str| text.chars().all(|c| c.is_whitespace() || c == '\u{200b}');
+  node.children.iter().all(|child| {
+    if let Some(text) = child.cast::<Text>() {
+      blank_text(&text.content)
+    } else if let Some(text) = child.cast::<TextSpecial>() {
+      blank_text(&text.content)
+    } else {
+      child.is::<Softbreak>() || child.is::<Hardbreak>()
+    }
+  })
+}
+
+/// Whether the markdown renders to anything visible, based on the plaintext of the document. Text
+/// which only consists of whitespace and invisible characters, or markup without any content like
+/// `**` or `[](https://example.com)`, is not renderable. Images and code blocks are.
+pub fn has_renderable_content(text: &str) -> bool {
+  let visible = |text: &str| {
+    text
+      .chars()
+      .any(|c| !c.is_whitespace() && !FORBIDDEN_DISPLAY_CHARS.contains(&c))
+  };
+  let root = MARKDOWN_PARSER.parse(text);
+  if visible(&root.collect_text()) {
+    return true;
+  }
+  // these are rendered, but don't contain any text nodes
+  let mut renderable = false;
+  root.walk(|node, _| {
+    if node.is::<Image>() {
+      renderable = true;
+    } else if let Some(code) = node.cast::<CodeBlock>() {
+      renderable |= visible(&code.content);
+    } else if let Some(code) = node.cast::<CodeFence>() {
+      renderable |= visible(&code.content);
+    }
+  });
+  renderable
+}
+
+/// Converts markdown to plain text, for places where HTML can't be shown like push notifications
+/// or search indexes. Block elements like paragraphs, headings and list items are each put on
+/// their own line. Images are replaced by their alt text, and links by their link text.
+pub fn markdown_to_plaintext(text: &str) -> String {
+  plaintext(text, false)
+}
+
+/// Same as [markdown_to_plaintext], but links are written as `text (url)` so that they can still
+/// be followed.
+pub fn markdown_to_plaintext_with_link_urls(text: &str) -> String {
+  plaintext(text, true)
+}
+
+fn plaintext(text: &str, link_urls: bool) -> String {
+  let mut out = String::new();
+  write_plaintext(&MARKDOWN_PARSER.parse(text), &mut out, link_urls);
+  out.trim_end().to_string()
+}
+
+fn write_plaintext(node: &Node, out: &mut String, link_urls: bool) {
+  let is_block = node.is::<Paragraph>()
+    || node.is::<ATXHeading>()
+    || node.is::<SetextHeader>()
+    || node.is::<ListItem>()
+    || node.is::<Blockquote>()
+    || node.is::<CodeBlock>()
+    || node.is::<CodeFence>()
+    || node.is::<SpoilerBlock>()
+    || node.is::<DefinitionTerm>()
+    || node.is::<DefinitionDetails>();
+  let new_line = |out: &mut String| {
+    if !out.is_empty() && !out.ends_with('\n') {
+      out.push('\n');
+    }
+  };
+  if is_block {
+    new_line(out);
+  }
+
+  if let Some(text) = node.cast::<Text>() {
+    out.push_str(&text.content);
+  } else if let Some(text) = node.cast::<TextSpecial>() {
+    out.push_str(&text.content);
+  } else if node.is::<Softbreak>() || node.is::<Hardbreak>() {
+    out.push('\n');
+  } else if let Some(code) = node.cast::<CodeBlock>() {
+    out.push_str(code.content.trim_end());
+  } else if let Some(code) = node.cast::<CodeFence>() {
+    out.push_str(code.content.trim_end());
+  } else {
+    if let Some(spoiler) = node.cast::<SpoilerBlock>() {
+      out.push_str(&spoiler.visible_text);
+      out.push('\n');
+    }
+    // images are dropped, but their children hold the alt text
+    for child in &node.children {
+      write_plaintext(child, out, link_urls);
+    }
+    if let Some(link) = node.cast::<Link>().filter(|_| link_urls) {
+      out.push_str(&format!(" ({})", link.url));
+    }
+  }
+
+  if is_block {
+    new_line(out);
+  }
+}
+
+/// Cheap signals about how "heavy" a piece of content is, for use in spam filters.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ContentWeight {
+  /// Number of links, including autolinks and bare urls
+  pub links: usize,
+  pub images: usize,
+  /// Number of characters of the text, without any markdown syntax
+  pub length: usize,
+  /// Share of uppercase letters among all letters, between 0 and 1
+  pub caps_ratio: f32,
+}
+
+/// Calculates the [ContentWeight] of markdown text, in a single pass over the parsed document.
+pub fn content_weight(text: &str) -> ContentWeight {
+  let mut weight = ContentWeight::default();
+  let mut letters = 0;
+  let mut uppercase = 0;
+  MARKDOWN_PARSER.parse(text).walk(|node, _| {
+    if node.is::<Link>() || node.is::<Autolink>() || node.is::<Linkified>() {
+      weight.links += 1;
+    } else if node.is::<Image>() {
+      weight.images += 1;
+    } else if let Some(text) = node.cast::<Text>() {
+      for c in text.content.chars() {
+        weight.length += 1;
+        if c.is_alphabetic() {
+          letters += 1;
+          if c.is_uppercase() {
+            uppercase += 1;
+          }
+        }
+      }
+    }
+  });
+  if letters > 0 {
+    weight.caps_ratio = uppercase as f32 / letters as f32;
+  }
+  weight
+}
+
+/// Counts the explicit links and autolinks like `<https://example.com>`, so that posts with too
+/// many links can be rejected. Unlike [content_weight], urls in plain text are not counted.
+pub fn count_markdown_links(text: &str) -> usize {
+  let mut links = 0;
+  MARKDOWN_PARSER.parse(text).walk(|node, _| {
+    if node.is::<Link>() || node.is::<Autolink>() {
+      links += 1;
+    }
+  });
+  links
+}
+
+/// Replaces the matches of each filter with its replacement, for instance-defined word filters.
+/// This is applied to the markdown before it is stored and rendered with [markdown_to_html].
+/// Inline code and code blocks are left unchanged, so that code snippets aren't corrupted. The
+/// replacement is inserted literally, without expanding capture groups like `$1`.
+pub fn apply_word_filters(text: &str, filters: &[(Regex, String)]) -> String {
+  let mut out = String::with_capacity(text.len());
+  for (prose, segment) in code_segments(text) {
+    if !prose {
+      out.push_str(segment);
+      continue;
+    }
+    let mut segment = segment.to_string();
+    for (regex, replacement) in filters {
+      segment = regex
+        .replace_all(&segment, NoExpand(replacement.as_str()))
+        .into_owned();
+    }
+    out.push_str(&segment);
+  }
+  out
+}
+
+/// Unique matches of word filters which block content instead of replacing words, so that the
+/// API can reject it. Like [apply_word_filters], code is ignored.
+pub fn blocked_words<'a>(text: &'a str, filters: &[Regex]) -> Vec<&'a str> {
+  let mut matches: Vec<&str> = code_segments(text)
+    .into_iter()
+    .filter(|(prose, _)| *prose)
+    .flat_map(|(_, segment)| filters.iter().flat_map(|r| r.find_iter(segment)))
+    .map(|m| m.as_str())
+    .collect();
+  matches.sort_unstable();
+  matches.dedup();
+  matches
+}
+
+/// Splits the markdown into parts which are prose (true) or code (false), in order.
+fn code_segments(text: &str) -> Vec<(bool, &str)> {
+  let mut ranges = vec![];
+  MARKDOWN_PARSER.parse(text).walk(|node, _| {
+    let is_code = node.is::<CodeInline>() || node.is::<CodeBlock>() || node.is::<CodeFence>();
+    if let (true, Some(srcmap)) = (is_code, &node.srcmap) {
+      ranges.push(srcmap.get_byte_offsets());
+    }
+  });
+
+  let mut segments = vec![];
+  let mut pos = 0;
+  for (start, end) in ranges {
+    let (Some(prose), Some(code)) = (text.get(pos..start), text.get(start..end)) else {
+      continue;
+    };
+    segments.push((true, prose));
+    segments.push((false, code));
+    pos = end;
+  }
+  segments.push((true, text.get(pos..).unwrap_or_default()));
+  segments
 }
 
 #[cfg(test)]
@@ -49,6 +863,11 @@ mod tests {
         "line breaks",
         "First\rSecond",
         "<p>First\nSecond</p>\n"),
+      (
+        "strikethrough",
+        "~~gone~~",
+        "<p><del>gone</del></p>\n"
+      ),
       (
         "emphasis",
         "__bold__ **bold** *italic* ***bold+italic***",
@@ -83,7 +902,7 @@ mod tests {
       (
         "basic spoiler",
         "::: spoiler click to see more\nhow spicy!\n:::\n",
-        "<details><summary>click to see more</summary><p>how spicy!\n</p></details>\n"
+        "<details class=\"spoiler\"><summary>click to see more</summary><div class=\"spoiler-body\">\n<p>how spicy!</p>\n</div></details>\n"
       ),
       (
           "escape html special chars",
@@ -103,10 +922,555 @@ mod tests {
     });
   }
 
+  #[test]
+  fn test_markdown_to_plaintext() {
+    let tests: Vec<_> = vec![
+      (
+        "headings",
+        "# h1\n## h2\n### h3\n#### h4\n##### h5\n###### h6",
+        "h1\nh2\nh3\nh4\nh5\nh6",
+      ),
+      ("line breaks", "First\rSecond", "First\nSecond"),
+      (
+        "emphasis",
+        "__bold__ **bold** *italic* ***bold+italic***",
+        "bold bold italic bold+italic",
+      ),
+      (
+        "blockquotes",
+        "> #### Hello\n > \n > - Hola\n > - 안영 \n>> Goodbye\n",
+        "Hello\nHola\n안영\nGoodbye",
+      ),
+      (
+        "lists (ordered, unordered)",
+        "1. pen\n2. apple\n3. apple pen\n- pen\n- pineapple\n- pineapple pen",
+        "pen\napple\napple pen\npen\npineapple\npineapple pen",
+      ),
+      (
+        "definition lists",
+        "Term\n: First\n: Second",
+        "Term\nFirst\nSecond",
+      ),
+      (
+        "code and code blocks",
+        "this is my amazing `code snippet` and my amazing ```code block```",
+        "this is my amazing code snippet and my amazing code block",
+      ),
+      (
+        "links",
+        "[Lemmy](https://join-lemmy.org/ \"Join Lemmy!\")",
+        "Lemmy",
+      ),
+      (
+        "images",
+        "![My linked image](https://image.com \"image alt text\")",
+        "My linked image",
+      ),
+      (
+        "basic spoiler",
+        "::: spoiler click to see more\nhow spicy!\n:::\n",
+        "click to see more\nhow spicy!",
+      ),
+      (
+        "html special chars are not escaped",
+        "<script>alert('xss');</script> hello &\"",
+        "<script>alert(‘xss’);</script> hello &\"",
+      ),
+      (
+        "paragraphs and fenced code",
+        "first paragraph\n\nsecond paragraph\n\n```\nfn main() {}\n```",
+        "first paragraph\nsecond paragraph\nfn main() {}",
+      ),
+    ];
+
+    tests.iter().for_each(|&(msg, input, expected)| {
+      let result = markdown_to_plaintext(input);
+
+      assert_eq!(
+        result, expected,
+        "Testing {}, with original input '{}'",
+        msg, input
+      );
+    });
+  }
+
+  #[test]
+  fn test_markdown_to_plaintext_with_link_urls() {
+    assert_eq!(
+      "Lemmy (https://join-lemmy.org/) and My linked image",
+      markdown_to_plaintext_with_link_urls(
+        "[Lemmy](https://join-lemmy.org/ \"Join Lemmy!\") and ![My linked image](https://image.com)"
+      )
+    );
+  }
+
+  #[test]
+  fn test_markdown_to_html_with_context() {
+    let html = markdown_to_html_with_context(
+      "@alice@lemmy.ml posted in **!rust@lemmy.ml**, ask `@bob@lemmy.ml`",
+      "https://example.com",
+    );
+    assert_eq!(
+      "<p><a href=\"https://example.com/u/alice@lemmy.ml\" class=\"mention\">@alice@lemmy.ml</a> posted in <strong><a href=\"https://example.com/c/rust@lemmy.ml\" class=\"mention\">!rust@lemmy.ml</a></strong>, ask <code>@bob@lemmy.ml</code></p>\n",
+      html
+    );
+
+    // without context, handles are left alone and hashtags use relative links
+    assert_eq!(
+      "<p>@alice@lemmy.ml</p>\n",
+      markdown_to_html("@alice@lemmy.ml")
+    );
+    assert_eq!(
+      "<p><a href=\"https://example.com/tag/rust\" class=\"hashtag\">#rust</a></p>\n",
+      markdown_to_html_with_context("#rust", "https://example.com")
+    );
+    assert_eq!(
+      "<p><a href=\"/tag/rust\" class=\"hashtag\">#rust</a></p>\n",
+      markdown_to_html("#rust")
+    );
+  }
+
+  #[test]
+  fn test_markdown_hashtags() {
+    // links can't be nested
+    assert_eq!(
+      "<p><a href=\"https://example.com\">about #rust</a></p>\n",
+      markdown_to_html("[about #rust](https://example.com)")
+    );
+    assert_eq!(
+      "<p>See <a href=\"https://example.com/#rust\">https://example.com/#rust</a></p>\n",
+      markdown_to_html("See https://example.com/#rust")
+    );
+    assert_eq!("learning #rust", markdown_to_plaintext("learning #rust"));
+    // the rule only runs at its own marker
+    assert_eq!(
+      "<p><a href=\"https://example.com\">link</a> by @user@example.com</p>\n",
+      markdown_to_html("[link](https://example.com) by @user@example.com")
+    );
+  }
+
+  #[test]
+  fn test_markdown_bare_urls() {
+    assert_eq!(
+      "<p>See <a href=\"https://example.com/foo\">https://example.com/foo</a> for details</p>\n",
+      markdown_to_html("See https://example.com/foo for details")
+    );
+    // trailing punctuation is not part of the link
+    assert_eq!(
+      "<p>Go to <a href=\"http://example.com/foo\">http://example.com/foo</a>.</p>\n",
+      markdown_to_html("Go to http://example.com/foo.")
+    );
+    assert_eq!(
+      "<p><code>https://example.com</code></p>\n",
+      markdown_to_html("`https://example.com`")
+    );
+    assert_eq!(
+      "<p><a href=\"https://b.com\">https://a.com</a></p>\n",
+      markdown_to_html("[https://a.com](https://b.com)")
+    );
+    // other schemes stay text
+    assert_eq!(
+      "<p>ftp://example.com/file</p>\n",
+      markdown_to_html("ftp://example.com/file")
+    );
+    // mentions are still recognized next to links
+    assert_eq!(
+      "<p><a href=\"https://example.com/u/alice@lemmy.ml\" class=\"mention\">@alice@lemmy.ml</a> wrote <a href=\"https://lemmy.ml/post/1\">https://lemmy.ml/post/1</a></p>\n",
+      markdown_to_html_with_context(
+        "@alice@lemmy.ml wrote https://lemmy.ml/post/1",
+        "https://example.com"
+      )
+    );
+  }
+
+  #[test]
+  fn test_markdown_link_schemes() {
+    let result = markdown_to_html("[click](javascript:alert(1))");
+    assert!(!result.contains("href"));
+    let result = markdown_to_html("[click](data:text/html,<script>alert(1)</script>)");
+    assert!(!result.contains("href"));
+    assert!(!result.contains("<script>"));
+    let result = markdown_to_html("![tracker](data:image/png;base64,iVBORw0KGgo=)");
+    assert!(!result.contains("<img"));
+    assert!(result.contains("tracker"));
+    assert_eq!(
+      "<p><a href=\"https://example.com/\">click</a></p>\n",
+      markdown_to_html("[click](https://example.com/)")
+    );
+    assert_eq!(
+      "<p><a href=\"mailto:alice@example.com\">mail</a></p>\n",
+      markdown_to_html("[mail](mailto:alice@example.com)")
+    );
+    assert_eq!(
+      "<p><a href=\"/c/main\">main</a></p>\n",
+      markdown_to_html("[main](/c/main)")
+    );
+
+    let limits = MarkdownLimits {
+      allowed_schemes: vec!["https".to_string()],
+      ..Default::default()
+    };
+    assert_eq!(
+      "<p><a href=\"https://a.com\">a</a> b ftp://c.com</p>\n",
+      markdown_to_html_with_limits(
+        "[a](https://a.com) [b](http://b.com) <ftp://c.com>",
+        &limits
+      )
+    );
+  }
+
+  #[test]
+  fn test_markdown_image_proxy() {
+    assert_eq!(
+      "<p><img src=\"/image_proxy?url=https%3A%2F%2Fevil.com%2Fpixel.png\" alt=\"pixel\" /></p>\n",
+      markdown_to_html_with_proxy("![pixel](https://evil.com/pixel.png)", "/image_proxy")
+    );
+    // linked images are proxied too, but the link itself is not
+    assert_eq!(
+      "<p><a href=\"https://example.com/\"><img src=\"/image_proxy?url=https%3A%2F%2Fevil.com%2Fa.png%3Fb%3Dc\" alt=\"a\" /></a></p>\n",
+      markdown_to_html_with_proxy(
+        "[![a](https://evil.com/a.png?b=c)](https://example.com/)",
+        "/image_proxy"
+      )
+    );
+    // data urls are not loaded at all
+    let result = markdown_to_html_with_proxy(
+      "![tracker](data:image/png;base64,iVBORw0KGgo=)",
+      "/image_proxy",
+    );
+    assert!(!result.contains("<img"));
+    assert!(result.contains("tracker"));
+    // local images don't need the proxy
+    assert_eq!(
+      "<p><img src=\"/pictrs/image/a.png\" alt=\"a\" /></p>\n",
+      markdown_to_html_with_proxy("![a](/pictrs/image/a.png)", "/image_proxy")
+    );
+  }
+
+  #[test]
+  fn test_markdown_custom_emojis() {
+    let emojis = HashMap::from([(
+      "blobcat".to_string(),
+      "https://example.com/emoji/blobcat.png".to_string(),
+    )]);
+    assert_eq!(
+      "<p>hi <img class=\"emoji\" src=\"https://example.com/emoji/blobcat.png\" alt=\":blobcat:\" title=\":blobcat:\" /> and :unknown:</p>\n",
+      markdown_to_html_with_emojis("hi :blobcat: and :unknown:", &emojis)
+    );
+    // the colons of unknown shortcodes don't prevent later matches
+    assert_eq!(
+      "<p>12:30 <img class=\"emoji\" src=\"https://example.com/emoji/blobcat.png\" alt=\":blobcat:\" title=\":blobcat:\" /></p>\n",
+      markdown_to_html_with_emojis("12:30 :blobcat:", &emojis)
+    );
+    assert_eq!(
+      "<p><code>:blobcat:</code></p>\n",
+      markdown_to_html_with_emojis("`:blobcat:`", &emojis)
+    );
+  }
+
+  #[test]
+  fn test_markdown_max_len() {
+    // short documents are unchanged
+    assert_eq!(
+      markdown_to_html("**a** b"),
+      markdown_to_html_with_max_len("**a** b", 1_000)
+    );
+
+    let text = format!("{}x{}", "*".repeat(2_000), "*".repeat(2_000));
+    let full = markdown_to_html(&text);
+    assert!(full.len() > 2_000);
+    let result = markdown_to_html_with_max_len(&text, 2_000);
+    assert!(result.len() <= 2_000);
+    assert!(result.ends_with(TRUNCATION_NOTICE));
+    for tag in ["p", "em", "strong"] {
+      assert_eq!(
+        result.matches(&format!("<{tag}>")).count(),
+        result.matches(&format!("</{tag}>")).count(),
+        "unclosed {tag} in {result}"
+      );
+    }
+
+    // long text is cut off in between
+    let text = format!("{}\n\n{}", "a".repeat(100), "b & c ".repeat(1_000));
+    let result = markdown_to_html_with_max_len(&text, 500);
+    assert!(result.len() <= 500);
+    assert!(result.starts_with(&format!("<p>{}</p>\n<p>b &amp; c", "a".repeat(100))));
+    assert!(result.ends_with(&format!("</p>\n{TRUNCATION_NOTICE}")));
+  }
+
+  #[test]
+  fn test_markdown_node_limit() {
+    let text = "- item\n".repeat(20_000);
+    let limits = MarkdownLimits {
+      max_nodes: 1_000,
+      ..Default::default()
+    };
+    let result = markdown_to_html_with_limits(&text, &limits);
+    assert!(result.starts_with("<ul>\n<li>item</li>\n"));
+    assert!(result.ends_with(TRUNCATION_NOTICE));
+    // every rendered list item counts towards the limit, and the list is still closed
+    let items = result.matches("<li>").count();
+    assert!(items > 0 && items <= 1_000);
+    assert_eq!(items, result.matches("</li>").count());
+    assert_eq!(1, result.matches("</ul>").count());
+    assert!(result.len() < 20_000);
+
+    // documents below the limit are rendered completely
+    let text = "- item\n".repeat(10);
+    let result = markdown_to_html_with_limits(&text, &limits);
+    assert_eq!(10, result.matches("<li>").count());
+    assert!(!result.contains(TRUNCATION_NOTICE));
+  }
+
+  #[test]
+  fn test_markdown_depth_limit() {
+    let max_depth = MarkdownLimits::default().max_depth;
+    let text = format!("{} deep", ">".repeat(50));
+    let result = markdown_to_html(&text);
+    assert_eq!(max_depth, result.matches("<blockquote>").count());
+    assert_eq!(max_depth, result.matches("</blockquote>").count());
+    // the content of flattened blockquotes is kept
+    assert!(result.contains("<p>deep</p>"));
+
+    let text = (0..20)
+      .map(|i| format!("{}- item {i}\n", "  ".repeat(i)))
+      .collect::<String>();
+    let result = markdown_to_html(&text);
+    assert_eq!(max_depth, result.matches("<ul>").count());
+    assert!(result.contains("item 19"));
+
+    // the limit can be lowered
+    let limits = MarkdownLimits {
+      max_depth: 3,
+      ..Default::default()
+    };
+    let result = markdown_to_html_with_limits(&format!("{} deep", ">".repeat(50)), &limits);
+    assert_eq!(3, result.matches("<blockquote>").count());
+
+    // shallow nesting is not changed
+    assert_eq!(
+      "<blockquote>\n<blockquote>\n<p>quote</p>\n</blockquote>\n</blockquote>\n",
+      markdown_to_html(">> quote")
+    );
+  }
+
+  #[test]
+  fn test_collapse_blank_lines() {
+    let text = "first\n\n\n\n\u{a0}\n\n&nbsp;\n&nbsp;\n\n\u{200b}\n\n\n\nsecond";
+    assert_eq!("<p>first</p>\n<p>second</p>\n", markdown_to_html(text));
+
+    // also inside of other blocks
+    let text = "> quote\n>\n> &nbsp;\n>\n> more";
+    assert_eq!(
+      "<blockquote>\n<p>quote</p>\n<p>more</p>\n</blockquote>\n",
+      markdown_to_html(text)
+    );
+
+    // blank lines between paragraphs are collapsed, those in code blocks of the same text kept
+    let text = "first\n\n\n\n\n\nsecond\n\n```\na\n\n\n\n\nb\n```";
+    assert_eq!(
+      "<p>first</p>\n<p>second</p>\n<pre><code>a\n\n\n\n\nb\n</code></pre>\n",
+      markdown_to_html(text)
+    );
+  }
+
+  #[test]
+  fn test_code_fence_language_class() {
+    assert_eq!(
+      "<pre><code class=\"language-rust\">fn main(){}\n</code></pre>\n",
+      markdown_to_html("```rust\nfn main(){}\n```")
+    );
+    // only the first word of the info string is the language
+    assert_eq!(
+      "<pre><code class=\"language-sh\">ls -l\n</code></pre>\n",
+      markdown_to_html("```sh title\nls -l\n```")
+    );
+    assert_eq!(
+      "<pre><code>plain\n</code></pre>\n",
+      markdown_to_html("```\nplain\n```")
+    );
+  }
+
+  #[test]
+  fn test_collapse_blank_lines_before_parsing() {
+    // five blank lines become a single paragraph boundary
+    assert_eq!(
+      "first\n\nsecond",
+      collapse_blank_lines("first\n\n\n\n\n\nsecond")
+    );
+    assert_eq!(
+      "first\n\nsecond",
+      collapse_blank_lines("first\n \n\t\n\r\n\nsecond")
+    );
+    // up to two blank lines are kept as they are
+    assert_eq!("a\n\n\nb", collapse_blank_lines("a\n\n\nb"));
+
+    // blank lines in code blocks are preserved
+    let fenced = "```\na\n\n\n\n\nb\n```\n\n\n\n\nafter";
+    assert_eq!(
+      "```\na\n\n\n\n\nb\n```\n\nafter",
+      collapse_blank_lines(fenced)
+    );
+    let tilde = "~~~~\n```\n\n\n\n\n~~~~";
+    assert_eq!(tilde, collapse_blank_lines(tilde));
+    let indented = "text\n\n    let a = 1;\n\n\n\n\n    let b = 2;";
+    assert_eq!(indented, collapse_blank_lines(indented));
+  }
+
+  #[test]
+  fn test_code_block_keeps_blank_lines() {
+    let text = "```\nfn a() {}\n\n\n\u{a0}\nfn b() {}\n```";
+    assert_eq!(
+      "<pre><code>fn a() {}\n\n\n\u{a0}\nfn b() {}\n</code></pre>\n",
+      markdown_to_html(text)
+    );
+
+    let text = "text\n\n    let a = 1;\n\n\n    let b = 2;";
+    assert_eq!(
+      "<p>text</p>\n<pre><code>let a = 1;\n\n\nlet b = 2;\n</code></pre>\n",
+      markdown_to_html(text)
+    );
+  }
+
+  #[test]
+  fn test_has_renderable_content() {
+    // genuinely empty
+    assert!(!has_renderable_content(""));
+    assert!(!has_renderable_content("****"));
+    assert!(!has_renderable_content("[](https://example.com)"));
+    assert!(!has_renderable_content("> \n>"));
+    // whitespace only
+    assert!(!has_renderable_content(" \n\n\t  \n"));
+    assert!(!has_renderable_content("&nbsp;\n\u{a0}"));
+    // invisible characters only
+    assert!(!has_renderable_content("\u{200b}\u{2060}\u{feff}"));
+    assert!(!has_renderable_content("**\u{3164}** _\u{2800}_"));
+
+    assert!(has_renderable_content("hello"));
+    assert!(has_renderable_content("\u{200b}**a**"));
+    assert!(has_renderable_content("![](https://example.com/image.png)"));
+    assert!(has_renderable_content("```\nfn main() {}\n```"));
+    assert!(has_renderable_content("    indented code"));
+  }
+
+  #[test]
+  fn test_content_weight() {
+    let weight = content_weight(
+      "BUY NOW [CHEAP](https://a.com) [PILLS](https://b.com) <https://c.com> ![](https://d.com/1.png) ![](https://d.com/2.png)",
+    );
+    assert_eq!(3, weight.links);
+    assert_eq!(2, weight.images);
+    assert!(weight.caps_ratio > 0.5);
+
+    let weight = content_weight("Hello **World**");
+    assert_eq!(0, weight.links);
+    assert_eq!(0, weight.images);
+    assert_eq!(11, weight.length);
+    assert!((weight.caps_ratio - 0.2).abs() < f32::EPSILON);
+
+    assert_eq!(ContentWeight::default(), content_weight(""));
+  }
+
+  #[test]
+  fn test_count_markdown_links() {
+    let text = "[one](https://a.com) <https://b.com> https://c.com [ref]\n\n\
+      ![image](https://d.com/1.png) `[code](https://e.com)` **[bold](https://f.com)**\n\n\
+      [ref]: https://g.com";
+    assert_eq!(4, count_markdown_links(text));
+    assert_eq!(
+      0,
+      count_markdown_links("visit example.com or https://example.com")
+    );
+    assert_eq!(0, count_markdown_links(""));
+  }
+
   #[test]
   fn test_sanitize_html() {
     let sanitized = sanitize_html("<script>alert('xss');</script> hello &\"'");
     let expected = "&lt;script>alert(&#x27;xss&#x27;);&lt;/script> hello &amp;&quot;&#x27;";
     assert_eq!(expected, sanitized)
   }
+
+  #[test]
+  fn test_apply_word_filters() {
+    let filters = vec![
+      (Regex::new(r"(?i)\bheck\b").unwrap(), "h*ck".to_string()),
+      (Regex::new("darn").unwrap(), "$0".to_string()),
+    ];
+    assert_eq!(
+      "What the h*ck, h*ck is $0",
+      apply_word_filters("What the heck, Heck is darn", &filters)
+    );
+    // code is left unchanged
+    assert_eq!(
+      "h*ck `heck` h*ck",
+      apply_word_filters("heck `heck` heck", &filters)
+    );
+    assert_eq!(
+      "h*ck\n\n```\nlet heck = 1;\n```\n\n    heck\n",
+      apply_word_filters("heck\n\n```\nlet heck = 1;\n```\n\n    heck\n", &filters)
+    );
+    assert_eq!("no match", apply_word_filters("no match", &filters));
+    // also inside of spoilers, which are parsed separately
+    assert_eq!(
+      "h*ck\n::: spoiler h*ck\nh*ck `heck`\n> ::: spoiler\n> `heck` h*ck\n> :::\n:::\n",
+      apply_word_filters(
+        "heck\n::: spoiler heck\nheck `heck`\n> ::: spoiler\n> `heck` heck\n> :::\n:::\n",
+        &filters
+      )
+    );
+  }
+
+  #[test]
+  fn test_blocked_words() {
+    let filters = vec![Regex::new("(?i)heck").unwrap()];
+    assert_eq!(
+      vec!["Heck", "heck"],
+      blocked_words("heck, Heck and heck", &filters)
+    );
+    assert!(blocked_words("only `heck` in code", &filters).is_empty());
+    assert!(blocked_words("::: spoiler\nonly `heck` in code\n:::", &filters).is_empty());
+  }
+
+  #[test]
+  fn test_remove_control_chars() {
+    assert_eq!("abc", sanitize_html("a\0b\x07c"));
+    assert_eq!("a\tb\nc\r\n", sanitize_html("a\tb\nc\r\n"));
+    assert_eq!("<p>abc</p>\n", markdown_to_html("a\0b\x07c\x1b"));
+    assert_eq!(
+      "<pre><code>a\tb\n</code></pre>\n",
+      markdown_to_html("```\na\tb\x7f\n```")
+    );
+  }
+
+  #[test]
+  fn test_sanitize_html_allowing() {
+    let allowed = ["b", "i", "code"];
+    assert_eq!(
+      "<b>bold</b> &amp; <i>italic</i>",
+      sanitize_html_allowing("<b>bold</b> & <I>italic</I>", &allowed)
+    );
+    assert_eq!(
+      "&lt;script>alert(&#x27;xss&#x27;);&lt;/script>",
+      sanitize_html_allowing("<script>alert('xss');</script>", &allowed)
+    );
+    // tags with attributes are escaped, even if the tag itself is allowed
+    assert_eq!(
+      "&lt;b onclick=&quot;alert(1)&quot;>click&lt;/b>",
+      sanitize_html_allowing("<b onclick=\"alert(1)\">click</b>", &allowed)
+    );
+    assert_eq!(
+      "&lt;b/>&lt;b >x&lt;/b>",
+      sanitize_html_allowing("<b/><b >x</b>", &allowed)
+    );
+    // unclosed tags are closed, unopened ones escaped
+    assert_eq!(
+      "<b>bold <i>both</i></b> &lt;/i> 1 &lt; 2",
+      sanitize_html_allowing("<b>bold <i>both</b> </i> 1 < 2", &allowed)
+    );
+    assert_eq!(
+      "<code>x</code>",
+      sanitize_html_allowing("<code>x", &allowed)
+    );
+  }
 }
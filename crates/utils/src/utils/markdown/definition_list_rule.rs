@@ -0,0 +1,257 @@
+// Custom Markdown plugin for definition lists, as in PHP Markdown Extra and Pandoc.
+//
+// FORMAT:
+// Input Markdown: Term\n: First definition\n: Second definition
+// Output HTML: <dl>\n<dt>Term</dt>\n<dd>First definition</dd>\n<dd>Second definition</dd>\n</dl>
+//
+// A term is a single line which is directly followed by a line starting with `:` and a space.
+// Lines after a definition which don't start a new definition or term are part of it. Several
+// terms can follow each other, optionally separated by a blank line. Terms and definitions only
+// contain inline markdown, so they are never wrapped in paragraphs.
+//
+// Lines which start a list, heading or other block are matched by those rules first. A `:` line
+// which is less indented than the content of a list item only continues the item, so it doesn't
+// turn the item into a term.
+
+use markdown_it::{
+  parser::{
+    block::{BlockRule, BlockState},
+    inline::InlineRoot,
+  },
+  MarkdownIt,
+  Node,
+  NodeValue,
+  Renderer,
+};
+
+#[derive(Debug)]
+struct DefinitionList;
+
+impl NodeValue for DefinitionList {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    fmt.cr();
+    fmt.open("dl", &node.attrs);
+    fmt.cr();
+    fmt.contents(&node.children);
+    fmt.cr();
+    fmt.close("dl");
+    fmt.cr();
+  }
+}
+
+#[derive(Debug)]
+pub(super) struct DefinitionTerm;
+
+impl NodeValue for DefinitionTerm {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    fmt.open("dt", &node.attrs);
+    fmt.contents(&node.children);
+    fmt.close("dt");
+    fmt.cr();
+  }
+}
+
+#[derive(Debug)]
+pub(super) struct DefinitionDetails;
+
+impl NodeValue for DefinitionDetails {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    fmt.open("dd", &node.attrs);
+    fmt.contents(&node.children);
+    fmt.close("dd");
+    fmt.cr();
+  }
+}
+
+/// Length of the `: ` marker at the start of a definition line, including the indentation, or
+/// `None` if the line doesn't start a definition.
+fn definition_marker_len(line: &str) -> Option<usize> {
+  let trimmed = line.trim_start_matches(' ');
+  let rest = trimmed.strip_prefix(": ")?;
+  let content = rest.trim_start_matches([' ', '\t']);
+  if content.trim().is_empty() {
+    return None;
+  }
+  Some(line.len() - content.len())
+}
+
+fn is_blank(state: &BlockState, line: usize) -> bool {
+  state.get_line(line).trim().is_empty()
+}
+
+/// Whether the line starts a definition. Lines which are less indented than the current block,
+/// like lazy continuation lines of a list item, belong to the enclosing block.
+fn is_definition(state: &BlockState, line: usize) -> bool {
+  state.line_indent(line) >= 0 && definition_marker_len(state.get_line(line)).is_some()
+}
+
+/// Whether the line can be a term, which is the case if the next line starts a definition.
+fn is_term(state: &BlockState, line: usize) -> bool {
+  line + 1 < state.line_max
+    && !is_blank(state, line)
+    && definition_marker_len(state.get_line(line)).is_none()
+    && (0..4).contains(&state.line_indent(line))
+    && is_definition(state, line + 1)
+}
+
+/// Wraps the inline content of the lines `start..end` into the node, without the first `skip`
+/// bytes.
+fn inline_node<T: NodeValue>(
+  value: T,
+  state: &BlockState,
+  start: usize,
+  end: usize,
+  skip: usize,
+) -> Node {
+  let (mut content, mut mapping) = state.get_lines(start, end, state.blk_indent, false);
+  let skip = skip.min(content.len());
+  content.drain(..skip);
+  content.truncate(content.trim_end().len());
+  for (i, (content_pos, source_pos)) in mapping.iter_mut().enumerate() {
+    if i == 0 {
+      *source_pos += skip;
+    } else {
+      *content_pos -= skip;
+    }
+  }
+  let mut node = Node::new(value);
+  node
+    .children
+    .push(Node::new(InlineRoot::new(content, mapping)));
+  node
+}
+
+struct DefinitionListScanner;
+
+impl BlockRule for DefinitionListScanner {
+  fn run(state: &mut BlockState) -> Option<(Node, usize)> {
+    if !is_term(state, state.line) {
+      return None;
+    }
+
+    let mut list = Node::new(DefinitionList);
+    let mut line = state.line;
+    let mut end = line;
+    while is_term(state, line) {
+      let indent = state.get_lines(line, line + 1, state.blk_indent, false).0;
+      let indent = indent.len() - indent.trim_start().len();
+      list
+        .children
+        .push(inline_node(DefinitionTerm, state, line, line + 1, indent));
+      line += 1;
+
+      while line < state.line_max {
+        if !is_definition(state, line) {
+          break;
+        }
+        let (first, _) = state.get_lines(line, line + 1, state.blk_indent, false);
+        let Some(marker_len) = definition_marker_len(&first) else {
+          break;
+        };
+        // lazy continuation lines, up to the next definition, term or blank line
+        let mut next = line + 1;
+        while next < state.line_max
+          && !is_blank(state, next)
+          && definition_marker_len(state.get_line(next)).is_none()
+          && !is_term(state, next)
+        {
+          next += 1;
+        }
+        list.children.push(inline_node(
+          DefinitionDetails,
+          state,
+          line,
+          next,
+          marker_len,
+        ));
+        line = next;
+      }
+      end = line;
+
+      // another term may follow after a blank line
+      if line + 1 < state.line_max && is_blank(state, line) && is_term(state, line + 1) {
+        line += 1;
+      }
+    }
+
+    Some((list, end - state.line))
+  }
+}
+
+pub fn add(markdown_parser: &mut MarkdownIt) {
+  markdown_parser.block.add_rule::<DefinitionListScanner>();
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use crate::utils::markdown::markdown_to_html;
+
+  #[test]
+  fn test_definition_list_markdown() {
+    let tests: Vec<_> = vec![
+      (
+        "term with two definitions",
+        "Term\n: First definition\n: Second definition",
+        "<dl>\n<dt>Term</dt>\n<dd>First definition</dd>\n<dd>Second definition</dd>\n</dl>\n",
+      ),
+      (
+        "several terms with inline markdown",
+        "**Warp**\n: Faster than *light*\n\nImpulse\n: Slower",
+        "<dl>\n<dt><strong>Warp</strong></dt>\n<dd>Faster than <em>light</em></dd>\n<dt>Impulse</dt>\n<dd>Slower</dd>\n</dl>\n",
+      ),
+      (
+        "definition over several lines",
+        "Term\n: first line\nsecond line\n\nafter",
+        "<dl>\n<dt>Term</dt>\n<dd>first line\nsecond line</dd>\n</dl>\n<p>after</p>\n",
+      ),
+      (
+        "after a paragraph",
+        "Intro\n\nTerm\n: Definition",
+        "<p>Intro</p>\n<dl>\n<dt>Term</dt>\n<dd>Definition</dd>\n</dl>\n",
+      ),
+      (
+        "colon after list item",
+        "- item\n: not a definition",
+        "<ul>\n<li>item\n: not a definition</li>\n</ul>\n",
+      ),
+      (
+        "colon after ordered list item",
+        "1. item\n: not a definition",
+        "<ol>\n<li>item\n: not a definition</li>\n</ol>\n",
+      ),
+      (
+        "definition inside a list item",
+        "- Term\n  : Definition",
+        "<ul>\n<li>\n<dl>\n<dt>Term</dt>\n<dd>Definition</dd>\n</dl>\n</li>\n</ul>\n",
+      ),
+      (
+        "ordered list",
+        "1. item\n2. other",
+        "<ol>\n<li>item</li>\n<li>other</li>\n</ol>\n",
+      ),
+      (
+        "colon without space",
+        "Term\n:not a definition",
+        "<p>Term\n:not a definition</p>\n",
+      ),
+      (
+        "colon with tab",
+        "Term\n:\tnot a definition",
+        "<p>Term\n:\tnot a definition</p>\n",
+      ),
+    ];
+
+    tests.iter().for_each(|&(msg, input, expected)| {
+      assert_eq!(
+        markdown_to_html(input),
+        expected,
+        "Testing {}, with original input '{}'",
+        msg,
+        input
+      );
+    });
+  }
+}
@@ -0,0 +1,171 @@
+// Custom Markdown plugin to link hashtags to the local tag pages.
+//
+// FORMAT:
+// Input Markdown: learning #rust today
+// Output HTML: learning <a href="/tag/rust" class="hashtag">#rust</a> today
+//
+// A tag consists of Unicode letters, digits and underscores and needs to contain at least one
+// letter, so that issue references like `#123` are left alone. Hashtags which are part of a word
+// (like `C#` or `a#b`) are ignored. Headings consume their `#` markers before inline rules run,
+// and code spans and code blocks are parsed before reaching the marker, so neither is affected.
+//
+// By default the links are relative, `set_hashtag_prefix()` points them to a specific instance.
+
+use markdown_it::{
+  parser::inline::{InlineRule, InlineState, Text},
+  MarkdownIt,
+  Node,
+  NodeValue,
+  Renderer,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+static TAG_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\w+").expect("compile regex"));
+
+#[derive(Debug)]
+pub(super) struct Hashtag {
+  name: String,
+  prefix: String,
+}
+
+impl NodeValue for Hashtag {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    let mut attrs = node.attrs.clone();
+    attrs.push(("href", format!("{}/tag/{}", self.prefix, self.name)));
+    attrs.push(("class", "hashtag".into()));
+
+    fmt.open("a", &attrs);
+    fmt.contents(&node.children);
+    fmt.close("a");
+  }
+}
+
+struct HashtagScanner;
+
+impl InlineRule for HashtagScanner {
+  const MARKER: char = '#';
+
+  // Invoked on every character which ends a text run, not only on the marker.
+  fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+    if !state.src.get(state.pos..)?.starts_with(Self::MARKER) {
+      return None;
+    }
+    let previous = state.src.get(..state.pos)?.chars().next_back();
+    if previous.is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '#') {
+      return None;
+    }
+
+    let start = state.pos + Self::MARKER.len_utf8();
+    let name = TAG_REGEX
+      .find(state.src.get(start..state.pos_max)?)?
+      .as_str();
+    if !name.chars().any(char::is_alphabetic) {
+      return None;
+    }
+
+    let mut node = Node::new(Hashtag {
+      name: name.to_string(),
+      prefix: String::new(),
+    });
+    // the text is kept as a child, so that it shows up in plaintext and when unwrapping the link
+    node.children.push(Node::new(Text {
+      content: format!("{}{name}", Self::MARKER),
+    }));
+    Some((node, Self::MARKER.len_utf8() + name.len()))
+  }
+}
+
+pub fn add(markdown_parser: &mut MarkdownIt) {
+  markdown_parser.inline.add_rule::<HashtagScanner>();
+}
+
+/// Points all hashtags in the document to the tag pages on the instance at
+/// `protocol_and_hostname`, instead of the relative path `/tag/<name>`.
+pub fn set_hashtag_prefix(node: &mut Node, protocol_and_hostname: &str) {
+  if let Some(hashtag) = node.cast_mut::<Hashtag>() {
+    hashtag.prefix = protocol_and_hostname.to_string();
+  }
+  for child in &mut node.children {
+    set_hashtag_prefix(child, protocol_and_hostname);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use crate::utils::markdown::hashtag_rule::{add, set_hashtag_prefix};
+  use markdown_it::MarkdownIt;
+
+  #[test]
+  fn test_hashtag_markdown() {
+    let tests: Vec<_> = vec![
+      (
+        "hashtag",
+        "learning #rust today",
+        "<p>learning <a href=\"/tag/rust\" class=\"hashtag\">#rust</a> today</p>\n",
+      ),
+      (
+        "hashtag with unicode letters at the end of a sentence",
+        "#café_münchen.",
+        "<p><a href=\"/tag/café_münchen\" class=\"hashtag\">#café_münchen</a>.</p>\n",
+      ),
+      (
+        "hashtag starting with a digit",
+        "#1st place",
+        "<p><a href=\"/tag/1st\" class=\"hashtag\">#1st</a> place</p>\n",
+      ),
+      (
+        "numeric issue reference is ignored",
+        "fixed in #123",
+        "<p>fixed in #123</p>\n",
+      ),
+      (
+        "hashtag inside a code span is ignored",
+        "run `#rust` here",
+        "<p>run <code>#rust</code> here</p>\n",
+      ),
+      (
+        "hashtag inside a code block is ignored",
+        "```\n#rust\n```",
+        "<pre><code>#rust\n</code></pre>\n",
+      ),
+      ("heading is not a hashtag", "# rust", "<h1>rust</h1>\n"),
+      (
+        "marker inside a word is ignored",
+        "C# and a#b or ##rust",
+        "<p>C# and a#b or ##rust</p>\n",
+      ),
+    ];
+
+    tests.iter().for_each(|&(msg, input, expected)| {
+      let md = &mut MarkdownIt::new();
+      markdown_it::plugins::cmark::add(md);
+      add(md);
+
+      assert_eq!(
+        md.parse(input).xrender(),
+        expected,
+        "Testing {}, with original input '{}'",
+        msg,
+        input
+      );
+    });
+  }
+
+  #[test]
+  fn test_hashtag_prefix() {
+    let md = &mut MarkdownIt::new();
+    markdown_it::plugins::cmark::add(md);
+    add(md);
+
+    let mut root = md.parse("**#rust**");
+    set_hashtag_prefix(&mut root, "https://example.com");
+    assert_eq!(
+      "<p><strong><a href=\"https://example.com/tag/rust\" class=\"hashtag\">#rust</a></strong></p>\n",
+      root.xrender()
+    );
+  }
+}
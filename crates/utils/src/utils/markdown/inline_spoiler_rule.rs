@@ -0,0 +1,214 @@
+// Custom Markdown plugin to manage inline spoilers.
+//
+// Uses the same syntax as Reddit and Discord, for spoilers within a sentence.
+//
+// FORMAT:
+// Input Markdown: the killer is >!HIDDEN_SPOILER!<
+// Output HTML: the killer is <span class="spoiler">HIDDEN_SPOILER</span>
+//
+// Hiding the content until it is clicked is up to the client, based on the class.
+// Code spans are parsed before reaching the spoiler marker, so their content is left untouched.
+//
+// A line starting with a spoiler would otherwise be parsed as blockquote. Such lines start a
+// paragraph instead, if the spoiler is closed on the same line. The paragraph continues until
+// the next blank line or blockquote.
+
+use markdown_it::{
+  parser::{
+    block::{BlockRule, BlockState},
+    inline::{InlineRoot, InlineRule, InlineState},
+  },
+  plugins::cmark::block::{blockquote::BlockquoteScanner, paragraph::Paragraph},
+  MarkdownIt,
+  Node,
+  NodeValue,
+  Renderer,
+};
+
+#[derive(Debug)]
+struct InlineSpoiler;
+
+const SPOILER_OPEN: &str = ">!";
+const SPOILER_CLOSE: &str = "!<";
+
+impl NodeValue for InlineSpoiler {
+  // Formats any node marked as an 'InlineSpoiler' into HTML.
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    let mut attrs = node.attrs.clone();
+    attrs.push(("class", "spoiler".into()));
+
+    fmt.open("span", &attrs);
+    fmt.contents(&node.children);
+    fmt.close("span");
+  }
+}
+
+struct InlineSpoilerScanner;
+
+impl InlineRule for InlineSpoilerScanner {
+  const MARKER: char = '>';
+
+  // Invoked on every '>' character in inline content to check if it begins a spoiler.
+  fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+    if !state
+      .src
+      .get(state.pos..state.pos_max)?
+      .starts_with(SPOILER_OPEN)
+    {
+      return None;
+    }
+
+    // Find the end fence, spoilers without any hidden text are not allowed.
+    let content_start = state.pos + SPOILER_OPEN.len();
+    let content_len = state
+      .src
+      .get(content_start..state.pos_max)?
+      .find(SPOILER_CLOSE)?;
+    if content_len == 0 {
+      return None;
+    }
+    let content_end = content_start + content_len;
+
+    // Parse the hidden text as inline content of the spoiler node, so that other Markdown syntax
+    // (ex: emphasis, links) can be rendered.
+    let old_node = std::mem::replace(&mut state.node, Node::new(InlineSpoiler));
+    let old_pos = state.pos;
+    let old_pos_max = state.pos_max;
+    state.pos = content_start;
+    state.pos_max = content_end;
+    let md = state.md;
+    md.inline.tokenize(state);
+    state.pos = old_pos;
+    state.pos_max = old_pos_max;
+    let node = std::mem::replace(&mut state.node, old_node);
+
+    Some((node, content_end + SPOILER_CLOSE.len() - old_pos))
+  }
+}
+
+/// Whether the line starts with a spoiler which is closed on the same line.
+fn starts_with_spoiler(line: &str) -> bool {
+  line
+    .trim_start()
+    .strip_prefix(SPOILER_OPEN)
+    .and_then(|rest| rest.find(SPOILER_CLOSE))
+    .is_some_and(|content_len| content_len > 0)
+}
+
+struct SpoilerParagraphScanner;
+
+impl BlockRule for SpoilerParagraphScanner {
+  fn run(state: &mut BlockState) -> Option<(Node, usize)> {
+    if state.line_indent(state.line) >= 4 || !starts_with_spoiler(state.get_line(state.line)) {
+      return None;
+    }
+
+    let mut end = state.line + 1;
+    while end < state.line_max {
+      let line = state.get_line(end).trim_start();
+      if line.is_empty() || (line.starts_with('>') && !starts_with_spoiler(line)) {
+        break;
+      }
+      end += 1;
+    }
+
+    let (content, mut mapping) = state.get_lines(state.line, end, state.blk_indent, false);
+    // leading whitespace of the first line is dropped, so its position moves
+    let skip = content.len() - content.trim_start().len();
+    for (i, (content_pos, source_pos)) in mapping.iter_mut().enumerate() {
+      if i == 0 {
+        *source_pos += skip;
+      } else {
+        *content_pos -= skip;
+      }
+    }
+    let content = content.trim().to_string();
+    let mut node = Node::new(Paragraph);
+    node
+      .children
+      .push(Node::new(InlineRoot::new(content, mapping)));
+    Some((node, end - state.line))
+  }
+}
+
+pub fn add(markdown_parser: &mut MarkdownIt) {
+  markdown_parser.inline.add_rule::<InlineSpoilerScanner>();
+  markdown_parser
+    .block
+    .add_rule::<SpoilerParagraphScanner>()
+    .before::<BlockquoteScanner>();
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use crate::utils::markdown::inline_spoiler_rule::add;
+  use markdown_it::MarkdownIt;
+
+  #[test]
+  fn test_inline_spoiler_markdown() {
+    let tests: Vec<_> = vec![
+      (
+        "inline spoiler in the middle of a sentence",
+        "the killer is >!Bob!< after all",
+        "<p>the killer is <span class=\"spoiler\">Bob</span> after all</p>\n",
+      ),
+      (
+        "inline spoiler adjacent to punctuation",
+        "guess who did it, >!Bob!<!",
+        "<p>guess who did it, <span class=\"spoiler\">Bob</span>!</p>\n",
+      ),
+      (
+        "inline spoiler inside a code span is ignored",
+        "use `>!Bob!<` to hide text",
+        "<p>use <code>&gt;!Bob!&lt;</code> to hide text</p>\n",
+      ),
+      (
+        "inline spoiler with emphasis inside and around",
+        "*really*, it was >!**Bob**!<",
+        "<p><em>really</em>, it was <span class=\"spoiler\"><strong>Bob</strong></span></p>\n",
+      ),
+      (
+        "unclosed inline spoiler",
+        "the killer is >!Bob",
+        "<p>the killer is &gt;!Bob</p>\n",
+      ),
+      (
+        "empty inline spoiler",
+        "nothing to see >!!< here",
+        "<p>nothing to see &gt;!!&lt; here</p>\n",
+      ),
+      (
+        "inline spoiler at the start of a line",
+        ">!Bob!< did it",
+        "<p><span class=\"spoiler\">Bob</span> did it</p>\n",
+      ),
+      (
+        "paragraph starting with an inline spoiler",
+        "text\n\n>!Bob!< did it\nafter all\n> quote",
+        "<p>text</p>\n<p><span class=\"spoiler\">Bob</span> did it\nafter all</p>\n<blockquote>\n<p>quote</p>\n</blockquote>\n",
+      ),
+      (
+        "unclosed inline spoiler at the start of a line is a blockquote",
+        ">!Bob",
+        "<blockquote>\n<p>!Bob</p>\n</blockquote>\n",
+      ),
+    ];
+
+    tests.iter().for_each(|&(msg, input, expected)| {
+      let md = &mut MarkdownIt::new();
+      markdown_it::plugins::cmark::add(md);
+      add(md);
+
+      assert_eq!(
+        md.parse(input).xrender(),
+        expected,
+        "Testing {}, with original input '{}'",
+        msg,
+        input
+      );
+    });
+  }
+}
@@ -0,0 +1,201 @@
+// Custom Markdown plugin to pass math through to the client.
+//
+// The math itself is rendered by the client, for example with KaTeX, based on the class. It is
+// kept as plain text here, so that no other Markdown syntax is applied to it.
+//
+// FORMAT:
+// Input Markdown: $a+b$
+// Output HTML: <span class="math-inline">a+b</span>
+//
+// Input Markdown: $$\int$$ (also over multiple lines, with $$ at the start and end)
+// Output HTML: <div class="math-block">\int</div>
+//
+// Same as in Pandoc, the opening `$` of inline math must be followed by a non-space character,
+// and the closing `$` must follow a non-space character and not be followed by a digit. This way
+// prices like `$5 and $10` are not treated as math.
+
+use markdown_it::{
+  parser::{
+    block::{BlockRule, BlockState},
+    inline::{InlineRule, InlineState, Text},
+  },
+  MarkdownIt,
+  Node,
+  NodeValue,
+  Renderer,
+};
+
+#[derive(Debug)]
+struct MathInline;
+
+impl NodeValue for MathInline {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    let mut attrs = node.attrs.clone();
+    attrs.push(("class", "math-inline".into()));
+
+    fmt.open("span", &attrs);
+    fmt.contents(&node.children);
+    fmt.close("span");
+  }
+}
+
+#[derive(Debug)]
+struct MathBlock;
+
+impl NodeValue for MathBlock {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    let mut attrs = node.attrs.clone();
+    attrs.push(("class", "math-block".into()));
+
+    fmt.cr();
+    fmt.open("div", &attrs);
+    fmt.contents(&node.children);
+    fmt.close("div");
+    fmt.cr();
+  }
+}
+
+const INLINE_DELIMITER: char = '$';
+const BLOCK_DELIMITER: &str = "$$";
+
+/// Node with the math as its only child, so that it is escaped when rendering.
+fn math_node(value: impl NodeValue, content: &str) -> Node {
+  let mut node = Node::new(value);
+  node.children.push(Node::new(Text {
+    content: content.to_string(),
+  }));
+  node
+}
+
+struct MathInlineScanner;
+
+impl InlineRule for MathInlineScanner {
+  const MARKER: char = INLINE_DELIMITER;
+
+  fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+    let src = state.src.get(state.pos..state.pos_max)?;
+    let rest = src.strip_prefix(INLINE_DELIMITER)?;
+
+    // Runs of several dollar signs are never inline math, keep them as they are.
+    let run_len = src.len() - src.trim_start_matches(INLINE_DELIMITER).len();
+    if run_len > 1 {
+      let content = src.get(..run_len)?.to_string();
+      return Some((Node::new(Text { content }), run_len));
+    }
+    if rest.starts_with(char::is_whitespace) {
+      return None;
+    }
+
+    let mut search_start = 0;
+    loop {
+      let content_len = search_start + rest.get(search_start..)?.find(INLINE_DELIMITER)?;
+      let content = rest.get(..content_len)?;
+      let after = rest.get(content_len + 1..)?;
+      if content.contains('\n') {
+        return None;
+      }
+      let valid_close = !content.is_empty()
+        && !content.ends_with(char::is_whitespace)
+        && !after.starts_with(|c: char| c.is_ascii_digit());
+      if valid_close {
+        return Some((math_node(MathInline, content), content_len + 2));
+      }
+      search_start = content_len + 1;
+    }
+  }
+}
+
+struct MathBlockScanner;
+
+impl BlockRule for MathBlockScanner {
+  fn run(state: &mut BlockState) -> Option<(Node, usize)> {
+    let first_line = state.get_line(state.line).trim();
+    let rest = first_line.strip_prefix(BLOCK_DELIMITER)?.trim();
+
+    // The whole block on a single line, like `$$x$$`
+    if let Some(content) = rest.strip_suffix(BLOCK_DELIMITER) {
+      let content = content.trim();
+      if content.is_empty() {
+        return None;
+      }
+      return Some((math_node(MathBlock, content), 1));
+    }
+
+    let mut lines: Vec<String> = vec![];
+    if !rest.is_empty() {
+      lines.push(rest.to_string());
+    }
+    for line_idx in state.line + 1..state.line_max {
+      let line = state.get_line(line_idx).trim();
+      if let Some(last) = line.strip_suffix(BLOCK_DELIMITER) {
+        if !last.trim().is_empty() {
+          lines.push(last.trim().to_string());
+        }
+        let content = lines.join("\n");
+        return Some((math_node(MathBlock, &content), line_idx - state.line + 1));
+      }
+      lines.push(line.to_string());
+    }
+    None
+  }
+}
+
+pub fn add(markdown_parser: &mut MarkdownIt) {
+  markdown_parser.block.add_rule::<MathBlockScanner>();
+  markdown_parser.inline.add_rule::<MathInlineScanner>();
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use crate::utils::markdown::markdown_to_html;
+
+  #[test]
+  fn test_math_markdown() {
+    let tests: Vec<_> = vec![
+      (
+        "inline math",
+        "so $a+b$ it is",
+        "<p>so <span class=\"math-inline\">a+b</span> it is</p>\n",
+      ),
+      (
+        "block math",
+        "$$\\int$$",
+        "<div class=\"math-block\">\\int</div>\n",
+      ),
+      (
+        "block math over multiple lines",
+        "$$\nx^2\n+ *y*\n$$",
+        "<div class=\"math-block\">x^2\n+ *y*</div>\n",
+      ),
+      ("prices are not math", "$5 and $10", "<p>$5 and $10</p>\n"),
+      (
+        "math content is escaped",
+        "$a<b$",
+        "<p><span class=\"math-inline\">a&lt;b</span></p>\n",
+      ),
+      (
+        "no inline math across line breaks",
+        "$a\nb$",
+        "<p>$a\nb$</p>\n",
+      ),
+      (
+        "inside a code span",
+        "`$a+b$`",
+        "<p><code>$a+b$</code></p>\n",
+      ),
+    ];
+
+    tests.iter().for_each(|&(msg, input, expected)| {
+      assert_eq!(
+        markdown_to_html(input),
+        expected,
+        "Testing {}, with original input '{}'",
+        msg,
+        input
+      );
+    });
+  }
+}
@@ -0,0 +1,254 @@
+// Custom Markdown plugin to link user and community handles.
+//
+// FORMAT:
+// Input Markdown: thanks @alice@lemmy.ml, see !rust@lemmy.ml
+// Output HTML: thanks <a href="https://example.com/u/alice@lemmy.ml" class="mention">@alice@lemmy.ml</a>,
+//   see <a href="https://example.com/c/rust@lemmy.ml" class="mention">!rust@lemmy.ml</a>
+//
+// The links point to the local instance, which is only known at render time. So the parser
+// leaves them empty and they need to be filled in with `resolve_mentions()` before rendering.
+// Optionally the display names of known actors are used as link text, with the handle as title.
+// Code spans and code blocks are parsed before reaching the marker, so their content is left
+// untouched. Handles which are part of a word (like email addresses) are ignored.
+
+use markdown_it::{
+  parser::inline::{InlineRule, InlineState, Text},
+  plugins::{
+    cmark::inline::{autolink::Autolink, link::Link},
+    extra::linkify::Linkified,
+  },
+  MarkdownIt,
+  Node,
+  NodeValue,
+  Renderer,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashMap;
+
+/// Name and domain of a handle, following directly after the `@` or `!` marker. The domain has to
+/// end with a letter or digit, so that punctuation at the end of a sentence is not included.
+static HANDLE_REGEX: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(r"^(?P<name>[\w.]+)@(?P<domain>[a-zA-Z0-9._:-]*[a-zA-Z0-9])").expect("compile regex")
+});
+
+#[derive(Debug)]
+struct Mention {
+  marker: char,
+  name: String,
+  domain: String,
+  href: String,
+  display_name: Option<String>,
+}
+
+impl Mention {
+  fn handle(&self) -> String {
+    format!("{}{}@{}", self.marker, self.name, self.domain)
+  }
+}
+
+impl NodeValue for Mention {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    let mut attrs = node.attrs.clone();
+    attrs.push(("href", self.href.clone()));
+    attrs.push(("class", "mention".into()));
+
+    match &self.display_name {
+      Some(display_name) => {
+        attrs.push(("title", self.handle()));
+        fmt.open("a", &attrs);
+        fmt.text(display_name);
+      }
+      None => {
+        fmt.open("a", &attrs);
+        fmt.text(&self.handle());
+      }
+    }
+    fmt.close("a");
+  }
+}
+
+fn scan_handle(state: &mut InlineState, marker: char) -> Option<(Node, usize)> {
+  // rules are invoked on every character which ends a text run, not only on their marker
+  if !state.src.get(state.pos..)?.starts_with(marker) {
+    return None;
+  }
+  // `foo@bar@example.com` is not a mention
+  let previous = state.src.get(..state.pos)?.chars().next_back();
+  if previous.is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '@' || c == '!') {
+    return None;
+  }
+
+  let start = state.pos + marker.len_utf8();
+  let caps = HANDLE_REGEX.captures(state.src.get(start..state.pos_max)?)?;
+  let mention = Mention {
+    marker,
+    name: caps.name("name")?.as_str().to_string(),
+    domain: caps.name("domain")?.as_str().to_string(),
+    href: String::new(),
+    display_name: None,
+  };
+  Some((Node::new(mention), marker.len_utf8() + caps.get(0)?.len()))
+}
+
+struct UserMentionScanner;
+
+impl InlineRule for UserMentionScanner {
+  const MARKER: char = '@';
+
+  fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+    scan_handle(state, Self::MARKER)
+  }
+}
+
+struct CommunityMentionScanner;
+
+impl InlineRule for CommunityMentionScanner {
+  const MARKER: char = '!';
+
+  fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+    scan_handle(state, Self::MARKER)
+  }
+}
+
+pub fn add(markdown_parser: &mut MarkdownIt) {
+  markdown_parser.inline.add_rule::<UserMentionScanner>();
+  markdown_parser.inline.add_rule::<CommunityMentionScanner>();
+}
+
+/// Points all mentions in the document to the profile on the instance at `protocol_and_hostname`.
+/// Links can't be nested, so mentions within a link are turned back into plain text.
+///
+/// If `display_names` contains the handle of a mention (eg `@name@domain` or `!name@domain`), the
+/// display name is used as link text, with the handle as title. Otherwise the handle is shown.
+pub fn resolve_mentions(
+  node: &mut Node,
+  protocol_and_hostname: &str,
+  display_names: Option<&HashMap<String, String>>,
+) {
+  resolve_mentions_inner(node, protocol_and_hostname, display_names, false)
+}
+
+fn resolve_mentions_inner(
+  node: &mut Node,
+  protocol_and_hostname: &str,
+  display_names: Option<&HashMap<String, String>>,
+  in_link: bool,
+) {
+  let in_link = in_link || node.is::<Link>() || node.is::<Autolink>() || node.is::<Linkified>();
+  if let Some(mention) = node.cast_mut::<Mention>() {
+    if in_link {
+      let content = mention.handle();
+      node.replace(Text { content });
+    } else {
+      let path = if mention.marker == '!' { "c" } else { "u" };
+      mention.href = format!(
+        "{protocol_and_hostname}/{path}/{}@{}",
+        mention.name, mention.domain
+      );
+      mention.display_name = display_names.and_then(|d| d.get(&mention.handle()).cloned());
+    }
+  }
+  for child in &mut node.children {
+    resolve_mentions_inner(child, protocol_and_hostname, display_names, in_link);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use crate::utils::markdown::mention_rule::{add, resolve_mentions};
+  use markdown_it::MarkdownIt;
+  use std::collections::HashMap;
+
+  #[test]
+  fn test_mention_markdown() {
+    let tests: Vec<_> = vec![
+      (
+        "user mention at the start of a line",
+        "@alice@lemmy.ml hello",
+        "<p><a href=\"https://example.com/u/alice@lemmy.ml\" class=\"mention\">@alice@lemmy.ml</a> hello</p>\n",
+      ),
+      (
+        "community mention at the end of a sentence",
+        "see !rust@lemmy.ml.",
+        "<p>see <a href=\"https://example.com/c/rust@lemmy.ml\" class=\"mention\">!rust@lemmy.ml</a>.</p>\n",
+      ),
+      (
+        "mention inside bold text",
+        "thanks **@bob@lemmy-alpha:8541**",
+        "<p>thanks <strong><a href=\"https://example.com/u/bob@lemmy-alpha:8541\" class=\"mention\">@bob@lemmy-alpha:8541</a></strong></p>\n",
+      ),
+      (
+        "mention inside a code span is ignored",
+        "use `@alice@lemmy.ml` to mention",
+        "<p>use <code>@alice@lemmy.ml</code> to mention</p>\n",
+      ),
+      (
+        "mention inside a code block is ignored",
+        "```\n@alice@lemmy.ml\n```",
+        "<pre><code>@alice@lemmy.ml\n</code></pre>\n",
+      ),
+      (
+        "email address is ignored",
+        "mail foo@bar.com or foo@bar@baz.com",
+        "<p>mail foo@bar.com or foo@bar@baz.com</p>\n",
+      ),
+      (
+        "mention inside a link is not linked again",
+        "[@alice@lemmy.ml](https://lemmy.ml/u/alice)",
+        "<p><a href=\"https://lemmy.ml/u/alice\">@alice@lemmy.ml</a></p>\n",
+      ),
+      (
+        "image is not a community mention",
+        "![alt](https://example.com/image.png)",
+        "<p><img src=\"https://example.com/image.png\" alt=\"alt\" /></p>\n",
+      ),
+    ];
+
+    tests.iter().for_each(|&(msg, input, expected)| {
+      let md = &mut MarkdownIt::new();
+      markdown_it::plugins::cmark::add(md);
+      add(md);
+
+      let mut root = md.parse(input);
+      resolve_mentions(&mut root, "https://example.com", None);
+      assert_eq!(
+        root.xrender(),
+        expected,
+        "Testing {}, with original input '{}'",
+        msg,
+        input
+      );
+    });
+  }
+
+  #[test]
+  fn test_mention_display_names() {
+    let md = &mut MarkdownIt::new();
+    markdown_it::plugins::cmark::add(md);
+    add(md);
+    let display_names = HashMap::from([
+      ("@alice@lemmy.ml".to_string(), "Alice <3".to_string()),
+      ("!rust@lemmy.ml".to_string(), "Rust".to_string()),
+    ]);
+
+    // known actors are shown with their display name, and the handle as title
+    let mut root = md.parse("hi @alice@lemmy.ml, see !rust@lemmy.ml");
+    resolve_mentions(&mut root, "https://example.com", Some(&display_names));
+    assert_eq!(
+      "<p>hi <a href=\"https://example.com/u/alice@lemmy.ml\" class=\"mention\" title=\"@alice@lemmy.ml\">Alice &lt;3</a>, see <a href=\"https://example.com/c/rust@lemmy.ml\" class=\"mention\" title=\"!rust@lemmy.ml\">Rust</a></p>\n",
+      root.xrender()
+    );
+
+    // unknown actors are shown with their handle
+    let mut root = md.parse("cc @bob@lemmy.ml");
+    resolve_mentions(&mut root, "https://example.com", Some(&display_names));
+    assert_eq!(
+      "<p>cc <a href=\"https://example.com/u/bob@lemmy.ml\" class=\"mention\">@bob@lemmy.ml</a></p>\n",
+      root.xrender()
+    );
+  }
+}
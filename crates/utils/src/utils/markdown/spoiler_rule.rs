@@ -7,7 +7,8 @@
 //
 // FORMAT:
 // Input Markdown: ::: spoiler VISIBLE_TEXT\nHIDDEN_SPOILER\n:::\n
-// Output HTML: <details><summary>VISIBLE_TEXT</summary><p>nHIDDEN_SPOILER</p></details>
+// Output HTML: <details class="spoiler"><summary>VISIBLE_TEXT</summary>
+//   <div class="spoiler-body">HIDDEN_SPOILER</div></details>
 //
 // Anatomy of a spoiler:
 //     keyword
@@ -16,6 +17,8 @@
 //  ^                ^
 // begin fence   visible text
 //
+// The visible text is optional, without it a default label is shown.
+//
 // HIDDEN_SPOILER
 //      ^
 //  hidden text
@@ -25,10 +28,8 @@
 // end fence
 
 use markdown_it::{
-  parser::{
-    block::{BlockRule, BlockState},
-    inline::InlineRoot,
-  },
+  common::sourcemap::SourcePos,
+  parser::block::{BlockRule, BlockState},
   MarkdownIt,
   Node,
   NodeValue,
@@ -38,31 +39,39 @@ use once_cell::sync::Lazy;
 use regex::Regex;
 
 #[derive(Debug)]
-struct SpoilerBlock {
-  visible_text: String,
+pub(super) struct SpoilerBlock {
+  pub(super) visible_text: String,
 }
 
-const SPOILER_PREFIX: &str = "::: spoiler ";
 const SPOILER_SUFFIX: &str = ":::";
 const SPOILER_SUFFIX_NEWLINE: &str = ":::\n";
 
-static SPOILER_REGEX: Lazy<Regex> =
-  Lazy::new(|| Regex::new(r"^::: spoiler .*$").expect("compile spoiler markdown regex."));
+/// Shown as visible text for spoilers which don't have any.
+const DEFAULT_VISIBLE_TEXT: &str = "Spoiler";
+
+static SPOILER_REGEX: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(r"^:::\s*spoiler(?:\s+(.*))?$").expect("compile spoiler markdown regex.")
+});
 
 impl NodeValue for SpoilerBlock {
   // Formats any node marked as a 'SpoilerBlock' into HTML.
   // See the SpoilerBlockScanner#run implementation to see how these nodes get added to the tree.
   fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    // The class allows clients to style spoilers differently from other details elements.
+    let mut attrs = node.attrs.clone();
+    attrs.push(("class", "spoiler".into()));
+
     fmt.cr();
-    fmt.open("details", &node.attrs);
+    fmt.open("details", &attrs);
     fmt.open("summary", &[]);
     // Not allowing special styling to the visible text to keep it simple.
     // If allowed, would need to parse the child nodes to assign to visible vs hidden text sections.
     fmt.text(&self.visible_text);
     fmt.close("summary");
-    fmt.open("p", &[]);
+    // Use a div instead of p, as the body may consist of multiple blocks like lists or paragraphs.
+    fmt.open("div", &[("class", "spoiler-body".into())]);
     fmt.contents(&node.children);
-    fmt.close("p");
+    fmt.close("div");
     fmt.close("details");
     fmt.cr();
   }
@@ -78,9 +87,13 @@ impl BlockRule for SpoilerBlockScanner {
     let first_line: &str = state.get_line(state.line).trim();
 
     // 1. Check if the first line contains the spoiler syntax...
-    if !SPOILER_REGEX.is_match(first_line) {
-      return None;
-    }
+    let captures = SPOILER_REGEX.captures(first_line)?;
+    let visible_text = captures
+      .get(1)
+      .map(|m| m.as_str().trim())
+      .filter(|text| !text.is_empty())
+      .unwrap_or(DEFAULT_VISIBLE_TEXT)
+      .to_string();
 
     let begin_spoiler_line_idx: usize = state.line + 1;
     let mut end_fence_line_idx: usize = begin_spoiler_line_idx;
@@ -110,15 +123,14 @@ impl BlockRule for SpoilerBlockScanner {
         true,
       );
 
-      let mut node = Node::new(SpoilerBlock {
-        visible_text: String::from(first_line.replace(SPOILER_PREFIX, "").trim()),
-      });
+      let mut node = Node::new(SpoilerBlock { visible_text });
 
-      // Add the spoiler content as children; marking as a child tells the tree to process the
-      // node again, which means other Markdown syntax (ex: emphasis, links) can be rendered.
-      node
-        .children
-        .push(Node::new(InlineRoot::new(spoiler_content, mapping)));
+      // Parse the spoiler content as separate document and add its blocks as children, so that
+      // other Markdown syntax (ex: emphasis, links, lists) can be rendered.
+      node.children = std::mem::take(&mut state.md.parse(&spoiler_content).children);
+      for child in &mut node.children {
+        map_to_source(child, &mapping);
+      }
 
       // NOTE: Not using begin_spoiler_line_idx here because of incorrect results when
       //       state.line == 0 (subtracts an idx) vs the expected correct result (adds an idx).
@@ -129,6 +141,32 @@ impl BlockRule for SpoilerBlockScanner {
   }
 }
 
+/// The spoiler content is parsed as separate document, so the source positions of its nodes are
+/// relative to that content. Moves the positions of the node and its descendants to the same
+/// place in the whole document, using the line mapping from [BlockState::get_lines].
+fn map_to_source(node: &mut Node, mapping: &[(usize, usize)]) {
+  node.walk_mut(|node, _| {
+    if let Some(srcmap) = &node.srcmap {
+      let (start, end) = srcmap.get_byte_offsets();
+      node.srcmap = Some(SourcePos::new(
+        source_offset(start, mapping),
+        source_offset(end, mapping),
+      ));
+    }
+  });
+}
+
+/// Position in the document of the byte at `offset` in the content of the lines.
+fn source_offset(offset: usize, mapping: &[(usize, usize)]) -> usize {
+  mapping
+    .iter()
+    .rev()
+    .find(|(content_pos, _)| *content_pos <= offset)
+    .map_or(offset, |(content_pos, source_pos)| {
+      source_pos + offset - content_pos
+    })
+}
+
 pub fn add(markdown_parser: &mut MarkdownIt) {
   markdown_parser.block.add_rule::<SpoilerBlockScanner>();
 }
@@ -139,7 +177,7 @@ mod tests {
   #![allow(clippy::indexing_slicing)]
 
   use crate::utils::markdown::spoiler_rule::add;
-  use markdown_it::MarkdownIt;
+  use markdown_it::{plugins::cmark::inline::backticks::CodeInline, MarkdownIt};
 
   #[test]
   fn test_spoiler_markdown() {
@@ -150,39 +188,54 @@ mod tests {
         "<p>::: spoiler click to see more\nbut I never finished</p>\n",
       ),
       (
-        "another invalid spoiler",
+        "spoiler without visible text",
         "::: spoiler\nnever added the lead in\n:::",
-        "<p>::: spoiler\nnever added the lead in\n:::</p>\n",
+        "<details class=\"spoiler\"><summary>Spoiler</summary><div class=\"spoiler-body\">\n<p>never added the lead in</p>\n</div></details>\n"
+      ),
+      (
+        "spoiler without visible text or space",
+        ":::spoiler\nnever added the lead in\n:::",
+        "<details class=\"spoiler\"><summary>Spoiler</summary><div class=\"spoiler-body\">\n<p>never added the lead in</p>\n</div></details>\n"
+      ),
+      (
+        "not a spoiler keyword",
+        "::: spoilers\nnever added the lead in\n:::",
+        "<p>::: spoilers\nnever added the lead in\n:::</p>\n",
       ),
       (
         "basic spoiler, but no newline at the end",
         "::: spoiler click to see more\nhow spicy!\n:::",
-        "<details><summary>click to see more</summary><p>how spicy!\n</p></details>\n"
+        "<details class=\"spoiler\"><summary>click to see more</summary><div class=\"spoiler-body\">\n<p>how spicy!</p>\n</div></details>\n"
       ),
       (
         "basic spoiler with a newline at the end",
         "::: spoiler click to see more\nhow spicy!\n:::\n",
-        "<details><summary>click to see more</summary><p>how spicy!\n</p></details>\n"
+        "<details class=\"spoiler\"><summary>click to see more</summary><div class=\"spoiler-body\">\n<p>how spicy!</p>\n</div></details>\n"
       ),
       (
         "spoiler with extra markdown on the call to action (no extra parsing)",
         "::: spoiler _click to see more_\nhow spicy!\n:::\n",
-        "<details><summary>_click to see more_</summary><p>how spicy!\n</p></details>\n"
+        "<details class=\"spoiler\"><summary>_click to see more_</summary><div class=\"spoiler-body\">\n<p>how spicy!</p>\n</div></details>\n"
       ),
       (
         "spoiler with extra markdown in the fenced spoiler block",
         "::: spoiler click to see more\n**how spicy!**\n*i have many lines*\n:::\n",
-        "<details><summary>click to see more</summary><p><strong>how spicy!</strong>\n<em>i have many lines</em>\n</p></details>\n"
+        "<details class=\"spoiler\"><summary>click to see more</summary><div class=\"spoiler-body\">\n<p><strong>how spicy!</strong>\n<em>i have many lines</em></p>\n</div></details>\n"
       ),
       (
         "spoiler mixed with other content",
         "hey you\npsst, wanna hear a secret?\n::: spoiler lean in and i'll tell you\n**you are breathtaking!**\n:::\nwhatcha think about that?",
-        "<p>hey you\npsst, wanna hear a secret?</p>\n<details><summary>lean in and i'll tell you</summary><p><strong>you are breathtaking!</strong>\n</p></details>\n<p>whatcha think about that?</p>\n"
+        "<p>hey you\npsst, wanna hear a secret?</p>\n<details class=\"spoiler\"><summary>lean in and i'll tell you</summary><div class=\"spoiler-body\">\n<p><strong>you are breathtaking!</strong></p>\n</div></details>\n<p>whatcha think about that?</p>\n"
       ),
       (
         "spoiler mixed with indented content",
         "- did you know that\n::: spoiler the call was\n***coming from inside the house!***\n:::\n - crazy, right?",
-        "<ul>\n<li>did you know that</li>\n</ul>\n<details><summary>the call was</summary><p><em><strong>coming from inside the house!</strong></em>\n</p></details>\n<ul>\n<li>crazy, right?</li>\n</ul>\n"
+        "<ul>\n<li>did you know that</li>\n</ul>\n<details class=\"spoiler\"><summary>the call was</summary><div class=\"spoiler-body\">\n<p><em><strong>coming from inside the house!</strong></em></p>\n</div></details>\n<ul>\n<li>crazy, right?</li>\n</ul>\n"
+      ),
+      (
+        "spoiler with multiple blocks in the fenced spoiler block",
+        "::: spoiler click to see more\nfirst paragraph\n\n- one\n- two\n:::\n",
+        "<details class=\"spoiler\"><summary>click to see more</summary><div class=\"spoiler-body\">\n<p>first paragraph</p>\n<ul>\n<li>one</li>\n<li>two</li>\n</ul>\n</div></details>\n"
       )
     ];
 
@@ -200,4 +253,28 @@ mod tests {
       );
     });
   }
+
+  #[test]
+  fn test_spoiler_source_positions() {
+    let md = &mut MarkdownIt::new();
+    markdown_it::plugins::cmark::add(md);
+    add(md);
+
+    // the positions of nodes inside the spoiler point to the same text in the whole document
+    let input = "intro\n\n::: spoiler hint\nsome `code` here\n> ::: spoiler nested\n> more `code`\n> :::\n:::\n";
+    let mut code = vec![];
+    md.parse(input).walk(|node, _| {
+      if node.is::<CodeInline>() {
+        let (start, end) = node.srcmap.as_ref().unwrap().get_byte_offsets();
+        code.push((start, &input[start..end]));
+      }
+    });
+    assert_eq!(
+      vec![
+        (input.find("`code`").unwrap(), "`code`"),
+        (input.rfind("`code`").unwrap(), "`code`")
+      ],
+      code
+    );
+  }
 }
@@ -0,0 +1,45 @@
+// Custom Markdown plugin to render strikethrough as deleted text.
+//
+// FORMAT:
+// Input Markdown: ~~gone~~
+// Output HTML: <del>gone</del>
+//
+// Parsing is already done by the strikethrough plugin which `markdown_it::plugins::extra::add`
+// enables, so it must not be added a second time. That plugin renders `<s>` though, so its nodes
+// are replaced once parsing is finished.
+
+use markdown_it::{
+  parser::core::CoreRule,
+  plugins::extra::strikethrough::Strikethrough,
+  MarkdownIt,
+  Node,
+  NodeValue,
+  Renderer,
+};
+
+#[derive(Debug)]
+struct Deleted;
+
+impl NodeValue for Deleted {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    fmt.open("del", &node.attrs);
+    fmt.contents(&node.children);
+    fmt.close("del");
+  }
+}
+
+struct DeletedRule;
+
+impl CoreRule for DeletedRule {
+  fn run(root: &mut Node, _: &MarkdownIt) {
+    root.walk_mut(|node, _| {
+      if node.is::<Strikethrough>() {
+        node.replace(Deleted);
+      }
+    });
+  }
+}
+
+pub fn add(markdown_parser: &mut MarkdownIt) {
+  markdown_parser.add_rule::<DeletedRule>();
+}
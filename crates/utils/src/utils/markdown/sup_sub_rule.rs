@@ -0,0 +1,154 @@
+// Custom Markdown plugin for superscript and subscript.
+//
+// Uses the same syntax as Pandoc, for formulas and units.
+//
+// FORMAT:
+// Input Markdown: x^2^ and H~2~O
+// Output HTML: x<sup>2</sup> and H<sub>2</sub>O
+//
+// The content can't contain whitespace, so a marker never matches across words or lines. The
+// strikethrough plugin treats every run of tildes as a delimiter, so subscript is parsed before it
+// and leaves double tildes to it.
+
+use markdown_it::{
+  generics::inline::emph_pair::EmphPairScanner,
+  parser::inline::{InlineRule, InlineState},
+  MarkdownIt,
+  Node,
+  NodeValue,
+  Renderer,
+};
+
+#[derive(Debug)]
+struct Superscript;
+
+impl NodeValue for Superscript {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    fmt.open("sup", &node.attrs);
+    fmt.contents(&node.children);
+    fmt.close("sup");
+  }
+}
+
+#[derive(Debug)]
+struct Subscript;
+
+impl NodeValue for Subscript {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    fmt.open("sub", &node.attrs);
+    fmt.contents(&node.children);
+    fmt.close("sub");
+  }
+}
+
+struct SuperscriptScanner;
+
+impl InlineRule for SuperscriptScanner {
+  const MARKER: char = '^';
+
+  fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+    scan(state, Self::MARKER, Node::new(Superscript))
+  }
+}
+
+struct SubscriptScanner;
+
+impl InlineRule for SubscriptScanner {
+  const MARKER: char = '~';
+
+  fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+    scan(state, Self::MARKER, Node::new(Subscript))
+  }
+}
+
+/// Parses `marker` content `marker` at the current position into `node`.
+fn scan(state: &mut InlineState, marker: char, node: Node) -> Option<(Node, usize)> {
+  let src = state.src.get(state.pos..state.pos_max)?;
+  let rest = src.strip_prefix(marker)?;
+  if rest.starts_with(marker) {
+    return None;
+  }
+
+  // Find the closing marker, empty content and whitespace are not allowed. A doubled closing
+  // marker belongs to strikethrough, like in `~~a~b~~`.
+  let content_len = rest.find(|c: char| c == marker || c.is_whitespace())?;
+  let after = rest.get(content_len..)?;
+  if content_len == 0
+    || !after.starts_with(marker)
+    || after.get(marker.len_utf8()..)?.starts_with(marker)
+  {
+    return None;
+  }
+  let content_start = state.pos + marker.len_utf8();
+  let content_end = content_start + content_len;
+
+  // Parse the content as inline Markdown, so that for example emphasis is rendered.
+  let old_node = std::mem::replace(&mut state.node, node);
+  let old_pos = state.pos;
+  let old_pos_max = state.pos_max;
+  state.pos = content_start;
+  state.pos_max = content_end;
+  let md = state.md;
+  md.inline.tokenize(state);
+  state.pos = old_pos;
+  state.pos_max = old_pos_max;
+  let node = std::mem::replace(&mut state.node, old_node);
+
+  Some((node, content_end + marker.len_utf8() - old_pos))
+}
+
+pub fn add(markdown_parser: &mut MarkdownIt) {
+  markdown_parser.inline.add_rule::<SuperscriptScanner>();
+  markdown_parser
+    .inline
+    .add_rule::<SubscriptScanner>()
+    .before::<EmphPairScanner<'~', true>>();
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use crate::utils::markdown::markdown_to_html;
+
+  #[test]
+  fn test_sup_sub_markdown() {
+    let tests: Vec<_> = vec![
+      ("superscript", "x^2^", "<p>x<sup>2</sup></p>\n"),
+      ("subscript", "H~2~O", "<p>H<sub>2</sub>O</p>\n"),
+      (
+        "strikethrough is not subscript",
+        "~~del~~",
+        "<p><del>del</del></p>\n",
+      ),
+      (
+        "no match across whitespace",
+        "2^10 and 2^20",
+        "<p>2^10 and 2^20</p>\n",
+      ),
+      (
+        "no match across line breaks",
+        "a~b\nc~d",
+        "<p>a~b\nc~d</p>\n",
+      ),
+      (
+        "subscript inside strikethrough",
+        "~~H~2~O~~ and ~~a~b~~",
+        "<p><del>H<sub>2</sub>O</del> and <del>a~b</del></p>\n",
+      ),
+      ("empty content", "a^^b", "<p>a^^b</p>\n"),
+      ("inside a code span", "`x^2^`", "<p><code>x^2^</code></p>\n"),
+    ];
+
+    tests.iter().for_each(|&(msg, input, expected)| {
+      assert_eq!(
+        markdown_to_html(input),
+        expected,
+        "Testing {}, with original input '{}'",
+        msg,
+        input
+      );
+    });
+  }
+}
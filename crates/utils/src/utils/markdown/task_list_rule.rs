@@ -0,0 +1,156 @@
+// Custom Markdown plugin for task lists, as in GitHub Flavored Markdown.
+//
+// FORMAT:
+// Input Markdown: - [ ] open\n- [x] done
+// Output HTML: <ul>\n<li><input type="checkbox" disabled="" /> open</li>
+//   <li><input type="checkbox" disabled="" checked="" /> done</li>\n</ul>
+//
+// The checkboxes are disabled, as their state can only be changed by editing the text. Only the
+// start of a list item is checked, so `[ ]` anywhere else stays text. Nested lists are handled
+// like any other list.
+
+use markdown_it::{
+  parser::{core::CoreRule, inline::Text},
+  plugins::cmark::block::{list::ListItem, paragraph::Paragraph},
+  MarkdownIt,
+  Node,
+  NodeValue,
+  Renderer,
+};
+
+#[derive(Debug)]
+struct TaskCheckbox {
+  checked: bool,
+}
+
+impl NodeValue for TaskCheckbox {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    let mut attrs = node.attrs.clone();
+    attrs.push(("type", "checkbox".into()));
+    attrs.push(("disabled", String::new()));
+    if self.checked {
+      attrs.push(("checked", String::new()));
+    }
+    fmt.self_close("input", &attrs);
+  }
+}
+
+/// Length of the `[ ]` marker, which needs to be followed by a space.
+const MARKER_LEN: usize = 3;
+
+/// Turns a `[ ] ` or `[x] ` at the start of the inline content into a checkbox.
+fn add_checkbox(item: &mut Node) {
+  // items of loose lists wrap their content in a paragraph
+  let in_paragraph = item.children.first().is_some_and(Node::is::<Paragraph>);
+  let content = match item.children.first_mut() {
+    Some(paragraph) if in_paragraph => paragraph,
+    _ => item,
+  };
+
+  // the marker may be split over several text nodes
+  let mut start = String::new();
+  for child in &content.children {
+    let Some(text) = child.cast::<Text>() else {
+      break;
+    };
+    start.push_str(&text.content);
+    if start.len() > MARKER_LEN {
+      break;
+    }
+  }
+  let checked = match start.get(..=MARKER_LEN) {
+    Some("[ ] ") => false,
+    Some("[x] " | "[X] ") => true,
+    _ => return,
+  };
+
+  let mut remaining = MARKER_LEN;
+  for child in &mut content.children {
+    let Some(text) = child.cast_mut::<Text>() else {
+      break;
+    };
+    let len = remaining.min(text.content.len());
+    text.content.drain(..len);
+    remaining -= len;
+    if remaining == 0 {
+      break;
+    }
+  }
+  content
+    .children
+    .retain(|c| c.cast::<Text>().map_or(true, |t| !t.content.is_empty()));
+  content
+    .children
+    .insert(0, Node::new(TaskCheckbox { checked }));
+}
+
+struct TaskListRule;
+
+impl CoreRule for TaskListRule {
+  fn run(root: &mut Node, _: &MarkdownIt) {
+    root.walk_mut(|node, _| {
+      if node.is::<ListItem>() {
+        add_checkbox(node);
+      }
+    });
+  }
+}
+
+pub fn add(markdown_parser: &mut MarkdownIt) {
+  markdown_parser.add_rule::<TaskListRule>();
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use crate::utils::markdown::markdown_to_html;
+
+  #[test]
+  fn test_task_list_markdown() {
+    let tests: Vec<_> = vec![
+      (
+        "unchecked and checked items",
+        "- [ ] open\n- [x] done\n- [X] also done",
+        "<ul>\n<li><input type=\"checkbox\" disabled=\"\" /> open</li>\n<li><input type=\"checkbox\" disabled=\"\" checked=\"\" /> done</li>\n<li><input type=\"checkbox\" disabled=\"\" checked=\"\" /> also done</li>\n</ul>\n",
+      ),
+      (
+        "nested task list",
+        "- [ ] parent\n  - [x] child",
+        "<ul>\n<li><input type=\"checkbox\" disabled=\"\" /> parent\n<ul>\n<li><input type=\"checkbox\" disabled=\"\" checked=\"\" /> child</li>\n</ul>\n</li>\n</ul>\n",
+      ),
+      (
+        "loose list",
+        "1. [x] first\n\n2. [ ] second",
+        "<ol>\n<li>\n<p><input type=\"checkbox\" disabled=\"\" checked=\"\" /> first</p>\n</li>\n<li>\n<p><input type=\"checkbox\" disabled=\"\" /> second</p>\n</li>\n</ol>\n",
+      ),
+      (
+        "marker with formatted text",
+        "- [ ] **bold**",
+        "<ul>\n<li><input type=\"checkbox\" disabled=\"\" /> <strong>bold</strong></li>\n</ul>\n",
+      ),
+      (
+        "not at the start of the item",
+        "- todo [ ] later",
+        "<ul>\n<li>todo [ ] later</li>\n</ul>\n",
+      ),
+      (
+        "marker without text after it",
+        "- [ ]",
+        "<ul>\n<li>[ ]</li>\n</ul>\n",
+      ),
+      ("outside of a list", "[x] done", "<p>[x] done</p>\n"),
+    ];
+
+    tests.iter().for_each(|&(msg, input, expected)| {
+      assert_eq!(
+        markdown_to_html(input),
+        expected,
+        "Testing {}, with original input '{}'",
+        msg,
+        input
+      );
+    });
+  }
+}
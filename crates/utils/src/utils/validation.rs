@@ -1,4 +1,8 @@
-use crate::error::{LemmyErrorExt, LemmyErrorType, LemmyResult};
+use crate::{
+  error::{LemmyErrorExt, LemmyErrorType, LemmyResult},
+  settings::structs::Settings,
+  utils::markdown::has_renderable_content,
+};
 use itertools::Itertools;
 use once_cell::sync::Lazy;
 use regex::{Regex, RegexBuilder};
@@ -25,8 +29,10 @@ const BIO_MAX_LENGTH: usize = 300;
 const SITE_NAME_MAX_LENGTH: usize = 20;
 const SITE_NAME_MIN_LENGTH: usize = 1;
 const SITE_DESCRIPTION_MAX_LENGTH: usize = 150;
+/// Maximum length in characters of a reaction, enough for custom emoji shortcodes.
+const REACTION_MAX_LENGTH: usize = 64;
 //Invisible unicode characters, taken from https://invisible-characters.com/
-const FORBIDDEN_DISPLAY_CHARS: [char; 53] = [
+pub(crate) const FORBIDDEN_DISPLAY_CHARS: [char; 53] = [
   '\u{0009}',
   '\u{00a0}',
   '\u{00ad}',
@@ -170,10 +176,31 @@ pub fn is_valid_body_field(body: &Option<String>, post: bool) -> LemmyResult<()>
   Ok(())
 }
 
+/// Rejects text which renders to nothing visible, if enabled with `reject_invisible_content` in the
+/// config.
+pub fn check_renderable_content(text: &str, settings: &Settings) -> LemmyResult<()> {
+  if settings.reject_invisible_content && !has_renderable_content(text) {
+    Err(LemmyErrorType::NoVisibleContent)?
+  }
+  Ok(())
+}
+
 pub fn is_valid_bio_field(bio: &str) -> LemmyResult<()> {
   max_length_check(bio, BIO_MAX_LENGTH, LemmyErrorType::BioLengthOverflow)
 }
 
+/// Rejects reactions which are empty, too long or contain whitespace.
+pub fn is_valid_reaction(shortcode: &str) -> LemmyResult<()> {
+  let valid = !shortcode.is_empty()
+    && shortcode.chars().count() <= REACTION_MAX_LENGTH
+    && !shortcode.chars().any(char::is_whitespace);
+  if valid {
+    Ok(())
+  } else {
+    Err(LemmyErrorType::InvalidReaction)?
+  }
+}
+
 /// Checks the site name length, the limit as defined in the DB.
 pub fn site_name_length_check(name: &str) -> LemmyResult<()> {
   min_length_check(name, SITE_NAME_MIN_LENGTH, LemmyErrorType::SiteNameRequired)?;
@@ -305,9 +332,11 @@ mod tests {
       is_valid_display_name,
       is_valid_matrix_id,
       is_valid_post_title,
+      is_valid_reaction,
       site_description_length_check,
       site_name_length_check,
       BIO_MAX_LENGTH,
+      REACTION_MAX_LENGTH,
       SITE_DESCRIPTION_MAX_LENGTH,
       SITE_NAME_MAX_LENGTH,
     },
@@ -381,6 +410,15 @@ mod tests {
     assert!(is_valid_post_title("\n \n \n \n    		").is_err()); // tabs/spaces/newlines
   }
 
+  #[test]
+  fn test_valid_reaction() {
+    assert!(is_valid_reaction("👍").is_ok());
+    assert!(is_valid_reaction(":blobcat:").is_ok());
+    assert!(is_valid_reaction("").is_err());
+    assert!(is_valid_reaction("two words").is_err());
+    assert!(is_valid_reaction(&"a".repeat(REACTION_MAX_LENGTH + 1)).is_err());
+  }
+
   #[test]
   fn test_valid_matrix_id() {
     assert!(is_valid_matrix_id("@dess:matrix.org").is_ok());
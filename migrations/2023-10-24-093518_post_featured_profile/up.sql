@@ -0,0 +1,3 @@
+ALTER TABLE post
+    ADD COLUMN featured_profile boolean DEFAULT FALSE NOT NULL;
+
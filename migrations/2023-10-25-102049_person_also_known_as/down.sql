@@ -0,0 +1,3 @@
+ALTER TABLE person
+    DROP COLUMN also_known_as;
+
@@ -0,0 +1,3 @@
+ALTER TABLE person
+    ADD COLUMN also_known_as text[] NOT NULL DEFAULT '{}';
+
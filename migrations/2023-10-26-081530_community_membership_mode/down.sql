@@ -0,0 +1,5 @@
+ALTER TABLE community
+    DROP COLUMN membership_mode;
+
+DROP TYPE community_membership_mode_enum;
+
@@ -0,0 +1,8 @@
+CREATE TYPE community_membership_mode_enum AS enum (
+    'Open',
+    'RequireApplication'
+);
+
+ALTER TABLE community
+    ADD COLUMN membership_mode community_membership_mode_enum DEFAULT 'Open' NOT NULL;
+
@@ -0,0 +1,7 @@
+DROP TRIGGER person_instance_admin ON local_user;
+
+DROP FUNCTION person_instance_admin;
+
+ALTER TABLE person
+    DROP COLUMN instance_admin;
+
@@ -0,0 +1,34 @@
+ALTER TABLE person
+    ADD COLUMN instance_admin boolean NOT NULL DEFAULT FALSE;
+
+-- Keep the flag of local persons in sync with local_user.admin, so that it can be federated
+-- without reading the local user
+UPDATE
+    person p
+SET
+    instance_admin = lu.admin
+FROM
+    local_user lu
+WHERE
+    lu.person_id = p.id;
+
+CREATE FUNCTION person_instance_admin ()
+    RETURNS TRIGGER
+    LANGUAGE plpgsql
+    AS $$
+BEGIN
+    UPDATE
+        person p
+    SET
+        instance_admin = NEW.admin
+    WHERE
+        p.id = NEW.person_id;
+    RETURN NULL;
+END
+$$;
+
+CREATE TRIGGER person_instance_admin
+    AFTER INSERT OR UPDATE OF admin ON local_user
+    FOR EACH ROW
+    EXECUTE FUNCTION person_instance_admin ();
+
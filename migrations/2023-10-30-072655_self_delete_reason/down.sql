@@ -0,0 +1,6 @@
+ALTER TABLE post
+    DROP COLUMN delete_reason;
+
+ALTER TABLE comment
+    DROP COLUMN delete_reason;
+
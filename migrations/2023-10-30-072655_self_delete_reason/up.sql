@@ -0,0 +1,6 @@
+ALTER TABLE post
+    ADD COLUMN delete_reason text;
+
+ALTER TABLE comment
+    ADD COLUMN delete_reason text;
+
@@ -0,0 +1,8 @@
+ALTER TABLE post
+    DROP COLUMN reply_policy;
+
+ALTER TABLE comment
+    DROP COLUMN reply_policy;
+
+DROP TYPE reply_policy_enum;
+
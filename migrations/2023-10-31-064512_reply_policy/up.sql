@@ -0,0 +1,12 @@
+CREATE TYPE reply_policy_enum AS enum (
+    'Everyone',
+    'Followers',
+    'Nobody'
+);
+
+ALTER TABLE post
+    ADD COLUMN reply_policy reply_policy_enum DEFAULT 'Everyone' NOT NULL;
+
+ALTER TABLE comment
+    ADD COLUMN reply_policy reply_policy_enum DEFAULT 'Everyone' NOT NULL;
+
@@ -0,0 +1,3 @@
+ALTER TABLE community
+    DROP COLUMN default_post_nsfw;
+
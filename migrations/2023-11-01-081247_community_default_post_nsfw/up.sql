@@ -0,0 +1,3 @@
+ALTER TABLE community
+    ADD COLUMN default_post_nsfw boolean DEFAULT FALSE NOT NULL;
+
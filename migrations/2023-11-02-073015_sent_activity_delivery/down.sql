@@ -0,0 +1,2 @@
+DROP TABLE sent_activity_delivery;
+
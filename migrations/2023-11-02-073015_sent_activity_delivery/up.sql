@@ -0,0 +1,12 @@
+-- Outcome of delivering a sent activity to a single inbox. Rows are removed together with the
+-- activity, so they can't grow beyond the retention of sent_activity.
+CREATE TABLE sent_activity_delivery (
+    id bigserial PRIMARY KEY,
+    sent_activity_id bigint REFERENCES sent_activity ON UPDATE CASCADE ON DELETE CASCADE NOT NULL,
+    inbox text NOT NULL,
+    success boolean NOT NULL,
+    error text,
+    updated timestamptz NOT NULL DEFAULT now(),
+    UNIQUE (sent_activity_id, inbox)
+);
+
@@ -0,0 +1,6 @@
+ALTER TABLE post
+    DROP COLUMN quoted_post_id;
+
+ALTER TABLE post
+    DROP COLUMN quote_url;
+
@@ -0,0 +1,6 @@
+ALTER TABLE post
+    ADD COLUMN quote_url varchar(512);
+
+ALTER TABLE post
+    ADD COLUMN quoted_post_id int REFERENCES post ON UPDATE CASCADE ON DELETE SET NULL;
+
@@ -0,0 +1,3 @@
+ALTER TABLE community
+    ADD COLUMN primary_language_id integer REFERENCES LANGUAGE NOT NULL DEFAULT 0;
+
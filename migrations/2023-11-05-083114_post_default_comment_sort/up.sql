@@ -0,0 +1,11 @@
+CREATE TYPE comment_sort_type_enum AS enum (
+    'Hot',
+    'Top',
+    'New',
+    'Old',
+    'Controversial'
+);
+
+ALTER TABLE post
+    ADD COLUMN default_comment_sort_type comment_sort_type_enum;
+
@@ -0,0 +1,3 @@
+ALTER TABLE community_aggregates
+    DROP COLUMN federated_users_active;
+
@@ -0,0 +1,3 @@
+ALTER TABLE community_aggregates
+    ADD COLUMN federated_users_active boolean NOT NULL DEFAULT FALSE;
+
@@ -0,0 +1,3 @@
+ALTER TABLE instance
+    ADD COLUMN paused boolean NOT NULL DEFAULT FALSE;
+
@@ -0,0 +1,27 @@
+-- Polls attached to posts. These are received from other platforms like Mastodon, which federate
+-- them as the Question type.
+CREATE TABLE poll (
+    id serial PRIMARY KEY,
+    post_id int REFERENCES post ON UPDATE CASCADE ON DELETE CASCADE NOT NULL UNIQUE,
+    multiple_choice boolean NOT NULL DEFAULT FALSE,
+    end_time timestamptz,
+    published timestamptz NOT NULL DEFAULT now(),
+    updated timestamptz
+);
+
+CREATE TABLE poll_option (
+    id serial PRIMARY KEY,
+    poll_id int REFERENCES poll ON UPDATE CASCADE ON DELETE CASCADE NOT NULL,
+    name text NOT NULL,
+    -- For remote polls this is the count reported by the origin instance
+    vote_count int NOT NULL DEFAULT 0,
+    UNIQUE (poll_id, name)
+);
+
+CREATE TABLE poll_vote (
+    poll_option_id int REFERENCES poll_option ON UPDATE CASCADE ON DELETE CASCADE NOT NULL,
+    person_id int REFERENCES person ON UPDATE CASCADE ON DELETE CASCADE NOT NULL,
+    published timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (poll_option_id, person_id)
+);
+
@@ -0,0 +1,3 @@
+ALTER TABLE received_activity
+    DROP COLUMN signature_algorithm;
+
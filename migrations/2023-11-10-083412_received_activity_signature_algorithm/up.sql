@@ -0,0 +1,3 @@
+ALTER TABLE received_activity
+    ADD COLUMN signature_algorithm text;
+
@@ -0,0 +1,2 @@
+DROP TABLE federation_community_blocklist;
+
@@ -0,0 +1,8 @@
+-- Remote communities which are blocked by the admins, even though their instance is allowed
+CREATE TABLE federation_community_blocklist (
+    id serial PRIMARY KEY,
+    community_id int REFERENCES community ON UPDATE CASCADE ON DELETE CASCADE NOT NULL UNIQUE,
+    published timestamptz NOT NULL DEFAULT now(),
+    updated timestamptz
+);
+
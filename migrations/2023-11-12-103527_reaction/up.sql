@@ -0,0 +1,21 @@
+-- Emoji reactions to posts and comments, which are federated with the EmojiReact activity. For
+-- reactions to comments, post_id is the post of the comment.
+CREATE TABLE reaction (
+    id serial PRIMARY KEY,
+    person_id int REFERENCES person ON UPDATE CASCADE ON DELETE CASCADE NOT NULL,
+    post_id int REFERENCES post ON UPDATE CASCADE ON DELETE CASCADE NOT NULL,
+    comment_id int REFERENCES COMMENT ON UPDATE CASCADE ON DELETE CASCADE,
+    -- Unicode emoji or the shortcode of a custom emoji, like :blobcat:
+    shortcode text NOT NULL,
+    published timestamptz NOT NULL DEFAULT now()
+);
+
+-- A person can react with each emoji only once to the same post or comment
+CREATE UNIQUE INDEX idx_reaction_post ON reaction (person_id, post_id, shortcode)
+WHERE
+    comment_id IS NULL;
+
+CREATE UNIQUE INDEX idx_reaction_comment ON reaction (person_id, comment_id, shortcode)
+WHERE
+    comment_id IS NOT NULL;
+
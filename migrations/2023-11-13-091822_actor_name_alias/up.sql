@@ -0,0 +1,20 @@
+-- Previous names of local persons and communities, so that webfinger can still resolve them
+-- after the actor was renamed. Each alias points to exactly one actor.
+CREATE TABLE actor_name_alias (
+    id serial PRIMARY KEY,
+    name varchar(255) NOT NULL,
+    person_id int REFERENCES person ON UPDATE CASCADE ON DELETE CASCADE,
+    community_id int REFERENCES community ON UPDATE CASCADE ON DELETE CASCADE,
+    published timestamptz NOT NULL DEFAULT now(),
+    CHECK ((person_id IS NULL) != (community_id IS NULL))
+);
+
+-- Names are compared case-insensitively, like in webfinger lookups of current names
+CREATE UNIQUE INDEX idx_actor_name_alias_person ON actor_name_alias (lower(name))
+WHERE
+    person_id IS NOT NULL;
+
+CREATE UNIQUE INDEX idx_actor_name_alias_community ON actor_name_alias (lower(name))
+WHERE
+    community_id IS NOT NULL;
+
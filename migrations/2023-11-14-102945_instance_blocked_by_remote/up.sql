@@ -0,0 +1,3 @@
+ALTER TABLE instance
+    ADD COLUMN blocked_by_remote timestamptz;
+
@@ -0,0 +1,3 @@
+ALTER TABLE local_site
+    ADD COLUMN federate_votes boolean NOT NULL DEFAULT TRUE;
+
@@ -0,0 +1,4 @@
+DROP INDEX idx_sent_activity_actor_apub_id;
+
+DROP INDEX idx_sent_activity_object_actor;
+
@@ -0,0 +1,5 @@
+-- Used to list the activities of an actor, including remote activities announced by a local community
+CREATE INDEX idx_sent_activity_actor_apub_id ON sent_activity (actor_apub_id);
+
+CREATE INDEX idx_sent_activity_object_actor ON sent_activity ((data -> 'object' ->> 'actor'));
+
@@ -0,0 +1,15 @@
+-- Deliveries of sent activities which failed and are retried by the federation worker of the
+-- instance. Rows are removed together with the activity, so they can't grow beyond the retention
+-- of sent_activity.
+CREATE TABLE federation_queue (
+    id bigserial PRIMARY KEY,
+    instance_id int REFERENCES instance ON UPDATE CASCADE ON DELETE CASCADE NOT NULL,
+    sent_activity_id bigint REFERENCES sent_activity ON UPDATE CASCADE ON DELETE CASCADE NOT NULL,
+    inbox text NOT NULL,
+    attempts int NOT NULL,
+    next_retry timestamptz NOT NULL,
+    UNIQUE (sent_activity_id, inbox)
+);
+
+CREATE INDEX idx_federation_queue_instance_next_retry ON federation_queue (instance_id, next_retry);
+
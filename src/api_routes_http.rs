@@ -1,6 +1,11 @@
 use actix_web::{guard, web};
 use lemmy_api::{
-  comment::{distinguish::distinguish_comment, like::like_comment, save::save_comment},
+  comment::{
+    distinguish::distinguish_comment,
+    like::like_comment,
+    react::react_to_comment,
+    save::save_comment,
+  },
   comment_report::{
     create::create_comment_report,
     list::list_comment_reports,
@@ -8,6 +13,7 @@ use lemmy_api::{
   },
   community::{
     add_mod::add_mod_to_community,
+    approve_follower::approve_community_follower,
     ban::ban_from_community,
     block::block_community,
     follow::follow_community,
@@ -47,6 +53,7 @@ use lemmy_api::{
     like::like_post,
     lock::lock_post,
     mark_read::mark_post_as_read,
+    react::react_to_post,
     save::save_post,
   },
   post_report::{
@@ -61,6 +68,7 @@ use lemmy_api::{
     resolve::resolve_pm_report,
   },
   site::{
+    actor_activities::list_actor_activities,
     block::block_instance,
     federated_instances::get_federated_instances,
     leave_admin::leave_admin,
@@ -175,7 +183,11 @@ pub fn config(cfg: &mut web::ServiceConfig, rate_limit: &RateLimitCell) {
           .route("/remove", web::post().to(remove_community))
           .route("/transfer", web::post().to(transfer_community))
           .route("/ban_user", web::post().to(ban_from_community))
-          .route("/mod", web::post().to(add_mod_to_community)),
+          .route("/mod", web::post().to(add_mod_to_community))
+          .route(
+            "/follower/approve",
+            web::post().to(approve_community_follower),
+          ),
       )
       .service(
         web::scope("/federated_instances")
@@ -202,6 +214,7 @@ pub fn config(cfg: &mut web::ServiceConfig, rate_limit: &RateLimitCell) {
           .route("/feature", web::post().to(feature_post))
           .route("/list", web::get().to(list_posts))
           .route("/like", web::post().to(like_post))
+          .route("/react", web::post().to(react_to_post))
           .route("/save", web::put().to(save_post))
           .route("/report", web::post().to(create_post_report))
           .route("/report/resolve", web::put().to(resolve_post_report))
@@ -226,6 +239,7 @@ pub fn config(cfg: &mut web::ServiceConfig, rate_limit: &RateLimitCell) {
           .route("/mark_as_read", web::post().to(mark_reply_as_read))
           .route("/distinguish", web::post().to(distinguish_comment))
           .route("/like", web::post().to(like_comment))
+          .route("/react", web::post().to(react_to_comment))
           .route("/save", web::put().to(save_comment))
           .route("/list", web::get().to(list_comments))
           .route("/report", web::post().to(create_comment_report))
@@ -311,6 +325,7 @@ pub fn config(cfg: &mut web::ServiceConfig, rate_limit: &RateLimitCell) {
         web::scope("/admin")
           .wrap(rate_limit.message())
           .route("/add", web::post().to(add_admin))
+          .route("/actor_activities", web::get().to(list_actor_activities))
           .route(
             "/registration_application/count",
             web::get().to(get_unread_registration_application_count),
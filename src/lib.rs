@@ -36,14 +36,15 @@ use lemmy_api_common::{
 };
 use lemmy_apub::{
   activities::{handle_outgoing_activities, match_outgoing_activities},
+  max_http_fetch_limit,
+  objects::instance::ApubSite,
   VerifyUrlData,
-  FEDERATION_HTTP_FETCH_LIMIT,
 };
 use lemmy_db_schema::{
   source::secret::Secret,
   utils::{build_db_pool, get_database_url, run_migrations},
 };
-use lemmy_federate::{start_stop_federation_workers_cancellable, Opts};
+use lemmy_federate::{start_stop_federation_workers_cancellable, Opts, RemoteBlockMiddleware};
 use lemmy_routes::{feeds, images, nodeinfo, webfinger};
 use lemmy_utils::{
   error::LemmyError,
@@ -171,14 +172,26 @@ pub async fn start_lemmy_server(args: CmdArgs) -> Result<(), LemmyError> {
     serve_prometheus(prometheus, context.clone())?;
   }
 
-  let federation_config = FederationConfig::builder()
+  let mut federation_config_builder = FederationConfig::builder();
+  federation_config_builder
     .domain(SETTINGS.hostname.clone())
     .app_data(context.clone())
     .client(client.clone())
-    .http_fetch_limit(FEDERATION_HTTP_FETCH_LIMIT)
+    .http_fetch_limit(max_http_fetch_limit(Some(&local_site), &SETTINGS))
     .debug(cfg!(debug_assertions))
     .http_signature_compat(true)
-    .url_verifier(Box::new(VerifyUrlData(context.inner_pool().clone())))
+    .url_verifier(Box::new(VerifyUrlData(context.clone())))
+    // sign fetches with the instance actor, for remote instances which require signed fetch
+    .signed_fetch_actor(&ApubSite::from(site_view.site.clone()));
+  let federation_config = federation_config_builder.build().await?;
+  // the federation workers deliver with a separate client, which watches the responses for
+  // instances that blocked us
+  let delivery_client = ClientBuilder::new(client_builder(&SETTINGS).build()?)
+    .with(TracingMiddleware::default())
+    .with(RemoteBlockMiddleware)
+    .build();
+  let delivery_config = federation_config_builder
+    .client(delivery_client)
     .build()
     .await?;
 
@@ -215,7 +228,7 @@ pub async fn start_lemmy_server(args: CmdArgs) -> Result<(), LemmyError> {
         process_count: args.federate_process_count,
       },
       pool.clone(),
-      federation_config.clone(),
+      delivery_config,
     )
   });
   let mut interrupt = tokio::signal::unix::signal(SignalKind::interrupt())?;
@@ -371,11 +371,13 @@ async fn active_counts(pool: &mut DbPool<'_>) {
 
   match conn {
     Ok(mut conn) => {
+      // The last value is whether the interval can be federated, in which case the count received
+      // from a remote community's instance is kept
       let intervals = vec![
-        ("1 day", "day"),
-        ("1 week", "week"),
-        ("1 month", "month"),
-        ("6 months", "half_year"),
+        ("1 day", "day", true),
+        ("1 week", "week", true),
+        ("1 month", "month", true),
+        ("6 months", "half_year", false),
       ];
 
       for i in &intervals {
@@ -389,7 +391,12 @@ async fn active_counts(pool: &mut DbPool<'_>) {
           .map_err(|e| error!("Failed to update site stats: {e}"))
           .ok();
 
-        let update_community_stmt = format!("update community_aggregates ca set users_active_{} = mv.count_ from community_aggregates_activity('{}') mv where ca.community_id = mv.community_id_", i.1, i.0);
+        let keep_federated = if i.2 {
+          " and not ca.federated_users_active"
+        } else {
+          ""
+        };
+        let update_community_stmt = format!("update community_aggregates ca set users_active_{} = mv.count_ from community_aggregates_activity('{}') mv where ca.community_id = mv.community_id_{}", i.1, i.0, keep_federated);
         sql_query(update_community_stmt)
           .execute(&mut conn)
           .await